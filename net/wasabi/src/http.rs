@@ -2,20 +2,289 @@ extern crate alloc;
 use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
-use noli::net::{lookup_host, SocketAddr, TcpStream};
+use core::cell::RefCell;
+use core::fmt;
+use noli::net::{lookup_host, IpV4Addr, SocketAddr, TcpStream};
 use saba_core::error::Error;
+use saba_core::http::Header;
 use saba_core::http::HttpResponse;
+use saba_core::http_request::HttpRequest;
+use saba_core::http_retry::RetryPolicy;
+use saba_core::http_stream::{BodyBuffer, BodySink, ResponseLimits, ResponseReader};
+use saba_core::resource_loader::ResourceLoader;
+use saba_core::url::{parse_ip_address, IpAddress, Scheme, Url};
 
-pub struct HttpClient {}
+// 使い終わった接続を保持しておく最大の数
+const MAX_IDLE_CONNECTIONS: usize = 6;
+
+// 受信するボディの最大のバイト数のデフォルト
+// これより大きなレスポンスでヒープを使い切らないように、受信をやめてエラーにする
+const DEFAULT_MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+// 次のリクエストに使い回せる接続
+struct IdleConnection {
+    host: String,
+    port: u16,
+    stream: TcpStream,
+}
+
+// 新しい接続でリクエストを送った結果
+enum Attempt {
+    Response(HttpResponse),
+    // 接続できなかった（サーバーにリクエストは届いていない）
+    ConnectFailed(Error),
+    // 送信に失敗したか、何も受信しないまま接続が閉じられた（リセットされた）
+    NoResponse(Error),
+}
+
+pub struct HttpClient {
+    // 同じホストへのリクエストには、保持しておいた接続を使い回す（keep-alive）
+    idle_connections: RefCell<Vec<IdleConnection>>,
+    // 受信するレスポンスのヘッダの数や大きさ、ボディの大きさの上限
+    limits: ResponseLimits,
+    // 一時的なネットワークの失敗の時に送り直す方針（デフォルトでは送り直さない）
+    retry_policy: RetryPolicy,
+    // 送り直すまでにミリ秒だけ待つ関数
+    sleep: fn(u64),
+}
+
+impl fmt::Debug for HttpClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpClient")
+            .field("idle_connections", &self.idle_connections.borrow().len())
+            .field("limits", &self.limits)
+            .field("retry_policy", &self.retry_policy)
+            .finish()
+    }
+}
 
 impl HttpClient {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            idle_connections: RefCell::new(Vec::new()),
+            limits: ResponseLimits {
+                max_body_size: Some(DEFAULT_MAX_BODY_SIZE),
+                ..ResponseLimits::default()
+            },
+            retry_policy: RetryPolicy::no_retry(),
+            sleep: |_ms| {},
+        }
+    }
+
+    pub fn set_max_body_size(&mut self, max_body_size: Option<usize>) {
+        self.limits.max_body_size = max_body_size;
+    }
+
+    pub fn set_response_limits(&mut self, limits: ResponseLimits) {
+        self.limits = limits;
+    }
+
+    // Wasabiのネットワークや、ホストから転送されたlocalhostのサーバーは、接続の拒否やリセットが起きやすい
+    // そのような失敗の時に、policyに従って待ってから送り直す
+    // 待ち方は環境によって異なるので、ミリ秒だけ待つ関数sleepを渡す
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy, sleep: fn(u64)) {
+        self.retry_policy = policy;
+        self.sleep = sleep;
     }
 
-    // domain名からIPアドレスへの変換（正引き）
     pub fn get(&self, host: String, port: u16, path: String) -> Result<HttpResponse, Error> {
-        let ips = match lookup_host(&host) {
+        self.get_with_headers(host, port, path, Vec::new())
+    }
+
+    // headersはリクエストに追加するヘッダ（Cookieなど）
+    pub fn get_with_headers(
+        &self,
+        host: String,
+        port: u16,
+        path: String,
+        headers: Vec<Header>,
+    ) -> Result<HttpResponse, Error> {
+        self.send("GET", host, port, path, headers, String::new())
+    }
+
+    // ボディを受信するたびにsinkに渡す（進捗の表示や、HTMLを受信しながらのトークン化に使う）
+    // 戻り値のレスポンスのボディは空
+    pub fn get_streaming(
+        &self,
+        host: String,
+        port: u16,
+        path: String,
+        headers: Vec<Header>,
+        sink: &mut dyn BodySink,
+    ) -> Result<HttpResponse, Error> {
+        self.request("GET", host, port, path, headers, String::new(), sink)
+    }
+
+    // フォームの送信やAPIの呼び出しなどで、bodyをPOSTする
+    // content_typeはbodyの種類（"application/x-www-form-urlencoded"など）
+    pub fn post(
+        &self,
+        host: String,
+        port: u16,
+        path: String,
+        body: String,
+        content_type: &str,
+    ) -> Result<HttpResponse, Error> {
+        self.post_with_headers(host, port, path, Vec::new(), body, content_type)
+    }
+
+    // headersはリクエストに追加するヘッダ（Cookieなど）
+    // bodyの種類はContent-Typeヘッダ、長さはContent-Lengthヘッダで伝える
+    pub fn post_with_headers(
+        &self,
+        host: String,
+        port: u16,
+        path: String,
+        mut headers: Vec<Header>,
+        body: String,
+        content_type: &str,
+    ) -> Result<HttpResponse, Error> {
+        headers.push(Header::new(
+            "Content-Type".to_string(),
+            content_type.to_string(),
+        ));
+        self.send("POST", host, port, path, headers, body)
+    }
+
+    // ボディを持たないレスポンスを受け取る（キャッシュの検証や、リソースの大きさの確認に使う）
+    pub fn head(
+        &self,
+        host: String,
+        port: u16,
+        path: String,
+        headers: Vec<Header>,
+    ) -> Result<HttpResponse, Error> {
+        self.send("HEAD", host, port, path, headers, String::new())
+    }
+
+    // 任意のメソッド（PUT、DELETEなど）でリクエストを送り、ボディを全て受信してからレスポンスを返す
+    // methodは英大文字のトークンで、それ以外はエラーになる
+    pub fn send(
+        &self,
+        method: &str,
+        host: String,
+        port: u16,
+        path: String,
+        headers: Vec<Header>,
+        body: String,
+    ) -> Result<HttpResponse, Error> {
+        let mut buffer = BodyBuffer::new();
+        let response = self.request(method, host, port, path, headers, body, &mut buffer)?;
+        Ok(response.with_body(buffer.into_bytes()))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn request(
+        &self,
+        method: &str,
+        host: String,
+        port: u16,
+        path: String,
+        headers: Vec<Header>,
+        body: String,
+        sink: &mut dyn BodySink,
+    ) -> Result<HttpResponse, Error> {
+        // リクエストの作成
+        // 行の区切りはCRLFで、ボディの長さはContent-Lengthで伝える
+        // 不正なリクエストは、接続する前にエラーにする
+        let mut request = HttpRequest::new(method, host.clone(), port, path)?;
+        let is_head = method == "HEAD";
+        request.add_header("Accept", "text/html")?;
+        request.add_header("Connection", "keep-alive")?;
+        for header in headers {
+            request.add_header(&header.name(), &header.value())?;
+        }
+        request.set_body(body);
+        let request = request.serialize();
+
+        // 保持しておいた接続は、サーバーがすでに閉じていることがある
+        // 送信に失敗するか、何も受信しないまま閉じられた場合は、新しい接続でもう一度送る
+        if let Some(mut stream) = self.take_idle_connection(&host, port) {
+            if send_request(&mut stream, &request).is_ok() {
+                if let Some(response) = self.receive(stream, &host, port, is_head, sink)? {
+                    return Ok(response);
+                }
+            }
+        }
+
+        // 新しい接続で送る
+        // 接続できなかった場合や、何も受信しないまま接続が閉じられた場合は、retry_policyに従って送り直す
+        // 後者はサーバーが処理したかもしれないので、冪等なメソッドの場合だけ送り直す
+        // 何も受信していないので、sinkに同じデータを2回渡すことはない
+        let ip = self.resolve(&host)?;
+        let idempotent = matches!(
+            method,
+            "GET" | "HEAD" | "PUT" | "DELETE" | "OPTIONS" | "TRACE"
+        );
+        let mut attempt = 1;
+        loop {
+            let error =
+                match self.send_on_new_connection(ip, &host, port, &request, is_head, sink)? {
+                    Attempt::Response(response) => return Ok(response),
+                    Attempt::ConnectFailed(e) => e,
+                    Attempt::NoResponse(e) if idempotent => e,
+                    Attempt::NoResponse(e) => return Err(e),
+                };
+            match self.retry_policy.backoff(attempt) {
+                Some(backoff_ms) => {
+                    (self.sleep)(backoff_ms);
+                    attempt += 1;
+                }
+                None => return Err(error),
+            }
+        }
+    }
+
+    fn send_on_new_connection(
+        &self,
+        ip: IpV4Addr,
+        host: &str,
+        port: u16,
+        request: &str,
+        is_head: bool,
+        sink: &mut dyn BodySink,
+    ) -> Result<Attempt, Error> {
+        let mut stream = match self.connect(ip, port) {
+            Ok(stream) => stream,
+            Err(e) => return Ok(Attempt::ConnectFailed(e)),
+        };
+        if let Err(e) = send_request(&mut stream, request) {
+            return Ok(Attempt::NoResponse(e));
+        }
+        match self.receive(stream, host, port, is_head, sink)? {
+            Some(response) => Ok(Attempt::Response(response)),
+            None => Ok(Attempt::NoResponse(Error::Network(
+                "Connection closed before receiving a response".to_string(),
+            ))),
+        }
+    }
+
+    // domain名からIPアドレスへの変換（正引き）をする
+    // hostがIPアドレスの場合は、名前解決せずにそのアドレスを使う
+    fn resolve(&self, host: &str) -> Result<IpV4Addr, Error> {
+        match parse_ip_address(host) {
+            Some(IpAddress::V4(address)) => Ok(IpV4Addr::new(address)),
+            Some(IpAddress::V6(address)) => Ok(IpV4Addr::new(ipv6_to_ipv4(address)?)),
+            None => self.lookup(host),
+        }
+    }
+
+    // TCPの接続を確立する
+    fn connect(&self, ip: IpV4Addr, port: u16) -> Result<TcpStream, Error> {
+        // intoメソッドを使ってSocketAddrに変換
+        let socket_addr: SocketAddr = (ip, port).into();
+
+        // TCPストリームの確立
+        match TcpStream::connect(socket_addr) {
+            Ok(stream) => Ok(stream),
+            Err(_e) => Err(Error::Network(
+                "Failed to connect to TCP stream".to_string(),
+            )),
+        }
+    }
+
+    fn lookup(&self, host: &str) -> Result<IpV4Addr, Error> {
+        let ips = match lookup_host(host) {
             // パターンマッチング lookup_hostの戻り値に対しての分岐
             Ok(ips) => ips,
             Err(e) => {
@@ -27,54 +296,35 @@ impl HttpClient {
         };
 
         // 1つのドメイン名に対して複数のIPアドレスを返す場合がある
-        if ips.len() < 1 {
-            return Err(Error::Network("Failed to find IP addresses".to_string()));
+        match ips.first() {
+            Some(ip) => Ok(*ip),
+            None => Err(Error::Network("Failed to find IP addresses".to_string())),
         }
+    }
 
-        // intoメソッドを使ってSocketAddrに変換
-        let socket_addr: SocketAddr = (ips[0], port).into();
-
-        // TCPストリームの確立
-        let mut stream = match TcpStream::connect(socket_addr) {
-            Ok(stream) => stream,
-            Err(_e) => {
-                return Err(Error::Network(
-                    "Failed to connect to TCP stream".to_string(),
-                ));
-            }
-        };
-
-        // リクエストラインの作成
-        let mut request = String::from("GET /");
-        request.push_str(&path);
-        request.push_str(" HTTP/1.1\n");
-
-        // ヘッダの追加
-        request.push_str("Host: ");
-        request.push_str(&host);
-        request.push('\n');
-        request.push_str("Accept: text/html\n");
-        request.push_str("Connection: close\n");
-        request.push('\n');
-
-        // リクエストの送信
-        // 何バイト送信したかを取得
-        // unused variableの警告を抑制するために先頭にアンダーバー
-        let _bytes_written = match stream.write(request.as_bytes()) {
-            Ok(bytes) => bytes,
-            Err(_) => {
-                return Err(Error::Network(
-                    "Failed to send a request to TCP stream".to_string(),
-                ));
-            }
-        };
-
+    // レスポンスを受信する
+    // HEADリクエストの場合は、ヘッダの後にボディを待たない
+    // 何も受信しないまま接続が閉じられた（リセットされた）場合はNone
+    fn receive(
+        &self,
+        mut stream: TcpStream,
+        host: &str,
+        port: u16,
+        is_head: bool,
+        sink: &mut dyn BodySink,
+    ) -> Result<Option<HttpResponse>, Error> {
         // レスポンスの受信
-        let mut received = Vec::new();
-        loop {
+        // ボディは受信するたびにsinkに渡し、Content-Lengthの分か、chunkedの最後のチャンクまで読んだら終了する
+        // 長さが分からない場合は、接続が閉じられるまで読む
+        let mut reader = ResponseReader::with_limits(self.limits);
+        if is_head {
+            reader.set_request_method("HEAD");
+        }
+        let complete = loop {
             let mut buf = [0u8; 4096];
             let bytes_read = match stream.read(&mut buf) {
                 Ok(bytes) => bytes,
+                Err(_) if reader.is_empty() => return Ok(None),
                 Err(_) => {
                     return Err(Error::Network(
                         "Failed to receive a request from TCP stream".to_string(),
@@ -82,18 +332,137 @@ impl HttpClient {
                 }
             };
 
-            // 読み込みが0バイトなら終了
+            // 読み込みが0バイトなら、接続が閉じられたので終了
             if bytes_read == 0 {
-                break;
+                break false;
             }
-            // 読み込んだバイト数だけVecに追加
-            received.extend_from_slice(&buf[..bytes_read]);
+            if reader.feed(&buf[..bytes_read], sink)? {
+                break true;
+            }
+        };
+
+        if reader.is_empty() {
+            return Ok(None);
         }
 
-        // receivedがUTF-8として有効であるかの判定
-        match core::str::from_utf8(&received) {
-            Ok(response) => HttpResponse::new(response.to_string()),
-            Err(e) => Err(Error::Network(format!("Invalid received response: {}", e))),
+        let response = reader.finish()?;
+        // 接続が閉じられていなければ、次のリクエストに使い回す
+        if complete && response.keep_alive() {
+            self.put_idle_connection(host, port, stream);
         }
+        Ok(Some(response))
     }
+
+    fn take_idle_connection(&self, host: &str, port: u16) -> Option<TcpStream> {
+        let mut idle_connections = self.idle_connections.borrow_mut();
+        let index = idle_connections
+            .iter()
+            .position(|c| c.host == host && c.port == port)?;
+        Some(idle_connections.remove(index).stream)
+    }
+
+    // 保持する数を超えた場合は、一番古い接続を閉じる
+    fn put_idle_connection(&self, host: &str, port: u16, stream: TcpStream) {
+        let mut idle_connections = self.idle_connections.borrow_mut();
+        if idle_connections.len() >= MAX_IDLE_CONNECTIONS {
+            idle_connections.remove(0);
+        }
+        idle_connections.push(IdleConnection {
+            host: host.to_string(),
+            port,
+            stream,
+        });
+    }
+}
+
+// Pageが外部のリソースを取得する時に使う
+impl ResourceLoader for HttpClient {
+    fn fetch(&self, url: Url) -> Result<HttpResponse, Error> {
+        self.fetch_with_headers(url, Vec::new())
+    }
+
+    fn fetch_with_headers(&self, url: Url, headers: Vec<Header>) -> Result<HttpResponse, Error> {
+        let (port, path) = port_and_path(&url)?;
+        self.get_with_headers(url.host(), port, path, headers)
+    }
+
+    fn request(
+        &self,
+        method: &str,
+        url: Url,
+        headers: Vec<Header>,
+        body: String,
+    ) -> Result<HttpResponse, Error> {
+        let (port, path) = port_and_path(&url)?;
+        self.send(method, url.host(), port, path, headers, body)
+    }
+
+    fn fetch_streaming(
+        &self,
+        url: Url,
+        headers: Vec<Header>,
+        sink: &mut dyn BodySink,
+    ) -> Result<HttpResponse, Error> {
+        let (port, path) = port_and_path(&url)?;
+        self.get_streaming(url.host(), port, path, headers, sink)
+    }
+
+    // Content-Typeはheadersに含まれているものを使い、ない場合はフォームの形式にする
+    fn post(&self, url: Url, headers: Vec<Header>, body: String) -> Result<HttpResponse, Error> {
+        let (port, path) = port_and_path(&url)?;
+        let (content_types, headers): (Vec<Header>, Vec<Header>) = headers
+            .into_iter()
+            .partition(|header| header.name().eq_ignore_ascii_case("Content-Type"));
+        let content_type = match content_types.first() {
+            Some(header) => header.value(),
+            None => "application/x-www-form-urlencoded".to_string(),
+        };
+        self.post_with_headers(url.host(), port, path, headers, body, &content_type)
+    }
+}
+
+// リクエストの送信
+fn send_request(stream: &mut TcpStream, request: &str) -> Result<(), Error> {
+    // 何バイト送信したかを取得
+    // unused variableの警告を抑制するために先頭にアンダーバー
+    let _bytes_written = match stream.write(request.as_bytes()) {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return Err(Error::Network(
+                "Failed to send a request to TCP stream".to_string(),
+            ));
+        }
+    };
+    Ok(())
+}
+
+// ネットワークはIPv4にしか対応していないので、IPv4で表せるIPv6アドレスだけをIPv4アドレスにする
+// ループバックアドレス（::1）は127.0.0.1、IPv4射影アドレス（::ffff:a.b.c.d）はa.b.c.dにする
+fn ipv6_to_ipv4(address: [u16; 8]) -> Result<[u8; 4], Error> {
+    match address {
+        [0, 0, 0, 0, 0, 0, 0, 1] => Ok([127, 0, 0, 1]),
+        [0, 0, 0, 0, 0, 0xffff, high, low] => {
+            let [a, b] = high.to_be_bytes();
+            let [c, d] = low.to_be_bytes();
+            Ok([a, b, c, d])
+        }
+        _ => Err(Error::Network(
+            "IPv6 addresses are not supported".to_string(),
+        )),
+    }
+}
+
+// リクエストに使うポート番号と、searchpartを含むパス
+fn port_and_path(url: &Url) -> Result<(u16, String), Error> {
+    // TLSに対応していないので、httpsのURLには接続しない
+    if url.scheme() != Scheme::Http {
+        return Err(Error::Network(format!(
+            "{} scheme is not supported yet",
+            url.scheme()
+        )));
+    }
+
+    // ポート番号はUrl::parseで検証済み
+    // 空白や日本語などはパーセントエンコードする
+    Ok((url.port(), url.request_path()))
 }
@@ -0,0 +1,109 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// ブックマークの一覧を表示する内部のページのURL
+pub const BOOKMARKS_URL: &str = "about:bookmarks";
+
+// 1つのブックマーク
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    title: String,
+    url: String,
+}
+
+impl Bookmark {
+    pub fn new(title: String, url: String) -> Self {
+        Self { title, url }
+    }
+
+    pub fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+}
+
+// 追加された順に並んだブックマークの一覧
+// 同じURLのブックマークは1つだけ持つ
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bookmarks {
+    bookmarks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    pub fn new() -> Self {
+        Self {
+            bookmarks: Vec::new(),
+        }
+    }
+
+    // ブックマークを追加する
+    // 同じURLのブックマークがすでにある場合は、並び順を変えずにタイトルだけ変える
+    // タイトルが空の場合はURLをタイトルにする
+    pub fn add(&mut self, title: String, url: String) {
+        let title = match title.trim() {
+            "" => url.clone(),
+            title => String::from(title),
+        };
+        match self
+            .bookmarks
+            .iter_mut()
+            .find(|bookmark| bookmark.url == url)
+        {
+            Some(bookmark) => bookmark.title = title,
+            None => self.bookmarks.push(Bookmark::new(title, url)),
+        }
+    }
+
+    // URLのブックマークを削除する
+    // 削除した場合はtrueを返す
+    pub fn remove(&mut self, url: &str) -> bool {
+        let len = self.bookmarks.len();
+        self.bookmarks.retain(|bookmark| bookmark.url != url);
+        self.bookmarks.len() != len
+    }
+
+    pub fn contains(&self, url: &str) -> bool {
+        self.bookmarks.iter().any(|bookmark| bookmark.url == url)
+    }
+
+    pub fn list(&self) -> Vec<Bookmark> {
+        self.bookmarks.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    // 同じURLを追加するとタイトルだけが変わり、削除できるか
+    #[test]
+    fn test_bookmarks() {
+        let mut bookmarks = Bookmarks::new();
+        bookmarks.add("Index".to_string(), "http://example.com/".to_string());
+        bookmarks.add(
+            " ".to_string(),
+            "http://localhost:8000/test.html".to_string(),
+        );
+        bookmarks.add("Top".to_string(), "http://example.com/".to_string());
+        assert_eq!(
+            alloc::vec![
+                Bookmark::new("Top".to_string(), "http://example.com/".to_string()),
+                Bookmark::new(
+                    "http://localhost:8000/test.html".to_string(),
+                    "http://localhost:8000/test.html".to_string()
+                ),
+            ],
+            bookmarks.list()
+        );
+
+        assert!(bookmarks.contains("http://example.com/"));
+        assert!(bookmarks.remove("http://example.com/"));
+        assert!(!bookmarks.remove("http://example.com/"));
+        assert!(!bookmarks.contains("http://example.com/"));
+        assert_eq!(1, bookmarks.list().len());
+    }
+}
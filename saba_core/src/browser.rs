@@ -1,21 +1,103 @@
+use crate::bookmark::Bookmark;
+use crate::bookmark::Bookmarks;
+use crate::bookmark::BOOKMARKS_URL;
+use crate::cookie::CookieJar;
+use crate::error::Error;
+use crate::http::parse_http_date;
+use crate::http::HttpResponse;
+use crate::http_cache::HttpCache;
+use crate::renderer::dom::form::FormMethod;
+use crate::renderer::dom::form::FormSubmission;
+use crate::renderer::js::console::ConsoleMessage;
+use crate::renderer::page::resolve_url;
+use crate::renderer::page::LoadEvent;
 use crate::renderer::page::Page;
+use crate::resource_loader::ResourceLoader;
+use crate::url::Scheme;
+use crate::url::Url;
 use alloc::rc::Rc;
+use alloc::string::String;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
+// 1回の移動で辿るリダイレクトの回数の上限
+// リダイレクトがループしている場合に、いつまでも取得し続けないようにする
+const MAX_REDIRECTS: usize = 20;
+
 #[derive(Debug, Clone)]
 pub struct Browser {
     active_page_index: usize,
     pages: Vec<Rc<RefCell<Page>>>,
+    // 訪れたページの履歴（古い順）
+    history: Vec<HistoryEntry>,
+    // 履歴の中で現在表示しているページの位置
+    history_index: usize,
+    // 全てのページで共有するクッキー
+    // ページを移動してもログインなどの状態を保てるように、ブラウザが持つ
+    cookie_jar: Rc<RefCell<CookieJar>>,
+    // 全てのページで共有するHTTPキャッシュ
+    // 同じページに戻った時や再読み込みの時に、ネットワークへのアクセスを減らす
+    http_cache: Rc<RefCell<HttpCache>>,
+    // 現在の時刻（1970年1月1日からの秒数）
+    current_time: u64,
+    // UIのイベントループの時計の時刻（ミリ秒）
+    // タイマーと<meta http-equiv="refresh">で予定された移動に使う
+    task_time: u64,
+    // 最後に読み込んだ文書のレスポンスのDateヘッダの時刻（1970年1月1日からの秒数）
+    // 日時を取得できない環境では、UIがこれで現在の時刻を合わせる
+    server_time: Option<u64>,
+    // <meta http-equiv="refresh">で予定された移動の時刻（task_timeと同じミリ秒）と移動先のURL
+    scheduled_navigation: Option<(u64, String)>,
+    // よく使うページのブックマーク
+    // about:bookmarksのページに一覧を表示する
+    bookmarks: Bookmarks,
+}
+
+// 履歴の1つの項目
+// 戻る・進むで同じ位置から表示できるように、スクロール量も覚えておく
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    url: String,
+    scroll_offset: i64,
+}
+
+impl HistoryEntry {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            scroll_offset: 0,
+        }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    pub fn scroll_offset(&self) -> i64 {
+        self.scroll_offset
+    }
 }
 
 impl Browser {
     pub fn new() -> Rc<RefCell<Self>> {
         let mut page = Page::new();
+        let cookie_jar = Rc::new(RefCell::new(CookieJar::new()));
+        page.set_cookie_jar(cookie_jar.clone());
+        let http_cache = Rc::new(RefCell::new(HttpCache::new()));
+        page.set_http_cache(http_cache.clone());
 
         let browser = Rc::new(RefCell::new(Self {
             active_page_index: 0,
             pages: Vec::new(),
+            history: Vec::new(),
+            history_index: 0,
+            cookie_jar,
+            http_cache,
+            current_time: 0,
+            task_time: 0,
+            server_time: None,
+            scheduled_navigation: None,
+            bookmarks: Bookmarks::new(),
         }));
 
         page.set_browser(Rc::downgrade(&browser));
@@ -27,4 +109,1095 @@ impl Browser {
     pub fn current_page(&self) -> Rc<RefCell<Page>> {
         self.pages[self.active_page_index].clone()
     }
+
+    pub fn cookie_jar(&self) -> Rc<RefCell<CookieJar>> {
+        self.cookie_jar.clone()
+    }
+
+    pub fn http_cache(&self) -> Rc<RefCell<HttpCache>> {
+        self.http_cache.clone()
+    }
+
+    // クッキーの有効期限とHTTPキャッシュの新鮮さの判定に使う現在の時刻（1970年1月1日からの秒数）を設定する
+    // 時計を持つ環境では、ページを読み込む前に呼び出す
+    // 起動してからの時間などではなく、実際の日時を渡す（HTTPの日付と比べるため）
+    pub fn set_current_time(&mut self, now: u64) {
+        self.current_time = now;
+        self.cookie_jar.borrow_mut().set_current_time(now);
+        self.http_cache.borrow_mut().set_current_time(now);
+    }
+
+    // 全てのページが外部のリソースを取得する方法を設定する
+    pub fn set_resource_loader(&self, resource_loader: Rc<dyn ResourceLoader>) {
+        for page in &self.pages {
+            page.borrow_mut()
+                .set_resource_loader(resource_loader.clone());
+        }
+    }
+
+    // 現在のページのビューポート上の点(x, y)がクリックされた時の処理
+    // リンクがクリックされた場合はリンク先に移動し、trueを返す
+    // フォームの送信ボタンがクリックされた場合はフォームを送信し、trueを返す
+    // クリックしたリンクや入力欄にはフォーカスを移す
+    // クリックで実行されたスクリプトがlocation.hrefに代入した場合は、そのURLに移動し、trueを返す
+    pub fn on_click(&mut self, x: i64, y: i64) -> Result<bool, Error> {
+        self.current_page().borrow_mut().focus_at(x, y);
+        let submission = self.current_page().borrow().form_submission(x, y);
+        if let Some(submission) = submission {
+            self.submit(submission)?;
+            return Ok(true);
+        }
+
+        let destination = self.current_page().borrow().on_click(x, y);
+        let navigation = self.current_page().borrow_mut().take_navigation();
+        match navigation.or(destination) {
+            Some(url) => {
+                self.navigate(url)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // フォーカスを持つ要素を実行する（Enterキー）
+    // リンクの場合はリンク先に移動し、送信ボタンや入力欄の場合はフォームを送信して、trueを返す
+    pub fn activate_focused(&mut self) -> Result<bool, Error> {
+        let page = self.current_page();
+        let submission = page.borrow().focused_form_submission();
+        if let Some(submission) = submission {
+            self.submit(submission)?;
+            return Ok(true);
+        }
+
+        let destination = page.borrow().focused_link();
+        match destination {
+            Some(url) => {
+                self.navigate(url)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // URLの文書を取得して現在のページに読み込み、履歴に追加する
+    // 戻った後に別のページに移動した場合は、進む先の履歴を捨てる
+    // 今のページのスクロール量は、戻ってきた時のために履歴に保存しておく
+    pub fn navigate(&mut self, url: String) -> Result<(), Error> {
+        let scroll_offset = self.current_page().borrow().scroll_offset();
+        let url = self.load(url)?;
+        self.push_history(url, scroll_offset);
+        Ok(())
+    }
+
+    // フォームを送信し、レスポンスの文書を現在のページに読み込む
+    // GETの場合は、フォームの内容をクエリに含むURLに移動する
+    // POSTの場合は送信先のURLを履歴に追加する（戻る・進むでは、送信し直さずにGETで読み込む）
+    pub fn submit(&mut self, submission: FormSubmission) -> Result<(), Error> {
+        let url = submission.url();
+        if submission.method() == FormMethod::Get {
+            return self.navigate(url);
+        }
+
+        let scroll_offset = self.current_page().borrow().scroll_offset();
+        let url = self.load_document(url, Some(submission.body()));
+        self.push_history(url, scroll_offset);
+        Ok(())
+    }
+
+    // 最後に読み込んだ文書のレスポンスのDateヘッダの時刻（1970年1月1日からの秒数）を取り出す
+    // 日時を取得できない環境のUIは、これで時計を合わせてset_current_timeを呼び出す
+    pub fn take_server_time(&mut self) -> Option<u64> {
+        self.server_time.take()
+    }
+
+    // <meta http-equiv="refresh">で予定された移動の時刻（ミリ秒）と移動先のURL
+    pub fn scheduled_navigation(&self) -> Option<(u64, String)> {
+        self.scheduled_navigation.clone()
+    }
+
+    // 予定された移動の時刻になっていれば、移動先のURLを読み込む
+    // 時刻は最後にrun_pending_tasksに渡された時刻で判断する
+    // 移動先は履歴に追加せず、現在の履歴の項目を置き換える
+    // 移動した場合はtrueを返す
+    pub fn run_scheduled_navigation(&mut self) -> Result<bool, Error> {
+        let url = match self.scheduled_navigation.take() {
+            Some((time, url)) if time <= self.task_time => url,
+            scheduled => {
+                self.scheduled_navigation = scheduled;
+                return Ok(false);
+            }
+        };
+
+        let url = self.load(url)?;
+        match self.history.get_mut(self.history_index) {
+            Some(entry) => *entry = HistoryEntry::new(url),
+            None => self.push_history(url, 0),
+        }
+        Ok(true)
+    }
+
+    // 新しく読み込んだURLを履歴に追加する
+    // 戻った後に別のページに移動した場合は、進む先の履歴を捨てる
+    fn push_history(&mut self, url: String, scroll_offset: i64) {
+        if !self.history.is_empty() {
+            self.history[self.history_index].scroll_offset = scroll_offset;
+            self.history.truncate(self.history_index + 1);
+        }
+        self.history.push(HistoryEntry::new(url));
+        self.history_index = self.history.len() - 1;
+    }
+
+    // 現在のページを読み込み直す（ツールバーの再読み込みのボタン）
+    // bypass_cacheがtrueの場合は、文書を取得し直す
+    pub fn reload(&self, bypass_cache: bool) -> Result<(), Error> {
+        if self.is_showing_bookmarks() {
+            self.show_bookmarks();
+            return Ok(());
+        }
+        self.current_page().borrow_mut().reload(bypass_cache)
+    }
+
+    pub fn bookmarks(&self) -> Vec<Bookmark> {
+        self.bookmarks.list()
+    }
+
+    // ブックマークを追加する
+    // 同じURLのブックマークがすでにある場合は、タイトルだけを変える
+    pub fn add_bookmark(&mut self, title: String, url: String) {
+        self.bookmarks.add(title, url);
+        if self.is_showing_bookmarks() {
+            self.show_bookmarks();
+        }
+    }
+
+    // 現在のページをブックマークに追加する（ツールバーのブックマークのボタン）
+    // タイトルにはページのURLを使う
+    // 表示しているページがない場合は何もせずにfalseを返す
+    pub fn bookmark_current_page(&mut self) -> bool {
+        let url = match self.history.get(self.history_index) {
+            Some(entry) => entry.url(),
+            None => return false,
+        };
+        self.add_bookmark(url.clone(), url);
+        true
+    }
+
+    // URLのブックマークを削除する
+    // 削除した場合はtrueを返す
+    pub fn remove_bookmark(&mut self, url: &str) -> bool {
+        let removed = self.bookmarks.remove(url);
+        if removed && self.is_showing_bookmarks() {
+            self.show_bookmarks();
+        }
+        removed
+    }
+
+    // ブックマークの一覧のページに移動する
+    pub fn open_bookmarks(&mut self) -> Result<(), Error> {
+        self.navigate(String::from(BOOKMARKS_URL))
+    }
+
+    fn is_showing_bookmarks(&self) -> bool {
+        self.history
+            .get(self.history_index)
+            .is_some_and(|entry| entry.url == BOOKMARKS_URL)
+    }
+
+    // 現在のページにブックマークの一覧を表示する
+    fn show_bookmarks(&self) {
+        let page = self.current_page();
+        let mut page = page.borrow_mut();
+        page.start_load(String::from(BOOKMARKS_URL));
+        page.receive_bookmarks(String::from(BOOKMARKS_URL), &self.bookmarks.list());
+        page.finish_load();
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.history_index > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.history_index + 1 < self.history.len()
+    }
+
+    // 履歴の1つ前のページに戻る
+    // 戻れた場合はtrueを返す
+    pub fn go_back(&mut self) -> Result<bool, Error> {
+        if !self.can_go_back() {
+            return Ok(false);
+        }
+        self.go_to(self.history_index - 1)?;
+        Ok(true)
+    }
+
+    // 履歴の1つ後のページに進む
+    // 進めた場合はtrueを返す
+    pub fn go_forward(&mut self) -> Result<bool, Error> {
+        if !self.can_go_forward() {
+            return Ok(false);
+        }
+        self.go_to(self.history_index + 1)?;
+        Ok(true)
+    }
+
+    pub fn history(&self) -> Vec<HistoryEntry> {
+        self.history.clone()
+    }
+
+    // 現在のページのまだ取り出されていない読み込みのイベントを古い順に取り出す
+    pub fn take_load_events(&self) -> Vec<LoadEvent> {
+        self.current_page().borrow_mut().take_load_events()
+    }
+
+    // 現在のページの、現在の時刻（ミリ秒）までに実行する時刻になったタイマーのタスクを実行する
+    // UIのイベントループから繰り返し呼び出す
+    // nowは<meta http-equiv="refresh">で予定された移動の時刻にも使う
+    // タスクの中でlocation.hrefに代入された場合は、そのURLに移動する
+    // タスクを1つでも実行した場合はtrueを返す
+    pub fn run_pending_tasks(&mut self, now: u64) -> Result<bool, Error> {
+        self.task_time = now;
+        let ran = self.current_page().borrow_mut().run_pending_tasks(now);
+        let navigation = self.current_page().borrow_mut().take_navigation();
+        if let Some(url) = navigation {
+            self.navigate(url)?;
+        }
+        Ok(ran)
+    }
+
+    // 現在のページのconsoleに出力されたメッセージを古い順に返す
+    pub fn console_messages(&self) -> Vec<ConsoleMessage> {
+        self.current_page().borrow().console_messages()
+    }
+
+    // 履歴のindex番目のページを読み込み、前回表示していた位置までスクロールする
+    // 今のページのスクロール量は、戻ってきた時のために履歴に保存しておく
+    fn go_to(&mut self, index: usize) -> Result<(), Error> {
+        let scroll_offset = self.current_page().borrow().scroll_offset();
+        let entry = self.history[index].clone();
+        // リダイレクトされた場合は、履歴のURLをリダイレクト先にする
+        let url = self.load(entry.url())?;
+
+        self.history[index].url = url;
+        self.history[self.history_index].scroll_offset = scroll_offset;
+        self.current_page()
+            .borrow_mut()
+            .scroll_to(entry.scroll_offset());
+        self.history_index = index;
+        Ok(())
+    }
+
+    // URLの文書を取得し、現在のページに読み込む
+    // 戻り値は読み込んだURL（リダイレクトされた場合はリダイレクト先）
+    // URLが不正な場合は、代わりにエラーの内容を説明する文書を表示する
+    // 表示している文書の中の移動であれば、文書を取得し直さない
+    // ネットワークにアクセスしないscheme（about、data、view-source）は、schemeごとに文書を作る
+    fn load(&mut self, url: String) -> Result<String, Error> {
+        let page = self.current_page();
+        let parsed_url = match Url::new(url.clone()).parse() {
+            Ok(parsed_url) => parsed_url,
+            Err(e) => {
+                let mut page = page.borrow_mut();
+                page.start_load(url.clone());
+                page.receive_error(url.clone(), &e);
+                page.finish_load();
+                self.scheduled_navigation = None;
+                return Ok(url);
+            }
+        };
+
+        let fragment = parsed_url.fragment();
+        let same_document = !fragment.is_empty()
+            && page
+                .borrow()
+                .url()
+                .is_some_and(|current| current.is_same_document(&parsed_url));
+        if same_document {
+            let mut page = page.borrow_mut();
+            page.set_url(parsed_url);
+            page.scroll_to_fragment(&fragment);
+            page.mark_visited(url.clone());
+            return Ok(url);
+        }
+
+        let url = match parsed_url.scheme() {
+            Scheme::Http | Scheme::Https => self.load_document(url, None),
+            Scheme::About => self.load_about(url, &parsed_url),
+            Scheme::Data => {
+                let response = parsed_url
+                    .data()
+                    .and_then(|(media_type, data)| document_response(&media_type, data));
+                self.load_response(url, response)
+            }
+            Scheme::ViewSource => self.load_view_source(url, parsed_url.raw_path()),
+        };
+        Ok(url)
+    }
+
+    // about:bookmarksはブックマークの一覧、about:blankは空の文書を表示する
+    fn load_about(&mut self, url: String, parsed_url: &Url) -> String {
+        if url == BOOKMARKS_URL {
+            self.scheduled_navigation = None;
+            self.show_bookmarks();
+            return url;
+        }
+        let response = match parsed_url.raw_path().as_str() {
+            "blank" => document_response("text/html", Vec::new()),
+            page => Err(Error::UnexpectedInput(alloc::format!(
+                "Unknown about page: {}",
+                page
+            ))),
+        };
+        self.load_response(url, response)
+    }
+
+    // view-source:の後ろのURLの文書を取得して、ソースをテキストとして表示する
+    fn load_view_source(&mut self, url: String, target: String) -> String {
+        self.current_page().borrow_mut().start_load(url.clone());
+        let (_, response) = self.fetch_document(target, None);
+        let response = response.and_then(|response| {
+            document_response("text/plain; charset=utf-8", response.body().into_bytes())
+        });
+        self.commit(url.clone(), response);
+        self.current_page().borrow_mut().finish_load();
+        url
+    }
+
+    // ネットワークを使わずに作ったレスポンスを現在のページに読み込む
+    fn load_response(&mut self, url: String, response: Result<HttpResponse, Error>) -> String {
+        self.current_page().borrow_mut().start_load(url.clone());
+        self.commit(url.clone(), response);
+        self.current_page().borrow_mut().finish_load();
+        url
+    }
+
+    // URLの文書を取得して現在のページに読み込み、読み込んだURLを返す
+    // bodyがある場合はPOSTで送信する
+    fn load_document(&mut self, url: String, body: Option<String>) -> String {
+        self.current_page().borrow_mut().start_load(url.clone());
+        let (url, response) = self.fetch_document(url, body);
+        self.commit(url.clone(), response);
+        self.current_page().borrow_mut().finish_load();
+        url
+    }
+
+    // URLの文書を取得する。3xxのリダイレクトの場合は、Locationヘッダのリダイレクト先を取得し直す
+    // 301、302と303のリダイレクトでは、POSTをGETに変えて送り直す
+    // 戻り値はレスポンスを取得したURLとレスポンス
+    fn fetch_document(
+        &self,
+        url: String,
+        mut body: Option<String>,
+    ) -> (String, Result<HttpResponse, Error>) {
+        let page = self.current_page();
+        let mut url = url;
+        for _ in 0..=MAX_REDIRECTS {
+            // fragmentはサーバーに送らない
+            let (document_url, fragment) = match url.split_once('#') {
+                Some((document_url, fragment)) => (document_url, Some(fragment)),
+                None => (url.as_str(), None),
+            };
+            let response = match &body {
+                Some(body) => page.borrow().post(String::from(document_url), body.clone()),
+                None => page.borrow().fetch(String::from(document_url)),
+            };
+            let response = match response {
+                Ok(response) => response,
+                Err(e) => return (url, Err(e)),
+            };
+
+            let status_code = response.status_code();
+            let location = match response.location() {
+                Some(location) if response.is_redirect() => location,
+                _ => return (url, Ok(response)),
+            };
+            if !matches!(status_code, 307 | 308) {
+                body = None;
+            }
+            let base = Url::new(String::from(document_url)).parse().ok();
+            let mut next = resolve_url(base.as_ref(), &location);
+            // リダイレクト先にfragmentがない場合は、元のURLのfragmentを引き継ぐ
+            if let (false, Some(fragment)) = (next.contains('#'), fragment) {
+                next = alloc::format!("{}#{}", next, fragment);
+            }
+            url = next;
+        }
+
+        let error = Error::Network(alloc::format!(
+            "Too many redirects (more than {})",
+            MAX_REDIRECTS
+        ));
+        (url, Err(error))
+    }
+
+    // 取得したレスポンスの文書を現在のページに読み込む
+    // 読み込んだURLは訪問済みとして記録する
+    // 取得に失敗した場合は、代わりにエラーの内容を説明する文書を表示する
+    // HTTPのエラーのステータスで本文がない場合も、ステータスを説明する文書を表示する
+    // URLにfragmentがある場合は、idがfragmentの要素までスクロールする
+    // 文書に<meta http-equiv="refresh">がある場合は、移動を予定する
+    // 読み込み中のスクリプトがlocation.hrefに代入した場合は、すぐに移動するように予定する
+    fn commit(&mut self, url: String, response: Result<HttpResponse, Error>) {
+        let page = self.current_page();
+        self.scheduled_navigation = None;
+        let response = match response.and_then(|response| {
+            let parsed_url = Url::new(url.clone()).parse()?;
+            Ok((parsed_url, response))
+        }) {
+            Ok((_, response))
+                if (response.is_client_error() || response.is_server_error())
+                    && response.body().trim().is_empty() =>
+            {
+                let error = Error::Network(alloc::format!(
+                    "HTTP error: {} {}",
+                    response.status_code(),
+                    response.reason()
+                ));
+                page.borrow_mut().receive_error(url, &error);
+                return;
+            }
+            Ok(response) => response,
+            Err(e) => {
+                page.borrow_mut().receive_error(url, &e);
+                return;
+            }
+        };
+        let (parsed_url, response) = response;
+        self.server_time = response
+            .header_values("Date")
+            .first()
+            .and_then(|date| parse_http_date(date));
+        let fragment = parsed_url.fragment();
+        let mut page = page.borrow_mut();
+        page.set_url(parsed_url);
+        page.receive_response(response);
+        page.load_imports();
+        page.load_images();
+        page.scroll_to_fragment(&fragment);
+        page.mark_visited(url);
+        self.scheduled_navigation = match page.take_navigation() {
+            Some(url) => Some((self.task_time, url)),
+            None => page.refresh().map(|(delay, url)| {
+                (
+                    self.task_time.saturating_add(delay.saturating_mul(1000)),
+                    url,
+                )
+            }),
+        };
+    }
+}
+
+// ネットワークを使わずに作る文書のレスポンス
+// media_typeに改行などの制御文字がある場合は取り除く
+fn document_response(media_type: &str, body: Vec<u8>) -> Result<HttpResponse, Error> {
+    let media_type = media_type.replace(|c: char| c.is_control(), "");
+    let head = alloc::format!("HTTP/1.1 200 OK\r\nContent-Type: {}\r\n\r\n", media_type);
+    HttpResponse::from_bytes(head.into_bytes()).map(|response| response.with_body(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::CHAR_HEIGHT_WITH_PADDING;
+    use crate::display_item::DisplayItem;
+    use crate::http::Header;
+    use alloc::string::ToString;
+
+    #[derive(Debug)]
+    struct TestLoader;
+
+    impl ResourceLoader for TestLoader {
+        fn fetch(&self, url: Url) -> Result<HttpResponse, Error> {
+            self.fetch_with_headers(url, Vec::new())
+        }
+
+        // ログインするとセッションのクッキーを設定し、クッキーがある場合だけユーザー名を表示する
+        fn fetch_with_headers(
+            &self,
+            url: Url,
+            headers: Vec<Header>,
+        ) -> Result<HttpResponse, Error> {
+            let url = url.url();
+            let cookie = headers
+                .iter()
+                .find(|header| header.name() == "Cookie")
+                .map(|header| header.value());
+            if url == "http://example.com:80/login.html" {
+                return HttpResponse::new(
+                    "HTTP/1.1 200 OK\nDate: Sun, 06 Nov 1994 08:49:37 GMT\nSet-Cookie: session=alice; Path=/\n\n<p>welcome</p>"
+                        .to_string(),
+                );
+            }
+            if url == "http://example.com:80/logout.html" {
+                return HttpResponse::new(
+                    "HTTP/1.1 200 OK\nSet-Cookie: session=; Max-Age=0; Path=/\n\n<p>bye</p>"
+                        .to_string(),
+                );
+            }
+            if url == "http://example.com:80/mypage.html" {
+                let user = match cookie.as_deref() {
+                    Some("session=alice") => "alice",
+                    _ => "guest",
+                };
+                return HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n<p>{}</p>", user));
+            }
+
+            // 古いURLは新しいURLにリダイレクトし、loop.htmlは自身にリダイレクトする
+            if url == "http://example.com:80/old.html" {
+                return HttpResponse::new(
+                    "HTTP/1.1 301 Moved Permanently\nLocation: next.html\n\n".to_string(),
+                );
+            }
+            if url == "http://example.com:80/loop.html" {
+                return HttpResponse::new(
+                    "HTTP/1.1 302 Found\nLocation: /loop.html\n\n".to_string(),
+                );
+            }
+
+            // 画面の高さよりも長いページ
+            if url == "http://example.com:80/long.html" {
+                let paragraphs: String = (0..100)
+                    .map(|i| alloc::format!("<p id=\"p{}\">{}</p>", i, i))
+                    .collect();
+                let body = alloc::format!("<html><head></head><body>{}</body></html>", paragraphs);
+                return HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", body));
+            }
+
+            let body = match url.as_str() {
+                "http://example.com:80/index.html" => {
+                    "<html><head></head><body><p>top</p><p><a href=\"next.html\">next</a></p></body></html>"
+                }
+                "http://example.com:80/next.html" => {
+                    "<html><head></head><body><a href=\"index.html\">back</a></body></html>"
+                }
+                "http://example.com:80/form.html" => {
+                    "<form action=\"search.html\"><input name=\"q\" value=\"saba\"><button name=\"go\" value=\"1\">Go</button></form><form method=\"post\" action=\"post.html\"><input name=\"msg\" value=\"hi there\"><input type=\"submit\" value=\"Send\"></form>"
+                }
+                "http://example.com:80/search.html?q=saba&go=1" => "<p>found</p>",
+                "http://example.com:80/refresh.html" => {
+                    "<html><head><meta http-equiv=\"refresh\" content=\"5; url=index.html\"></head><body>wait</body></html>"
+                }
+                "http://example.com:80/script.html" => {
+                    "<html><head></head><body><p onclick=\"location.href = 'next.html'\">go</p><script>setTimeout(() => window.location = 'index.html', 10);</script></body></html>"
+                }
+                "http://example.com:80/redirect.html" => {
+                    "<html><head><script>location.href = 'script.html';</script></head><body>redirect</body></html>"
+                }
+                _ => return Err(Error::Network(url)),
+            };
+            HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", body))
+        }
+
+        // フォームの内容をそのまま表示する
+        fn post(
+            &self,
+            url: Url,
+            headers: Vec<Header>,
+            body: String,
+        ) -> Result<HttpResponse, Error> {
+            let form_encoded = headers.iter().any(|header| {
+                header.name() == "Content-Type"
+                    && header.value() == "application/x-www-form-urlencoded"
+            });
+            if url.url() != "http://example.com:80/post.html" || !form_encoded {
+                return Err(Error::Network(url.url()));
+            }
+            HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n<p>{}</p>", body))
+        }
+    }
+
+    fn new_browser() -> Rc<RefCell<Browser>> {
+        let browser = Browser::new();
+        browser.borrow().set_resource_loader(Rc::new(TestLoader));
+        browser
+    }
+
+    // リンクをクリックすると、リンク先の文書が現在のページに読み込まれるか
+    #[test]
+    fn test_link_navigation() {
+        let browser = new_browser();
+        browser
+            .borrow_mut()
+            .navigate("http://example.com:80/index.html".to_string())
+            .expect("navigation should succeed");
+
+        // リンクの外をクリックしても移動しない
+        assert_eq!(Ok(false), browser.borrow_mut().on_click(0, 5));
+
+        assert_eq!(
+            Ok(true),
+            browser
+                .borrow_mut()
+                .on_click(0, CHAR_HEIGHT_WITH_PADDING + 5)
+        );
+        let page = browser.borrow().current_page();
+        assert_eq!(
+            "next.html",
+            page.borrow().url().expect("url should be set").path()
+        );
+        assert!(page.borrow().is_visited("http://example.com:80/next.html"));
+
+        // 取得に失敗した場合は、エラーの内容を説明する文書を表示して履歴に追加する
+        browser
+            .borrow_mut()
+            .navigate("http://example.com:80/missing.html".to_string())
+            .expect("navigation should succeed");
+        assert!(page.borrow().display_items().iter().any(|item| matches!(
+            item,
+            DisplayItem::Text { text, .. } if text == "Network error"
+        )));
+        assert!(!page
+            .borrow()
+            .is_visited("http://example.com:80/missing.html"));
+        assert_eq!(Ok(true), browser.borrow_mut().go_back());
+        assert_eq!(
+            "next.html",
+            page.borrow().url().expect("url should be set").path()
+        );
+
+        // URLが不正な場合も同じ
+        browser
+            .borrow_mut()
+            .navigate("ftp://example.com".to_string())
+            .expect("navigation should succeed");
+        assert!(page.borrow().display_items().iter().any(|item| matches!(
+            item,
+            DisplayItem::Text { text, .. } if text == "Invalid address"
+        )));
+        assert!(page.borrow().url().is_none());
+    }
+
+    // fragmentのあるURLでは、idが一致する要素までスクロールするか
+    // 同じ文書の中の移動では文書を取得し直さないか
+    #[test]
+    fn test_fragment_navigation() {
+        let browser = new_browser();
+        let mut browser = browser.borrow_mut();
+        browser
+            .navigate("http://example.com:80/long.html#p50".to_string())
+            .expect("navigation should succeed");
+        let page = browser.current_page();
+        let offset = page.borrow().scroll_offset();
+        assert!(offset > 0);
+        let frame = page.borrow().frame().expect("frame should exist");
+
+        browser
+            .navigate("http://example.com:80/long.html#p51".to_string())
+            .expect("navigation should succeed");
+        assert_eq!(
+            offset + CHAR_HEIGHT_WITH_PADDING,
+            page.borrow().scroll_offset()
+        );
+        assert!(Rc::ptr_eq(
+            &frame,
+            &page.borrow().frame().expect("frame should exist")
+        ));
+
+        // 戻ると、前のfragmentの位置に戻る
+        assert_eq!(Ok(true), browser.go_back());
+        assert_eq!(offset, page.borrow().scroll_offset());
+
+        // 見つからないfragmentではスクロールしない
+        browser
+            .navigate("http://example.com:80/long.html#missing".to_string())
+            .expect("navigation should succeed");
+        assert_eq!(offset, page.borrow().scroll_offset());
+
+        // パーセントエンコードされたfragment
+        browser
+            .navigate("http://example.com:80/long.html#p%35%31".to_string())
+            .expect("navigation should succeed");
+        assert_eq!(
+            offset + CHAR_HEIGHT_WITH_PADDING,
+            page.borrow().scroll_offset()
+        );
+
+        // 一致する要素がない"top"はページの先頭
+        browser
+            .navigate("http://example.com:80/long.html#top".to_string())
+            .expect("navigation should succeed");
+        assert_eq!(0, page.borrow().scroll_offset());
+    }
+
+    // 送信ボタンをクリックすると、GETではクエリに、POSTでは本文にフォームの内容を入れて送信するか
+    #[test]
+    fn test_form_submission() {
+        let browser = new_browser();
+        let mut browser = browser.borrow_mut();
+        let page = browser.current_page();
+        let position = |page: &Rc<RefCell<Page>>, label: &str| {
+            page.borrow()
+                .display_items()
+                .iter()
+                .find_map(|item| match item {
+                    DisplayItem::Text {
+                        text, layout_point, ..
+                    } if text == label => Some((layout_point.x() + 1, layout_point.y() + 1)),
+                    _ => None,
+                })
+                .expect("label should be painted")
+        };
+        let text = |page: &Rc<RefCell<Page>>| match page.borrow().display_items().first() {
+            Some(DisplayItem::Text { text, .. }) => text.clone(),
+            _ => String::new(),
+        };
+
+        browser
+            .navigate("http://example.com:80/form.html".to_string())
+            .expect("navigation should succeed");
+        // 入力欄をクリックしても送信しない
+        let (x, y) = position(&page, "saba");
+        assert_eq!(Ok(false), browser.on_click(x, y));
+
+        let (x, y) = position(&page, "Go");
+        assert_eq!(Ok(true), browser.on_click(x, y));
+        assert_eq!("found", text(&page));
+        assert_eq!(
+            "http://example.com:80/search.html?q=saba&go=1",
+            browser.history()[1].url()
+        );
+
+        assert_eq!(Ok(true), browser.go_back());
+        let (x, y) = position(&page, "Send");
+        assert_eq!(Ok(true), browser.on_click(x, y));
+        assert_eq!("msg=hi+there", text(&page));
+        assert_eq!(
+            "http://example.com:80/post.html",
+            browser.history()[1].url()
+        );
+    }
+
+    // Tabキーでフォーカスを移したリンクや送信ボタンを、Enterキーで実行できるか
+    #[test]
+    fn test_keyboard_navigation() {
+        let browser = new_browser();
+        let mut browser = browser.borrow_mut();
+        let page = browser.current_page();
+        browser
+            .navigate("http://example.com:80/index.html".to_string())
+            .expect("navigation should succeed");
+        assert_eq!(Ok(false), browser.activate_focused());
+
+        assert!(page.borrow_mut().focus_next(false));
+        assert_eq!(Ok(true), browser.activate_focused());
+        assert_eq!(
+            "next.html",
+            page.borrow().url().expect("url should be set").path()
+        );
+
+        browser
+            .navigate("http://example.com:80/form.html".to_string())
+            .expect("navigation should succeed");
+        assert!(page.borrow_mut().focus_next(false));
+        assert!(page.borrow_mut().focus_next(false));
+        assert_eq!(Ok(true), browser.activate_focused());
+        assert_eq!(
+            "http://example.com:80/search.html?q=saba&go=1",
+            page.borrow().url().expect("url should be set").url()
+        );
+    }
+
+    // 3xxのリダイレクトを辿り、リダイレクト先のURLを履歴に追加するか
+    // リダイレクトがループしている場合は、エラーの内容を説明する文書を表示するか
+    #[test]
+    fn test_redirect() {
+        let browser = new_browser();
+        let mut browser = browser.borrow_mut();
+        let page = browser.current_page();
+
+        browser
+            .navigate("http://example.com:80/old.html#top".to_string())
+            .expect("navigation should succeed");
+        assert_eq!(
+            "http://example.com:80/next.html#top",
+            page.borrow().url().expect("url should be set").url()
+        );
+        assert_eq!(
+            "http://example.com:80/next.html#top",
+            browser.history()[0].url()
+        );
+
+        browser
+            .navigate("http://example.com:80/loop.html".to_string())
+            .expect("navigation should succeed");
+        assert!(page.borrow().display_items().iter().any(|item| matches!(
+            item,
+            DisplayItem::Text { text, .. } if text == "Too many redirects (more than 20)"
+        )));
+    }
+
+    // ネットワークにアクセスしないschemeのURLでは、schemeごとに文書を作るか
+    #[test]
+    fn test_special_schemes() {
+        let browser = new_browser();
+        let mut browser = browser.borrow_mut();
+        let page = browser.current_page();
+        let has_text = |page: &Rc<RefCell<Page>>, expected: &str| {
+            page.borrow().display_items().iter().any(
+                |item| matches!(item, DisplayItem::Text { text, .. } if text.contains(expected)),
+            )
+        };
+
+        browser
+            .navigate("data:text/html,%3Cp%3Ehello%3C/p%3E".to_string())
+            .expect("navigation should succeed");
+        assert!(has_text(&page, "hello"));
+
+        browser
+            .navigate("view-source:http://example.com:80/index.html".to_string())
+            .expect("navigation should succeed");
+        assert!(has_text(&page, "<html>"));
+        assert_eq!(
+            "view-source:http://example.com:80/index.html",
+            browser.history()[1].url()
+        );
+
+        browser
+            .navigate("about:blank".to_string())
+            .expect("navigation should succeed");
+        assert!(page.borrow().display_items().is_empty());
+
+        browser
+            .navigate("about:unknown".to_string())
+            .expect("navigation should succeed");
+        assert!(has_text(&page, "Unknown about page: unknown"));
+    }
+
+    // <meta http-equiv="refresh">で予定した時刻になると移動し、現在の履歴の項目を置き換えるか
+    #[test]
+    fn test_meta_refresh() {
+        let browser = new_browser();
+        let mut browser = browser.borrow_mut();
+        assert_eq!(Ok(false), browser.run_pending_tasks(100_000));
+        browser
+            .navigate("http://example.com:80/refresh.html".to_string())
+            .expect("navigation should succeed");
+        assert_eq!(
+            Some((105_000, "http://example.com:80/index.html".to_string())),
+            browser.scheduled_navigation()
+        );
+
+        assert_eq!(Ok(false), browser.run_scheduled_navigation());
+        assert_eq!(Ok(false), browser.run_pending_tasks(104_999));
+        assert_eq!(Ok(false), browser.run_scheduled_navigation());
+        assert_eq!(Ok(false), browser.run_pending_tasks(105_000));
+        assert_eq!(Ok(true), browser.run_scheduled_navigation());
+        assert_eq!(
+            "index.html",
+            browser
+                .current_page()
+                .borrow()
+                .url()
+                .expect("url should be set")
+                .path()
+        );
+        assert_eq!(1, browser.history().len());
+        assert_eq!(None, browser.scheduled_navigation());
+    }
+
+    // スクリプトがlocation.hrefに代入すると移動するか
+    // 読み込み中の代入は現在の履歴の項目を置き換え、クリックやタイマーでの代入は履歴に追加する
+    #[test]
+    fn test_script_navigation() {
+        let browser = new_browser();
+        let mut browser = browser.borrow_mut();
+        let path = |browser: &Browser| {
+            browser
+                .current_page()
+                .borrow()
+                .url()
+                .expect("url should be set")
+                .path()
+        };
+        browser
+            .navigate("http://example.com:80/redirect.html".to_string())
+            .expect("navigation should succeed");
+        assert_eq!(
+            Some((0, "http://example.com:80/script.html".to_string())),
+            browser.scheduled_navigation()
+        );
+        assert_eq!(Ok(true), browser.run_scheduled_navigation());
+        assert_eq!("script.html", path(&browser));
+        assert_eq!(1, browser.history().len());
+
+        assert_eq!(Ok(true), browser.on_click(0, 5));
+        assert_eq!("next.html", path(&browser));
+        assert_eq!(2, browser.history().len());
+
+        browser.go_back().expect("going back should succeed");
+        assert_eq!(Ok(false), browser.run_pending_tasks(9));
+        assert_eq!("script.html", path(&browser));
+        assert_eq!(Ok(true), browser.run_pending_tasks(10));
+        assert_eq!("index.html", path(&browser));
+    }
+
+    // 移動すると読み込みの各段階のイベントが順に記録されるか
+    // 文書の中の移動では、文書を読み込み直さないのでイベントはないか
+    #[test]
+    fn test_load_events() {
+        let browser = new_browser();
+        let mut browser = browser.borrow_mut();
+        browser
+            .navigate("http://example.com:80/old.html".to_string())
+            .expect("navigation should succeed");
+        assert_eq!(
+            alloc::vec![
+                LoadEvent::Started("http://example.com:80/old.html".to_string()),
+                LoadEvent::DomReady,
+                LoadEvent::SubresourcesLoaded,
+                LoadEvent::Finished,
+            ],
+            browser.take_load_events()
+        );
+        assert!(!browser.current_page().borrow().is_loading());
+
+        browser
+            .navigate("http://example.com:80/next.html#top".to_string())
+            .expect("navigation should succeed");
+        assert!(browser.take_load_events().is_empty());
+
+        // 取得に失敗した場合も、エラーの文書を読み込み終える
+        browser
+            .navigate("http://example.com:80/missing.html".to_string())
+            .expect("navigation should succeed");
+        assert_eq!(
+            Some(&LoadEvent::Finished),
+            browser.take_load_events().last()
+        );
+    }
+
+    // about:bookmarksにブックマークの一覧が表示され、リンクをクリックすると移動できるか
+    // 一覧を表示している間にブックマークを削除すると、一覧が作り直されるか
+    #[test]
+    fn test_bookmarks() {
+        let browser = new_browser();
+        let mut browser = browser.borrow_mut();
+        assert!(!browser.bookmark_current_page());
+        browser
+            .navigate("http://example.com:80/index.html".to_string())
+            .expect("navigation should succeed");
+        assert!(browser.bookmark_current_page());
+        browser.add_bookmark(
+            "Next".to_string(),
+            "http://example.com:80/next.html".to_string(),
+        );
+        assert_eq!(2, browser.bookmarks().len());
+
+        browser.open_bookmarks().expect("navigation should succeed");
+        let page = browser.current_page();
+        let link_point = |text: &str| {
+            page.borrow()
+                .display_items()
+                .into_iter()
+                .find_map(|item| match item {
+                    DisplayItem::Text {
+                        text: t,
+                        layout_point,
+                        ..
+                    } if t == text => Some(layout_point),
+                    _ => None,
+                })
+        };
+        assert!(link_point("http://example.com:80/index.html").is_some());
+        let next = link_point("Next").expect("bookmark should be shown");
+        assert_eq!(BOOKMARKS_URL, browser.history()[1].url());
+
+        assert_eq!(Ok(true), browser.on_click(next.x() + 1, next.y() + 1));
+        assert_eq!(
+            "next.html",
+            page.borrow().url().expect("url should be set").path()
+        );
+
+        assert_eq!(Ok(true), browser.go_back());
+        assert!(browser.remove_bookmark("http://example.com:80/next.html"));
+        assert!(link_point("Next").is_none());
+        assert!(!browser.remove_bookmark("http://example.com:80/next.html"));
+    }
+
+    // ログインで受け取ったクッキーが、ページを移動しても送られるか
+    #[test]
+    fn test_cookies() {
+        let browser = new_browser();
+        let mut browser = browser.borrow_mut();
+        let page = browser.current_page();
+        let text = |page: &Rc<RefCell<Page>>| match page.borrow().display_items().first() {
+            Some(DisplayItem::Text { text, .. }) => text.clone(),
+            _ => String::new(),
+        };
+
+        browser
+            .navigate("http://example.com:80/mypage.html".to_string())
+            .expect("navigation should succeed");
+        assert_eq!("guest", text(&page));
+
+        browser
+            .navigate("http://example.com:80/login.html".to_string())
+            .expect("navigation should succeed");
+        // Dateヘッダの日時は一度だけ取り出せる
+        assert_eq!(Some(784_111_777), browser.take_server_time());
+        assert_eq!(None, browser.take_server_time());
+        browser
+            .navigate("http://example.com:80/mypage.html".to_string())
+            .expect("navigation should succeed");
+        assert_eq!("alice", text(&page));
+        assert_eq!(1, browser.cookie_jar().borrow().cookies().len());
+
+        // 期限切れのクッキーで上書きされると、送らなくなる
+        browser
+            .navigate("http://example.com:80/logout.html".to_string())
+            .expect("navigation should succeed");
+        browser
+            .navigate("http://example.com:80/mypage.html".to_string())
+            .expect("navigation should succeed");
+        assert_eq!("guest", text(&page));
+    }
+
+    // 戻る・進むで履歴をたどり、前回のスクロール位置から表示されるか
+    #[test]
+    fn test_history() {
+        let browser = new_browser();
+        let mut browser = browser.borrow_mut();
+        assert!(!browser.can_go_back());
+        assert_eq!(Ok(false), browser.go_back());
+
+        browser
+            .navigate("http://example.com:80/long.html".to_string())
+            .expect("navigation should succeed");
+        browser.current_page().borrow_mut().scroll_by(100);
+        browser
+            .navigate("http://example.com:80/next.html".to_string())
+            .expect("navigation should succeed");
+        assert!(browser.can_go_back());
+        assert!(!browser.can_go_forward());
+
+        assert_eq!(Ok(true), browser.go_back());
+        let page = browser.current_page();
+        assert_eq!(
+            "long.html",
+            page.borrow().url().expect("url should be set").path()
+        );
+        assert_eq!(100, page.borrow().scroll_offset());
+        assert!(browser.can_go_forward());
+
+        assert_eq!(Ok(true), browser.go_forward());
+        assert_eq!(
+            "next.html",
+            page.borrow().url().expect("url should be set").path()
+        );
+        assert_eq!(0, page.borrow().scroll_offset());
+
+        // 戻った後に別のページに移動すると、進む先の履歴は捨てられる
+        browser.go_back().expect("go back should succeed");
+        browser
+            .navigate("http://example.com:80/index.html".to_string())
+            .expect("navigation should succeed");
+        assert!(!browser.can_go_forward());
+        let urls: Vec<String> = browser.history().iter().map(|e| e.url()).collect();
+        assert_eq!(
+            alloc::vec![
+                "http://example.com:80/long.html".to_string(),
+                "http://example.com:80/index.html".to_string(),
+            ],
+            urls
+        );
+    }
 }
@@ -19,6 +19,18 @@ pub static TITLE_BAR_HEIGHT: i64 = 24;
 
 pub static TOOLBAR_HEIGHT: i64 = 26;
 
+// ツールバーの右端にあるブックマークのボタンの幅
+pub static BOOKMARK_BUTTON_WIDTH: i64 = 80;
+
+// ブックマークのボタンの左にある再読み込みのボタンの幅
+pub static RELOAD_BUTTON_WIDTH: i64 = 56;
+
+// 再読み込みのボタンの左に並ぶ、戻るボタンと進むボタンのそれぞれの幅
+pub static HISTORY_BUTTON_WIDTH: i64 = 24;
+
+// コンテンツエリアの右端にあるスクロールバーの幅
+pub static SCROLLBAR_WIDTH: i64 = 12;
+
 pub static CONTENT_AREA_WIDTH: i64 = WINDOW_WIDTH - WINDOW_PADDING * 2;
 pub static CONTENT_AREA_HEIGHT: i64 =
     WINDOW_HEIGHT - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT - WINDOW_PADDING * 2;
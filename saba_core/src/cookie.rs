@@ -0,0 +1,334 @@
+use crate::http::parse_http_date;
+use crate::url::Url;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+// 1つのクッキー
+// Set-Cookieヘッダを受け取ったURLのホストとパスをもとに、送り先の範囲を決める
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    // 送り先のホスト
+    domain: String,
+    // trueの場合はdomainと完全に一致するホストだけに送る（Domain属性がない場合）
+    host_only: bool,
+    // 送り先のパス（先頭が/）
+    path: String,
+    // 有効期限（1970年1月1日からの秒数）
+    // Noneの場合はブラウザを閉じるまで有効なセッションクッキー
+    expires: Option<u64>,
+    // trueの場合はHTTPのリクエストにだけ付けて、スクリプトには見せない（HttpOnly属性）
+    http_only: bool,
+    // trueの場合はhttpsのoriginへのリクエストにだけ付ける（Secure属性）
+    secure: bool,
+}
+
+impl Cookie {
+    // Set-Cookieヘッダの値をパースする
+    // urlはヘッダを受け取ったリソースのURL、nowは現在の時刻（1970年1月1日からの秒数）
+    // Secure属性のあるクッキーは、httpsのoriginから受け取った場合だけ受け付ける
+    pub fn parse(set_cookie: &str, url: &Url, now: u64) -> Option<Self> {
+        let mut parts = set_cookie.split(';');
+        let (name, value) = parts.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut cookie = Self {
+            name: name.to_string(),
+            value: value.trim().to_string(),
+            domain: url.host().to_lowercase(),
+            host_only: true,
+            path: default_path(url),
+            expires: None,
+            http_only: false,
+            secure: false,
+        };
+        let mut max_age = None;
+        for attribute in parts {
+            let (key, value) = match attribute.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (attribute.trim(), ""),
+            };
+            if key.eq_ignore_ascii_case("domain") {
+                let domain = value.trim_start_matches('.').to_lowercase();
+                if domain.is_empty() {
+                    continue;
+                }
+                // 他のドメインのクッキーは設定できない
+                if !domain_matches(&cookie.domain, &domain) {
+                    return None;
+                }
+                cookie.domain = domain;
+                cookie.host_only = false;
+            } else if key.eq_ignore_ascii_case("path") {
+                if value.starts_with('/') {
+                    cookie.path = value.to_string();
+                }
+            } else if key.eq_ignore_ascii_case("max-age") {
+                if let Ok(seconds) = value.parse::<i64>() {
+                    max_age = Some(seconds);
+                }
+            } else if key.eq_ignore_ascii_case("expires") {
+                if let Some(expires) = parse_http_date(value) {
+                    cookie.expires = Some(expires);
+                }
+            } else if key.eq_ignore_ascii_case("httponly") {
+                cookie.http_only = true;
+            } else if key.eq_ignore_ascii_case("secure") {
+                if !url.origin().is_secure() {
+                    return None;
+                }
+                cookie.secure = true;
+            }
+        }
+        // Max-AgeはExpiresより優先される
+        // 0以下の場合はすぐに期限切れになる
+        if let Some(seconds) = max_age {
+            cookie.expires = Some(if seconds <= 0 {
+                0
+            } else {
+                now.saturating_add(seconds as u64)
+            });
+        }
+
+        Some(cookie)
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn value(&self) -> String {
+        self.value.clone()
+    }
+
+    pub fn domain(&self) -> String {
+        self.domain.clone()
+    }
+
+    pub fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    pub fn expires(&self) -> Option<u64> {
+        self.expires
+    }
+
+    pub fn http_only(&self) -> bool {
+        self.http_only
+    }
+
+    pub fn secure(&self) -> bool {
+        self.secure
+    }
+
+    // nowの時点で期限切れかどうか
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    // urlへのリクエストに付けて送るクッキーかどうか
+    pub fn matches(&self, url: &Url) -> bool {
+        let host = url.host().to_lowercase();
+        let host_matches = if self.host_only {
+            host == self.domain
+        } else {
+            domain_matches(&host, &self.domain)
+        };
+        host_matches
+            && path_matches(&request_path(url), &self.path)
+            && (!self.secure || url.origin().is_secure())
+    }
+
+    // 同じ名前・ドメイン・パスのクッキーは上書きする
+    fn is_same(&self, other: &Cookie) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+}
+
+// ブラウザが持つクッキーの保存場所
+// ページの移動をまたいでクッキーを保持し、リクエストのたびに送るクッキーを選ぶ
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CookieJar {
+    // 設定された順に並んだクッキー
+    cookies: Vec<Cookie>,
+    // 有効期限の判定に使う現在の時刻（1970年1月1日からの秒数）
+    // 時計を持たない環境では0のままになるので、すでに期限切れのクッキーの削除だけが働く
+    current_time: u64,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self {
+            cookies: Vec::new(),
+            current_time: 0,
+        }
+    }
+
+    // 有効期限の判定に使う現在の時刻を設定し、期限切れのクッキーを取り除く
+    pub fn set_current_time(&mut self, now: u64) {
+        self.current_time = now;
+        self.cookies.retain(|cookie| !cookie.is_expired(now));
+    }
+
+    pub fn current_time(&self) -> u64 {
+        self.current_time
+    }
+
+    pub fn cookies(&self) -> Vec<Cookie> {
+        self.cookies.clone()
+    }
+
+    // urlから受け取ったSet-Cookieヘッダの値を保存する
+    // 期限切れのクッキーは、同じクッキーを削除する指示として扱う
+    pub fn set_cookie(&mut self, url: &Url, set_cookie: &str) {
+        let cookie = match Cookie::parse(set_cookie, url, self.current_time) {
+            Some(cookie) => cookie,
+            None => return,
+        };
+        self.cookies.retain(|c| !c.is_same(&cookie));
+        if !cookie.is_expired(self.current_time) {
+            self.cookies.push(cookie);
+        }
+    }
+
+    // urlへのリクエストに付けるCookieヘッダの値
+    // パスが長い（より詳しい）クッキーから順に並べる
+    // 送るクッキーがない場合はNone
+    pub fn cookie_header(&self, url: &Url) -> Option<String> {
+        let mut cookies: Vec<&Cookie> = self
+            .cookies
+            .iter()
+            .filter(|cookie| !cookie.is_expired(self.current_time) && cookie.matches(url))
+            .collect();
+        if cookies.is_empty() {
+            return None;
+        }
+        cookies.sort_by_key(|cookie| core::cmp::Reverse(cookie.path.len()));
+
+        let pairs: Vec<String> = cookies
+            .iter()
+            .map(|cookie| alloc::format!("{}={}", cookie.name, cookie.value))
+            .collect();
+        Some(pairs.join("; "))
+    }
+}
+
+// hostがdomain自身か、そのサブドメインかどうか
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+// リクエストのパスがクッキーのパスの下にあるかどうか
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+// リクエストのパス（Urlのpathは先頭の/を含まない）
+fn request_path(url: &Url) -> String {
+    alloc::format!("/{}", url.raw_path())
+}
+
+// Path属性がない場合のパス
+// リクエストのパスの最後の/より前の部分（ディレクトリ）
+fn default_path(url: &Url) -> String {
+    let path = request_path(url);
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(index) => path[..index].to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::new(s.to_string())
+            .parse()
+            .expect("url should be valid")
+    }
+
+    // Set-Cookieヘッダの属性のパース
+    #[test]
+    fn test_parse() {
+        let cookie = Cookie::parse(
+            "id=a3fWa; Domain=.Example.com; Path=/docs; Max-Age=60; HttpOnly",
+            &url("http://www.example.com/login"),
+            100,
+        )
+        .expect("cookie should be parsed");
+        assert_eq!("id", cookie.name());
+        assert_eq!("a3fWa", cookie.value());
+        assert_eq!("example.com", cookie.domain());
+        assert_eq!("/docs", cookie.path());
+        assert_eq!(Some(160), cookie.expires());
+        assert!(cookie.http_only());
+
+        // Path属性がない場合はリクエストのディレクトリ
+        let cookie = Cookie::parse("a=b", &url("http://example.com/dir/page.html"), 0)
+            .expect("cookie should be parsed");
+        assert_eq!("/dir", cookie.path());
+        assert_eq!(None, cookie.expires());
+        assert!(!cookie.http_only());
+
+        // 他のドメインのクッキーと、httpで受け取ったSecureなクッキーは受け付けない
+        assert!(Cookie::parse("a=b; Domain=other.com", &url("http://example.com"), 0).is_none());
+        assert!(Cookie::parse("a=b; Secure", &url("http://example.com"), 0).is_none());
+        assert!(Cookie::parse("novalue", &url("http://example.com"), 0).is_none());
+    }
+
+    // Secureなクッキーはhttpsのoriginにだけ送る
+    #[test]
+    fn test_secure() {
+        let mut jar = CookieJar::new();
+        jar.set_cookie(&url("https://example.com/"), "id=1; Secure");
+        assert!(jar.cookies()[0].secure());
+        assert_eq!(
+            Some("id=1".to_string()),
+            jar.cookie_header(&url("https://example.com/a"))
+        );
+        assert_eq!(None, jar.cookie_header(&url("http://example.com/a")));
+    }
+
+    // 送り先のホストとパスに一致するクッキーを選び、期限切れのクッキーを取り除く
+    #[test]
+    fn test_cookie_jar() {
+        let mut jar = CookieJar::new();
+        jar.set_current_time(1000);
+        let login = url("http://example.com/account/login");
+        jar.set_cookie(&login, "session=abc; Path=/");
+        jar.set_cookie(&login, "theme=dark");
+        jar.set_cookie(&login, "token=xyz; Max-Age=10");
+
+        assert_eq!(
+            Some("theme=dark; token=xyz; session=abc".to_string()),
+            jar.cookie_header(&url("http://example.com/account/settings"))
+        );
+        assert_eq!(
+            Some("session=abc".to_string()),
+            jar.cookie_header(&url("http://example.com/accounts"))
+        );
+        // Domain属性がないクッキーはサブドメインに送らない
+        assert_eq!(None, jar.cookie_header(&url("http://www.example.com/")));
+
+        // 値の上書きと、期限切れによる削除
+        jar.set_cookie(&login, "session=def; Path=/");
+        jar.set_cookie(&login, "theme=; Expires=Thu, 01 Jan 1970 00:00:00 GMT");
+        jar.set_current_time(1010);
+        assert_eq!(
+            Some("session=def".to_string()),
+            jar.cookie_header(&url("http://example.com/account/settings"))
+        );
+        assert_eq!(1, jar.cookies().len());
+    }
+}
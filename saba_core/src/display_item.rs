@@ -0,0 +1,246 @@
+use crate::renderer::image::bitmap::Bitmap;
+use crate::renderer::layout::computed_style::Color;
+use crate::renderer::layout::computed_style::EdgeColors;
+use crate::renderer::layout::computed_style::EdgeSizes;
+use crate::renderer::layout::computed_style::FontSize;
+use crate::renderer::layout::computed_style::FontStyle;
+use crate::renderer::layout::computed_style::FontWeight;
+use crate::renderer::layout::computed_style::TextDecoration;
+use crate::renderer::layout::font_metrics::FontMetrics;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutRect;
+use crate::renderer::layout::layout_object::LayoutSize;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// 描画する内容を表す要素
+// レイアウトツリーから作成し、UIはこのリストを先頭から順に描画する
+// リストは重なりの順番（z-index）に並んでいて、後ろの要素ほど手前に描画される
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisplayItem {
+    // 塗りつぶした四角形（CSSのbackground-color）
+    // 位置と大きさはパディングの外側の矩形
+    // radiusは角の丸みの半径で、0の場合は角が丸くない
+    Rect {
+        layout_point: LayoutPoint,
+        size: LayoutSize,
+        color: Color,
+        radius: i64,
+    },
+    // 1行分のテキスト
+    Text {
+        text: String,
+        layout_point: LayoutPoint,
+        // UIが見出しやリンクを区別して描画できるように、フォントと文字の装飾の情報を持つ
+        font_size: FontSize,
+        font_weight: FontWeight,
+        font_style: FontStyle,
+        font_family: String,
+        color: Color,
+        text_decoration: TextDecoration,
+    },
+    // 四角形の枠線（CSSのborder）
+    // 位置と大きさはボーダーの外側の矩形で、辺ごとに太さと色を持つ
+    // radiusは外側の角の丸みの半径
+    Border {
+        layout_point: LayoutPoint,
+        size: LayoutSize,
+        widths: EdgeSizes,
+        colors: EdgeColors,
+        radius: i64,
+    },
+    // layout_pointからend_pointまでの直線（下線など）
+    Line {
+        layout_point: LayoutPoint,
+        end_point: LayoutPoint,
+        width: i64,
+        color: Color,
+    },
+    // デコードした画像（img要素）
+    // 位置と大きさはコンテンツの矩形で、UIは画像をこの大きさに拡大・縮小して描画する
+    Image {
+        layout_point: LayoutPoint,
+        size: LayoutSize,
+        bitmap: Rc<Bitmap>,
+    },
+    // これ以降の描画を矩形の中に制限する（overflow: hiddenなど）
+    // UIはクリップをスタックに積み、重なっている全ての矩形の内側だけに描画する
+    PushClip(LayoutRect),
+    // 直前のPushClipで積んだクリップを取り除く
+    PopClip,
+    // これ以降の描画を1つのグループにまとめ、不透明度（0.0から1.0）を掛けて合成する（CSSのopacity）
+    // UIは不透明度をスタックに積み、重なっている全ての不透明度を掛けた値で描画する
+    PushOpacity(f64),
+    // 直前のPushOpacityで積んだ不透明度を取り除く
+    PopOpacity,
+}
+
+impl DisplayItem {
+    // 描画する位置をずらしたもの
+    // スクロールしている時に、ページ上の位置を画面上の位置にするために使う
+    pub fn translated(self, dx: i64, dy: i64) -> Self {
+        match self {
+            DisplayItem::Rect {
+                layout_point,
+                size,
+                color,
+                radius,
+            } => DisplayItem::Rect {
+                layout_point: LayoutPoint::new(layout_point.x() + dx, layout_point.y() + dy),
+                size,
+                color,
+                radius,
+            },
+            DisplayItem::Text {
+                text,
+                layout_point,
+                font_size,
+                font_weight,
+                font_style,
+                font_family,
+                color,
+                text_decoration,
+            } => DisplayItem::Text {
+                text,
+                layout_point: LayoutPoint::new(layout_point.x() + dx, layout_point.y() + dy),
+                font_size,
+                font_weight,
+                font_style,
+                font_family,
+                color,
+                text_decoration,
+            },
+            DisplayItem::Border {
+                layout_point,
+                size,
+                widths,
+                colors,
+                radius,
+            } => DisplayItem::Border {
+                layout_point: LayoutPoint::new(layout_point.x() + dx, layout_point.y() + dy),
+                size,
+                widths,
+                colors,
+                radius,
+            },
+            DisplayItem::Line {
+                layout_point,
+                end_point,
+                width,
+                color,
+            } => DisplayItem::Line {
+                layout_point: LayoutPoint::new(layout_point.x() + dx, layout_point.y() + dy),
+                end_point: LayoutPoint::new(end_point.x() + dx, end_point.y() + dy),
+                width,
+                color,
+            },
+            DisplayItem::Image {
+                layout_point,
+                size,
+                bitmap,
+            } => DisplayItem::Image {
+                layout_point: LayoutPoint::new(layout_point.x() + dx, layout_point.y() + dy),
+                size,
+                bitmap,
+            },
+            DisplayItem::PushClip(rect) => DisplayItem::PushClip(LayoutRect::new(
+                LayoutPoint::new(rect.point().x() + dx, rect.point().y() + dy),
+                rect.size(),
+            )),
+            DisplayItem::PopClip => DisplayItem::PopClip,
+            DisplayItem::PushOpacity(opacity) => DisplayItem::PushOpacity(opacity),
+            DisplayItem::PopOpacity => DisplayItem::PopOpacity,
+        }
+    }
+
+    // 描画される領域を含む矩形
+    // 線は太さの分だけ右と下に広げる
+    // クリップを取り除く操作と不透明度の操作は領域を持たないのでNone
+    pub fn bounds(&self, font_metrics: &dyn FontMetrics) -> Option<LayoutRect> {
+        let rect = match self {
+            DisplayItem::Text {
+                text,
+                layout_point,
+                font_size,
+                ..
+            } => LayoutRect::new(
+                *layout_point,
+                LayoutSize::new(
+                    font_metrics.text_width(text, *font_size),
+                    font_metrics.line_height(*font_size),
+                ),
+            ),
+            DisplayItem::Rect {
+                layout_point, size, ..
+            }
+            | DisplayItem::Border {
+                layout_point, size, ..
+            }
+            | DisplayItem::Image {
+                layout_point, size, ..
+            } => LayoutRect::new(*layout_point, *size),
+            DisplayItem::Line {
+                layout_point,
+                end_point,
+                width,
+                ..
+            } => LayoutRect::new(
+                LayoutPoint::new(
+                    layout_point.x().min(end_point.x()),
+                    layout_point.y().min(end_point.y()),
+                ),
+                LayoutSize::new(
+                    (end_point.x() - layout_point.x()).abs() + width,
+                    (end_point.y() - layout_point.y()).abs() + width,
+                ),
+            ),
+            DisplayItem::PushClip(rect) => *rect,
+            DisplayItem::PopClip | DisplayItem::PushOpacity(_) | DisplayItem::PopOpacity => {
+                return None
+            }
+        };
+        Some(rect)
+    }
+
+    // 描画する位置
+    // クリップを取り除く操作と不透明度の操作は位置を持たないのでNone
+    pub fn layout_point(&self) -> Option<LayoutPoint> {
+        match self {
+            DisplayItem::Rect { layout_point, .. }
+            | DisplayItem::Text { layout_point, .. }
+            | DisplayItem::Border { layout_point, .. }
+            | DisplayItem::Line { layout_point, .. }
+            | DisplayItem::Image { layout_point, .. } => Some(*layout_point),
+            DisplayItem::PushClip(rect) => Some(rect.point()),
+            DisplayItem::PopClip | DisplayItem::PushOpacity(_) | DisplayItem::PopOpacity => None,
+        }
+    }
+}
+
+// 前のフレームの描画内容oldと新しい描画内容newのうち、変わった描画内容
+// 新しく描画するものと、前のフレームから消えたものの両方を含む
+// リストの同じ位置にあるものどうしを比べるので、途中で描画内容が増えたり減ったりすると、
+// それ以降の描画内容は全て変わったものとして扱う
+pub fn changed_display_items(old: &[DisplayItem], new: &[DisplayItem]) -> Vec<DisplayItem> {
+    let common = old.len().min(new.len());
+    let mut changed = Vec::new();
+    for (old_item, new_item) in old.iter().zip(new) {
+        if old_item != new_item {
+            changed.push(new_item.clone());
+            changed.push(old_item.clone());
+        }
+    }
+    changed.extend(new[common..].iter().cloned());
+    changed.extend(old[common..].iter().cloned());
+    changed
+}
+
+// 描画内容が描画される領域を全て含む矩形
+// 領域を持つ描画内容がない場合はNone
+pub fn bounding_rect(items: &[DisplayItem], font_metrics: &dyn FontMetrics) -> Option<LayoutRect> {
+    items
+        .iter()
+        .filter_map(|item| item.bounds(font_metrics))
+        .reduce(|rect, bounds| rect.union(&bounds))
+}
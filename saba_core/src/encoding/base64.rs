@@ -0,0 +1,95 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// バイト列をBase64の文字列にする（Basic認証のAuthorizationヘッダに使う）
+// 3バイトごとに6ビットずつ4文字にし、足りない分は=で埋める
+// https://www.rfc-editor.org/rfc/rfc4648#section-4
+pub fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (n >> (18 - 6 * i)) & 0x3F;
+                encoded.push(ALPHABET[index as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+// Base64の文字列をバイト列に戻す（data:のURLに使う）
+// 空白は読み飛ばし、最後の=は省略してもよい
+// Base64として不正な場合はNone
+// https://infra.spec.whatwg.org/#forgiving-base64-decode
+pub fn decode(encoded: &[u8]) -> Option<Vec<u8>> {
+    let mut values: Vec<u8> = encoded
+        .iter()
+        .filter(|b| !b.is_ascii_whitespace())
+        .copied()
+        .collect();
+    if values.len().is_multiple_of(4) {
+        for _ in 0..2 {
+            if values.last() == Some(&b'=') {
+                values.pop();
+            }
+        }
+    }
+    if values.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut decoded = Vec::new();
+    let mut n: u32 = 0;
+    for (i, b) in values.iter().enumerate() {
+        let value = ALPHABET.iter().position(|a| a == b)?;
+        n = n << 6 | value as u32;
+        if i % 4 == 3 {
+            decoded.extend_from_slice(&[(n >> 16) as u8, (n >> 8) as u8, n as u8]);
+            n = 0;
+        }
+    }
+    match values.len() % 4 {
+        2 => decoded.push((n >> 4) as u8),
+        3 => decoded.extend_from_slice(&[(n >> 10) as u8, (n >> 2) as u8]),
+        _ => {}
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!("", encode(b""));
+        assert_eq!("Zg==", encode(b"f"));
+        assert_eq!("Zm8=", encode(b"fo"));
+        assert_eq!("Zm9v", encode(b"foo"));
+        assert_eq!(
+            "QWxhZGRpbjpvcGVuIHNlc2FtZQ==",
+            encode(b"Aladdin:open sesame")
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(Some(b"".to_vec()), decode(b""));
+        assert_eq!(Some(b"f".to_vec()), decode(b"Zg=="));
+        assert_eq!(Some(b"fo".to_vec()), decode(b"Zm8"));
+        assert_eq!(Some(b"foo".to_vec()), decode(b"Zm 9v\n"));
+        assert_eq!(None, decode(b"Z"));
+        assert_eq!(None, decode(b"Zm9v!"));
+        assert_eq!(None, decode(b"Zg=a"));
+    }
+}
@@ -0,0 +1,336 @@
+pub mod base64;
+mod jis0208;
+pub mod percent;
+
+use alloc::string::{String, ToString};
+use jis0208::{IBM_EXTENSIONS, JIS0208};
+
+// IBM拡張文字の表の最初のポインタ
+const IBM_EXTENSIONS_START: usize = 10716;
+
+// ボディのバイト列を文字列にする時に使う文字エンコーディング
+// https://encoding.spec.whatwg.org/#names-and-labels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    ShiftJis,
+    EucJp,
+    // ISO-8859-1やUS-ASCIIと指定された場合もこれを使う
+    Windows1252,
+}
+
+impl Encoding {
+    // Content-Typeのcharsetや<meta charset>に書かれた名前から探す
+    // 大文字と小文字は区別せず、サポートしていない名前の場合はNone
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = label
+            .trim_matches(|c: char| c.is_ascii_whitespace())
+            .to_ascii_lowercase();
+        match label.as_str() {
+            "unicode-1-1-utf-8" | "unicode11utf8" | "unicode20utf8" | "utf-8" | "utf8"
+            | "x-unicode20utf8" => Some(Self::Utf8),
+            "csshiftjis" | "ms932" | "ms_kanji" | "shift-jis" | "shift_jis" | "sjis"
+            | "windows-31j" | "x-sjis" => Some(Self::ShiftJis),
+            "cseucpkdfmtjapanese" | "euc-jp" | "x-euc-jp" => Some(Self::EucJp),
+            "ansi_x3.4-1968" | "ascii" | "cp1252" | "cp819" | "csisolatin1" | "ibm819"
+            | "iso-8859-1" | "iso-ir-100" | "iso8859-1" | "iso88591" | "iso_8859-1"
+            | "iso_8859-1:1987" | "l1" | "latin1" | "us-ascii" | "windows-1252" | "x-cp1252" => {
+                Some(Self::Windows1252)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::ShiftJis => "Shift_JIS",
+            Self::EucJp => "EUC-JP",
+            Self::Windows1252 => "windows-1252",
+        }
+    }
+
+    // バイト列を文字列にする
+    // 不正なバイト列はU+FFFDに置き換える
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            Self::Utf8 => String::from_utf8_lossy(bytes).to_string(),
+            Self::ShiftJis => decode_shift_jis(bytes),
+            Self::EucJp => decode_euc_jp(bytes),
+            Self::Windows1252 => bytes.iter().map(|b| windows_1252_char(*b)).collect(),
+        }
+    }
+}
+
+// ボディをデコードする
+// BOMがあればUTF-8、なければencodingを使い、encodingもなければUTF-8とする
+// https://encoding.spec.whatwg.org/#decode
+pub fn decode(bytes: &[u8], encoding: Option<Encoding>) -> String {
+    if let Some(rest) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        return Encoding::Utf8.decode(rest);
+    }
+    encoding.unwrap_or(Encoding::Utf8).decode(bytes)
+}
+
+// Content-Typeヘッダの値のcharsetパラメータ
+pub fn charset_from_content_type(content_type: &str) -> Option<Encoding> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::from_label(value.trim().trim_matches(|c| c == '"' || c == '\''))
+    })
+}
+
+// HTMLの先頭1024バイトから<meta charset>か<meta http-equiv content="...; charset=...">を探す
+// 完全なprescanではなく、meta要素の中の最初のcharsetだけを見る
+// UTF-16が指定されている場合は、バイト列がASCII互換なのでUTF-8とする
+// https://html.spec.whatwg.org/multipage/parsing.html#prescan-a-byte-stream-to-determine-its-encoding
+pub fn prescan_meta_charset(bytes: &[u8]) -> Option<Encoding> {
+    let head = &bytes[..bytes.len().min(1024)];
+    let head: String = head
+        .iter()
+        .map(|b| (*b as char).to_ascii_lowercase())
+        .collect();
+
+    let mut rest = head.as_str();
+    while let Some(start) = rest.find("<meta") {
+        let tag = &rest[start + 5..];
+        let end = tag.find('>').unwrap_or(tag.len());
+        let attributes = &tag[..end];
+        rest = &tag[end..];
+
+        let value = match attributes.find("charset") {
+            Some(index) => attributes[index + 7..].trim_start(),
+            None => continue,
+        };
+        let value = match value.strip_prefix('=') {
+            Some(value) => value.trim_start().trim_start_matches(['"', '\'']),
+            None => continue,
+        };
+        let label_end = value
+            .find(|c: char| c.is_ascii_whitespace() || matches!(c, '"' | '\'' | ';' | '/'))
+            .unwrap_or(value.len());
+        let label = &value[..label_end];
+        if label.starts_with("utf-16") {
+            return Some(Encoding::Utf8);
+        }
+        if let Some(encoding) = Encoding::from_label(label) {
+            return Some(encoding);
+        }
+    }
+    None
+}
+
+// JIS X 0208のポインタに対応する文字
+fn jis0208_char(pointer: usize) -> Option<char> {
+    let code_point = if pointer < JIS0208.len() {
+        JIS0208[pointer]
+    } else if pointer >= IBM_EXTENSIONS_START {
+        *IBM_EXTENSIONS.get(pointer - IBM_EXTENSIONS_START)?
+    } else {
+        0
+    };
+    if code_point == 0 {
+        return None;
+    }
+    char::from_u32(code_point as u32)
+}
+
+// https://encoding.spec.whatwg.org/#shift_jis-decoder
+fn decode_shift_jis(bytes: &[u8]) -> String {
+    let mut result = String::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let lead = bytes[i];
+        i += 1;
+        match lead {
+            0x00..=0x80 => result.push(lead as char),
+            // 半角カタカナ
+            0xA1..=0xDF => result.push(
+                char::from_u32(0xFF61 + (lead - 0xA1) as u32)
+                    .unwrap_or(char::REPLACEMENT_CHARACTER),
+            ),
+            0x81..=0x9F | 0xE0..=0xFC => {
+                let trail = match bytes.get(i) {
+                    Some(trail) => *trail,
+                    None => {
+                        result.push(char::REPLACEMENT_CHARACTER);
+                        break;
+                    }
+                };
+                let offset = if trail < 0x7F { 0x40 } else { 0x41 };
+                let lead_offset = if lead < 0xA0 { 0x81 } else { 0xC1 };
+                let c = if matches!(trail, 0x40..=0x7E | 0x80..=0xFC) {
+                    let pointer = (lead - lead_offset) as usize * 188 + (trail - offset) as usize;
+                    match pointer {
+                        // 外字は私用領域の文字にする
+                        8836..=10715 => char::from_u32(0xE000 + (pointer - 8836) as u32),
+                        _ => jis0208_char(pointer),
+                    }
+                } else {
+                    None
+                };
+                match c {
+                    Some(c) => {
+                        result.push(c);
+                        i += 1;
+                    }
+                    None => {
+                        result.push(char::REPLACEMENT_CHARACTER);
+                        // 2バイト目がASCIIの場合は、その文字として読み直す
+                        if !trail.is_ascii() {
+                            i += 1;
+                        }
+                    }
+                }
+            }
+            _ => result.push(char::REPLACEMENT_CHARACTER),
+        }
+    }
+    result
+}
+
+// https://encoding.spec.whatwg.org/#euc-jp-decoder
+// JIS X 0212（0x8Fで始まる3バイトの文字）は表を持っていないのでU+FFFDにする
+fn decode_euc_jp(bytes: &[u8]) -> String {
+    let mut result = String::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let lead = bytes[i];
+        i += 1;
+        if lead.is_ascii() {
+            result.push(lead as char);
+            continue;
+        }
+        let trail = match (lead, bytes.get(i)) {
+            (0x8E | 0x8F | 0xA1..=0xFE, Some(trail)) => *trail,
+            (0x8E | 0x8F | 0xA1..=0xFE, None) => {
+                result.push(char::REPLACEMENT_CHARACTER);
+                break;
+            }
+            _ => {
+                result.push(char::REPLACEMENT_CHARACTER);
+                continue;
+            }
+        };
+        let c = match (lead, trail) {
+            // 半角カタカナ
+            (0x8E, 0xA1..=0xDF) => char::from_u32(0xFF61 + (trail - 0xA1) as u32),
+            (0x8F, 0xA1..=0xFE) => {
+                if bytes.get(i + 1).is_some_and(|b| (0xA1..=0xFE).contains(b)) {
+                    i += 1;
+                }
+                None
+            }
+            (0xA1..=0xFE, 0xA1..=0xFE) => {
+                jis0208_char((lead - 0xA1) as usize * 94 + (trail - 0xA1) as usize)
+            }
+            _ => None,
+        };
+        if c.is_some() || (0xA1..=0xFE).contains(&trail) {
+            i += 1;
+        }
+        result.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+    }
+    result
+}
+
+// windows-1252の1バイトの文字
+// 0x80から0x9Fまで以外はISO-8859-1と同じ
+fn windows_1252_char(byte: u8) -> char {
+    const C1: [u16; 32] = [
+        0x20AC, 0x0081, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160,
+        0x2039, 0x0152, 0x008D, 0x017D, 0x008F, 0x0090, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022,
+        0x2013, 0x2014, 0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0x009D, 0x017E, 0x0178,
+    ];
+    match byte {
+        0x80..=0x9F => {
+            char::from_u32(C1[(byte - 0x80) as usize] as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+        }
+        _ => byte as char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 名前の大文字と小文字や前後の空白は区別しない
+    #[test]
+    fn test_from_label() {
+        assert_eq!(
+            Some(Encoding::ShiftJis),
+            Encoding::from_label(" Shift_JIS ")
+        );
+        assert_eq!(Some(Encoding::EucJp), Encoding::from_label("EUC-JP"));
+        assert_eq!(
+            Some(Encoding::Windows1252),
+            Encoding::from_label("iso-8859-1")
+        );
+        assert_eq!(Some(Encoding::Utf8), Encoding::from_label("utf8"));
+        assert_eq!(None, Encoding::from_label("koi8-r"));
+    }
+
+    // 「日本語ｶﾅ①」と不正なバイト列
+    #[test]
+    fn test_decode_shift_jis() {
+        let bytes = b"\x93\xfa\x96\x7b\x8c\xea\xb6\xc5\x87\x40a";
+        assert_eq!("日本語ｶﾅ①a", Encoding::ShiftJis.decode(bytes));
+        // 2バイト目がASCIIの場合は、その文字として読み直す
+        assert_eq!("\u{fffd} A", Encoding::ShiftJis.decode(b"\x81\x20A"));
+        assert_eq!("\u{fffd}", Encoding::ShiftJis.decode(b"\x93"));
+    }
+
+    #[test]
+    fn test_decode_euc_jp() {
+        let bytes = b"\xc6\xfc\xcb\xdc\xb8\xec\x8e\xb6\x8e\xc5a";
+        assert_eq!("日本語ｶﾅa", Encoding::EucJp.decode(bytes));
+        assert_eq!("\u{fffd}b", Encoding::EucJp.decode(b"\x8f\xb0\xa1b"));
+    }
+
+    #[test]
+    fn test_decode_windows_1252() {
+        assert_eq!(
+            "caf\u{e9} \u{20ac}",
+            Encoding::Windows1252.decode(b"caf\xe9 \x80")
+        );
+    }
+
+    // BOMはencodingより優先される
+    #[test]
+    fn test_decode_bom() {
+        assert_eq!(
+            "あ",
+            decode(b"\xEF\xBB\xBF\xE3\x81\x82", Some(Encoding::ShiftJis))
+        );
+        assert_eq!("あ", decode(b"\x82\xa0", Some(Encoding::ShiftJis)));
+        assert_eq!("あ", decode("あ".as_bytes(), None));
+    }
+
+    #[test]
+    fn test_charset() {
+        assert_eq!(
+            Some(Encoding::ShiftJis),
+            charset_from_content_type("text/html; charset=\"Shift_JIS\"")
+        );
+        assert_eq!(None, charset_from_content_type("text/html"));
+
+        assert_eq!(
+            Some(Encoding::EucJp),
+            prescan_meta_charset(b"<html><head><meta charset=\"euc-jp\"></head>")
+        );
+        assert_eq!(
+            Some(Encoding::ShiftJis),
+            prescan_meta_charset(
+                b"<meta name=viewport><META http-equiv=Content-Type content='text/html; charset=SJIS'>"
+            )
+        );
+        assert_eq!(
+            Some(Encoding::Utf8),
+            prescan_meta_charset(b"<meta charset=utf-16le>")
+        );
+        assert_eq!(None, prescan_meta_charset(b"<p>charset=sjis</p>"));
+    }
+}
@@ -0,0 +1,124 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// URLに書けない文字を、UTF-8のバイトごとに%XXにする
+// すでに%XXになっている部分はそのままにするので、何度エンコードしても同じ結果になる
+// https://url.spec.whatwg.org/#percent-encoded-bytes
+
+// パスの部分をエンコードする
+// https://url.spec.whatwg.org/#path-percent-encode-set
+pub fn encode_path(path: &str) -> String {
+    encode(path, |b| {
+        matches!(
+            b,
+            b' ' | b'"' | b'#' | b'<' | b'>' | b'?' | b'`' | b'{' | b'}'
+        )
+    })
+}
+
+// ?より後ろのsearchpartをエンコードする
+// https://url.spec.whatwg.org/#query-percent-encode-set
+pub fn encode_query(query: &str) -> String {
+    encode(query, |b| matches!(b, b' ' | b'"' | b'#' | b'<' | b'>'))
+}
+
+// リクエストの送り先（searchpartを含むパス）をエンコードする
+// 最初の?より前をパス、後ろをsearchpartとして扱う
+pub fn encode_request_target(target: &str) -> String {
+    match target.split_once('?') {
+        Some((path, query)) => alloc::format!("{}?{}", encode_path(path), encode_query(query)),
+        None => encode_path(target),
+    }
+}
+
+// application/x-www-form-urlencodedの名前や値をエンコードする
+// 英数字と*-._以外はUTF-8のバイトごとに%XXにし、空白は+にする
+// https://url.spec.whatwg.org/#urlencoded-serializing
+pub fn encode_form_component(s: &str) -> String {
+    let mut encoded = String::new();
+    for b in s.bytes() {
+        match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                encoded.push(b as char)
+            }
+            b' ' => encoded.push('+'),
+            _ => push_escaped(&mut encoded, b),
+        }
+    }
+    encoded
+}
+
+// %XXを元のバイトに戻す
+// %の後が16進数の2文字でない場合は、そのままにする
+pub fn decode(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| core::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(b) = hex {
+                decoded.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    decoded
+}
+
+// 制御文字とASCII以外、should_encodeがtrueを返すバイトを%XXにする
+fn encode(s: &str, should_encode: fn(u8) -> bool) -> String {
+    let mut encoded = String::new();
+    for b in s.bytes() {
+        if !(0x20..=0x7E).contains(&b) || should_encode(b) {
+            push_escaped(&mut encoded, b);
+        } else {
+            encoded.push(b as char);
+        }
+    }
+    encoded
+}
+
+fn push_escaped(encoded: &mut String, b: u8) {
+    encoded.push_str(&alloc::format!("%{:02X}", b));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_request_target() {
+        assert_eq!(
+            "docs/a%20b/%E3%81%82.html?q=%E3%81%82%20b&x=%22?{}",
+            encode_request_target("docs/a b/あ.html?q=あ b&x=\"?{}")
+        );
+        // すでにエンコードされている部分はそのまま
+        assert_eq!(
+            "a%20b?c%2Fd",
+            encode_request_target(&encode_request_target("a%20b?c%2Fd"))
+        );
+        assert_eq!("%3Cscript%3E%60", encode_path("<script>`"));
+    }
+
+    #[test]
+    fn test_form_component() {
+        assert_eq!(
+            "rust+%26+saba%3D%E3%81%82",
+            encode_form_component("rust & saba=あ")
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!("a b/あ".as_bytes(), decode("a%20b%2f%E3%81%82"));
+        assert_eq!(b"100%".to_vec(), decode("100%"));
+        assert_eq!(b"%zz".to_vec(), decode("%zz"));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::encoding::{self, Encoding};
 use crate::error::Error;
 use alloc::format;
 use alloc::string::{String, ToString};
@@ -8,17 +9,20 @@ pub struct HttpResponse {
     version: String,
     status_code: u32,
     reason: String,
-    headers: Vec<Header>,
-    body: String,
+    headers: HeaderMap,
+    // 受信したままのボディ
+    // 画像やフォントなどのUTF-8ではないボディも壊さずに保持し、文字列はbody()で必要な時に作る
+    body_bytes: Vec<u8>,
 }
 
 impl HttpResponse {
     // コンストラクタ
+    // 行の区切りはCRLFとLFのどちらも受け付ける
     pub fn new(raw_response: String) -> Result<Self, Error> {
-        let preprocessed_response = raw_response.trim_start().replace("\n\r", "\n");
+        let preprocessed_response = raw_response.trim_start();
 
         let (status_line, remaining) = match preprocessed_response.split_once('\n') {
-            Some((status_line, remaining)) => (status_line, remaining),
+            Some((status_line, remaining)) => (trim_cr(status_line), remaining),
             None => {
                 return Err(Error::Network(format!(
                     "invalid http response: {}",
@@ -27,30 +31,104 @@ impl HttpResponse {
             }
         };
 
-        let (headers, body) = match remaining.split_once("\n\n") {
-            Some((h, b)) => {
-                let mut headers = Vec::new();
-                for header in h.split('\n') {
-                    let splitted_header: Vec<&str> = header.splitn(2, ':').collect();
-                    headers.push(Header::new(
-                        String::from(splitted_header[0].trim()),
-                        String::from(splitted_header[1].trim()),
-                    ))
-                }
-                (headers, b)
+        // 空行までがヘッダで、それ以降がボディ
+        // 空行がない場合は、ステータスライン以降を全てボディとする
+        let mut header_lines = Vec::new();
+        let mut rest = remaining;
+        let mut body = None;
+        while let Some((line, next)) = rest.split_once('\n') {
+            let line = trim_cr(line);
+            if line.is_empty() {
+                body = Some(next);
+                break;
             }
-            None => (Vec::new(), remaining),
+            header_lines.push(line);
+            rest = next;
+        }
+        let (headers, body) = match body {
+            Some(b) => (HeaderMap::parse(&header_lines), b),
+            None => (HeaderMap::new(), remaining),
         };
 
-        let statuses: Vec<&str> = status_line.split(' ').collect();
+        // 理由句は空白を含むことがある（例: Not Found）
+        let mut statuses = status_line.splitn(3, ' ');
+        let version = statuses.next().unwrap_or_default();
+        let status_code = statuses.next().and_then(|s| s.parse().ok()).unwrap_or(404);
+        let reason = statuses.next().unwrap_or_default();
 
-        Ok(Self {
-            version: statuses[0].to_string(),
-            status_code: statuses[1].parse().unwrap_or(404),
-            reason: statuses[2].to_string(),
+        let mut response = Self {
+            version: version.to_string(),
+            status_code,
+            reason: reason.to_string(),
             headers,
-            body: body.to_string(),
-        })
+            body_bytes: Vec::new(),
+        };
+        response.set_body_bytes(body.as_bytes().to_vec());
+        Ok(response)
+    }
+
+    // 受信したバイト列から作る
+    // 画像などのUTF-8ではないボディもそのまま保持する
+    pub fn from_bytes(raw_response: Vec<u8>) -> Result<Self, Error> {
+        let (head_end, body_start) = match find_head_end(&raw_response) {
+            Some(s) => s,
+            None => return Self::new(String::from_utf8_lossy(&raw_response).to_string()),
+        };
+
+        let mut head = String::from_utf8_lossy(&raw_response[..head_end]).to_string();
+        head.push_str("\n\n");
+        let mut response = Self::new(head)?;
+        response.set_body_bytes(raw_response[body_start..].to_vec());
+        Ok(response)
+    }
+
+    // 受信したボディを保持する
+    // Transfer-Encodingの最後がchunkedの場合は、チャンクをつなげた内容にする
+    fn set_body_bytes(&mut self, body_bytes: Vec<u8>) {
+        let body_bytes = if self.is_chunked() {
+            decode_chunked(&body_bytes)
+        } else {
+            body_bytes
+        };
+        self.body_bytes = body_bytes;
+    }
+
+    // 少しずつ受信してデコードしたボディを設定したレスポンスにする
+    // chunkedのデコードはしないので、チャンクをつなげた後の内容を渡す
+    pub fn with_body(mut self, body_bytes: Vec<u8>) -> Self {
+        self.body_bytes = body_bytes;
+        self
+    }
+
+    // Transfer-Encodingの最後がchunkedかどうか
+    pub fn is_chunked(&self) -> bool {
+        self.headers
+            .get_all("Transfer-Encoding")
+            .iter()
+            .flat_map(|value| value.split(','))
+            .map(|coding| coding.trim())
+            .rfind(|coding| !coding.is_empty())
+            .is_some_and(|coding| coding.eq_ignore_ascii_case("chunked"))
+    }
+
+    // レスポンスの後も同じ接続で次のリクエストを送れるか
+    // HTTP/1.1はConnection: closeがなければ使い続け、HTTP/1.0はConnection: keep-aliveがある場合だけ使い続ける
+    // https://www.rfc-editor.org/rfc/rfc9112#section-9.3
+    pub fn keep_alive(&self) -> bool {
+        let options: Vec<String> = self
+            .headers
+            .get_all("Connection")
+            .iter()
+            .flat_map(|value| value.split(','))
+            .map(|option| option.trim().to_ascii_lowercase())
+            .collect();
+        if options.iter().any(|option| option == "close") {
+            return false;
+        }
+        if self.version == "HTTP/1.0" {
+            return options.iter().any(|option| option == "keep-alive");
+        }
+        true
     }
 
     // 以降はゲッターメソッド
@@ -62,26 +140,237 @@ impl HttpResponse {
         self.status_code
     }
 
+    // 2xx
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status_code)
+    }
+
+    // Locationヘッダに従って移動するステータス
+    // 300や304などの他の3xxは含まない
+    // https://fetch.spec.whatwg.org/#redirect-status
+    pub fn is_redirect(&self) -> bool {
+        matches!(self.status_code, 301 | 302 | 303 | 307 | 308)
+    }
+
+    // 4xx
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.status_code)
+    }
+
+    // 5xx
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.status_code)
+    }
+
     pub fn reason(&self) -> String {
         self.reason.clone()
     }
 
     pub fn headers(&self) -> Vec<Header> {
+        self.headers.headers.clone()
+    }
+
+    pub fn header_map(&self) -> HeaderMap {
         self.headers.clone()
     }
 
+    // Content-Typeのcharsetの文字エンコーディングで文字列にする（指定がない場合はUTF-8）
+    // 読めない部分はU+FFFDに置き換える
     pub fn body(&self) -> String {
-        self.body.clone()
+        encoding::decode(&self.body_bytes, self.charset())
     }
 
+    pub fn body_bytes(&self) -> Vec<u8> {
+        self.body_bytes.clone()
+    }
+
+    // ヘッダ名の大文字と小文字は区別しない
     pub fn header_value(&self, name: &str) -> Result<String, String> {
-        for header in &self.headers {
-            if header.name == name {
-                return Ok(header.value.clone());
+        match self.headers.get(name) {
+            Some(value) => Ok(value),
+            None => Err(format!("failed to find {} in headers", name)),
+        }
+    }
+
+    // 同じ名前の全てのヘッダの値（Set-Cookieなど）
+    // ヘッダ名の大文字と小文字は区別しない
+    pub fn header_values(&self, name: &str) -> Vec<String> {
+        self.headers.get_all(name)
+    }
+
+    // Content-Typeヘッダの値（パラメータを含む）
+    pub fn content_type(&self) -> Option<String> {
+        self.headers.get("Content-Type")
+    }
+
+    // Content-Typeのcharsetパラメータの文字エンコーディング
+    // 指定がない場合やサポートしていない場合はNone
+    pub fn charset(&self) -> Option<Encoding> {
+        self.content_type()
+            .and_then(|content_type| encoding::charset_from_content_type(&content_type))
+    }
+
+    // Content-Lengthヘッダの値
+    // 複数の値が食い違う場合や数値でない場合は、信用できないのでNoneにする
+    // https://www.rfc-editor.org/rfc/rfc9110#section-8.6
+    pub fn content_length(&self) -> Option<usize> {
+        let mut length = None;
+        for value in self.headers.get_all("Content-Length") {
+            for v in value.split(',') {
+                let v = v.trim();
+                if v.is_empty() || !v.chars().all(|c| c.is_ascii_digit()) {
+                    return None;
+                }
+                let n = v.parse::<usize>().ok()?;
+                match length {
+                    Some(l) if l != n => return None,
+                    _ => length = Some(n),
+                }
             }
         }
+        length
+    }
+
+    // Locationヘッダの値（リダイレクト先）
+    pub fn location(&self) -> Option<String> {
+        self.headers
+            .get("Location")
+            .filter(|location| !location.is_empty())
+    }
 
-        Err(format!("failed to find {} in headers", name))
+    // 全てのSet-Cookieヘッダの値
+    // クッキーの値はカンマを含むことがあるので、ヘッダごとに分けたまま返す
+    pub fn set_cookies(&self) -> Vec<String> {
+        self.headers.get_all("Set-Cookie")
+    }
+}
+
+// ヘッダの終わり（空行）の位置と、ボディの始まりの位置
+pub(crate) fn find_head_end(bytes: &[u8]) -> Option<(usize, usize)> {
+    bytes
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| (i, i + 4))
+        .or_else(|| {
+            bytes
+                .windows(2)
+                .position(|w| w == b"\n\n")
+                .map(|i| (i, i + 2))
+        })
+}
+
+// chunked形式のボディをデコードする
+// 各チャンクは「16進数のサイズ（;以降は拡張）、改行、データ、改行」で、サイズ0のチャンクで終わる
+// 途中で途切れている場合は、そこまでに受け取った内容を返す
+// https://www.rfc-editor.org/rfc/rfc9112#section-7.1
+fn decode_chunked(bytes: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::new();
+    let mut rest = bytes;
+    while let Some(line_end) = rest.iter().position(|b| *b == b'\n') {
+        let size_line = String::from_utf8_lossy(&rest[..line_end]).to_string();
+        let size = size_line.split(';').next().unwrap_or_default().trim();
+        let size = match usize::from_str_radix(size, 16) {
+            Ok(size) => size,
+            Err(_) => break,
+        };
+        // サイズ0のチャンクの後はトレーラーなので読まない
+        if size == 0 {
+            break;
+        }
+        rest = &rest[line_end + 1..];
+        if rest.len() <= size {
+            decoded.extend_from_slice(rest);
+            break;
+        }
+        decoded.extend_from_slice(&rest[..size]);
+        rest = &rest[size..];
+        // チャンクのデータの後の改行を読み飛ばす
+        rest = rest.strip_prefix(b"\r").unwrap_or(rest);
+        rest = rest.strip_prefix(b"\n").unwrap_or(rest);
+    }
+    decoded
+}
+
+// 行末のCRを取り除く
+fn trim_cr(line: &str) -> &str {
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+// レスポンスのヘッダの一覧
+// 名前の大文字と小文字は区別せずに引き、同じ名前のヘッダは受け取った順に全て保持する
+#[derive(Debug, Clone, Default)]
+pub struct HeaderMap {
+    headers: Vec<Header>,
+}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self {
+            headers: Vec::new(),
+        }
+    }
+
+    // ヘッダの行をパースする
+    // 空白で始まる行は前のヘッダの続き（obs-fold）として扱い、:のない行は無視する
+    fn parse(lines: &[&str]) -> Self {
+        let mut map = Self::new();
+        for line in lines {
+            if line.starts_with([' ', '\t']) {
+                if let Some(last) = map.headers.last_mut() {
+                    if !last.value.is_empty() {
+                        last.value.push(' ');
+                    }
+                    last.value.push_str(line.trim());
+                }
+                continue;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                let name = name.trim();
+                if !name.is_empty() {
+                    map.append(name.to_string(), value.trim().to_string());
+                }
+            }
+        }
+        map
+    }
+
+    pub fn append(&mut self, name: String, value: String) {
+        self.headers.push(Header::new(name, value));
+    }
+
+    // 最初に見つかった値
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.headers
+            .iter()
+            .find(|header| header.name.eq_ignore_ascii_case(name))
+            .map(|header| header.value.clone())
+    }
+
+    // 同じ名前の全ての値
+    pub fn get_all(&self, name: &str) -> Vec<String> {
+        self.headers
+            .iter()
+            .filter(|header| header.name.eq_ignore_ascii_case(name))
+            .map(|header| header.value.clone())
+            .collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|header| header.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Header> {
+        self.headers.iter()
     }
 }
 
@@ -95,6 +384,73 @@ impl Header {
     pub fn new(name: String, value: String) -> Self {
         Self { name, value }
     }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn value(&self) -> String {
+        self.value.clone()
+    }
+}
+
+// HTTPの日時（例: Sun, 06 Nov 1994 08:49:37 GMT）を1970年1月1日からの秒数にする
+// DateやExpiresヘッダ、クッキーのExpires属性に使う
+// 古い形式（06-Nov-94）の日付も受け付ける
+pub fn parse_http_date(date: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let mut day = None;
+    let mut month = None;
+    let mut year = None;
+    let mut time = None;
+    for token in date.split([' ', ',', '-']).filter(|t| !t.is_empty()) {
+        if token.contains(':') {
+            let parts: Vec<&str> = token.split(':').collect();
+            if parts.len() != 3 {
+                return None;
+            }
+            let hour = parts[0].parse::<u64>().ok()?;
+            let minute = parts[1].parse::<u64>().ok()?;
+            let second = parts[2].parse::<u64>().ok()?;
+            time = Some(hour * 3600 + minute * 60 + second);
+        } else if let Some(index) = MONTHS
+            .iter()
+            .position(|m| token.to_lowercase().starts_with(m))
+        {
+            month = Some(index as u64 + 1);
+        } else if let Ok(number) = token.parse::<u64>() {
+            if day.is_none() && token.len() <= 2 {
+                day = Some(number);
+            } else {
+                // 2桁の年は、70以上を1900年代、それ以外を2000年代とする
+                year = Some(match number {
+                    0..=69 => number + 2000,
+                    70..=99 => number + 1900,
+                    _ => number,
+                });
+            }
+        }
+    }
+
+    let (day, month, year, time) = (day?, month?, year?, time?);
+    if !(1..=31).contains(&day) || year < 1970 {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * 86400 + time)
+}
+
+// 1970年1月1日からの日数
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    // 3月始まりの年にすると、うるう日が年の最後になる
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
 }
 
 #[cfg(test)]
@@ -152,4 +508,160 @@ mod tests {
 
         assert_eq!(res.body(), "body message".to_string());
     }
+
+    // UTF-8ではないボディ
+    #[test]
+    fn test_from_bytes() {
+        let mut raw = b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\n\r\n".to_vec();
+        raw.extend_from_slice(&[0x89, 0x50, 0x0d, 0x0a, 0xff]);
+        let res = HttpResponse::from_bytes(raw).expect("failed to parse");
+
+        assert_eq!(res.status_code(), 200);
+        assert_eq!(
+            res.header_value("Content-Type"),
+            Ok("image/png".to_string())
+        );
+        assert_eq!(res.body_bytes(), alloc::vec![0x89, 0x50, 0x0d, 0x0a, 0xff]);
+        assert_eq!(res.body(), "\u{fffd}P\r\n\u{fffd}".to_string());
+    }
+
+    // Content-Typeのcharsetでボディを文字列にする
+    #[test]
+    fn test_charset() {
+        let mut raw = b"HTTP/1.1 200 OK\nContent-Type: text/plain; charset=Shift_JIS\n\n".to_vec();
+        raw.extend_from_slice(b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd");
+        let res = HttpResponse::from_bytes(raw).expect("failed to parse");
+
+        assert_eq!(res.charset(), Some(Encoding::ShiftJis));
+        assert_eq!(res.body(), "こんにちは".to_string());
+    }
+
+    // chunked形式のボディ
+    #[test]
+    fn test_chunked() {
+        let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n<p>he\r\n7;ext=1\r\nllo</p>\r\n0\r\nExpires: 0\r\n\r\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse");
+        assert_eq!(res.body(), "<p>hello</p>".to_string());
+
+        // チャンクのデータはUTF-8の文字の途中で分かれることがある
+        let mut raw = b"HTTP/1.1 200 OK\nTransfer-Encoding: gzip, Chunked\n\n".to_vec();
+        raw.extend_from_slice(b"2\n\xe3\x81\n1\n\x82\n0\n\n");
+        let res = HttpResponse::from_bytes(raw).expect("failed to parse");
+        assert_eq!(res.body(), "あ".to_string());
+
+        // 途中で途切れている
+        let raw = "HTTP/1.1 200 OK\nTransfer-Encoding: chunked\n\na\nabc".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse");
+        assert_eq!(res.body(), "abc".to_string());
+
+        // chunkedではない
+        let raw = "HTTP/1.1 200 OK\nTransfer-Encoding: chunked, identity\n\n5\nhello".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse");
+        assert_eq!(res.body(), "5\nhello".to_string());
+    }
+
+    // 接続を使い続けられるか
+    #[test]
+    fn test_keep_alive() {
+        let keep_alive = |raw: &str| {
+            HttpResponse::new(raw.to_string())
+                .expect("failed to parse")
+                .keep_alive()
+        };
+        assert!(keep_alive("HTTP/1.1 200 OK\n\n"));
+        assert!(!keep_alive("HTTP/1.1 200 OK\nConnection: Close\n\n"));
+        assert!(!keep_alive("HTTP/1.0 200 OK\n\n"));
+        assert!(keep_alive("HTTP/1.0 200 OK\nConnection: keep-alive\n\n"));
+    }
+
+    // 同じ名前の複数のヘッダ
+    #[test]
+    fn test_header_values() {
+        let raw = "HTTP/1.1 200 OK\nSet-Cookie: a=1\nset-cookie: b=2\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse");
+
+        assert_eq!(
+            res.header_values("Set-Cookie"),
+            alloc::vec!["a=1".to_string(), "b=2".to_string()]
+        );
+        assert!(res.header_values("Date").is_empty());
+    }
+
+    // CRLFの区切り、空白を含む理由句、複数行にまたがるヘッダ
+    #[test]
+    fn test_crlf() {
+        let raw = "HTTP/1.1 404 Not Found\r\nX-Long: a\r\n  b\r\nbroken line\r\ncontent-type: text/html\r\n\r\nbody\r\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse");
+
+        assert_eq!(res.status_code(), 404);
+        assert_eq!(res.reason(), "Not Found");
+        assert_eq!(res.header_value("X-Long"), Ok("a b".to_string()));
+        assert_eq!(res.header_map().len(), 2);
+        assert!(res.header_map().contains("Content-Type"));
+        assert_eq!(res.body(), "body\r\n".to_string());
+    }
+
+    // 型付きのアクセサ
+    #[test]
+    fn test_accessors() {
+        let raw = "HTTP/1.1 302 Found\nContent-Type: text/html; charset=utf-8\nContent-Length: 0\nLOCATION: /next\nSet-Cookie: a=1; Expires=Sun, 06 Nov 1994 08:49:37 GMT\nSet-Cookie: b=2\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse");
+
+        assert_eq!(
+            res.content_type(),
+            Some("text/html; charset=utf-8".to_string())
+        );
+        assert_eq!(res.content_length(), Some(0));
+        assert_eq!(res.location(), Some("/next".to_string()));
+        assert_eq!(
+            res.set_cookies(),
+            alloc::vec![
+                "a=1; Expires=Sun, 06 Nov 1994 08:49:37 GMT".to_string(),
+                "b=2".to_string()
+            ]
+        );
+
+        let raw = "HTTP/1.1 200 OK\nContent-Length: 5\nContent-Length: 5, 6\n\n".to_string();
+        let res = HttpResponse::new(raw).expect("failed to parse");
+        assert_eq!(res.content_length(), None);
+        assert_eq!(res.content_type(), None);
+        assert_eq!(res.location(), None);
+    }
+
+    // ステータスコードによる分類
+    #[test]
+    fn test_status_classification() {
+        let status =
+            |line: &str| HttpResponse::new(format!("{}\n\n", line)).expect("failed to parse");
+
+        let ok = status("HTTP/1.1 204 No Content");
+        assert!(ok.is_success() && !ok.is_redirect() && !ok.is_client_error());
+        let moved = status("HTTP/1.1 301 Moved Permanently");
+        assert!(moved.is_redirect() && !moved.is_success());
+        let not_modified = status("HTTP/1.1 304 Not Modified");
+        assert!(!not_modified.is_redirect());
+        let not_found = status("HTTP/1.1 404 Not Found");
+        assert!(not_found.is_client_error() && !not_found.is_server_error());
+        let unavailable = status("HTTP/1.1 503 Service Unavailable");
+        assert!(unavailable.is_server_error() && !unavailable.is_client_error());
+    }
+
+    // HTTPの日時
+    #[test]
+    fn test_parse_http_date() {
+        assert_eq!(
+            Some(784111777),
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT")
+        );
+        assert_eq!(
+            Some(784111777),
+            parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT")
+        );
+        assert_eq!(Some(0), parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"));
+        assert_eq!(
+            Some(951782400),
+            parse_http_date("Tue, 29 Feb 2000 00:00:00 GMT")
+        );
+        assert_eq!(None, parse_http_date("tomorrow"));
+    }
 }
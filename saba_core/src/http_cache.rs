@@ -0,0 +1,339 @@
+use crate::http::parse_http_date;
+use crate::http::Header;
+use crate::http::HttpResponse;
+use crate::url::Url;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+// キャッシュしたレスポンスを使う前に確認した結果
+#[derive(Debug, Clone)]
+pub enum CacheLookup {
+    // 新鮮なのでネットワークにアクセスせずにそのまま使える
+    Fresh(HttpResponse),
+    // 古くなっているので、条件付きリクエストで変更されていないか確かめる
+    // 値はリクエストに付けるヘッダ（If-None-Match、If-Modified-Since）
+    Stale(Vec<Header>),
+    // キャッシュしていない
+    Miss,
+}
+
+// キャッシュした1つのレスポンス
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    response: HttpResponse,
+    // 保存した（最後に確かめた）時刻（1970年1月1日からの秒数）
+    stored_at: u64,
+    // 保存してから新鮮なままでいられる秒数
+    freshness_lifetime: u64,
+    // 条件付きリクエストに使う検証子
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl CacheEntry {
+    fn is_fresh(&self, now: u64) -> bool {
+        now < self.stored_at.saturating_add(self.freshness_lifetime)
+    }
+
+    fn validators(&self) -> Vec<Header> {
+        let mut headers = Vec::new();
+        if let Some(etag) = &self.etag {
+            headers.push(Header::new("If-None-Match".to_string(), etag.clone()));
+        }
+        if let Some(last_modified) = &self.last_modified {
+            headers.push(Header::new(
+                "If-Modified-Since".to_string(),
+                last_modified.clone(),
+            ));
+        }
+        headers
+    }
+}
+
+// メモリ上のHTTPキャッシュ
+// URLごとにレスポンスと検証子、新鮮さを保存し、ネットワークにアクセスする前に確認する
+// Cache-ControlとExpiresヘッダに従って新鮮さを決め、古くなったものは条件付きリクエストで確かめる
+#[derive(Debug, Clone, Default)]
+pub struct HttpCache {
+    entries: BTreeMap<String, CacheEntry>,
+    // 新鮮さの判定に使う現在の時刻（1970年1月1日からの秒数）
+    current_time: u64,
+}
+
+impl HttpCache {
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            current_time: 0,
+        }
+    }
+
+    pub fn set_current_time(&mut self, now: u64) {
+        self.current_time = now;
+    }
+
+    pub fn current_time(&self) -> u64 {
+        self.current_time
+    }
+
+    // URLのレスポンスをキャッシュしているかどうか
+    pub fn contains(&self, url: &Url) -> bool {
+        self.entries.contains_key(&cache_key(url))
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // URLのリソースを取得する前に、キャッシュを使えるか確認する
+    pub fn lookup(&self, url: &Url) -> CacheLookup {
+        let entry = match self.entries.get(&cache_key(url)) {
+            Some(entry) => entry,
+            None => return CacheLookup::Miss,
+        };
+        if entry.is_fresh(self.current_time) {
+            return CacheLookup::Fresh(entry.response.clone());
+        }
+
+        let validators = entry.validators();
+        if validators.is_empty() {
+            CacheLookup::Miss
+        } else {
+            CacheLookup::Stale(validators)
+        }
+    }
+
+    // 受け取ったレスポンスをキャッシュに保存し、ページが使うレスポンスを返す
+    // 304 Not Modifiedの場合は、キャッシュしていたレスポンスの新鮮さと検証子を更新して返す
+    // Cache-Control: no-storeのレスポンスは保存せず、以前のものも取り除く
+    pub fn store(&mut self, url: &Url, response: HttpResponse) -> HttpResponse {
+        let key = cache_key(url);
+        let directives = CacheControl::new(&response);
+        if directives.no_store {
+            self.entries.remove(&key);
+            return response;
+        }
+
+        let now = self.current_time;
+        if response.status_code() == 304 {
+            return match self.entries.get_mut(&key) {
+                Some(entry) => {
+                    entry.stored_at = now;
+                    if let Some(lifetime) = freshness_lifetime(&response, &directives, now) {
+                        entry.freshness_lifetime = lifetime;
+                    }
+                    // 304に新しい検証子が含まれている場合は、次の確認からそれを使う
+                    if let Some(etag) = response.header_values("ETag").first() {
+                        entry.etag = Some(etag.clone());
+                    }
+                    if let Some(last_modified) = response.header_values("Last-Modified").first() {
+                        entry.last_modified = Some(last_modified.clone());
+                    }
+                    entry.response.clone()
+                }
+                None => response,
+            };
+        }
+        if response.status_code() != 200 {
+            return response;
+        }
+
+        let entry = CacheEntry {
+            response: response.clone(),
+            stored_at: now,
+            freshness_lifetime: freshness_lifetime(&response, &directives, now).unwrap_or(0),
+            etag: response.header_values("ETag").first().cloned(),
+            last_modified: response.header_values("Last-Modified").first().cloned(),
+        };
+        // 新鮮さも検証子もないレスポンスは使い道がないので保存しない
+        if entry.freshness_lifetime == 0 && entry.etag.is_none() && entry.last_modified.is_none() {
+            self.entries.remove(&key);
+        } else {
+            self.entries.insert(key, entry);
+        }
+        response
+    }
+}
+
+// Cache-Controlヘッダの指示のうち、サポートしているもの
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct CacheControl {
+    no_store: bool,
+    no_cache: bool,
+    max_age: Option<u64>,
+}
+
+impl CacheControl {
+    fn new(response: &HttpResponse) -> Self {
+        let mut directives = Self::default();
+        for value in response.header_values("Cache-Control") {
+            for directive in value.split(',') {
+                let (name, argument) = match directive.split_once('=') {
+                    Some((name, argument)) => {
+                        (name.trim(), Some(argument.trim().trim_matches('"')))
+                    }
+                    None => (directive.trim(), None),
+                };
+                if name.eq_ignore_ascii_case("no-store") {
+                    directives.no_store = true;
+                } else if name.eq_ignore_ascii_case("no-cache") {
+                    directives.no_cache = true;
+                } else if name.eq_ignore_ascii_case("max-age") {
+                    directives.max_age = argument.and_then(|a| a.parse::<u64>().ok());
+                }
+            }
+        }
+        directives
+    }
+}
+
+// レスポンスが新鮮なままでいられる秒数
+// no-cache、max-age、Expires、Last-Modifiedからの推測の順に決める
+// どれもない場合はNone
+fn freshness_lifetime(response: &HttpResponse, directives: &CacheControl, now: u64) -> Option<u64> {
+    if directives.no_cache {
+        return Some(0);
+    }
+    if let Some(max_age) = directives.max_age {
+        return Some(max_age);
+    }
+
+    let date = response
+        .header_values("Date")
+        .first()
+        .and_then(|date| parse_http_date(date))
+        .unwrap_or(now);
+    if let Some(expires) = response.header_values("Expires").first() {
+        // 不正な日時は期限切れとして扱う
+        return Some(
+            parse_http_date(expires)
+                .map(|expires| expires.saturating_sub(date))
+                .unwrap_or(0),
+        );
+    }
+    // 明示的な指定がない場合は、最後に変更されてからの時間の10%だけ新鮮とみなす
+    response
+        .header_values("Last-Modified")
+        .first()
+        .and_then(|last_modified| parse_http_date(last_modified))
+        .map(|last_modified| date.saturating_sub(last_modified) / 10)
+}
+
+// fragmentを除いたURL
+fn cache_key(url: &Url) -> String {
+    let mut key = alloc::format!("{}/{}", url.origin(), url.raw_path());
+    if !url.raw_searchpart().is_empty() {
+        key.push('?');
+        key.push_str(&url.raw_searchpart());
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::new(s.to_string())
+            .parse()
+            .expect("url should be valid")
+    }
+
+    fn response(headers: &str, body: &str) -> HttpResponse {
+        HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n{}\n\n{}", headers, body))
+            .expect("response should be valid")
+    }
+
+    // max-ageの間は新鮮で、過ぎると検証子を付けた条件付きリクエストになる
+    #[test]
+    fn test_max_age() {
+        let mut cache = HttpCache::new();
+        cache.set_current_time(1000);
+        let url = url("http://example.com/a.css#top");
+        cache.store(
+            &url,
+            response("Cache-Control: max-age=60\nETag: \"v1\"", "p {}"),
+        );
+
+        cache.set_current_time(1059);
+        assert!(matches!(
+            cache.lookup(&url),
+            CacheLookup::Fresh(response) if response.body() == "p {}"
+        ));
+
+        cache.set_current_time(1060);
+        match cache.lookup(&url) {
+            CacheLookup::Stale(headers) => {
+                assert_eq!(1, headers.len());
+                assert_eq!("If-None-Match", headers[0].name());
+                assert_eq!("\"v1\"", headers[0].value());
+            }
+            _ => panic!("cache should be stale"),
+        }
+
+        // 304の場合はキャッシュしていたレスポンスを返し、新鮮さを更新する
+        let not_modified = HttpResponse::new(
+            "HTTP/1.1 304 Not Modified\nCache-Control: max-age=30\n\n".to_string(),
+        )
+        .expect("response should be valid");
+        assert_eq!("p {}", cache.store(&url, not_modified).body());
+        cache.set_current_time(1089);
+        assert!(matches!(cache.lookup(&url), CacheLookup::Fresh(_)));
+
+        // 304に含まれる新しい検証子は、次の確認に使う
+        let not_modified = HttpResponse::new(
+            "HTTP/1.1 304 Not Modified\nCache-Control: max-age=0\nETag: \"v2\"\nLast-Modified: Thu, 01 Jan 1970 00:00:00 GMT\n\n"
+                .to_string(),
+        )
+        .expect("response should be valid");
+        assert_eq!("p {}", cache.store(&url, not_modified).body());
+        match cache.lookup(&url) {
+            CacheLookup::Stale(headers) => {
+                assert_eq!(2, headers.len());
+                assert_eq!("\"v2\"", headers[0].value());
+                assert_eq!("If-Modified-Since", headers[1].name());
+            }
+            _ => panic!("cache should be stale"),
+        }
+    }
+
+    // ExpiresとDate、no-store、no-cache
+    #[test]
+    fn test_expires_and_directives() {
+        let mut cache = HttpCache::new();
+        let a = url("http://example.com/a.png");
+        cache.store(
+            &a,
+            response(
+                "Date: Thu, 01 Jan 1970 00:00:00 GMT\nExpires: Thu, 01 Jan 1970 00:01:40 GMT",
+                "",
+            ),
+        );
+        cache.set_current_time(99);
+        assert!(matches!(cache.lookup(&a), CacheLookup::Fresh(_)));
+        cache.set_current_time(100);
+        assert!(matches!(cache.lookup(&a), CacheLookup::Miss));
+
+        // no-storeは保存せず、以前のものも取り除く
+        cache.store(&a, response("Cache-Control: no-store", ""));
+        assert!(!cache.contains(&a));
+
+        // no-cacheは保存するが、毎回確かめる
+        let b = url("http://example.com/b.html");
+        cache.store(
+            &b,
+            response(
+                "Cache-Control: no-cache, max-age=60\nLast-Modified: Thu, 01 Jan 1970 00:00:00 GMT",
+                "",
+            ),
+        );
+        assert!(matches!(cache.lookup(&b), CacheLookup::Stale(_)));
+
+        // 新鮮さも検証子もないレスポンスは保存しない
+        let c = url("http://example.com/c.html");
+        cache.store(&c, response("Content-Type: text/html", ""));
+        assert!(!cache.contains(&c));
+    }
+}
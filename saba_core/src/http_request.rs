@@ -0,0 +1,192 @@
+use crate::encoding::percent::encode_request_target;
+use crate::error::Error;
+use crate::http::Header;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// httpのデフォルトのポート番号
+// これ以外のポートの場合は、Hostヘッダにポート番号も書く
+const DEFAULT_PORT: u16 = 80;
+
+// サーバーに送るHTTP/1.1のリクエスト
+// https://www.rfc-editor.org/rfc/rfc9112#section-3
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    method: String,
+    host: String,
+    port: u16,
+    // 先頭の/を含まないパス（searchpartを含む）
+    // Urlのpath()と同じ形
+    path: String,
+    headers: Vec<Header>,
+    body: String,
+}
+
+impl HttpRequest {
+    // メソッドは英大文字、ホスト名は空白や改行を含まないもの、パスは改行などの制御文字を含まないものだけを受け付ける
+    // パスの空白や日本語などは、リクエストラインに書く時にパーセントエンコードする
+    pub fn new(method: &str, host: String, port: u16, path: String) -> Result<Self, Error> {
+        if method.is_empty() || !method.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err(Error::UnexpectedInput(format!(
+                "invalid request method: {}",
+                method
+            )));
+        }
+        if host.is_empty() || host.chars().any(|c| c.is_ascii_whitespace() || c == '/') {
+            return Err(Error::UnexpectedInput(format!("invalid host: {}", host)));
+        }
+        if path.chars().any(|c| c.is_control()) {
+            return Err(Error::UnexpectedInput(format!(
+                "invalid request target: {}",
+                path
+            )));
+        }
+
+        Ok(Self {
+            method: method.to_string(),
+            host,
+            port,
+            path,
+            headers: Vec::new(),
+            body: String::new(),
+        })
+    }
+
+    // ヘッダを追加する
+    // 名前が空か、名前や値に改行を含むヘッダは、リクエストを壊すのでエラーにする
+    pub fn add_header(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        let valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_graphic() && c != ':' && c != '"');
+        if !valid_name || value.contains(['\r', '\n']) {
+            return Err(Error::UnexpectedInput(format!(
+                "invalid header: {}: {}",
+                name, value
+            )));
+        }
+        self.headers
+            .push(Header::new(name.to_string(), value.trim().to_string()));
+        Ok(())
+    }
+
+    pub fn set_body(&mut self, body: String) {
+        self.body = body;
+    }
+
+    // リクエストターゲット（origin-form）
+    // パスが空の場合も"/"にする
+    pub fn target(&self) -> String {
+        format!(
+            "/{}",
+            encode_request_target(self.path.trim_start_matches('/'))
+        )
+    }
+
+    // Hostヘッダの値
+    pub fn host_header(&self) -> String {
+        if self.port == DEFAULT_PORT {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    // サーバーに送る文字列にする
+    // 行の区切りはCRLFで、ボディがあるか、ボディを送るメソッド（POST、PUT、PATCH）の場合はContent-Lengthでバイト数を伝える
+    pub fn serialize(&self) -> String {
+        let mut request = format!("{} {} HTTP/1.1\r\n", self.method, self.target());
+        request.push_str(&format!("Host: {}\r\n", self.host_header()));
+        for header in &self.headers {
+            request.push_str(&format!("{}: {}\r\n", header.name(), header.value()));
+        }
+        if !self.body.is_empty() || matches!(self.method.as_str(), "POST" | "PUT" | "PATCH") {
+            request.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        }
+        request.push_str("\r\n");
+        request.push_str(&self.body);
+        request
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // パスが空でも/をリクエストターゲットにして、行をCRLFで区切るか
+    #[test]
+    fn test_get() {
+        let mut request = HttpRequest::new("GET", "example.com".to_string(), 80, "".to_string())
+            .expect("request should be valid");
+        request
+            .add_header("Connection", "close")
+            .expect("header should be valid");
+        assert_eq!(
+            "GET / HTTP/1.1\r\nHost: example.com\r\nConnection: close\r\n\r\n",
+            request.serialize()
+        );
+    }
+
+    // デフォルト以外のポートはHostヘッダに含め、ボディの長さをバイト数で伝えるか
+    #[test]
+    fn test_post() {
+        let mut request = HttpRequest::new(
+            "POST",
+            "localhost".to_string(),
+            8000,
+            "form/post.html?x=1".to_string(),
+        )
+        .expect("request should be valid");
+        request.set_body("msg=こんにちは".to_string());
+        assert_eq!(
+            "POST /form/post.html?x=1 HTTP/1.1\r\nHost: localhost:8000\r\nContent-Length: 19\r\n\r\nmsg=こんにちは",
+            request.serialize()
+        );
+    }
+
+    // パスの空白や日本語をパーセントエンコードするか
+    #[test]
+    fn test_target() {
+        let request = HttpRequest::new(
+            "GET",
+            "example.com".to_string(),
+            80,
+            "/search results/あ.html?q=a b".to_string(),
+        )
+        .expect("request should be valid");
+        assert_eq!("/search%20results/%E3%81%82.html?q=a%20b", request.target());
+    }
+
+    // HEADやPUTなどのメソッド
+    #[test]
+    fn test_methods() {
+        let request = HttpRequest::new("HEAD", "example.com".to_string(), 80, "a.css".to_string())
+            .expect("request should be valid");
+        assert_eq!(
+            "HEAD /a.css HTTP/1.1\r\nHost: example.com\r\n\r\n",
+            request.serialize()
+        );
+
+        let request = HttpRequest::new("PUT", "example.com".to_string(), 80, "api".to_string())
+            .expect("request should be valid");
+        assert_eq!(
+            "PUT /api HTTP/1.1\r\nHost: example.com\r\nContent-Length: 0\r\n\r\n",
+            request.serialize()
+        );
+    }
+
+    // リクエストを壊す値はエラーになるか
+    #[test]
+    fn test_invalid() {
+        assert!(HttpRequest::new("get", "example.com".to_string(), 80, "".to_string()).is_err());
+        assert!(HttpRequest::new("GET", "".to_string(), 80, "".to_string()).is_err());
+        assert!(
+            HttpRequest::new("GET", "example.com".to_string(), 80, "a\r\nb".to_string()).is_err()
+        );
+        let mut request = HttpRequest::new("GET", "example.com".to_string(), 80, "".to_string())
+            .expect("request should be valid");
+        assert!(request.add_header("X-Test", "a\r\nHost: evil").is_err());
+        assert!(request.add_header("Bad Name", "a").is_err());
+    }
+}
@@ -0,0 +1,96 @@
+// 接続の拒否やリセットなど、一時的なネットワークの失敗の時にリクエストを送り直す方針
+// 待ち時間は送り直すたびにmultiplier倍にし、max_backoff_msを上限にする（指数バックオフ）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    // 最初の1回を含めた最大の試行回数
+    max_attempts: u32,
+    // 1回目に送り直すまでに待つミリ秒
+    initial_backoff_ms: u64,
+    multiplier: u64,
+    max_backoff_ms: u64,
+}
+
+impl RetryPolicy {
+    // 送り直すたびに待ち時間を2倍にし、10秒を上限にする
+    pub fn new(max_attempts: u32, initial_backoff_ms: u64) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff_ms,
+            multiplier: 2,
+            max_backoff_ms: 10_000,
+        }
+    }
+
+    // 送り直さない
+    pub fn no_retry() -> Self {
+        Self::new(1, 0)
+    }
+
+    pub fn with_multiplier(mut self, multiplier: u64) -> Self {
+        self.multiplier = multiplier.max(1);
+        self
+    }
+
+    pub fn with_max_backoff(mut self, max_backoff_ms: u64) -> Self {
+        self.max_backoff_ms = max_backoff_ms;
+        self
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    // attempt回目（1から数える）の試行に失敗した後、送り直すまでに待つミリ秒
+    // 試行回数を使い切った場合はNone
+    pub fn backoff(&self, attempt: u32) -> Option<u64> {
+        if attempt == 0 || attempt >= self.max_attempts {
+            return None;
+        }
+
+        let factor = self.multiplier.saturating_pow(attempt - 1);
+        Some(
+            self.initial_backoff_ms
+                .saturating_mul(factor)
+                .min(self.max_backoff_ms),
+        )
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::no_retry()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_retry() {
+        let policy = RetryPolicy::default();
+        assert_eq!(1, policy.max_attempts());
+        assert_eq!(None, policy.backoff(1));
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::new(4, 100);
+        assert_eq!(Some(100), policy.backoff(1));
+        assert_eq!(Some(200), policy.backoff(2));
+        assert_eq!(Some(400), policy.backoff(3));
+        assert_eq!(None, policy.backoff(4));
+    }
+
+    #[test]
+    fn test_max_backoff() {
+        let policy = RetryPolicy::new(10, 500)
+            .with_multiplier(3)
+            .with_max_backoff(2000);
+        assert_eq!(Some(500), policy.backoff(1));
+        assert_eq!(Some(1500), policy.backoff(2));
+        assert_eq!(Some(2000), policy.backoff(3));
+        assert_eq!(Some(2000), policy.backoff(9));
+        assert_eq!(None, policy.backoff(10));
+    }
+}
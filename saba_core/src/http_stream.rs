@@ -0,0 +1,508 @@
+use crate::error::Error;
+use crate::http::find_head_end;
+use crate::http::HttpResponse;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// ボディをどこまで受信したか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    // これまでに受信した（chunkedをデコードした後の）ボディのバイト数
+    pub received: usize,
+    // Content-Lengthで分かる場合は、ボディ全体のバイト数
+    pub total: Option<usize>,
+}
+
+// 受信したボディを少しずつ受け取る
+// 全てを受信するのを待たずに、HTMLのトークン化や進捗の表示ができる
+pub trait BodySink {
+    // ヘッダを全て受信した時に呼ばれる（レスポンスのボディは空）
+    fn on_response(&mut self, response: &HttpResponse) {
+        let _ = response;
+    }
+
+    // ボディの一部を受信するたびに呼ばれる
+    fn on_data(&mut self, data: &[u8], progress: Progress);
+}
+
+// ボディを全てメモリに保持するBodySink
+#[derive(Debug, Clone, Default)]
+pub struct BodyBuffer {
+    body: Vec<u8>,
+}
+
+impl BodyBuffer {
+    pub fn new() -> Self {
+        Self { body: Vec::new() }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.body
+    }
+}
+
+impl BodySink for BodyBuffer {
+    fn on_data(&mut self, data: &[u8], _progress: Progress) {
+        self.body.extend_from_slice(data);
+    }
+}
+
+// 受信するレスポンスの大きさの上限
+// サーバーがおかしなレスポンスを返しても、ヒープを使い切らずにエラーにする
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseLimits {
+    // ステータス行とヘッダを合わせた最大のバイト数
+    // chunked形式のサイズの行やトレーラーの1行も、これより長い場合はエラーにする
+    pub max_head_size: usize,
+    // ヘッダ（トレーラーを含む）の最大の数
+    pub max_header_count: usize,
+    // ボディの最大のバイト数（Noneの場合は制限しない）
+    pub max_body_size: Option<usize>,
+}
+
+impl Default for ResponseLimits {
+    fn default() -> Self {
+        Self {
+            max_head_size: 64 * 1024,
+            max_header_count: 100,
+            max_body_size: None,
+        }
+    }
+}
+
+// ボディの長さの決め方
+// https://www.rfc-editor.org/rfc/rfc9112#section-6.3
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Framing {
+    // 残りのバイト数
+    Length(usize),
+    Chunked(ChunkState),
+    // 接続が閉じられるまで
+    UntilClose,
+}
+
+// chunked形式のどこを読んでいるか
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ChunkState {
+    // チャンクのサイズの行
+    Size(Vec<u8>),
+    // チャンクのデータの残りのバイト数
+    Data(usize),
+    // チャンクのデータの後の改行
+    DataEnd,
+    // 最後のチャンクの後のトレーラーの行
+    Trailer(Vec<u8>),
+    Done,
+}
+
+// 受信したバイト列を少しずつ渡して、レスポンスを読み進める
+// ヘッダはまとめてパースし、ボディはchunkedをデコードしながらBodySinkに渡す
+// ボディを保持しないので、大きなレスポンスでもメモリを使い切らない
+#[derive(Debug, Clone)]
+pub struct ResponseReader {
+    // ヘッダを全て受信するまでのバイト列
+    head: Vec<u8>,
+    // ヘッダを受信した後のレスポンス
+    response: Option<HttpResponse>,
+    framing: Framing,
+    received: usize,
+    total: Option<usize>,
+    limits: ResponseLimits,
+    // 受信したトレーラーの数
+    trailer_count: usize,
+    // HEADリクエストのレスポンスは、Content-Lengthがあってもボディを持たない
+    head_request: bool,
+}
+
+impl ResponseReader {
+    // ヘッダの上限はデフォルトのまま、ボディの上限だけを決める
+    pub fn new(max_body_size: Option<usize>) -> Self {
+        Self::with_limits(ResponseLimits {
+            max_body_size,
+            ..ResponseLimits::default()
+        })
+    }
+
+    pub fn with_limits(limits: ResponseLimits) -> Self {
+        Self {
+            head: Vec::new(),
+            response: None,
+            framing: Framing::UntilClose,
+            received: 0,
+            total: None,
+            limits,
+            trailer_count: 0,
+            head_request: false,
+        }
+    }
+
+    // リクエストのメソッドを設定する
+    // HEADの場合は、ヘッダの後にボディを待たない
+    pub fn set_request_method(&mut self, method: &str) {
+        self.head_request = method == "HEAD";
+    }
+
+    // まだ何も受信していないか
+    pub fn is_empty(&self) -> bool {
+        self.head.is_empty() && self.response.is_none()
+    }
+
+    // レスポンスを最後まで受信したか
+    // 接続が閉じられるまで長さが分からない場合は、falseのまま
+    pub fn is_complete(&self) -> bool {
+        self.response.is_some()
+            && matches!(
+                self.framing,
+                Framing::Length(0) | Framing::Chunked(ChunkState::Done)
+            )
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            received: self.received,
+            total: self.total,
+        }
+    }
+
+    // 受信したバイト列を読み進める
+    // 戻り値はレスポンスを最後まで受信したかどうか
+    pub fn feed(&mut self, bytes: &[u8], sink: &mut dyn BodySink) -> Result<bool, Error> {
+        if self.response.is_some() {
+            return self.read_body(bytes, sink);
+        }
+
+        self.head.extend_from_slice(bytes);
+        let body_start = match find_head_end(&self.head) {
+            Some((_, body_start)) if body_start <= self.limits.max_head_size => body_start,
+            None if self.head.len() <= self.limits.max_head_size => return Ok(false),
+            _ => return Err(head_too_large(self.limits.max_head_size)),
+        };
+        let response = HttpResponse::from_bytes(self.head[..body_start].to_vec())?;
+        if response.header_map().len() > self.limits.max_header_count {
+            return Err(too_many_headers(self.limits.max_header_count));
+        }
+        self.start_body(&response)?;
+        sink.on_response(&response);
+        self.response = Some(response);
+
+        // ヘッダと一緒に受信したボディの先頭を読む
+        let head = core::mem::take(&mut self.head);
+        self.read_body(&head[body_start..], sink)
+    }
+
+    // 受信を終えて、ヘッダを受信したレスポンスを返す（ボディは空）
+    // 空行がないまま接続が閉じられた場合は、受信した全てをヘッダとして扱う
+    pub fn finish(self) -> Result<HttpResponse, Error> {
+        match self.response {
+            Some(response) => Ok(response),
+            None if self.head.is_empty() => Err(Error::Network(
+                "Connection closed before receiving a response".to_string(),
+            )),
+            None => HttpResponse::from_bytes(self.head),
+        }
+    }
+
+    fn start_body(&mut self, response: &HttpResponse) -> Result<(), Error> {
+        self.framing = if self.head_request || matches!(response.status_code(), 204 | 304) {
+            // ボディを持たないレスポンス
+            Framing::Length(0)
+        } else if response.is_chunked() {
+            Framing::Chunked(ChunkState::Size(Vec::new()))
+        } else if !response.header_values("Transfer-Encoding").is_empty() {
+            Framing::UntilClose
+        } else if let Some(length) = response.content_length() {
+            self.total = Some(length);
+            Framing::Length(length)
+        } else {
+            Framing::UntilClose
+        };
+        if let (Some(total), Some(max)) = (self.total, self.limits.max_body_size) {
+            if total > max {
+                return Err(too_large(max));
+            }
+        }
+        Ok(())
+    }
+
+    fn read_body(&mut self, mut bytes: &[u8], sink: &mut dyn BodySink) -> Result<bool, Error> {
+        while !bytes.is_empty() && !self.is_complete() {
+            let (rest, data) = match &mut self.framing {
+                Framing::Length(remaining) => {
+                    let n = (*remaining).min(bytes.len());
+                    *remaining -= n;
+                    (&bytes[n..], Some(&bytes[..n]))
+                }
+                Framing::UntilClose => (&bytes[bytes.len()..], Some(bytes)),
+                Framing::Chunked(state) => {
+                    let in_trailer = matches!(state, ChunkState::Trailer(_));
+                    let (rest, data) = read_chunked(state, bytes, self.limits.max_head_size)?;
+                    // トレーラーの1行を読み終えたら数える
+                    if in_trailer && matches!(state, ChunkState::Trailer(line) if line.is_empty()) {
+                        self.trailer_count += 1;
+                        if self.trailer_count > self.limits.max_header_count {
+                            return Err(too_many_headers(self.limits.max_header_count));
+                        }
+                    }
+                    (rest, data)
+                }
+            };
+            if let Some(data) = data {
+                self.deliver(data, sink)?;
+            }
+            bytes = rest;
+        }
+        Ok(self.is_complete())
+    }
+
+    fn deliver(&mut self, data: &[u8], sink: &mut dyn BodySink) -> Result<(), Error> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.received += data.len();
+        if let Some(max) = self.limits.max_body_size {
+            if self.received > max {
+                return Err(too_large(max));
+            }
+        }
+        sink.on_data(data, self.progress());
+        Ok(())
+    }
+}
+
+fn too_large(max: usize) -> Error {
+    Error::Network(alloc::format!("response body is larger than {} bytes", max))
+}
+
+fn head_too_large(max: usize) -> Error {
+    Error::Network(alloc::format!(
+        "response header is larger than {} bytes",
+        max
+    ))
+}
+
+fn too_many_headers(max: usize) -> Error {
+    Error::Network(alloc::format!("response has more than {} headers", max))
+}
+
+// chunked形式のバイト列を、状態を1つ進める分だけ読む
+// 戻り値は残りのバイト列と、チャンクのデータ
+// max_line_sizeより長いサイズの行やトレーラーはエラーにする
+fn read_chunked<'a>(
+    state: &mut ChunkState,
+    bytes: &'a [u8],
+    max_line_size: usize,
+) -> Result<(&'a [u8], Option<&'a [u8]>), Error> {
+    match state {
+        ChunkState::Size(line) | ChunkState::Trailer(line) => {
+            let (part, rest, line_end) = match bytes.iter().position(|b| *b == b'\n') {
+                Some(i) => (&bytes[..i], &bytes[i + 1..], true),
+                None => (bytes, &bytes[bytes.len()..], false),
+            };
+            line.extend_from_slice(part);
+            if line.len() > max_line_size {
+                return Err(head_too_large(max_line_size));
+            }
+            if !line_end {
+                return Ok((rest, None));
+            }
+            let text = String::from_utf8_lossy(line).to_string();
+            let text = text.trim_end_matches('\r');
+            *state = match state {
+                ChunkState::Size(_) => {
+                    let size = text.split(';').next().unwrap_or_default().trim();
+                    match usize::from_str_radix(size, 16) {
+                        Ok(0) => ChunkState::Trailer(Vec::new()),
+                        Ok(size) => ChunkState::Data(size),
+                        Err(_) => {
+                            return Err(Error::Network(alloc::format!(
+                                "invalid chunk size: {}",
+                                text
+                            )))
+                        }
+                    }
+                }
+                // トレーラーは空行まで読み飛ばす
+                _ if text.is_empty() => ChunkState::Done,
+                _ => ChunkState::Trailer(Vec::new()),
+            };
+            Ok((rest, None))
+        }
+        ChunkState::Data(remaining) => {
+            let n = (*remaining).min(bytes.len());
+            *remaining -= n;
+            if *remaining == 0 {
+                *state = ChunkState::DataEnd;
+            }
+            Ok((&bytes[n..], Some(&bytes[..n])))
+        }
+        ChunkState::DataEnd => {
+            // CRLFのCRは読み飛ばし、LFで次のチャンクに進む
+            let rest = &bytes[1..];
+            if bytes[0] == b'\n' {
+                *state = ChunkState::Size(Vec::new());
+            }
+            Ok((rest, None))
+        }
+        ChunkState::Done => Ok((&bytes[bytes.len()..], None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 受け取ったデータと進捗を記録するBodySink
+    #[derive(Default)]
+    struct Recorder {
+        status: Option<u32>,
+        chunks: Vec<Vec<u8>>,
+        progress: Vec<Progress>,
+    }
+
+    impl BodySink for Recorder {
+        fn on_response(&mut self, response: &HttpResponse) {
+            self.status = Some(response.status_code());
+        }
+
+        fn on_data(&mut self, data: &[u8], progress: Progress) {
+            self.chunks.push(data.to_vec());
+            self.progress.push(progress);
+        }
+    }
+
+    // 1バイトずつ渡しても、ヘッダとボディを正しく読めるか
+    fn feed_bytewise(reader: &mut ResponseReader, raw: &[u8], sink: &mut dyn BodySink) -> bool {
+        let mut complete = false;
+        for b in raw {
+            complete = reader.feed(&[*b], sink).expect("response should be valid");
+        }
+        complete
+    }
+
+    #[test]
+    fn test_content_length() {
+        let mut reader = ResponseReader::new(None);
+        let mut recorder = Recorder::default();
+        assert!(!reader
+            .feed(
+                b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nhello",
+                &mut recorder
+            )
+            .expect("response should be valid"));
+        assert_eq!(Some(200), recorder.status);
+        assert!(reader
+            .feed(b"worldEXTRA", &mut recorder)
+            .expect("response should be valid"));
+        assert_eq!(
+            alloc::vec![b"hello".to_vec(), b"world".to_vec()],
+            recorder.chunks
+        );
+        assert_eq!(
+            Progress {
+                received: 10,
+                total: Some(10)
+            },
+            recorder.progress[1]
+        );
+        let response = reader.finish().expect("response should be valid");
+        assert_eq!(Some(10), response.content_length());
+    }
+
+    #[test]
+    fn test_chunked() {
+        let raw = b"HTTP/1.1 200 OK\nTransfer-Encoding: chunked\n\n5\r\n<p>he\r\n7;ext=1\r\nllo</p>\r\n0\r\nExpires: 0\r\n\r\n";
+        let mut reader = ResponseReader::new(None);
+        let mut buffer = BodyBuffer::new();
+        assert!(feed_bytewise(&mut reader, raw, &mut buffer));
+        assert_eq!(b"<p>hello</p>".to_vec(), buffer.into_bytes());
+        assert_eq!(
+            Progress {
+                received: 12,
+                total: None
+            },
+            reader.progress()
+        );
+    }
+
+    // 長さが分からない場合は、接続が閉じられるまで読む
+    #[test]
+    fn test_until_close() {
+        let mut reader = ResponseReader::new(None);
+        let mut buffer = BodyBuffer::new();
+        assert!(!reader
+            .feed(b"HTTP/1.0 200 OK\n\nhello", &mut buffer)
+            .expect("response should be valid"));
+        assert!(!reader.is_complete());
+        assert_eq!(b"hello".to_vec(), buffer.into_bytes());
+
+        let reader = ResponseReader::new(None);
+        assert!(reader.is_empty());
+        assert!(reader.finish().is_err());
+    }
+
+    // HEADのレスポンスは、Content-Lengthがあってもボディを待たない
+    #[test]
+    fn test_head() {
+        let mut reader = ResponseReader::new(None);
+        reader.set_request_method("HEAD");
+        let mut buffer = BodyBuffer::new();
+        assert!(reader
+            .feed(b"HTTP/1.1 200 OK\nContent-Length: 5\n\n", &mut buffer)
+            .expect("response should be valid"));
+        let response = reader.finish().expect("response should be valid");
+        assert_eq!(Some(5), response.content_length());
+        assert!(buffer.into_bytes().is_empty());
+    }
+
+    // 大きすぎるボディはエラーにする
+    #[test]
+    fn test_max_body_size() {
+        let mut reader = ResponseReader::new(Some(4));
+        let mut buffer = BodyBuffer::new();
+        assert!(reader
+            .feed(b"HTTP/1.1 200 OK\nContent-Length: 5\n\n", &mut buffer)
+            .is_err());
+
+        let mut reader = ResponseReader::new(Some(4));
+        assert!(reader.feed(b"HTTP/1.0 200 OK\n\nhell", &mut buffer).is_ok());
+        assert!(reader.feed(b"o", &mut buffer).is_err());
+    }
+
+    // ヘッダが大きすぎる場合や多すぎる場合はエラーにする
+    #[test]
+    fn test_header_limits() {
+        let limits = ResponseLimits {
+            max_head_size: 64,
+            max_header_count: 2,
+            max_body_size: None,
+        };
+        let mut buffer = BodyBuffer::new();
+
+        let mut reader = ResponseReader::with_limits(limits);
+        assert!(reader
+            .feed(b"HTTP/1.1 200 OK\nA: 1\nB: 2\n\n", &mut buffer)
+            .is_ok());
+
+        let mut reader = ResponseReader::with_limits(limits);
+        assert!(reader
+            .feed(b"HTTP/1.1 200 OK\nA: 1\nB: 2\nC: 3\n\n", &mut buffer)
+            .is_err());
+
+        // 空行を受信する前でも、上限を超えたらエラーにする
+        let mut reader = ResponseReader::with_limits(limits);
+        assert!(reader.feed(b"HTTP/1.1 200 OK\nX: ", &mut buffer).is_ok());
+        assert!(reader.feed(&[b'a'; 64], &mut buffer).is_err());
+
+        // chunked形式の長すぎるサイズの行や、多すぎるトレーラー
+        let mut reader = ResponseReader::with_limits(limits);
+        let chunked = b"HTTP/1.1 200 OK\nTransfer-Encoding: chunked\n\n";
+        assert!(reader.feed(chunked, &mut buffer).is_ok());
+        assert!(reader.feed(&[b'0'; 65], &mut buffer).is_err());
+
+        let mut reader = ResponseReader::with_limits(limits);
+        assert!(reader.feed(chunked, &mut buffer).is_ok());
+        assert!(reader
+            .feed(b"0\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n", &mut buffer)
+            .is_err());
+    }
+}
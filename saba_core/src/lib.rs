@@ -2,10 +2,19 @@
 
 extern crate alloc;
 
+pub mod bookmark;
 pub mod browser;
 pub mod constants;
+pub mod cookie;
+pub mod display_item;
+pub mod encoding;
 pub mod error;
 pub mod http;
+pub mod http_cache;
+pub mod http_request;
+pub mod http_retry;
+pub mod http_stream;
 pub mod renderer;
+pub mod resource_loader;
 pub mod url;
 pub mod utils;
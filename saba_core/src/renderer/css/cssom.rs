@@ -1,8 +1,13 @@
 use crate::renderer::css::token::CssToken;
 use crate::renderer::css::token::CssTokenizer;
+use crate::renderer::dom::node::Node;
+use crate::renderer::dom::node::NodeKind;
+use alloc::format;
+use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec::Vec;
+use core::cell::Cell;
 use core::iter::Peekable;
 
 // ルートノード（StyleSheet）
@@ -10,6 +15,24 @@ use core::iter::Peekable;
 #[derive(Debug, Clone, PartialEq)]
 pub struct StyleSheet {
     pub rules: Vec<QualifiedRule>,
+    // @importで読み込むスタイルシートのURL（書かれた順）
+    // まだ取得していないので、Pageが取得してマージする
+    pub imports: Vec<String>,
+    // パース中に見つかったエラー
+    pub errors: Vec<CssParseError>,
+    // スタイルシートの出どころ
+    pub origin: CascadeOrigin,
+}
+
+// スタイルシートの出どころ（カスケードオリジン）
+// UAスタイルシートのルールは詳細度に関わらず作成者のルールより優先度が低い
+// https://www.w3.org/TR/css-cascade-4/#cascading-origins
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CascadeOrigin {
+    // ブラウザのデフォルトのスタイル
+    UserAgent,
+    // ページの作成者のスタイル
+    Author,
 }
 
 impl Default for StyleSheet {
@@ -20,12 +43,59 @@ impl Default for StyleSheet {
 
 impl StyleSheet {
     pub fn new() -> Self {
-        Self { rules: Vec::new() }
+        Self {
+            rules: Vec::new(),
+            imports: Vec::new(),
+            errors: Vec::new(),
+            origin: CascadeOrigin::Author,
+        }
+    }
+
+    pub fn set_origin(&mut self, origin: CascadeOrigin) {
+        self.origin = origin;
     }
 
     pub fn set_rules(&mut self, rules: Vec<QualifiedRule>) {
         self.rules = rules;
     }
+
+    pub fn set_imports(&mut self, imports: Vec<String>) {
+        self.imports = imports;
+    }
+
+    pub fn set_errors(&mut self, errors: Vec<CssParseError>) {
+        self.errors = errors;
+    }
+
+    // @importで読み込んだスタイルシートのルールをマージする
+    // インポートされたルールは、インポートした側のルールより前に書かれたものとして扱う
+    pub fn merge_imports(&mut self, imported: Vec<StyleSheet>) {
+        let mut rules = Vec::new();
+        for sheet in imported {
+            rules.extend(sheet.rules);
+        }
+        rules.append(&mut self.rules);
+        self.rules = rules;
+        self.imports = Vec::new();
+    }
+}
+
+// CSSのパースエラー
+// エラーが起きてもその宣言やルールだけを読み飛ばしてパースを続けるので、後から確認できるように記録する
+// https://www.w3.org/TR/css-syntax-3/#error-handling
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CssParseError {
+    message: String,
+}
+
+impl CssParseError {
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
 }
 
 // ルールノード（QualifiedRule）
@@ -66,15 +136,256 @@ pub enum Selector {
     TypeSelector(String),
     ClassSelector(String),
     IdSelector(String),
+    // 全ての要素にマッチする全称セレクタ（*）
+    UniversalSelector,
+    // 単純セレクタを組み合わせた複合セレクタ（div.card#main）
+    Compound(Vec<SimpleSelector>),
     UnknownSelector,
 }
 
+// セレクタの詳細度
+// (IDセレクタの数, クラス・属性・擬似クラスセレクタの数, タイプセレクタの数)の順に比較する
+// https://www.w3.org/TR/selectors-4/#specificity-rules
+pub type Specificity = (u32, u32, u32);
+
+impl Selector {
+    // ノードがセレクタにマッチしているかを確認する
+    pub fn matches(&self, node: &Node) -> bool {
+        let element = match node.kind {
+            NodeKind::Element(ref e) => e,
+            _ => return false,
+        };
+
+        match self {
+            Selector::TypeSelector(type_name) => element.kind().to_string() == *type_name,
+            Selector::ClassSelector(class_name) => element.class_names().contains(class_name),
+            Selector::IdSelector(id_name) => element.id().as_ref() == Some(id_name),
+            Selector::UniversalSelector => true,
+            Selector::Compound(simple_selectors) => {
+                simple_selectors.iter().all(|simple| simple.matches(node))
+            }
+            Selector::UnknownSelector => false,
+        }
+    }
+
+    pub fn specificity(&self) -> Specificity {
+        match self {
+            Selector::TypeSelector(_) => (0, 0, 1),
+            Selector::ClassSelector(_) => (0, 1, 0),
+            Selector::IdSelector(_) => (1, 0, 0),
+            Selector::UniversalSelector | Selector::UnknownSelector => (0, 0, 0),
+            Selector::Compound(simple_selectors) => {
+                let mut specificity = (0, 0, 0);
+                for simple in simple_selectors {
+                    match simple {
+                        SimpleSelector::Id(_) => specificity.0 += 1,
+                        SimpleSelector::Class(_)
+                        | SimpleSelector::Attribute { .. }
+                        | SimpleSelector::PseudoClass(_) => specificity.1 += 1,
+                        SimpleSelector::Type(_) => specificity.2 += 1,
+                        SimpleSelector::Universal => {}
+                    }
+                }
+                specificity
+            }
+        }
+    }
+}
+
+// 複合セレクタを構成する単純セレクタ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimpleSelector {
+    Universal,
+    Type(String),
+    Class(String),
+    Id(String),
+    // 属性セレクタ [attr] [attr="v"] [attr^="v"] など
+    Attribute {
+        name: String,
+        operator: AttributeOperator,
+        value: String,
+    },
+    // 擬似クラス :hover :first-child など
+    PseudoClass(PseudoClass),
+}
+
+impl SimpleSelector {
+    // 単純セレクタ1つだけのセレクタに変換する
+    pub fn into_selector(self) -> Selector {
+        match self {
+            SimpleSelector::Universal => Selector::UniversalSelector,
+            SimpleSelector::Type(name) => Selector::TypeSelector(name),
+            SimpleSelector::Class(name) => Selector::ClassSelector(name),
+            SimpleSelector::Id(name) => Selector::IdSelector(name),
+            SimpleSelector::Attribute { .. } | SimpleSelector::PseudoClass(_) => {
+                Selector::Compound(alloc::vec![self])
+            }
+        }
+    }
+
+    // ノードが単純セレクタにマッチするか
+    // 擬似クラスは兄弟ノードや要素の状態も見るので、要素ではなくノードを受け取る
+    pub fn matches(&self, node: &Node) -> bool {
+        let element = match node.kind {
+            NodeKind::Element(ref e) => e,
+            _ => return false,
+        };
+
+        match self {
+            SimpleSelector::Universal => true,
+            SimpleSelector::Type(type_name) => element.kind().to_string() == *type_name,
+            SimpleSelector::Class(class_name) => element.class_names().contains(class_name),
+            SimpleSelector::Id(id_name) => element.id().as_ref() == Some(id_name),
+            SimpleSelector::Attribute {
+                name,
+                operator,
+                value,
+            } => match element.get_attribute(name) {
+                Some(attr_value) => operator.matches(&attr_value, value),
+                None => false,
+            },
+            SimpleSelector::PseudoClass(pseudo_class) => pseudo_class.matches(node),
+        }
+    }
+}
+
+// 擬似クラス
+// https://www.w3.org/TR/selectors-4/#pseudo-classes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PseudoClass {
+    Hover,
+    Active,
+    Visited,
+    Focus,
+    FirstChild,
+    // :nth-child(An+B)
+    NthChild { a: i64, b: i64 },
+}
+
+impl PseudoClass {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "hover" => Some(Self::Hover),
+            "active" => Some(Self::Active),
+            "visited" => Some(Self::Visited),
+            "focus" => Some(Self::Focus),
+            "first-child" => Some(Self::FirstChild),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, node: &Node) -> bool {
+        match self {
+            Self::Hover => node.state().hover,
+            Self::Active => node.state().active,
+            Self::Visited => node.state().visited,
+            Self::Focus => node.state().focus,
+            Self::FirstChild => element_index(node) == 1,
+            Self::NthChild { a, b } => {
+                let index = element_index(node);
+                if *a == 0 {
+                    return index == *b;
+                }
+                // index = a*n + b となる0以上の整数nが存在するか
+                let diff = index - b;
+                diff % a == 0 && diff / a >= 0
+            }
+        }
+    }
+}
+
+// 兄弟要素の中で何番目か（1から数える）
+// テキストノードは数えない
+fn element_index(node: &Node) -> i64 {
+    let mut index = 1;
+    let mut sibling = node.previous_sibling().upgrade();
+    while let Some(s) = sibling {
+        if let NodeKind::Element(_) = s.borrow().kind {
+            index += 1;
+        }
+        sibling = s.borrow().previous_sibling().upgrade();
+    }
+    index
+}
+
+// An+Bの形式の文字列を解釈する
+// odd => 2n+1、even => 2n、3 => 0n+3、-n+3 => -1n+3
+// https://www.w3.org/TR/css-syntax-3/#anb-microsyntax
+fn parse_an_plus_b(s: &str) -> Option<(i64, i64)> {
+    match s {
+        "odd" => return Some((2, 1)),
+        "even" => return Some((2, 0)),
+        _ => {}
+    }
+
+    match s.find('n') {
+        Some(i) => {
+            let a = match &s[..i] {
+                "" | "+" => 1,
+                "-" => -1,
+                a => a.parse().ok()?,
+            };
+            let b = match &s[i + 1..] {
+                "" => 0,
+                b => b.parse().ok()?,
+            };
+            Some((a, b))
+        }
+        None => Some((0, s.parse().ok()?)),
+    }
+}
+
+// 属性セレクタの比較方法
+// https://www.w3.org/TR/selectors-4/#attribute-selectors
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AttributeOperator {
+    Exists,         // [attr]
+    Equals,         // [attr="v"]
+    Includes,       // [attr~="v"] 空白区切りのどれかと一致
+    DashMatch,      // [attr|="v"] "v"か"v-"から始まる
+    PrefixMatch,    // [attr^="v"]
+    SuffixMatch,    // [attr$="v"]
+    SubstringMatch, // [attr*="v"]
+}
+
+impl AttributeOperator {
+    // '='の前の区切り文字から比較方法を決める
+    fn from_delim(c: char) -> Option<Self> {
+        match c {
+            '~' => Some(Self::Includes),
+            '|' => Some(Self::DashMatch),
+            '^' => Some(Self::PrefixMatch),
+            '$' => Some(Self::SuffixMatch),
+            '*' => Some(Self::SubstringMatch),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, attr_value: &str, value: &str) -> bool {
+        match self {
+            Self::Exists => true,
+            Self::Equals => attr_value == value,
+            Self::Includes => attr_value.split_ascii_whitespace().any(|v| v == value),
+            Self::DashMatch => {
+                attr_value == value
+                    || (attr_value.starts_with(value) && attr_value[value.len()..].starts_with('-'))
+            }
+            // 空文字列の場合は何にもマッチしない
+            Self::PrefixMatch => !value.is_empty() && attr_value.starts_with(value),
+            Self::SuffixMatch => !value.is_empty() && attr_value.ends_with(value),
+            Self::SubstringMatch => !value.is_empty() && attr_value.contains(value),
+        }
+    }
+}
+
 // 宣言ノード（Declaration）
 // プロパティ（property）と値（value）のセット
+// margin: 10px 20pxのように値が複数ある場合、valueは先頭の値で、valuesに全ての値を持つ
 #[derive(Debug, Clone, PartialEq)]
 pub struct Declaration {
     pub property: String,
     pub value: ComponentValue,
+    pub values: Vec<ComponentValue>,
 }
 
 impl Default for Declaration {
@@ -88,6 +399,7 @@ impl Declaration {
         Self {
             property: String::new(),
             value: ComponentValue::Ident(String::new()),
+            values: Vec::new(),
         }
     }
 
@@ -96,23 +408,38 @@ impl Declaration {
     }
 
     pub fn set_value(&mut self, value: ComponentValue) {
-        self.value = value;
+        self.value = value.clone();
+        self.values = alloc::vec![value];
+    }
+
+    pub fn add_value(&mut self, value: ComponentValue) {
+        self.values.push(value);
     }
 }
 
 // コンポーネント値ノード（Component value）
 // CSSのトークンと同等
+// ただし関数はCssToken::FunctionValueとして引数をまとめて1つの値にする
 pub type ComponentValue = CssToken;
 
 // CSSOMを構築するパーサー
 #[derive(Debug, Clone)]
 pub struct CssParser {
     t: Peekable<CssTokenizer>,
+    // 先読みしたトークンの直前に空白があったか（トークナイザと共有する）
+    after_whitespace: Rc<Cell<bool>>,
+    imports: Vec<String>,
+    errors: Vec<CssParseError>,
 }
 
 impl CssParser {
     pub fn new(t: CssTokenizer) -> Self {
-        Self { t: t.peekable() }
+        Self {
+            after_whitespace: t.after_whitespace(),
+            t: t.peekable(),
+            imports: Vec::new(),
+            errors: Vec::new(),
+        }
     }
 
     // トークン列からCSSOMを構築する
@@ -122,6 +449,8 @@ impl CssParser {
 
         // トークン列からルールのリストを作成し、StyleSheetのフィールドに設定する
         sheet.set_rules(self.consume_list_of_rules());
+        sheet.set_imports(core::mem::take(&mut self.imports));
+        sheet.set_errors(core::mem::take(&mut self.errors));
         sheet
     }
 
@@ -137,10 +466,16 @@ impl CssParser {
             };
 
             match token {
+                // @importは他のルールより前にある場合のみ有効
+                CssToken::AtKeyword(keyword) if keyword == "import" && rules.is_empty() => {
+                    self.t.next();
+                    self.consume_import_rule();
+                }
                 // AtKeywordトークンが出てきた場合、他のCSSをimportする@import、メディアクエリを表す@mediaなどのルールが始まることを表す
+                // サポートしていないルールはブロックごと読み飛ばす
                 CssToken::AtKeyword(_keyword) => {
-                    // Skip AtKeyword rules (e.g., @import, @media) by consuming them
-                    self.consume_qualified_rule();
+                    self.t.next();
+                    self.skip_at_rule();
                 }
                 _ => {
                     // １つのルールを解釈し、ベクタに追加する
@@ -154,6 +489,87 @@ impl CssParser {
         }
     }
 
+    // @importルールの解釈
+    // @import url(a.css); @import url("a.css"); @import "a.css"; の形式をサポートする
+    // URLの後のメディアクエリは無視する
+    fn consume_import_rule(&mut self) {
+        let url = match self.t.next() {
+            Some(CssToken::Url(url)) | Some(CssToken::StringToken(url)) => Some(url),
+            Some(CssToken::Function(name)) if name == "url" => match self.t.next() {
+                Some(CssToken::StringToken(url)) => Some(url),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        // ;までトークンを進める
+        for token in self.t.by_ref() {
+            if token == CssToken::SemiColon {
+                break;
+            }
+        }
+
+        if let Some(url) = url {
+            self.imports.push(url);
+        }
+    }
+
+    // @ルールを;か対応する}まで読み飛ばす
+    // @media screen { p { color: red; } } のようにブロックが入れ子になっていても1つのルールとして扱う
+    fn skip_at_rule(&mut self) {
+        while let Some(token) = self.t.next() {
+            match token {
+                CssToken::SemiColon => return,
+                CssToken::OpenCurly => {
+                    self.skip_block();
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // {の次のトークンから、対応する}までを読み飛ばす
+    fn skip_block(&mut self) {
+        let mut depth = 1;
+        for token in self.t.by_ref() {
+            match token {
+                CssToken::OpenCurly => depth += 1,
+                CssToken::CloseCurly => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // 宣言の終わり（;か、宣言ブロックを閉じる}の直前）までトークンを進める
+    fn skip_to_end_of_declaration(&mut self) {
+        while let Some(token) = self.t.peek() {
+            match token {
+                CssToken::SemiColon => {
+                    self.t.next();
+                    return;
+                }
+                CssToken::CloseCurly => return,
+                CssToken::OpenCurly => {
+                    self.t.next();
+                    self.skip_block();
+                }
+                _ => {
+                    self.t.next();
+                }
+            }
+        }
+    }
+
+    fn record_error(&mut self, message: String) {
+        self.errors.push(CssParseError::new(message));
+    }
+
     // 一つのルールの解釈
     fn consume_qualified_rule(&mut self) -> Option<QualifiedRule> {
         let mut rule = QualifiedRule::new();
@@ -161,7 +577,10 @@ impl CssParser {
         loop {
             let token = match self.t.peek() {
                 Some(t) => t,
-                None => return None,
+                None => {
+                    self.record_error("unexpected end of stylesheet in rule prelude".to_string());
+                    return None;
+                }
             };
 
             match token {
@@ -180,57 +599,191 @@ impl CssParser {
     }
 
     // セレクタの解釈
+    // div.card#mainのように連続する単純セレクタは1つの複合セレクタにまとめる
     fn consume_selector(&mut self) -> Selector {
-        let token = match self.t.next() {
-            Some(t) => t,
-            None => panic!("should have a token but got None"),
-        };
+        let mut compound: Vec<SimpleSelector> = Vec::new();
+        let mut is_unknown = false;
 
-        match token {
-            // #に続くものはID名でIDセレクタと呼ぶ
-            // #は省略する #id => id
-            // IDセレクタを作成
-            CssToken::HashToken(value) => Selector::IdSelector(value[1..].to_string()),
-            // .ならクラスセレクタを返す
-            CssToken::Delim(delim) => {
-                if delim == '.' {
-                    return Selector::ClassSelector(self.consume_ident());
-                }
-                panic!("Parse error: {:?} is an unexpected token.", token);
+        while let Some(token) = self.t.peek().cloned() {
+            // 空白で区切られた単純セレクタは子孫結合子（.a .b）なので、ルール全体を無視する
+            // トークナイザは先読みしたトークンまで進んでいるので、フラグはそのトークンのもの
+            if !compound.is_empty() && self.after_whitespace.get() && starts_simple_selector(&token)
+            {
+                is_unknown = true;
             }
-            // a:hover（マウスを置くと反応）のようなセレクタはタイプセレクタとしても扱うため、もしコロンが出てきた場合は宣言ブロックの開始直前までトークンを進める
-            // a:active（クリックすると反応）などもある
-            CssToken::Ident(ident) => {
-                if self.t.peek() == Some(&CssToken::Colon) {
-                    while let Some(token) = self.t.peek() {
-                        if token == &CssToken::OpenCurly {
-                            break;
+
+            match token {
+                // 宣言ブロックの開始でセレクタは終わり
+                CssToken::OpenCurly => break,
+                // #に続くものはID名でIDセレクタと呼ぶ
+                // #は省略する #id => id
+                CssToken::HashToken(value) => {
+                    self.t.next();
+                    compound.push(SimpleSelector::Id(value[1..].to_string()));
+                }
+                // [ なら属性セレクタ
+                CssToken::OpenSquareBracket => {
+                    self.t.next();
+                    match self.consume_attribute_selector() {
+                        Some(simple) => compound.push(simple),
+                        None => {
+                            self.skip_to_declaration_block();
+                            is_unknown = true;
                         }
-                        self.t.next();
                     }
-                    // If we exited the loop and didn't find OpenCurly, input is malformed
-                    if self.t.peek() != Some(&CssToken::OpenCurly) {
-                        // Handle malformed input: return UnknownSelector or panic
-                        return Selector::UnknownSelector;
+                }
+                // .ならクラスセレクタ
+                CssToken::Delim('.') => {
+                    self.t.next();
+                    match self.t.peek() {
+                        Some(CssToken::Ident(_)) => {
+                            compound.push(SimpleSelector::Class(self.consume_ident()))
+                        }
+                        _ => is_unknown = true,
                     }
                 }
-                Selector::TypeSelector(ident.to_string())
-            }
-            CssToken::AtKeyword(_keyword) => {
-                // @から始まるルールを無視するために、宣言ブロックの開始直前までトークンを進める
-                while let Some(token) = self.t.peek() {
-                    if token == &CssToken::OpenCurly {
-                        break;
+                // タイプセレクタと全称セレクタは複合セレクタの先頭にしか来ないので、
+                // 途中で現れた場合は子孫結合子（div p）などで区切られた次の複合セレクタとみなす
+                // 結合子はまだサポートしていないので、一部だけでマッチしないようにルール全体を無視する
+                CssToken::Ident(ident) => {
+                    self.t.next();
+                    if !compound.is_empty() {
+                        is_unknown = true;
                     }
+                    compound.push(SimpleSelector::Type(ident));
+                }
+                CssToken::Delim('*') => {
                     self.t.next();
+                    if !compound.is_empty() {
+                        is_unknown = true;
+                    }
+                    compound.push(SimpleSelector::Universal);
                 }
-                // If we exited the loop and didn't find OpenCurly, input is malformed
-                if self.t.peek() != Some(&CssToken::OpenCurly) {
-                    return Selector::UnknownSelector;
+                // 結合子とセレクタリスト（h1, h2）もサポートしていないので、同様にルール全体を無視する
+                CssToken::Delim('>')
+                | CssToken::Delim('+')
+                | CssToken::Delim('~')
+                | CssToken::Delim(',') => {
+                    self.t.next();
+                    is_unknown = true;
                 }
-                Selector::UnknownSelector
+                // a:hover（マウスを置くと反応）やa:active（クリックすると反応）などの擬似クラス
+                // サポートしていない擬似クラスや擬似要素（::before）の場合はルール全体を無視する
+                CssToken::Colon => {
+                    self.t.next();
+                    match self.consume_pseudo_class() {
+                        Some(pseudo_class) => {
+                            compound.push(SimpleSelector::PseudoClass(pseudo_class))
+                        }
+                        None => {
+                            self.skip_to_declaration_block();
+                            is_unknown = true;
+                        }
+                    }
+                }
+                CssToken::AtKeyword(_keyword) => {
+                    // @から始まるルールを無視するために、宣言ブロックの開始直前までトークンを進める
+                    self.skip_to_declaration_block();
+                    is_unknown = true;
+                }
+                _ => {
+                    self.t.next();
+                    is_unknown = true;
+                }
+            }
+        }
+
+        if is_unknown || compound.is_empty() {
+            return Selector::UnknownSelector;
+        }
+
+        if compound.len() == 1 {
+            return compound[0].clone().into_selector();
+        }
+
+        Selector::Compound(compound)
+    }
+
+    // 属性セレクタの解釈
+    // '['の次のトークンから']'までを読む
+    fn consume_attribute_selector(&mut self) -> Option<SimpleSelector> {
+        let name = match self.t.next() {
+            Some(CssToken::Ident(name)) => name,
+            _ => return None,
+        };
+
+        let operator = match self.t.next() {
+            Some(CssToken::CloseSquareBracket) => {
+                return Some(SimpleSelector::Attribute {
+                    name,
+                    operator: AttributeOperator::Exists,
+                    value: String::new(),
+                })
+            }
+            Some(CssToken::Delim('=')) => AttributeOperator::Equals,
+            Some(CssToken::Delim(c)) => {
+                let operator = AttributeOperator::from_delim(c)?;
+                if self.t.next() != Some(CssToken::Delim('=')) {
+                    return None;
+                }
+                operator
+            }
+            _ => return None,
+        };
+
+        let value = match self.t.next() {
+            Some(CssToken::Ident(value)) | Some(CssToken::StringToken(value)) => value,
+            _ => return None,
+        };
+
+        if self.t.next() != Some(CssToken::CloseSquareBracket) {
+            return None;
+        }
+
+        Some(SimpleSelector::Attribute {
+            name,
+            operator,
+            value,
+        })
+    }
+
+    // 擬似クラスの解釈
+    // ':'の次のトークンから読む
+    fn consume_pseudo_class(&mut self) -> Option<PseudoClass> {
+        match self.t.next() {
+            Some(CssToken::Ident(name)) => PseudoClass::from_name(&name),
+            Some(CssToken::Function(name)) if name == "nth-child" => {
+                // ')'までのトークンを文字列に戻してAn+Bとして解釈する
+                let mut argument = String::new();
+                loop {
+                    match self.t.next()? {
+                        CssToken::CloseParenthesis => break,
+                        CssToken::Ident(ident) => argument.push_str(&ident),
+                        CssToken::Delim(c) => argument.push(c),
+                        CssToken::Number(n) => {
+                            // 符号付きの数字は符号が取り除かれているので、正の数の場合は'+'を補う
+                            if n >= 0.0 && !argument.is_empty() && !argument.ends_with('+') {
+                                argument.push('+');
+                            }
+                            argument.push_str(&format!("{}", n));
+                        }
+                        _ => return None,
+                    }
+                }
+                let (a, b) = parse_an_plus_b(&argument)?;
+                Some(PseudoClass::NthChild { a, b })
+            }
+            _ => None,
+        }
+    }
+
+    // 宣言ブロックの開始（{）の直前までトークンを進める
+    fn skip_to_declaration_block(&mut self) {
+        while let Some(token) = self.t.peek() {
+            if token == &CssToken::OpenCurly {
+                break;
             }
-            _ => Selector::UnknownSelector,
+            self.t.next();
         }
     }
 
@@ -260,8 +813,11 @@ impl CssParser {
                         declarations.push(declaration);
                     }
                 }
+                // 宣言の始まりではないトークンは、その宣言の終わりまで読み飛ばす
                 _ => {
-                    self.t.next();
+                    let message = format!("unexpected token {:?} in declaration list", token);
+                    self.record_error(message);
+                    self.skip_to_end_of_declaration();
                 }
             }
         }
@@ -276,16 +832,53 @@ impl CssParser {
         // Declaration構造体のプロパティに識別子を設定
         declaration.set_property(self.consume_ident());
 
-        // もし次のトークンがコロンでない場合、パースエラーなので、Noneを返す
+        // もし次のトークンがコロンでない場合、パースエラーなので、宣言の終わりまで読み飛ばしてNoneを返す
         // 想定だと property: value
-        match self.t.next() {
-            Some(CssToken::Colon) => {}
-            _ => return None,
+        match self.t.peek() {
+            Some(CssToken::Colon) => {
+                self.t.next();
+            }
+            _ => {
+                let message = format!("expected ':' after property {}", declaration.property);
+                self.record_error(message);
+                self.skip_to_end_of_declaration();
+                return None;
+            }
+        }
+
+        // 値がない場合もパースエラー
+        match self.t.peek() {
+            None | Some(CssToken::SemiColon) | Some(CssToken::CloseCurly) => {
+                let message = format!("missing value for property {}", declaration.property);
+                self.record_error(message);
+                self.skip_to_end_of_declaration();
+                return None;
+            }
+            _ => {}
         }
 
         // Declaration構造体の値にコンポーネント値を設定する
         declaration.set_value(self.consume_component_value());
 
+        // 2つ目以降の値を宣言の終わりまで読み込む
+        loop {
+            match self.t.peek() {
+                None | Some(CssToken::CloseCurly) => break,
+                Some(CssToken::SemiColon) => {
+                    self.t.next();
+                    break;
+                }
+                Some(CssToken::OpenCurly) => {
+                    self.t.next();
+                    self.skip_block();
+                }
+                Some(_) => {
+                    let value = self.consume_component_value();
+                    declaration.add_value(value);
+                }
+            }
+        }
+
         Some(declaration)
     }
 
@@ -307,12 +900,51 @@ impl CssParser {
     // コンポーネント値の解釈
     // コンポーネント値はCSSのトークンと同等なので、存在をすることを確認
     fn consume_component_value(&mut self) -> ComponentValue {
-        self.t
+        let token = self
+            .t
             .next()
-            .expect("should have a token in consume_component_value")
+            .expect("should have a token in consume_component_value");
+
+        match token {
+            CssToken::Function(name) => self.consume_function(name),
+            _ => token,
+        }
+    }
+
+    // 関数の解釈
+    // 閉じ丸括弧（)）が出てくるまでを引数とする
+    fn consume_function(&mut self, name: String) -> ComponentValue {
+        let mut arguments = Vec::new();
+
+        loop {
+            match self.t.peek() {
+                None => break,
+                Some(CssToken::CloseParenthesis) => {
+                    self.t.next();
+                    break;
+                }
+                // calc(var(--x))のように関数は入れ子になることがある
+                Some(_) => arguments.push(self.consume_component_value()),
+            }
+        }
+
+        CssToken::FunctionValue { name, arguments }
     }
 }
 
+// 単純セレクタ（タイプ・全称・クラス・ID・属性・擬似クラス）の始まりになるトークンか
+fn starts_simple_selector(token: &CssToken) -> bool {
+    matches!(
+        token,
+        CssToken::Ident(_)
+            | CssToken::HashToken(_)
+            | CssToken::Delim('.')
+            | CssToken::Delim('*')
+            | CssToken::OpenSquareBracket
+            | CssToken::Colon
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -428,4 +1060,312 @@ mod tests {
             assert_eq!(exp, rule);
         }
     }
+
+    // 関数の値のテスト
+    #[test]
+    fn test_function_value() {
+        let style = "p { color: rgb(255, 0, 0); width: calc(100 - min(1, 2)); }".to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        assert_eq!(cssom.rules.len(), 1);
+        let declarations = &cssom.rules[0].declarations;
+        assert_eq!(declarations.len(), 2);
+        assert_eq!(
+            ComponentValue::FunctionValue {
+                name: "rgb".to_string(),
+                arguments: vec![
+                    CssToken::Number(255.0),
+                    CssToken::Delim(','),
+                    CssToken::Number(0.0),
+                    CssToken::Delim(','),
+                    CssToken::Number(0.0),
+                ],
+            },
+            declarations[0].value
+        );
+        assert_eq!(
+            ComponentValue::FunctionValue {
+                name: "calc".to_string(),
+                arguments: vec![
+                    CssToken::Number(100.0),
+                    CssToken::Ident("-".to_string()),
+                    CssToken::FunctionValue {
+                        name: "min".to_string(),
+                        arguments: vec![
+                            CssToken::Number(1.0),
+                            CssToken::Delim(','),
+                            CssToken::Number(2.0),
+                        ],
+                    },
+                ],
+            },
+            declarations[1].value
+        );
+    }
+
+    // 全称セレクタと複合セレクタのテスト
+    #[test]
+    fn test_universal_and_compound_selector() {
+        let style =
+            "* { color: red; } div.card#main { color: blue; } p.a.b { color: green; }".to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        let expected = [
+            Selector::UniversalSelector,
+            Selector::Compound(vec![
+                SimpleSelector::Type("div".to_string()),
+                SimpleSelector::Class("card".to_string()),
+                SimpleSelector::Id("main".to_string()),
+            ]),
+            Selector::Compound(vec![
+                SimpleSelector::Type("p".to_string()),
+                SimpleSelector::Class("a".to_string()),
+                SimpleSelector::Class("b".to_string()),
+            ]),
+        ];
+        assert_eq!(cssom.rules.len(), expected.len());
+
+        for (exp, rule) in expected.into_iter().zip(cssom.rules) {
+            assert_eq!(exp, rule.selector);
+        }
+    }
+
+    // 結合子やセレクタリストを含むルールは、一部だけでマッチしないように無視する
+    #[test]
+    fn test_combinator_is_unknown() {
+        let style =
+            "div p.x { color: red; } a > b { color: red; } h1, h2 { color: red; } p { color: red; }"
+                .to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        let expected = [
+            Selector::UnknownSelector,
+            Selector::UnknownSelector,
+            Selector::UnknownSelector,
+            Selector::TypeSelector("p".to_string()),
+        ];
+        assert_eq!(cssom.rules.len(), expected.len());
+
+        for (exp, rule) in expected.into_iter().zip(cssom.rules) {
+            assert_eq!(exp, rule.selector);
+        }
+    }
+
+    // 属性セレクタのテスト
+    #[test]
+    fn test_attribute_selector() {
+        let style = "[href] { color: red; } a[target=\"_blank\"] { color: blue; } [href^='http'] { color: green; }"
+            .to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        let expected = [
+            Selector::Compound(vec![SimpleSelector::Attribute {
+                name: "href".to_string(),
+                operator: AttributeOperator::Exists,
+                value: "".to_string(),
+            }]),
+            Selector::Compound(vec![
+                SimpleSelector::Type("a".to_string()),
+                SimpleSelector::Attribute {
+                    name: "target".to_string(),
+                    operator: AttributeOperator::Equals,
+                    value: "_blank".to_string(),
+                },
+            ]),
+            Selector::Compound(vec![SimpleSelector::Attribute {
+                name: "href".to_string(),
+                operator: AttributeOperator::PrefixMatch,
+                value: "http".to_string(),
+            }]),
+        ];
+        assert_eq!(cssom.rules.len(), expected.len());
+
+        for (exp, rule) in expected.into_iter().zip(cssom.rules) {
+            assert_eq!(exp, rule.selector);
+        }
+    }
+
+    // 属性セレクタの比較方法のテスト
+    #[test]
+    fn test_attribute_operator() {
+        assert!(AttributeOperator::Exists.matches("", ""));
+        assert!(AttributeOperator::Equals.matches("a", "a"));
+        assert!(!AttributeOperator::Equals.matches("ab", "a"));
+        assert!(AttributeOperator::Includes.matches("a b c", "b"));
+        assert!(AttributeOperator::DashMatch.matches("en-US", "en"));
+        assert!(!AttributeOperator::DashMatch.matches("english", "en"));
+        assert!(AttributeOperator::PrefixMatch.matches("http://a", "http"));
+        assert!(!AttributeOperator::PrefixMatch.matches("http://a", ""));
+        assert!(AttributeOperator::SuffixMatch.matches("a.png", ".png"));
+        assert!(AttributeOperator::SubstringMatch.matches("abc", "b"));
+    }
+
+    // 擬似クラスのテスト
+    #[test]
+    fn test_pseudo_class() {
+        let style = "a:hover { color: red; } li:first-child { color: blue; } li:nth-child(2n+1) { color: green; } p::before { color: red; }"
+            .to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        let expected = [
+            Selector::Compound(vec![
+                SimpleSelector::Type("a".to_string()),
+                SimpleSelector::PseudoClass(PseudoClass::Hover),
+            ]),
+            Selector::Compound(vec![
+                SimpleSelector::Type("li".to_string()),
+                SimpleSelector::PseudoClass(PseudoClass::FirstChild),
+            ]),
+            Selector::Compound(vec![
+                SimpleSelector::Type("li".to_string()),
+                SimpleSelector::PseudoClass(PseudoClass::NthChild { a: 2, b: 1 }),
+            ]),
+            Selector::UnknownSelector,
+        ];
+        assert_eq!(cssom.rules.len(), expected.len());
+
+        for (exp, rule) in expected.into_iter().zip(cssom.rules) {
+            assert_eq!(exp, rule.selector);
+        }
+    }
+
+    // An+Bの解釈のテスト
+    #[test]
+    fn test_parse_an_plus_b() {
+        assert_eq!(Some((2, 1)), parse_an_plus_b("odd"));
+        assert_eq!(Some((2, 0)), parse_an_plus_b("even"));
+        assert_eq!(Some((0, 3)), parse_an_plus_b("3"));
+        assert_eq!(Some((1, 0)), parse_an_plus_b("n"));
+        assert_eq!(Some((-1, 3)), parse_an_plus_b("-n+3"));
+        assert_eq!(Some((3, -1)), parse_an_plus_b("3n-1"));
+        assert_eq!(None, parse_an_plus_b("x"));
+    }
+
+    // 詳細度のテスト
+    #[test]
+    fn test_specificity() {
+        let style = "h1 { color: red; } .a { color: red; } #b { color: red; } * { color: red; } h1.a#b[x]:hover { color: red; }"
+            .to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        let expected = [(0, 0, 1), (0, 1, 0), (1, 0, 0), (0, 0, 0), (1, 3, 1)];
+        assert_eq!(cssom.rules.len(), expected.len());
+
+        for (exp, rule) in expected.into_iter().zip(cssom.rules) {
+            assert_eq!(exp, rule.selector.specificity());
+        }
+    }
+
+    // @importのテスト
+    #[test]
+    fn test_import() {
+        let style = "@import url(a.css); @import url(\"b.css\") screen; @import 'c.css'; p { color: red; } @import 'd.css';"
+            .to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        assert_eq!(
+            vec![
+                "a.css".to_string(),
+                "b.css".to_string(),
+                "c.css".to_string()
+            ],
+            cssom.imports
+        );
+        assert_eq!(1, cssom.rules.len());
+        assert_eq!(
+            Selector::TypeSelector("p".to_string()),
+            cssom.rules[0].selector
+        );
+    }
+
+    // インポートしたルールが前にマージされるか
+    #[test]
+    fn test_merge_imports() {
+        let mut sheet = CssParser::new(CssTokenizer::new(
+            "@import 'a.css'; p { color: red; }".to_string(),
+        ))
+        .parse_stylesheet();
+        let imported =
+            CssParser::new(CssTokenizer::new("h1 { color: blue; }".to_string())).parse_stylesheet();
+        sheet.merge_imports(vec![imported]);
+
+        assert!(sheet.imports.is_empty());
+        assert_eq!(2, sheet.rules.len());
+        assert_eq!(
+            Selector::TypeSelector("h1".to_string()),
+            sheet.rules[0].selector
+        );
+        assert_eq!(
+            Selector::TypeSelector("p".to_string()),
+            sheet.rules[1].selector
+        );
+    }
+
+    // エラーのある宣言やルールを読み飛ばしてパースを続けるか
+    #[test]
+    fn test_error_recovery() {
+        let style = "p { color red; background-color: ; 10px; display: none; } @media screen { h1 { color: blue; } } h2 { color: green; } h3 "
+            .to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        assert_eq!(2, cssom.rules.len());
+        assert_eq!(
+            Selector::TypeSelector("p".to_string()),
+            cssom.rules[0].selector
+        );
+        assert_eq!(
+            vec![Declaration {
+                property: "display".to_string(),
+                value: ComponentValue::Ident("none".to_string()),
+                values: vec![ComponentValue::Ident("none".to_string())],
+            }],
+            cssom.rules[0].declarations
+        );
+        assert_eq!(
+            Selector::TypeSelector("h2".to_string()),
+            cssom.rules[1].selector
+        );
+        assert_eq!(
+            "green".to_string(),
+            match &cssom.rules[1].declarations[0].value {
+                ComponentValue::Ident(v) => v.clone(),
+                _ => "".to_string(),
+            }
+        );
+        // color red、background-color: 、10px、h3 の4つ
+        assert_eq!(4, cssom.errors.len());
+    }
+
+    // 空白で区切られた子孫結合子は、1つの複合セレクタにまとめずに無視する
+    #[test]
+    fn test_descendant_combinator_is_unknown() {
+        let style =
+            ".a .b { color: red; } p .x { color: red; } #x .y { color: red; } p.x { color: red; }"
+                .to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        let expected = [
+            Selector::UnknownSelector,
+            Selector::UnknownSelector,
+            Selector::UnknownSelector,
+            Selector::Compound(vec![
+                SimpleSelector::Type("p".to_string()),
+                SimpleSelector::Class("x".to_string()),
+            ]),
+        ];
+        assert_eq!(cssom.rules.len(), expected.len());
+
+        for (exp, rule) in expected.into_iter().zip(cssom.rules) {
+            assert_eq!(exp, rule.selector);
+        }
+    }
 }
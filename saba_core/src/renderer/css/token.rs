@@ -1,5 +1,7 @@
+use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::vec::Vec;
+use core::cell::Cell;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum CssToken {
@@ -12,15 +14,28 @@ pub enum CssToken {
     CloseParenthesis,    // 丸括弧（閉じ）　')'
     OpenCurly,           // 波括弧（開き）　'{'
     CloseCurly,          // 波括弧（閉じ）　'}'
+    OpenSquareBracket,   // 角括弧（開き）　'['
+    CloseSquareBracket,  // 角括弧（閉じ）　']'
     Ident(String),       // 識別子トークン
     StringToken(String), // 文字列トークン
     AtKeyword(String),   // アットキーワードトークン
+    Function(String),    // 関数トークン　'rgb(' の 'rgb'
+    Url(String),         // URLトークン　url(bg.png) の 'bg.png'
+    // 関数の値　rgb(255, 0, 0) など
+    // トークナイザは作らず、CSSOMの構築時に関数トークンと引数をまとめて作る
+    FunctionValue {
+        name: String,
+        arguments: Vec<CssToken>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct CssTokenizer {
     pos: usize,
     input: Vec<char>,
+    // 最後に返したトークンの直前に空白があったか
+    // 空白はトークンにしないので、セレクタの子孫結合子（div p）を見分けるためにパーサーと共有する
+    after_whitespace: Rc<Cell<bool>>,
 }
 
 impl CssTokenizer {
@@ -29,24 +44,32 @@ impl CssTokenizer {
         Self {
             pos: 0,
             input: css.chars().collect(),
+            after_whitespace: Rc::new(Cell::new(false)),
         }
     }
 
-    // もう一度"や'が出てくるまで、文字列を消費
+    // 最後に返したトークンの直前に空白があったかを表すフラグ
+    pub fn after_whitespace(&self) -> Rc<Cell<bool>> {
+        self.after_whitespace.clone()
+    }
+
+    // 開始と同じ"か'が出てくるまで、文字列を消費
+    // 閉じられないまま入力が終わった場合は、そこまでを文字列とする
     fn consume_string_token(&mut self) -> String {
+        let quote = self.input[self.pos];
         let mut s = String::new();
 
         loop {
+            self.pos += 1;
             if self.pos >= self.input.len() {
                 return s;
             }
 
-            self.pos += 1;
             let c = self.input[self.pos];
-            match c {
-                '"' | '\'' => break,
-                _ => s.push(c),
+            if c == quote {
+                break;
             }
+            s.push(c);
         }
         s
     }
@@ -89,6 +112,55 @@ impl CssTokenizer {
         num
     }
 
+    // 識別子、関数トークン、URLトークンのいずれかを作る
+    // 識別子の直後に'('が続く場合は関数トークン
+    fn consume_ident_like_token(&mut self) -> CssToken {
+        let name = self.consume_ident_token();
+
+        if self.input.get(self.pos) == Some(&'(') {
+            if name.eq_ignore_ascii_case("url") {
+                // 引用符で囲まれていないURLはURLトークンとして扱う
+                let mut p = self.pos + 1;
+                while matches!(self.input.get(p), Some(' ') | Some('\n')) {
+                    p += 1;
+                }
+                if !matches!(self.input.get(p), Some('"') | Some('\'')) {
+                    self.pos = p;
+                    return CssToken::Url(self.consume_url_token());
+                }
+            }
+            // '('の位置にいるので、呼び出し元で次の文字に進めば'('は消費される
+            return CssToken::Function(name);
+        }
+
+        // 識別子の次の文字まで進んでいるので1つ戻す
+        self.pos -= 1;
+        CssToken::Ident(name)
+    }
+
+    // ')'が出てくるまでの文字列をURLとして消費する
+    // 終了時は')'の位置にいる
+    fn consume_url_token(&mut self) -> String {
+        let mut url = String::new();
+        while let Some(&c) = self.input.get(self.pos) {
+            if c == ')' {
+                break;
+            }
+            url.push(c);
+            self.pos += 1;
+        }
+        String::from(url.trim_end())
+    }
+
+    // posの位置から数字が始まるかどうか（"1"や".5"）
+    fn starts_number(&self, pos: usize) -> bool {
+        match self.input.get(pos) {
+            Some(c) if c.is_ascii_digit() => true,
+            Some('.') => matches!(self.input.get(pos + 1), Some(c) if c.is_ascii_digit()),
+            _ => false,
+        }
+    }
+
     // 文字、数字、ハイフン(-)、アンダースコア(_)が出続けている間は識別子として扱う
     fn consume_ident_token(&mut self) -> String {
         let mut s = String::new();
@@ -96,7 +168,11 @@ impl CssTokenizer {
 
         loop {
             self.pos += 1;
-            let c = self.input[self.pos];
+            // 入力の終わりに達した場合も識別子の終わりとする
+            let c = match self.input.get(self.pos) {
+                Some(&c) => c,
+                None => break,
+            };
             match c {
                 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => {
                     s.push(c);
@@ -115,6 +191,7 @@ impl Iterator for CssTokenizer {
 
     // 入力のCSS文字列の1文字ずつ見ていく
     fn next(&mut self) -> Option<Self::Item> {
+        self.after_whitespace.set(false);
         loop {
             if self.pos >= self.input.len() {
                 return None;
@@ -128,13 +205,20 @@ impl Iterator for CssTokenizer {
                 ')' => CssToken::CloseParenthesis,
                 ',' => CssToken::Delim(','),
                 '.' => CssToken::Delim('.'),
+                '*' => CssToken::Delim('*'),
+                '>' => CssToken::Delim('>'),
+                '~' => CssToken::Delim('~'),
                 ':' => CssToken::Colon,
                 ';' => CssToken::SemiColon,
                 '{' => CssToken::OpenCurly,
                 '}' => CssToken::CloseCurly,
-                ' ' | '\n' => {
+                '[' => CssToken::OpenSquareBracket,
+                ']' => CssToken::CloseSquareBracket,
+                '=' | '^' | '$' | '|' => CssToken::Delim(c),
+                ' ' | '\n' | '\t' | '\r' => {
                     // 今は一時的に空文字と改行をスキップ
                     self.pos += 1;
+                    self.after_whitespace.set(true);
                     continue;
                 }
                 '"' | '\'' => {
@@ -155,10 +239,26 @@ impl Iterator for CssTokenizer {
                     CssToken::HashToken(value)
                 }
                 '-' => {
-                    // 負の数は取り扱わないので、識別子の一つとして扱う
-                    let t = CssToken::Ident(self.consume_ident_token());
-                    self.pos -= 1;
-                    t
+                    // 数字が続く場合は負の数、それ以外は識別子の一つとして扱う
+                    if self.starts_number(self.pos + 1) {
+                        self.pos += 1;
+                        let t = CssToken::Number(-self.consume_numeric_token());
+                        self.pos -= 1;
+                        t
+                    } else {
+                        self.consume_ident_like_token()
+                    }
+                }
+                '+' => {
+                    // 数字が続く場合は正の数、それ以外は区切り文字
+                    if self.starts_number(self.pos + 1) {
+                        self.pos += 1;
+                        let t = CssToken::Number(self.consume_numeric_token());
+                        self.pos -= 1;
+                        t
+                    } else {
+                        CssToken::Delim('+')
+                    }
                 }
                 '@' => {
                     // 次の3文字が識別子として有効な文字の場合、at-keyword-tokenトークンを作成して返す
@@ -176,14 +276,9 @@ impl Iterator for CssTokenizer {
                         CssToken::Delim('@')
                     }
                 }
-                'a'..='z' | 'A'..='Z' | '_' => {
-                    let t = CssToken::Ident(self.consume_ident_token());
-                    self.pos -= 1;
-                    t
-                }
-                _ => {
-                    unimplemented!("char {} is not implemented yet", c);
-                }
+                'a'..='z' | 'A'..='Z' | '_' => self.consume_ident_like_token(),
+                // サポートしていない文字は区切り文字として扱い、パーサーに任せる
+                _ => CssToken::Delim(c),
             };
 
             // 次の文字へ移動
@@ -298,4 +393,87 @@ mod tests {
         }
         assert!(t.next().is_none());
     }
+
+    // 符号付きの数字
+    #[test]
+    fn test_signed_number() {
+        let style = "p { margin-left: -10px; top: +2.5; z: -.5; }".to_string();
+        let mut t = CssTokenizer::new(style);
+        let expected = [
+            CssToken::Ident("p".to_string()),
+            CssToken::OpenCurly,
+            CssToken::Ident("margin-left".to_string()),
+            CssToken::Colon,
+            CssToken::Number(-10.0),
+            CssToken::Ident("px".to_string()),
+            CssToken::SemiColon,
+            CssToken::Ident("top".to_string()),
+            CssToken::Colon,
+            CssToken::Number(2.5),
+            CssToken::SemiColon,
+            CssToken::Ident("z".to_string()),
+            CssToken::Colon,
+            CssToken::Number(-0.5),
+            CssToken::SemiColon,
+            CssToken::CloseCurly,
+        ];
+        for e in expected {
+            assert_eq!(Some(e.clone()), t.next());
+        }
+        assert!(t.next().is_none());
+    }
+
+    // 関数トークンとURLトークン
+    #[test]
+    fn test_function() {
+        let style = "p { color: rgb(255, 0, 0); background: url( bg.png ); }".to_string();
+        let mut t = CssTokenizer::new(style);
+        let expected = [
+            CssToken::Ident("p".to_string()),
+            CssToken::OpenCurly,
+            CssToken::Ident("color".to_string()),
+            CssToken::Colon,
+            CssToken::Function("rgb".to_string()),
+            CssToken::Number(255.0),
+            CssToken::Delim(','),
+            CssToken::Number(0.0),
+            CssToken::Delim(','),
+            CssToken::Number(0.0),
+            CssToken::CloseParenthesis,
+            CssToken::SemiColon,
+            CssToken::Ident("background".to_string()),
+            CssToken::Colon,
+            CssToken::Url("bg.png".to_string()),
+            CssToken::SemiColon,
+            CssToken::CloseCurly,
+        ];
+        for e in expected {
+            assert_eq!(Some(e.clone()), t.next());
+        }
+        assert!(t.next().is_none());
+    }
+
+    // サポートしていない文字や途中で終わる入力でもパニックしないか
+    #[test]
+    fn test_unknown_and_truncated() {
+        let style = "a/b! あ".to_string();
+        let t = CssTokenizer::new(style);
+        let expected = [
+            CssToken::Ident("a".to_string()),
+            CssToken::Delim('/'),
+            CssToken::Ident("b".to_string()),
+            CssToken::Delim('!'),
+            CssToken::Delim('あ'),
+        ];
+        assert_eq!(expected.to_vec(), t.collect::<Vec<CssToken>>());
+
+        let t = CssTokenizer::new("p { color".to_string());
+        assert_eq!(Some(CssToken::Ident("color".to_string())), t.last());
+
+        let t = CssTokenizer::new("content: 'it\"s".to_string());
+        assert_eq!(Some(CssToken::StringToken("it\"s".to_string())), t.last());
+
+        let t = CssTokenizer::new("#".to_string());
+        assert_eq!(Some(CssToken::HashToken("#".to_string())), t.last());
+    }
 }
@@ -0,0 +1,31 @@
+use crate::renderer::css::cssom::CascadeOrigin;
+use crate::renderer::css::cssom::CssParser;
+use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::token::CssTokenizer;
+use alloc::string::ToString;
+
+// ブラウザが持つデフォルトのスタイルシート（UAスタイルシート）
+// https://html.spec.whatwg.org/multipage/rendering.html
+const UA_STYLESHEET: &str = r#"
+style { display: none; }
+script { display: none; }
+strong { font-weight: bold; }
+em { font-style: italic; }
+a[href] { color: blue; text-decoration: underline; }
+ul { padding-left: 40px; }
+ol { padding-left: 40px; }
+hr { margin: 8px 0; border: 1px inset gray; }
+pre { white-space: pre; font-family: monospace; }
+input { border: 1px solid gray; padding: 1px 2px; }
+input[type=hidden] { display: none; }
+button { display: inline-block; border: 1px outset gray; padding: 1px 6px; }
+"#;
+
+// UAスタイルシートを作成する
+// 作成者のスタイルシートより前に置き、どのルールよりも優先度を低くする
+pub fn ua_stylesheet() -> StyleSheet {
+    let t = CssTokenizer::new(UA_STYLESHEET.to_string());
+    let mut sheet = CssParser::new(t).parse_stylesheet();
+    sheet.set_origin(CascadeOrigin::UserAgent);
+    sheet
+}
@@ -1,42 +1,68 @@
-use crate::renderer::dom::node::Element;
 use crate::renderer::dom::node::ElementKind;
+use crate::renderer::dom::node::ElementState;
 use crate::renderer::dom::node::Node;
 use crate::renderer::dom::node::NodeKind;
+use crate::renderer::dom::node::Window;
 use alloc::rc::Rc;
+use alloc::rc::Weak;
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
+// 行きがけ順で最初に見つかった特定の種類の要素を返す
+// 探索は引数のノードから始まり、その子孫と後ろの兄弟ノードを対象にする
 pub fn get_target_element_node(
     node: Option<Rc<RefCell<Node>>>,
     element_kind: ElementKind,
 ) -> Option<Rc<RefCell<Node>>> {
-    match node {
-        Some(n) => {
-            // impl<T: Display + ?Sized> ToString for T
-            // ElementKind は Display を実装していないため .to_string() は Debug 表現 (例: "Html") を返す点に注意
-            if n.borrow().kind()
-                == NodeKind::Element(Element::new(&element_kind.to_string(), Vec::new()))
-            {
-                return Some(n.clone());
-            }
+    let mut stack = Vec::new();
+    if let Some(n) = node {
+        stack.push(n);
+    }
 
-            // 子供のノードに対して探索
-            let result1 = get_target_element_node(n.borrow().first_child(), element_kind);
-            // 兄弟のノードに対して探索
-            let result2 = get_target_element_node(n.borrow().next_sibling(), element_kind);
+    while let Some(n) = stack.pop() {
+        if n.borrow().element_kind() == Some(element_kind) {
+            return Some(n);
+        }
 
-            if result1.is_none() && result2.is_none() {
-                return None;
-            }
-            if result1.is_none() {
-                return result2;
-            }
-            result1
+        // 子供のノードを兄弟のノードより先に探索するため、兄弟から先にスタックに積む
+        if let Some(sibling) = n.borrow().next_sibling() {
+            stack.push(sibling);
+        }
+        if let Some(child) = n.borrow().first_child() {
+            stack.push(child);
+        }
+    }
+
+    None
+}
+
+// 特定の種類の要素を全て行きがけ順で返す
+pub fn get_target_element_nodes(
+    node: Option<Rc<RefCell<Node>>>,
+    element_kind: ElementKind,
+) -> Vec<Rc<RefCell<Node>>> {
+    let mut result = Vec::new();
+    let mut stack = Vec::new();
+    if let Some(n) = node {
+        stack.push(n);
+    }
+
+    while let Some(n) = stack.pop() {
+        if n.borrow().element_kind() == Some(element_kind) {
+            result.push(n.clone());
+        }
+
+        if let Some(sibling) = n.borrow().next_sibling() {
+            stack.push(sibling);
+        }
+        if let Some(child) = n.borrow().first_child() {
+            stack.push(child);
         }
-        None => None,
     }
+
+    result
 }
 
 pub fn get_style_content(root: Rc<RefCell<Node>>) -> String {
@@ -54,3 +80,632 @@ pub fn get_style_content(root: Rc<RefCell<Node>>) -> String {
     };
     content
 }
+
+// 全ての<style>要素の中身を文書順で返す
+pub fn get_style_contents(root: Rc<RefCell<Node>>) -> Vec<String> {
+    get_target_element_nodes(Some(root), ElementKind::Style)
+        .iter()
+        .map(|style_node| match style_node.borrow().first_child() {
+            Some(text_node) => match text_node.borrow().kind() {
+                NodeKind::Text(s) => s,
+                _ => "".to_string(),
+            },
+            None => "".to_string(),
+        })
+        .collect()
+}
+
+// 文書に含まれるスタイルシートの取得元
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StyleSource {
+    // <style>要素の中身
+    Inline(String),
+    // <link rel="stylesheet">のhref属性の値（解決する前のURL）
+    Link(String),
+}
+
+// <style>要素と<link rel="stylesheet">要素を集め、文書順で返す
+// カスケードでは後のスタイルシートのルールほど優先されるので、順番を保つ
+pub fn get_style_sources(root: Rc<RefCell<Node>>) -> Vec<StyleSource> {
+    let mut result = Vec::new();
+    let mut stack = alloc::vec![root];
+    while let Some(n) = stack.pop() {
+        match n.borrow().element_kind() {
+            Some(ElementKind::Style) => {
+                let content = match n.borrow().first_child() {
+                    Some(text_node) => match text_node.borrow().kind() {
+                        NodeKind::Text(s) => s,
+                        _ => "".to_string(),
+                    },
+                    None => "".to_string(),
+                };
+                result.push(StyleSource::Inline(content));
+            }
+            Some(ElementKind::Link) => {
+                if let Some(href) = stylesheet_link_href(&n) {
+                    result.push(StyleSource::Link(href));
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(sibling) = n.borrow().next_sibling() {
+            stack.push(sibling);
+        }
+        if let Some(child) = n.borrow().first_child() {
+            stack.push(child);
+        }
+    }
+    result
+}
+
+// rel属性にstylesheetが含まれる<link>要素のhref属性の値
+// rel属性は空白区切りで、大文字と小文字を区別しない
+// 代替スタイルシート（alternate stylesheet）はユーザーが選ぶまで適用しないので含めない
+fn stylesheet_link_href(node: &Rc<RefCell<Node>>) -> Option<String> {
+    let element = node.borrow().get_element()?;
+    let rel = element.get_attribute("rel")?;
+    let has_keyword = |name: &str| {
+        rel.split_ascii_whitespace()
+            .any(|keyword| keyword.eq_ignore_ascii_case(name))
+    };
+    if !has_keyword("stylesheet") || has_keyword("alternate") {
+        return None;
+    }
+    element
+        .get_attribute("href")
+        .filter(|href| !href.is_empty())
+}
+
+// ノードが変更されたことを記録し、祖先のノードに子孫が変更されたことを伝える
+// Pageはルートのフラグを見て、スタイルやレイアウトの再計算が必要かどうかを判断する
+pub fn mark_dirty(node: &Rc<RefCell<Node>>) {
+    node.borrow_mut().set_dirty(true);
+
+    let mut parent = node.borrow().parent().upgrade();
+    while let Some(p) = parent {
+        // すでにフラグが立っている場合、それより上の祖先も立っている
+        if p.borrow().is_child_dirty() {
+            break;
+        }
+        p.borrow_mut().set_child_dirty(true);
+        parent = p.borrow().parent().upgrade();
+    }
+}
+
+// ツリーのどこかが変更されているか
+pub fn needs_update(root: &Rc<RefCell<Node>>) -> bool {
+    let node = root.borrow();
+    node.is_dirty() || node.is_child_dirty()
+}
+
+// ツリー全体の変更フラグを下ろす
+pub fn clear_dirty(root: &Rc<RefCell<Node>>) {
+    let mut stack = alloc::vec![root.clone()];
+    while let Some(n) = stack.pop() {
+        if !n.borrow().is_dirty() && !n.borrow().is_child_dirty() {
+            continue;
+        }
+        n.borrow_mut().set_dirty(false);
+        n.borrow_mut().set_child_dirty(false);
+
+        let mut child = n.borrow().first_child();
+        while let Some(c) = child {
+            child = c.borrow().next_sibling();
+            stack.push(c);
+        }
+    }
+}
+
+// 親ノードの最後の子としてノードを追加する
+// すでにツリーの中にあるノードは、元の位置から取り除いてから追加する（移動する）
+pub fn append_child(parent: &Rc<RefCell<Node>>, child: Rc<RefCell<Node>>) {
+    let old_parent = child.borrow().parent().upgrade();
+    if let Some(old_parent) = old_parent {
+        remove_child(&old_parent, &child);
+    }
+
+    let last_child = parent.borrow().last_child().upgrade();
+    match last_child {
+        Some(last) => {
+            last.borrow_mut().set_next_sibling(Some(child.clone()));
+            child
+                .borrow_mut()
+                .set_previous_sibling(Rc::downgrade(&last));
+        }
+        None => parent.borrow_mut().set_first_child(Some(child.clone())),
+    }
+    parent.borrow_mut().set_last_child(Rc::downgrade(&child));
+    child.borrow_mut().set_parent(Rc::downgrade(parent));
+
+    // 文書に挿入された要素をid/classの索引に登録する
+    if let Some(window) = owner_window(parent) {
+        for n in subtree_nodes(&child) {
+            window.borrow_mut().register_node(&n);
+        }
+    }
+
+    mark_dirty(parent);
+}
+
+// 親ノードから子ノードを取り除く
+pub fn remove_child(parent: &Rc<RefCell<Node>>, child: &Rc<RefCell<Node>>) {
+    // 取り除いた後はルートまで辿れなくなるので、先にウィンドウを探しておく
+    let window = owner_window(parent);
+
+    let previous = child.borrow().previous_sibling().upgrade();
+    let next = child.borrow().next_sibling();
+
+    match previous {
+        Some(ref p) => p.borrow_mut().set_next_sibling(next.clone()),
+        None => parent.borrow_mut().set_first_child(next.clone()),
+    }
+    match next {
+        Some(ref n) => n
+            .borrow_mut()
+            .set_previous_sibling(previous.as_ref().map_or_else(Weak::new, Rc::downgrade)),
+        None => parent
+            .borrow_mut()
+            .set_last_child(previous.as_ref().map_or_else(Weak::new, Rc::downgrade)),
+    }
+
+    child.borrow_mut().set_parent(Weak::new());
+    child.borrow_mut().set_previous_sibling(Weak::new());
+    child.borrow_mut().set_next_sibling(None);
+
+    // 文書から取り除かれた要素をid/classの索引から外す
+    if let Some(window) = window {
+        for n in subtree_nodes(child) {
+            window.borrow_mut().unregister_node(&n);
+        }
+    }
+
+    mark_dirty(parent);
+}
+
+// ノードが属する文書のウィンドウ
+// ウィンドウへの参照は文書のルートだけが持つので、ルートまで親を辿る
+fn owner_window(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Window>>> {
+    let mut root = node.clone();
+    loop {
+        let parent = root.borrow().parent().upgrade();
+        match parent {
+            Some(p) => root = p,
+            None => break,
+        }
+    }
+    let window = root.borrow().window().upgrade();
+    window
+}
+
+// ノード自身とその子孫を行きがけ順で返す
+fn subtree_nodes(node: &Rc<RefCell<Node>>) -> Vec<Rc<RefCell<Node>>> {
+    let mut nodes = alloc::vec![node.clone()];
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        nodes.extend(subtree_nodes(&c));
+        child = c.borrow().next_sibling();
+    }
+    nodes
+}
+
+// ノードの子孫のテキストを文書の順番に連結する
+pub fn get_text_content(node: &Rc<RefCell<Node>>) -> String {
+    if let NodeKind::Text(text) = node.borrow().kind() {
+        return text;
+    }
+    let mut text = String::new();
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        text.push_str(&get_text_content(&c));
+        child = c.borrow().next_sibling();
+    }
+    text
+}
+
+// ノードの中身を1つのテキストに置き換える
+pub fn set_text_content(node: &Rc<RefCell<Node>>, text: &str) {
+    if let NodeKind::Text(ref mut s) = node.borrow_mut().kind {
+        *s = text.to_string();
+    }
+    if matches!(node.borrow().kind(), NodeKind::Text(_)) {
+        mark_dirty(node);
+        return;
+    }
+
+    loop {
+        let child = match node.borrow().first_child() {
+            Some(c) => c,
+            None => break,
+        };
+        remove_child(node, &child);
+    }
+    append_child(
+        node,
+        Rc::new(RefCell::new(Node::new(NodeKind::Text(text.to_string())))),
+    );
+}
+
+// ホバー状態を設定する
+// 子孫の要素がホバーされている場合、祖先の要素も:hoverにマッチするので祖先にも設定する
+pub fn set_hover(node: &Rc<RefCell<Node>>, hover: bool) {
+    update_state_with_ancestors(node, |state| state.hover = hover);
+}
+
+// アクティブ（マウスのボタンが押されている）状態を設定する
+pub fn set_active(node: &Rc<RefCell<Node>>, active: bool) {
+    update_state_with_ancestors(node, |state| state.active = active);
+}
+
+// リンク先を訪問済みかどうかを設定する
+// ホバーなどと違い、祖先の要素には影響しない
+pub fn set_visited(node: &Rc<RefCell<Node>>, visited: bool) {
+    let mut state = node.borrow().state();
+    if state.visited == visited {
+        return;
+    }
+    state.visited = visited;
+    node.borrow_mut().set_state(state);
+    mark_dirty(node);
+}
+
+// フォーカスを持つかどうかを設定する
+// :focusは要素自身だけにマッチするので、祖先の要素には影響しない
+pub fn set_focus(node: &Rc<RefCell<Node>>, focus: bool) {
+    let mut state = node.borrow().state();
+    if state.focus == focus {
+        return;
+    }
+    state.focus = focus;
+    node.borrow_mut().set_state(state);
+    mark_dirty(node);
+}
+
+// キーボードでフォーカスを移せる要素かどうか
+// href属性を持つ<a>と、無効（disabled）でない<input>（hiddenを除く）と<button>
+pub fn is_focusable(node: &Rc<RefCell<Node>>) -> bool {
+    let element = match node.borrow().get_element() {
+        Some(element) => element,
+        None => return false,
+    };
+    match element.kind() {
+        ElementKind::A => element.get_attribute("href").is_some(),
+        ElementKind::Input => {
+            element.get_attribute("disabled").is_none()
+                && !element
+                    .get_attribute("type")
+                    .is_some_and(|t| t.trim().eq_ignore_ascii_case("hidden"))
+        }
+        ElementKind::Button => element.get_attribute("disabled").is_none(),
+        _ => false,
+    }
+}
+
+// rootとその子孫から、キーボードでフォーカスを移せる要素を文書の順番に集める
+pub fn get_focusable_nodes(root: &Rc<RefCell<Node>>) -> Vec<Rc<RefCell<Node>>> {
+    let mut nodes = Vec::new();
+    collect_focusable_nodes(root, &mut nodes);
+    nodes
+}
+
+fn collect_focusable_nodes(node: &Rc<RefCell<Node>>, nodes: &mut Vec<Rc<RefCell<Node>>>) {
+    if is_focusable(node) {
+        nodes.push(node.clone());
+    }
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        collect_focusable_nodes(&c, nodes);
+        child = c.borrow().next_sibling();
+    }
+}
+
+fn update_state_with_ancestors(node: &Rc<RefCell<Node>>, update: impl Fn(&mut ElementState)) {
+    let mut current = Some(node.clone());
+    while let Some(n) = current {
+        let mut state = n.borrow().state();
+        update(&mut state);
+        if state != n.borrow().state() {
+            n.borrow_mut().set_state(state);
+            // 状態によってスタイルが変わるので再計算が必要
+            mark_dirty(&n);
+        }
+        current = n.borrow().parent().upgrade();
+    }
+}
+
+// 子要素を持たない空要素（void element）のタグ名
+// https://html.spec.whatwg.org/multipage/syntax.html#void-elements
+const VOID_ELEMENTS: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+// ノード自身を含むHTML文字列（outerHTML）を返す
+// パース後のDOMツリーをHTMLとして書き出すことで、ソース表示やパーサーのテストに利用できる
+pub fn get_outer_html(node: Rc<RefCell<Node>>) -> String {
+    let mut result = String::new();
+    serialize_node(&node, &mut result);
+    result
+}
+
+// ノードの子孫のみのHTML文字列（innerHTML）を返す
+pub fn get_inner_html(node: Rc<RefCell<Node>>) -> String {
+    let mut result = String::new();
+    serialize_children(&node, &mut result);
+    result
+}
+
+fn serialize_node(node: &Rc<RefCell<Node>>, result: &mut String) {
+    let kind = node.borrow().kind();
+    match kind {
+        NodeKind::Document => serialize_children(node, result),
+        NodeKind::Element(e) => {
+            let tag = e.kind().to_string();
+            result.push('<');
+            result.push_str(&tag);
+            for attr in e.attributes() {
+                result.push(' ');
+                result.push_str(&attr.name());
+                result.push_str("=\"");
+                result.push_str(&escape_string(&attr.value(), true));
+                result.push('"');
+            }
+            result.push('>');
+
+            // 空要素は子要素も終了タグも持たない
+            if VOID_ELEMENTS.contains(&tag.as_str()) {
+                return;
+            }
+
+            serialize_children(node, result);
+
+            result.push_str("</");
+            result.push_str(&tag);
+            result.push('>');
+        }
+        NodeKind::Text(s) => {
+            // <style>と<script>の中身はテキストとしてそのまま出力する
+            let is_raw_text = match node.borrow().parent().upgrade() {
+                Some(parent) => matches!(
+                    parent.borrow().element_kind(),
+                    Some(ElementKind::Style) | Some(ElementKind::Script)
+                ),
+                None => false,
+            };
+            if is_raw_text {
+                result.push_str(&s);
+            } else {
+                result.push_str(&escape_string(&s, false));
+            }
+        }
+    }
+}
+
+fn serialize_children(node: &Rc<RefCell<Node>>, result: &mut String) {
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        serialize_node(&c, result);
+        child = c.borrow().next_sibling();
+    }
+}
+
+// HTMLの特殊文字をエスケープする
+// 属性値の場合はダブルクオートもエスケープする
+fn escape_string(s: &str, attribute_mode: bool) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '\u{a0}' => escaped.push_str("&nbsp;"),
+            '"' if attribute_mode => escaped.push_str("&quot;"),
+            '<' if !attribute_mode => escaped.push_str("&lt;"),
+            '>' if !attribute_mode => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    fn parse(html: &str) -> Rc<RefCell<Node>> {
+        let t = HtmlTokenizer::new(html.to_string());
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+        document.clone()
+    }
+
+    // パース結果をHTMLとして書き出せるか
+    #[test]
+    fn test_outer_html() {
+        let document = parse("<html><head></head><body><p class=\"a\">text</p></body></html>");
+        assert_eq!(
+            "<html><head></head><body><p class=\"a\">text</p></body></html>",
+            get_outer_html(document)
+        );
+    }
+
+    // 子孫のみを書き出せるか
+    #[test]
+    fn test_inner_html() {
+        let document = parse("<html><head></head><body><h1>title</h1></body></html>");
+        let body =
+            get_target_element_node(Some(document), ElementKind::Body).expect("body should exist");
+        assert_eq!("<h1>title</h1>", get_inner_html(body));
+    }
+
+    // テキストと属性値のエスケープ
+    #[test]
+    fn test_escape() {
+        assert_eq!("a &amp; b &lt;c&gt;", escape_string("a & b <c>", false));
+        assert_eq!(
+            "say &quot;hi&quot; <>",
+            escape_string("say \"hi\" <>", true)
+        );
+    }
+
+    // 全ての一致する要素を文書順で返すか
+    #[test]
+    fn test_get_target_element_nodes() {
+        let document = parse(
+            "<html><head></head><body><p>a</p><h1>b</h1><p><a>c</a></p><p>d</p></body></html>",
+        );
+        let ps = get_target_element_nodes(Some(document.clone()), ElementKind::P);
+        assert_eq!(3, ps.len());
+        let a = get_target_element_node(Some(document), ElementKind::A).expect("a should exist");
+        assert!(Rc::ptr_eq(
+            &a.borrow().parent().upgrade().expect("parent should exist"),
+            &ps[1]
+        ));
+    }
+
+    // <style>と<link rel="stylesheet">が文書順で集められるか
+    #[test]
+    fn test_get_style_sources() {
+        let document = parse("<html><head><link rel=\"stylesheet\" href=\"a.css\"><style>p{}</style><link rel=\"icon\" href=\"a.ico\"><link rel=\"alternate stylesheet\" href=\"c.css\"><link rel=\"StyleSheet\" href=\"b.css\"></head></html>");
+        assert_eq!(
+            alloc::vec![
+                StyleSource::Link("a.css".to_string()),
+                StyleSource::Inline("p{}".to_string()),
+                StyleSource::Link("b.css".to_string()),
+            ],
+            get_style_sources(document)
+        );
+    }
+
+    // DOMを変更した時にルートまで変更フラグが伝わるか
+    #[test]
+    fn test_mutation_marks_dirty() {
+        let document = parse("<html><head></head><body><p>a</p><p>b</p></body></html>");
+        assert!(!needs_update(&document));
+
+        let p = get_target_element_node(Some(document.clone()), ElementKind::P)
+            .expect("p should exist");
+        set_text_content(&p, "changed");
+        assert!(needs_update(&document));
+        assert!(p.borrow().is_dirty());
+        assert_eq!(
+            "<p>changed</p><p>b</p>",
+            get_inner_html(p.borrow().parent().upgrade().expect("body should exist"))
+        );
+
+        clear_dirty(&document);
+        assert!(!needs_update(&document));
+        assert!(!p.borrow().is_dirty());
+
+        let body = get_target_element_node(Some(document.clone()), ElementKind::Body)
+            .expect("body should exist");
+        remove_child(&body, &p);
+        assert!(needs_update(&document));
+        assert_eq!("<p>b</p>", get_inner_html(body));
+    }
+
+    // ツリーの中にあるノードを追加すると、元の位置から移動するか
+    #[test]
+    fn test_append_child_moves_node() {
+        let document = parse("<html><head></head><body><p>a</p><p>b</p><p>c</p></body></html>");
+        let body = get_target_element_node(Some(document.clone()), ElementKind::Body)
+            .expect("body should exist");
+        let ps = get_target_element_nodes(Some(document), ElementKind::P);
+
+        append_child(&body, ps[0].clone());
+        assert_eq!("<p>b</p><p>c</p><p>a</p>", get_inner_html(body.clone()));
+        assert!(Rc::ptr_eq(
+            &body
+                .borrow()
+                .first_child()
+                .expect("first child should exist"),
+            &ps[1]
+        ));
+        assert!(Rc::ptr_eq(
+            &body
+                .borrow()
+                .last_child()
+                .upgrade()
+                .expect("last child should exist"),
+            &ps[0]
+        ));
+        assert!(Rc::ptr_eq(
+            &ps[0]
+                .borrow()
+                .previous_sibling()
+                .upgrade()
+                .expect("previous sibling should exist"),
+            &ps[2]
+        ));
+        assert!(ps[0].borrow().next_sibling().is_none());
+
+        // 同じ親の最後の子を追加し直しても、ツリーが壊れない
+        append_child(&body, ps[0].clone());
+        assert_eq!("<p>b</p><p>c</p><p>a</p>", get_inner_html(body));
+    }
+
+    // DOMを変更した時にid/classの索引も更新されるか
+    #[test]
+    fn test_mutation_updates_index() {
+        let t = HtmlTokenizer::new(
+            "<html><head></head><body><p id=\"a\" class=\"c\"><a id=\"b\" class=\"c\">x</a></p><p id=\"a\">y</p></body></html>"
+                .to_string(),
+        );
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+        let body = get_target_element_node(Some(document.clone()), ElementKind::Body)
+            .expect("body should exist");
+        let ps = get_target_element_nodes(Some(document), ElementKind::P);
+
+        // 子孫も含めて索引から外れ、同じidの別の要素が見つかるようになる
+        remove_child(&body, &ps[0]);
+        let a = window
+            .borrow()
+            .get_element_by_id("a")
+            .expect("a should exist");
+        assert!(Rc::ptr_eq(&a, &ps[1]));
+        assert!(window.borrow().get_element_by_id("b").is_none());
+        assert!(window.borrow().get_elements_by_class_name("c").is_empty());
+
+        // 挿入し直すと、子孫も含めて索引に登録される
+        append_child(&body, ps[0].clone());
+        assert!(window.borrow().get_element_by_id("b").is_some());
+        assert_eq!(2, window.borrow().get_elements_by_class_name("c").len());
+
+        // textContentで取り除かれた子孫も索引から外れる
+        set_text_content(&ps[0], "z");
+        assert!(window.borrow().get_element_by_id("b").is_none());
+        assert_eq!(1, window.borrow().get_elements_by_class_name("c").len());
+    }
+
+    // <style>の中身はエスケープしない
+    #[test]
+    fn test_raw_text() {
+        let document = parse("<html><head><style>a>b{}</style></head></html>");
+        let style = get_target_element_node(Some(document), ElementKind::Style)
+            .expect("style should exist");
+        assert_eq!("<style>a>b{}</style>", get_outer_html(style));
+    }
+
+    // ホバー状態が祖先にも設定され、解除できるか
+    #[test]
+    fn test_set_hover() {
+        let document = parse("<html><head></head><body><p><a>link</a></p></body></html>");
+        let a = get_target_element_node(Some(document.clone()), ElementKind::A)
+            .expect("a should exist");
+        let body = get_target_element_node(Some(document.clone()), ElementKind::Body)
+            .expect("body should exist");
+
+        set_hover(&a, true);
+        assert!(a.borrow().state().hover);
+        assert!(body.borrow().state().hover);
+        assert!(!a.borrow().state().active);
+        assert!(needs_update(&document));
+
+        clear_dirty(&document);
+        set_hover(&a, false);
+        assert!(!a.borrow().state().hover);
+        assert!(!body.borrow().state().hover);
+        assert!(needs_update(&document));
+    }
+}
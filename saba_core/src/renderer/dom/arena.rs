@@ -0,0 +1,238 @@
+use crate::renderer::dom::node::ElementKind;
+use crate::renderer::dom::node::Node;
+use crate::renderer::dom::node::NodeKind;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// アリーナ内のノードを指すハンドル
+// Rc<RefCell<Node>>の代わりにインデックスでノードを参照する
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+// アリーナに格納される1つのノード
+// 親子・兄弟関係は全てNodeIdで持つので、参照カウントや借用チェックが不要
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArenaNode {
+    kind: NodeKind,
+    parent: Option<NodeId>,
+    first_child: Option<NodeId>,
+    last_child: Option<NodeId>,
+    previous_sibling: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+}
+
+impl ArenaNode {
+    fn new(kind: NodeKind) -> Self {
+        Self {
+            kind,
+            parent: None,
+            first_child: None,
+            last_child: None,
+            previous_sibling: None,
+            next_sibling: None,
+        }
+    }
+
+    pub fn kind(&self) -> &NodeKind {
+        &self.kind
+    }
+}
+
+// 全てのノードを1つのVecで管理するDOMツリー
+// インデックス0は常にDocumentノード
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeArena {
+    nodes: Vec<ArenaNode>,
+}
+
+impl Default for NodeArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NodeArena {
+    pub fn new() -> Self {
+        Self {
+            nodes: alloc::vec![ArenaNode::new(NodeKind::Document)],
+        }
+    }
+
+    // 既存のRc<RefCell<Node>>のツリーからアリーナを作成する
+    // パーサーやレイアウトを少しずつ移行するための変換
+    pub fn from_tree(root: &Rc<RefCell<Node>>) -> Self {
+        let mut arena = Self::new();
+        let root_id = arena.root();
+        arena.nodes[root_id.0].kind = root.borrow().kind();
+        arena.copy_children(root, root_id);
+        arena
+    }
+
+    fn copy_children(&mut self, node: &Rc<RefCell<Node>>, parent: NodeId) {
+        let mut child = node.borrow().first_child();
+        while let Some(c) = child {
+            let id = self.create_node(c.borrow().kind());
+            self.append_child(parent, id);
+            self.copy_children(&c, id);
+            child = c.borrow().next_sibling();
+        }
+    }
+
+    // ルート（Document）ノード
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    // ツリーに属さない新しいノードを作成する
+    pub fn create_node(&mut self, kind: NodeKind) -> NodeId {
+        self.nodes.push(ArenaNode::new(kind));
+        NodeId(self.nodes.len() - 1)
+    }
+
+    // 親ノードの最後の子としてノードを追加する
+    pub fn append_child(&mut self, parent: NodeId, child: NodeId) {
+        let last_child = self.nodes[parent.0].last_child;
+
+        match last_child {
+            Some(last) => {
+                self.nodes[last.0].next_sibling = Some(child);
+                self.nodes[child.0].previous_sibling = Some(last);
+            }
+            None => {
+                self.nodes[parent.0].first_child = Some(child);
+            }
+        }
+
+        self.nodes[parent.0].last_child = Some(child);
+        self.nodes[child.0].parent = Some(parent);
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&ArenaNode> {
+        self.nodes.get(id.0)
+    }
+
+    pub fn kind(&self, id: NodeId) -> NodeKind {
+        self.nodes[id.0].kind.clone()
+    }
+
+    pub fn kind_mut(&mut self, id: NodeId) -> &mut NodeKind {
+        &mut self.nodes[id.0].kind
+    }
+
+    pub fn element_kind(&self, id: NodeId) -> Option<ElementKind> {
+        match self.nodes[id.0].kind {
+            NodeKind::Element(ref e) => Some(e.kind()),
+            _ => None,
+        }
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].parent
+    }
+
+    pub fn first_child(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].first_child
+    }
+
+    pub fn last_child(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].last_child
+    }
+
+    pub fn previous_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].previous_sibling
+    }
+
+    pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].next_sibling
+    }
+
+    // 子ノードを先頭から順に返す
+    pub fn children(&self, id: NodeId) -> Vec<NodeId> {
+        let mut children = Vec::new();
+        let mut child = self.first_child(id);
+        while let Some(c) = child {
+            children.push(c);
+            child = self.next_sibling(c);
+        }
+        children
+    }
+
+    // 行きがけ順で最初に見つかった要素を返す
+    pub fn find_element(&self, from: NodeId, element_kind: ElementKind) -> Option<NodeId> {
+        let mut stack = alloc::vec![from];
+        while let Some(id) = stack.pop() {
+            if self.element_kind(id) == Some(element_kind) {
+                return Some(id);
+            }
+            // 先頭の子から探索するように逆順でスタックに積む
+            for child in self.children(id).into_iter().rev() {
+                stack.push(child);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::string::ToString;
+    use crate::renderer::dom::node::Element;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    // ノードを追加した時に親子・兄弟関係が正しく設定されるか
+    #[test]
+    fn test_append_child() {
+        let mut arena = NodeArena::new();
+        let root = arena.root();
+        let html = arena.create_node(NodeKind::Element(Element::new("html", Vec::new())));
+        let head = arena.create_node(NodeKind::Element(Element::new("head", Vec::new())));
+        let body = arena.create_node(NodeKind::Element(Element::new("body", Vec::new())));
+        arena.append_child(root, html);
+        arena.append_child(html, head);
+        arena.append_child(html, body);
+
+        assert_eq!(Some(html), arena.first_child(root));
+        assert_eq!(Some(root), arena.parent(html));
+        assert_eq!(Some(head), arena.first_child(html));
+        assert_eq!(Some(body), arena.last_child(html));
+        assert_eq!(Some(body), arena.next_sibling(head));
+        assert_eq!(Some(head), arena.previous_sibling(body));
+        assert_eq!(alloc::vec![head, body], arena.children(html));
+    }
+
+    // Rcのツリーから変換できるか
+    #[test]
+    fn test_from_tree() {
+        let html = "<html><head></head><body><p>text</p></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+        let arena = NodeArena::from_tree(&document);
+
+        let p = arena
+            .find_element(arena.root(), ElementKind::P)
+            .expect("p should exist");
+        let text = arena.first_child(p).expect("text should exist");
+        assert_eq!(NodeKind::Text("text".to_string()), arena.kind(text));
+        assert_eq!(
+            Some(ElementKind::Body),
+            arena.element_kind(arena.parent(p).expect("body should exist"))
+        );
+    }
+}
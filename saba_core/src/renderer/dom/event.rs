@@ -0,0 +1,276 @@
+use crate::renderer::dom::node::Node;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+
+// https://dom.spec.whatwg.org/#interface-event
+
+// イベントの伝搬の段階
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EventPhase {
+    None,
+    // ルートからターゲットの親に向かって伝搬している
+    Capturing,
+    // ターゲット自身
+    AtTarget,
+    // ターゲットの親からルートに向かって伝搬している
+    Bubbling,
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    event_type: String,
+    bubbles: bool,
+    phase: EventPhase,
+    target: Option<Rc<RefCell<Node>>>,
+    current_target: Option<Rc<RefCell<Node>>>,
+    propagation_stopped: bool,
+    default_prevented: bool,
+}
+
+impl Event {
+    pub fn new(event_type: &str, bubbles: bool) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            bubbles,
+            phase: EventPhase::None,
+            target: None,
+            current_target: None,
+            propagation_stopped: false,
+            default_prevented: false,
+        }
+    }
+
+    // マウスのクリックイベント
+    // clickイベントはバブリングする
+    pub fn click() -> Self {
+        Self::new("click", true)
+    }
+
+    pub fn event_type(&self) -> String {
+        self.event_type.clone()
+    }
+
+    pub fn bubbles(&self) -> bool {
+        self.bubbles
+    }
+
+    pub fn phase(&self) -> EventPhase {
+        self.phase
+    }
+
+    pub fn target(&self) -> Option<Rc<RefCell<Node>>> {
+        self.target.clone()
+    }
+
+    pub fn current_target(&self) -> Option<Rc<RefCell<Node>>> {
+        self.current_target.clone()
+    }
+
+    // これ以降のノードにイベントを伝搬させない
+    pub fn stop_propagation(&mut self) {
+        self.propagation_stopped = true;
+    }
+
+    pub fn propagation_stopped(&self) -> bool {
+        self.propagation_stopped
+    }
+
+    // リンクの遷移などのデフォルトの動作をキャンセルする
+    pub fn prevent_default(&mut self) {
+        self.default_prevented = true;
+    }
+
+    pub fn default_prevented(&self) -> bool {
+        self.default_prevented
+    }
+}
+
+// イベントが発生した時に呼ばれる関数
+pub type EventCallback = Rc<dyn Fn(&mut Event)>;
+
+// ノードに登録されるイベントリスナー
+#[derive(Clone)]
+pub struct EventListener {
+    event_type: String,
+    callback: EventCallback,
+    capture: bool,
+}
+
+impl EventListener {
+    pub fn new(event_type: &str, callback: EventCallback, capture: bool) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            callback,
+            capture,
+        }
+    }
+
+    pub fn event_type(&self) -> String {
+        self.event_type.clone()
+    }
+
+    pub fn capture(&self) -> bool {
+        self.capture
+    }
+
+    pub fn callback(&self) -> EventCallback {
+        self.callback.clone()
+    }
+}
+
+// クロージャはDebugを実装していないので、手動で実装する
+impl Debug for EventListener {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EventListener")
+            .field("event_type", &self.event_type)
+            .field("capture", &self.capture)
+            .finish()
+    }
+}
+
+// ターゲットのノードにイベントを送る
+// キャプチャ、ターゲット、バブリングの順にリスナーを呼び出す
+// デフォルトの動作を行ってよい場合にtrueを返す
+pub fn dispatch_event(target: &Rc<RefCell<Node>>, event: &mut Event) -> bool {
+    event.target = Some(target.clone());
+
+    // ターゲットの親からルートまでのノードを集める
+    let mut ancestors = Vec::new();
+    let mut parent = target.borrow().parent().upgrade();
+    while let Some(p) = parent {
+        parent = p.borrow().parent().upgrade();
+        ancestors.push(p);
+    }
+
+    // キャプチャフェーズ：ルートからターゲットの親へ
+    event.phase = EventPhase::Capturing;
+    for node in ancestors.iter().rev() {
+        if event.propagation_stopped {
+            break;
+        }
+        invoke_listeners(node, event, Some(true));
+    }
+
+    // ターゲットフェーズ
+    if !event.propagation_stopped {
+        event.phase = EventPhase::AtTarget;
+        invoke_listeners(target, event, None);
+    }
+
+    // バブリングフェーズ：ターゲットの親からルートへ
+    if event.bubbles {
+        event.phase = EventPhase::Bubbling;
+        for node in ancestors.iter() {
+            if event.propagation_stopped {
+                break;
+            }
+            invoke_listeners(node, event, Some(false));
+        }
+    }
+
+    event.phase = EventPhase::None;
+    event.current_target = None;
+
+    !event.default_prevented
+}
+
+// ノードに登録されているリスナーのうち、イベントの種類とフェーズが一致するものを呼び出す
+// captureがNoneの場合はフェーズに関わらず全てのリスナーを呼び出す
+fn invoke_listeners(node: &Rc<RefCell<Node>>, event: &mut Event, capture: Option<bool>) {
+    event.current_target = Some(node.clone());
+
+    // コールバックの中でノードを借用できるように、先にリスナーを複製しておく
+    let listeners = node.borrow().event_listeners();
+    for listener in listeners {
+        if listener.event_type != event.event_type {
+            continue;
+        }
+        if let Some(c) = capture {
+            if listener.capture != c {
+                continue;
+            }
+        }
+        (listener.callback)(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::api::get_target_element_node;
+    use crate::renderer::dom::node::ElementKind;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+    use alloc::format;
+
+    fn create_document() -> Rc<RefCell<Node>> {
+        let html = "<html><head></head><body><p><a href=\"/\">link</a></p></body></html>";
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        let document = window.borrow().document();
+        document.clone()
+    }
+
+    fn add_logger(node: &Rc<RefCell<Node>>, log: &Rc<RefCell<Vec<String>>>, capture: bool) {
+        let log = log.clone();
+        let name = format!("{:?}", node.borrow().element_kind());
+        node.borrow_mut().add_event_listener(
+            "click",
+            Rc::new(move |e: &mut Event| {
+                log.borrow_mut().push(format!("{}:{:?}", name, e.phase()));
+            }),
+            capture,
+        );
+    }
+
+    // キャプチャ、ターゲット、バブリングの順にリスナーが呼ばれるか
+    #[test]
+    fn test_capture_and_bubble() {
+        let document = create_document();
+        let body = get_target_element_node(Some(document.clone()), ElementKind::Body)
+            .expect("body should exist");
+        let a = get_target_element_node(Some(document), ElementKind::A).expect("a should exist");
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        add_logger(&body, &log, true);
+        add_logger(&body, &log, false);
+        add_logger(&a, &log, false);
+
+        assert!(dispatch_event(&a, &mut Event::click()));
+        assert_eq!(
+            alloc::vec![
+                "Some(Body):Capturing".to_string(),
+                "Some(A):AtTarget".to_string(),
+                "Some(Body):Bubbling".to_string(),
+            ],
+            *log.borrow()
+        );
+    }
+
+    // stopPropagationとpreventDefault
+    #[test]
+    fn test_stop_propagation() {
+        let document = create_document();
+        let body = get_target_element_node(Some(document.clone()), ElementKind::Body)
+            .expect("body should exist");
+        let a = get_target_element_node(Some(document), ElementKind::A).expect("a should exist");
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        add_logger(&body, &log, false);
+        a.borrow_mut().add_event_listener(
+            "click",
+            Rc::new(|e: &mut Event| {
+                e.stop_propagation();
+                e.prevent_default();
+            }),
+            false,
+        );
+
+        assert!(!dispatch_event(&a, &mut Event::click()));
+        assert!(log.borrow().is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+use crate::encoding::percent::encode_form_component;
+use crate::renderer::dom::node::ElementKind;
+use crate::renderer::dom::node::Node;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// フォームの送信方法（<form method>）
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FormMethod {
+    Get,
+    Post,
+}
+
+// 送信するフォームの内容
+// GETの場合はurlのクエリに、POSTの場合はbodyに、エンコードした値が入る
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormSubmission {
+    method: FormMethod,
+    url: String,
+    body: String,
+}
+
+impl FormSubmission {
+    pub fn new(method: FormMethod, url: String, body: String) -> Self {
+        Self { method, url, body }
+    }
+
+    pub fn method(&self) -> FormMethod {
+        self.method
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    pub fn body(&self) -> String {
+        self.body.clone()
+    }
+}
+
+// 要素の属性の値
+fn attribute(node: &Rc<RefCell<Node>>, name: &str) -> Option<String> {
+    node.borrow()
+        .get_element()
+        .and_then(|element| element.get_attribute(name))
+}
+
+// <input>のtype属性の値（小文字）
+// 指定がない場合はtext
+fn input_type(node: &Rc<RefCell<Node>>) -> String {
+    attribute(node, "type")
+        .map(|t| t.trim().to_ascii_lowercase())
+        .unwrap_or("text".to_string())
+}
+
+// フォームを送信するボタンかどうか
+// <input type="submit">と、type属性がないかsubmitの<button>
+pub fn is_submit_button(node: &Rc<RefCell<Node>>) -> bool {
+    match node.borrow().element_kind() {
+        Some(ElementKind::Input) => input_type(node) == "submit",
+        Some(ElementKind::Button) => attribute(node, "type")
+            .map(|t| t.trim().eq_ignore_ascii_case("submit"))
+            .unwrap_or(true),
+        _ => false,
+    }
+}
+
+// 要素が属するフォーム（祖先の<form>）
+pub fn form_owner(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    let mut current = node.borrow().parent().upgrade();
+    while let Some(n) = current {
+        if n.borrow().element_kind() == Some(ElementKind::Form) {
+            return Some(n);
+        }
+        current = n.borrow().parent().upgrade();
+    }
+    None
+}
+
+// フォームが送信する名前と値の組を文書順に集める
+// name属性のない要素、disabledな要素、チェックされていないチェックボックスとラジオボタンは送信しない
+// ボタンは、フォームを送信したボタン（submitter）だけを送信する
+pub fn form_data(
+    form: &Rc<RefCell<Node>>,
+    submitter: Option<&Rc<RefCell<Node>>>,
+) -> Vec<(String, String)> {
+    let mut data = Vec::new();
+    collect_form_data(form.borrow().first_child(), submitter, &mut data);
+    data
+}
+
+fn collect_form_data(
+    node: Option<Rc<RefCell<Node>>>,
+    submitter: Option<&Rc<RefCell<Node>>>,
+    data: &mut Vec<(String, String)>,
+) {
+    let mut current = node;
+    while let Some(n) = current {
+        if let Some(entry) = form_data_entry(&n, submitter) {
+            data.push(entry);
+        }
+        collect_form_data(n.borrow().first_child(), submitter, data);
+        current = n.borrow().next_sibling();
+    }
+}
+
+fn form_data_entry(
+    node: &Rc<RefCell<Node>>,
+    submitter: Option<&Rc<RefCell<Node>>>,
+) -> Option<(String, String)> {
+    let kind = node.borrow().element_kind();
+    if !matches!(kind, Some(ElementKind::Input) | Some(ElementKind::Button)) {
+        return None;
+    }
+    let name = attribute(node, "name").filter(|name| !name.is_empty())?;
+    if attribute(node, "disabled").is_some() {
+        return None;
+    }
+    let value = attribute(node, "value");
+    let is_submitter = submitter.is_some_and(|s| Rc::ptr_eq(s, node));
+
+    if kind == Some(ElementKind::Button) {
+        return is_submitter.then(|| (name, value.unwrap_or_default()));
+    }
+    match input_type(node).as_str() {
+        "checkbox" | "radio" => {
+            attribute(node, "checked").map(|_| (name, value.unwrap_or("on".to_string())))
+        }
+        "submit" => is_submitter.then(|| (name, value.unwrap_or_default())),
+        "button" | "reset" | "image" | "file" => None,
+        _ => Some((name, value.unwrap_or_default())),
+    }
+}
+
+// 名前と値の組をapplication/x-www-form-urlencodedの形式にする
+pub fn url_encode(data: &[(String, String)]) -> String {
+    let pairs: Vec<String> = data
+        .iter()
+        .map(|(name, value)| {
+            alloc::format!(
+                "{}={}",
+                encode_form_component(name),
+                encode_form_component(value)
+            )
+        })
+        .collect();
+    pairs.join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::api::get_target_element_node;
+    use crate::renderer::dom::api::get_target_element_nodes;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    // 送信する値の集め方とエンコード
+    #[test]
+    fn test_form_data() {
+        let html = "<html><head></head><body><form>\
+            <input name=\"q\" value=\"rust & saba\"><input name=\"empty\">\
+            <p><input type=\"checkbox\" name=\"a\" checked><input type=\"checkbox\" name=\"b\"></p>\
+            <input name=\"off\" value=\"x\" disabled><input value=\"no name\">\
+            <input type=\"submit\" name=\"go\" value=\"Go\"><button name=\"btn\" value=\"1\">b</button>\
+            </form><input name=\"outside\"></body></html>"
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+        let form = get_target_element_node(Some(document.clone()), ElementKind::Form)
+            .expect("form should exist");
+        let button = get_target_element_node(Some(document.clone()), ElementKind::Button)
+            .expect("button should exist");
+        let inputs = get_target_element_nodes(Some(document), ElementKind::Input);
+
+        assert!(is_submit_button(&button));
+        assert!(is_submit_button(&inputs[6]));
+        assert!(!is_submit_button(&inputs[0]));
+        assert!(Rc::ptr_eq(
+            &form,
+            &form_owner(&inputs[0]).expect("form should exist")
+        ));
+        assert!(form_owner(&inputs[7]).is_none());
+
+        let data = form_data(&form, Some(&button));
+        assert_eq!("q=rust+%26+saba&empty=&a=on&btn=1", url_encode(&data));
+        let data = form_data(&form, Some(&inputs[6]));
+        assert_eq!("q=rust+%26+saba&empty=&a=on&go=Go", url_encode(&data));
+    }
+}
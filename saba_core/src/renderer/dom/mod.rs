@@ -1,2 +1,6 @@
 pub mod api;
+pub mod arena;
+pub mod event;
+pub mod form;
 pub mod node;
+pub mod selection;
@@ -1,4 +1,9 @@
+use crate::renderer::dom::event::EventCallback;
+use crate::renderer::dom::event::EventListener;
 use crate::renderer::html::attribute::Attribute;
+use crate::renderer::image::bitmap::Bitmap;
+use crate::url::Url;
+use alloc::collections::BTreeMap;
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
@@ -12,6 +17,16 @@ use core::str::FromStr;
 #[derive(Debug, Clone)]
 pub struct Window {
     document: Rc<RefCell<Node>>,
+    // 現在表示している文書のURL（window.location）
+    location: Option<Url>,
+    // このウィンドウでの閲覧履歴（window.history）
+    history: History,
+    // id属性の値から要素ノードを引くための索引
+    // getElementByIdなどの検索に使う（セレクタのマッチは要素ごとに属性を見る）
+    // パーサーとDOMを変更するAPIが、ノードの挿入・削除に合わせて更新する
+    id_index: BTreeMap<String, Rc<RefCell<Node>>>,
+    // class名から要素ノードを引くための索引（文書に挿入された順）
+    class_index: BTreeMap<String, Vec<Rc<RefCell<Node>>>>,
 }
 
 impl Default for Window {
@@ -22,23 +37,160 @@ impl Default for Window {
 
 impl Window {
     pub fn new() -> Self {
-        let window = Self {
+        Self {
             document: Rc::new(RefCell::new(Node::new(NodeKind::Document))),
+            location: None,
+            history: History::new(),
+            id_index: BTreeMap::new(),
+            class_index: BTreeMap::new(),
+        }
+    }
+
+    pub fn document(&self) -> Rc<RefCell<Node>> {
+        self.document.clone()
+    }
+
+    // 文書のURLを設定し、履歴に追加する
+    pub fn set_location(&mut self, url: Url) {
+        self.history.push(url.clone());
+        self.location = Some(url);
+    }
+
+    pub fn location(&self) -> Option<Url> {
+        self.location.clone()
+    }
+
+    pub fn history(&self) -> History {
+        self.history.clone()
+    }
+
+    // ツリーに挿入された要素ノードをid/classの索引に登録する
+    pub fn register_node(&mut self, node: &Rc<RefCell<Node>>) {
+        let element = match node.borrow().get_element() {
+            Some(e) => e,
+            None => return,
         };
 
-        window
-            .document
-            .borrow_mut()
-            .set_window(Rc::downgrade(&Rc::new(RefCell::new(window.clone()))));
+        if let Some(id) = element.id() {
+            // 同じidが複数ある場合は最初に挿入された要素を優先する
+            self.id_index.entry(id).or_insert_with(|| node.clone());
+        }
 
-        window
+        for class_name in element.class_names() {
+            self.class_index
+                .entry(class_name)
+                .or_default()
+                .push(node.clone());
+        }
     }
 
-    pub fn document(&self) -> Rc<RefCell<Node>> {
-        self.document.clone()
+    // ツリーから取り除かれた要素ノードをid/classの索引から外す
+    // 同じidを持つ別の要素が文書に残っている場合は、文書順で最初の要素を登録し直す
+    pub fn unregister_node(&mut self, node: &Rc<RefCell<Node>>) {
+        let element = match node.borrow().get_element() {
+            Some(e) => e,
+            None => return,
+        };
+
+        if let Some(id) = element.id() {
+            if self
+                .id_index
+                .get(&id)
+                .is_some_and(|indexed| Rc::ptr_eq(indexed, node))
+            {
+                self.id_index.remove(&id);
+                if let Some(other) = find_element_by_id(&self.document, &id) {
+                    self.id_index.insert(id, other);
+                }
+            }
+        }
+
+        for class_name in element.class_names() {
+            if let Some(nodes) = self.class_index.get_mut(&class_name) {
+                nodes.retain(|indexed| !Rc::ptr_eq(indexed, node));
+                if nodes.is_empty() {
+                    self.class_index.remove(&class_name);
+                }
+            }
+        }
+    }
+
+    // idに一致する要素を索引から取得する
+    pub fn get_element_by_id(&self, id: &str) -> Option<Rc<RefCell<Node>>> {
+        self.id_index.get(id).cloned()
+    }
+
+    // classに一致する要素を索引から取得する
+    pub fn get_elements_by_class_name(&self, class_name: &str) -> Vec<Rc<RefCell<Node>>> {
+        match self.class_index.get(class_name) {
+            Some(nodes) => nodes.clone(),
+            None => Vec::new(),
+        }
+    }
+}
+
+// rootの子孫から、idを持つ最初の要素を行きがけ順で探す
+fn find_element_by_id(root: &Rc<RefCell<Node>>, id: &str) -> Option<Rc<RefCell<Node>>> {
+    let mut stack = alloc::vec![root.clone()];
+    while let Some(n) = stack.pop() {
+        if n.borrow().get_element().and_then(|e| e.id()).as_deref() == Some(id) {
+            return Some(n);
+        }
+
+        if let Some(sibling) = n.borrow().next_sibling() {
+            if !Rc::ptr_eq(&n, root) {
+                stack.push(sibling);
+            }
+        }
+        if let Some(child) = n.borrow().first_child() {
+            stack.push(child);
+        }
+    }
+    None
+}
+
+// window.historyに対応する構造体
+// 今はウィンドウ内で訪れたURLを記録するだけ
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct History {
+    entries: Vec<Url>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, url: Url) {
+        self.entries.push(url);
+    }
+
+    // history.length
+    pub fn length(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn entries(&self) -> Vec<Url> {
+        self.entries.clone()
     }
 }
 
+// ユーザーの操作によって変わる要素の状態
+// :hover、:active、:visitedなどの擬似クラスのマッチに使う
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ElementState {
+    // マウスカーソルが要素（またはその子孫）の上にある
+    pub hover: bool,
+    // マウスのボタンが押されている
+    pub active: bool,
+    // 訪問済みのリンク
+    pub visited: bool,
+    // キーボードの入力先になっている（フォーカスを持つ）
+    pub focus: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct Node {
     // ノードの種類
@@ -55,6 +207,16 @@ pub struct Node {
     previous_sibling: Weak<RefCell<Node>>,
     // ノードの次の兄弟ノード
     next_sibling: Option<Rc<RefCell<Node>>>,
+    // ノードに登録されたイベントリスナー
+    event_listeners: Vec<EventListener>,
+    // ノード自身が変更されたか
+    dirty: bool,
+    // 子孫のノードのどれかが変更されたか
+    child_dirty: bool,
+    // ホバーなどの要素の状態
+    state: ElementState,
+    // img要素が読み込んだ画像
+    image: Option<Rc<Bitmap>>,
 }
 
 // ノードの種類で比較
@@ -76,6 +238,11 @@ impl Node {
             last_child: Weak::new(),
             previous_sibling: Weak::new(),
             next_sibling: None,
+            event_listeners: Vec::new(),
+            dirty: false,
+            child_dirty: false,
+            state: ElementState::default(),
+            image: None,
         }
     }
 
@@ -84,6 +251,11 @@ impl Node {
         self.window = window;
     }
 
+    // ゲッター
+    pub fn window(&self) -> Weak<RefCell<Window>> {
+        self.window.clone()
+    }
+
     // セッター
     pub fn set_parent(&mut self, parent: Weak<RefCell<Node>>) {
         self.parent = parent;
@@ -134,6 +306,122 @@ impl Node {
         self.next_sibling.as_ref().cloned()
     }
 
+    // イベントリスナーを登録する
+    pub fn add_event_listener(&mut self, event_type: &str, callback: EventCallback, capture: bool) {
+        self.event_listeners
+            .push(EventListener::new(event_type, callback, capture));
+    }
+
+    // 特定の種類のイベントリスナーを全て削除する
+    pub fn remove_event_listeners(&mut self, event_type: &str) {
+        self.event_listeners
+            .retain(|listener| listener.event_type() != event_type);
+    }
+
+    // ゲッター
+    pub fn event_listeners(&self) -> Vec<EventListener> {
+        self.event_listeners.clone()
+    }
+
+    // セッター
+    pub fn set_dirty(&mut self, dirty: bool) {
+        self.dirty = dirty;
+    }
+
+    // ゲッター
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    // セッター
+    pub fn set_child_dirty(&mut self, child_dirty: bool) {
+        self.child_dirty = child_dirty;
+    }
+
+    // ゲッター
+    pub fn is_child_dirty(&self) -> bool {
+        self.child_dirty
+    }
+
+    // セッター
+    pub fn set_state(&mut self, state: ElementState) {
+        self.state = state;
+    }
+
+    // ゲッター
+    pub fn state(&self) -> ElementState {
+        self.state
+    }
+
+    // セッター
+    pub fn set_image(&mut self, image: Option<Rc<Bitmap>>) {
+        self.image = image;
+    }
+
+    // ゲッター
+    pub fn image(&self) -> Option<Rc<Bitmap>> {
+        self.image.clone()
+    }
+
+    // 隣り合うテキストノードを1つにまとめ、空のテキストノードを取り除く
+    // 子孫のノードに対しても再帰的に行う
+    pub fn normalize(&mut self) {
+        let mut children: Vec<Rc<RefCell<Node>>> = Vec::new();
+        let mut child = self.first_child();
+        while let Some(c) = child {
+            child = c.borrow().next_sibling();
+
+            let text = match c.borrow().kind {
+                NodeKind::Text(ref s) => Some(s.clone()),
+                _ => None,
+            };
+
+            match text {
+                // 空のテキストノードは取り除く
+                Some(ref s) if s.is_empty() => {}
+                Some(s) => {
+                    // 直前のノードがテキストノードなら、そのノードに文字列を追加する
+                    let merged = match children.last() {
+                        Some(last) => match last.borrow_mut().kind {
+                            NodeKind::Text(ref mut t) => {
+                                t.push_str(&s);
+                                true
+                            }
+                            _ => false,
+                        },
+                        None => false,
+                    };
+                    if !merged {
+                        children.push(c);
+                    }
+                }
+                None => {
+                    c.borrow_mut().normalize();
+                    children.push(c);
+                }
+            }
+        }
+
+        // 残ったノードで兄弟関係を作り直す
+        self.first_child = children.first().cloned();
+        self.last_child = match children.last() {
+            Some(last) => Rc::downgrade(last),
+            None => Weak::new(),
+        };
+        let mut previous: Option<Rc<RefCell<Node>>> = None;
+        for c in children {
+            match previous {
+                Some(ref p) => {
+                    p.borrow_mut().set_next_sibling(Some(c.clone()));
+                    c.borrow_mut().set_previous_sibling(Rc::downgrade(p));
+                }
+                None => c.borrow_mut().set_previous_sibling(Weak::new()),
+            }
+            c.borrow_mut().set_next_sibling(None);
+            previous = Some(c);
+        }
+    }
+
     // ゲッター
     pub fn kind(&self) -> NodeKind {
         self.kind.clone()
@@ -203,13 +491,76 @@ impl Element {
     pub fn is_block_element(&self) -> bool {
         matches!(
             self.kind(),
-            ElementKind::Body | ElementKind::H1 | ElementKind::H2 | ElementKind::P
+            ElementKind::Body
+                | ElementKind::H1
+                | ElementKind::H2
+                | ElementKind::P
+                | ElementKind::Pre
+                | ElementKind::Ul
+                | ElementKind::Ol
+                | ElementKind::Li
+                | ElementKind::Hr
+                | ElementKind::Form
         )
     }
 
     pub fn attributes(&self) -> Vec<Attribute> {
         self.attributes.clone()
     }
+
+    // 指定した名前の属性の値を取得する
+    pub fn get_attribute(&self, name: &str) -> Option<String> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.name() == name)
+            .map(|attr| attr.value())
+    }
+
+    // id属性の値
+    pub fn id(&self) -> Option<String> {
+        self.get_attribute("id")
+    }
+
+    // data-*属性をキャメルケースの名前と値の組にする（element.dataset）
+    // data-foo-bar="x" => fooBar: x
+    // https://html.spec.whatwg.org/multipage/dom.html#dom-dataset
+    pub fn dataset(&self) -> BTreeMap<String, String> {
+        let mut dataset = BTreeMap::new();
+        for attr in &self.attributes {
+            let name = attr.name();
+            let key = match name.strip_prefix("data-") {
+                Some(key) => key,
+                None => continue,
+            };
+
+            let mut camel = String::new();
+            let mut chars = key.chars().peekable();
+            while let Some(c) = chars.next() {
+                // ハイフンの直後の小文字は大文字にしてハイフンを取り除く
+                if c == '-' {
+                    if let Some(next) = chars.peek() {
+                        if next.is_ascii_lowercase() {
+                            camel.push(next.to_ascii_uppercase());
+                            chars.next();
+                            continue;
+                        }
+                    }
+                }
+                camel.push(c);
+            }
+
+            dataset.insert(camel, attr.value());
+        }
+        dataset
+    }
+
+    // class属性を空白で区切ったclass名のリスト
+    pub fn class_names(&self) -> Vec<String> {
+        match self.get_attribute("class") {
+            Some(classes) => classes.split_ascii_whitespace().map(String::from).collect(),
+            None => Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -220,18 +571,46 @@ pub enum ElementKind {
     Head,
     // <style>
     Style,
+    // <link>
+    Link,
+    // <meta>
+    Meta,
     // <script>
     Script,
     // <body>
     Body,
     // <p>
     P,
+    // <pre>
+    Pre,
     // <h1>
     H1,
     // <h2>
     H2,
     // <a>
     A,
+    // <strong>
+    Strong,
+    // <em>
+    Em,
+    // <br>
+    Br,
+    // <ul>
+    Ul,
+    // <ol>
+    Ol,
+    // <li>
+    Li,
+    // <img>
+    Img,
+    // <hr>
+    Hr,
+    // <form>
+    Form,
+    // <input>
+    Input,
+    // <button>
+    Button,
 }
 
 impl FromStr for ElementKind {
@@ -242,12 +621,26 @@ impl FromStr for ElementKind {
             "html" => Ok(ElementKind::Html),
             "head" => Ok(ElementKind::Head),
             "style" => Ok(ElementKind::Style),
+            "link" => Ok(ElementKind::Link),
+            "meta" => Ok(ElementKind::Meta),
             "script" => Ok(ElementKind::Script),
             "body" => Ok(ElementKind::Body),
             "p" => Ok(ElementKind::P),
+            "pre" => Ok(ElementKind::Pre),
             "h1" => Ok(ElementKind::H1),
             "h2" => Ok(ElementKind::H2),
             "a" => Ok(ElementKind::A),
+            "strong" => Ok(ElementKind::Strong),
+            "em" => Ok(ElementKind::Em),
+            "br" => Ok(ElementKind::Br),
+            "ul" => Ok(ElementKind::Ul),
+            "ol" => Ok(ElementKind::Ol),
+            "li" => Ok(ElementKind::Li),
+            "img" => Ok(ElementKind::Img),
+            "hr" => Ok(ElementKind::Hr),
+            "form" => Ok(ElementKind::Form),
+            "input" => Ok(ElementKind::Input),
+            "button" => Ok(ElementKind::Button),
             _ => Err(format!("unimplemented element name {:?}", s)),
         }
     }
@@ -261,13 +654,57 @@ impl Display for ElementKind {
             ElementKind::Html => "html",
             ElementKind::Head => "head",
             ElementKind::Style => "style",
+            ElementKind::Link => "link",
+            ElementKind::Meta => "meta",
             ElementKind::Script => "script",
             ElementKind::Body => "body",
             ElementKind::P => "p",
+            ElementKind::Pre => "pre",
             ElementKind::H1 => "h1",
             ElementKind::H2 => "h2",
             ElementKind::A => "a",
+            ElementKind::Strong => "strong",
+            ElementKind::Em => "em",
+            ElementKind::Br => "br",
+            ElementKind::Ul => "ul",
+            ElementKind::Ol => "ol",
+            ElementKind::Li => "li",
+            ElementKind::Img => "img",
+            ElementKind::Hr => "hr",
+            ElementKind::Form => "form",
+            ElementKind::Input => "input",
+            ElementKind::Button => "button",
         };
         write!(f, "{}", s)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+    use alloc::string::ToString;
+
+    // data-*属性をdatasetとして取得できるか
+    #[test]
+    fn test_dataset() {
+        let html =
+            "<html><head></head><body><p id=\"a\" data-user-id=\"42\" data-x=\"y\">t</p></body></html>"
+                .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let p = window
+            .borrow()
+            .get_element_by_id("a")
+            .expect("failed to get p");
+        let dataset = p
+            .borrow()
+            .get_element()
+            .expect("failed to get an element")
+            .dataset();
+
+        assert_eq!(2, dataset.len());
+        assert_eq!(Some(&"42".to_string()), dataset.get("userId"));
+        assert_eq!(Some(&"y".to_string()), dataset.get("x"));
+    }
+}
@@ -0,0 +1,219 @@
+use crate::renderer::dom::node::ElementKind;
+use crate::renderer::dom::node::Node;
+use crate::renderer::dom::node::NodeKind;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// 選択範囲の端の位置
+// テキストノードと、その中の何文字目の前か（DOMのテキストでの文字数）で表す
+#[derive(Debug, Clone)]
+pub struct SelectionPoint {
+    node: Rc<RefCell<Node>>,
+    offset: usize,
+}
+
+impl SelectionPoint {
+    pub fn new(node: Rc<RefCell<Node>>, offset: usize) -> Self {
+        Self { node, offset }
+    }
+
+    pub fn node(&self) -> Rc<RefCell<Node>> {
+        self.node.clone()
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+// 同じノードの同じ位置かどうかで比較する
+impl PartialEq for SelectionPoint {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.node, &other.node) && self.offset == other.offset
+    }
+}
+
+// テキストの選択範囲
+// 選択を始めた位置（anchor）と、マウスのドラッグに合わせて動く位置（focus）を持つ
+// focusがanchorより前にある場合もある
+// https://w3c.github.io/selection-api/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    anchor: SelectionPoint,
+    focus: SelectionPoint,
+}
+
+impl Selection {
+    pub fn new(anchor: SelectionPoint, focus: SelectionPoint) -> Self {
+        Self { anchor, focus }
+    }
+
+    pub fn anchor(&self) -> SelectionPoint {
+        self.anchor.clone()
+    }
+
+    pub fn focus(&self) -> SelectionPoint {
+        self.focus.clone()
+    }
+
+    pub fn set_focus(&mut self, focus: SelectionPoint) {
+        self.focus = focus;
+    }
+
+    // 何も選択されていない（anchorとfocusが同じ位置）かどうか
+    pub fn is_collapsed(&self) -> bool {
+        self.anchor == self.focus
+    }
+
+    // 選択範囲に含まれるテキストノードと、その中の範囲（何文字目から何文字目の前まで）を文書の順番に返す
+    // rootはテキストノードを探す文書
+    pub fn ranges(&self, root: &Rc<RefCell<Node>>) -> Vec<(Rc<RefCell<Node>>, usize, usize)> {
+        let nodes = get_text_nodes(root);
+        let position = |point: &SelectionPoint| {
+            nodes
+                .iter()
+                .position(|node| Rc::ptr_eq(node, &point.node))
+                .map(|index| (index, point.offset))
+        };
+        let (start, end) = match (position(&self.anchor), position(&self.focus)) {
+            (Some(anchor), Some(focus)) if anchor <= focus => (anchor, focus),
+            (Some(anchor), Some(focus)) => (focus, anchor),
+            _ => return Vec::new(),
+        };
+
+        let mut ranges = Vec::new();
+        for (index, node) in nodes.iter().enumerate().take(end.0 + 1).skip(start.0) {
+            let len = match &node.borrow().kind {
+                NodeKind::Text(text) => text.chars().count(),
+                _ => 0,
+            };
+            let from = if index == start.0 {
+                start.1.min(len)
+            } else {
+                0
+            };
+            let to = if index == end.0 { end.1.min(len) } else { len };
+            if from < to {
+                ranges.push((node.clone(), from, to));
+            }
+        }
+        ranges
+    }
+
+    // 選択されたテキスト
+    // 表示と同じように連続する空白や改行は1つの空白にまとめ、ブロック要素が変わる所では改行する
+    pub fn selected_text(&self, root: &Rc<RefCell<Node>>) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        let mut line = String::new();
+        let mut last_block: Option<Rc<RefCell<Node>>> = None;
+        for (node, from, to) in self.ranges(root) {
+            let block = containing_block(&node);
+            let same_block = match (&last_block, &block) {
+                (Some(last), Some(block)) => Rc::ptr_eq(last, block),
+                (None, None) => true,
+                _ => false,
+            };
+            if !same_block && !line.is_empty() {
+                lines.push(collapse_whitespace(&line));
+                line = String::new();
+            }
+            last_block = block;
+
+            if let NodeKind::Text(text) = &node.borrow().kind {
+                line.extend(text.chars().skip(from).take(to - from));
+            }
+        }
+        if !line.is_empty() {
+            lines.push(collapse_whitespace(&line));
+        }
+        lines.join("\n")
+    }
+}
+
+// rootとその子孫のテキストノードを文書の順番に集める
+// <style>と<script>の中身は表示されないので含めない
+pub fn get_text_nodes(root: &Rc<RefCell<Node>>) -> Vec<Rc<RefCell<Node>>> {
+    let mut nodes = Vec::new();
+    collect_text_nodes(root, &mut nodes);
+    nodes
+}
+
+fn collect_text_nodes(node: &Rc<RefCell<Node>>, nodes: &mut Vec<Rc<RefCell<Node>>>) {
+    match node.borrow().element_kind() {
+        Some(ElementKind::Style) | Some(ElementKind::Script) => return,
+        _ => {}
+    }
+    if let NodeKind::Text(_) = node.borrow().kind {
+        nodes.push(node.clone());
+    }
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        collect_text_nodes(&c, nodes);
+        child = c.borrow().next_sibling();
+    }
+}
+
+// ノードを含む一番近いブロック要素
+fn containing_block(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    let mut current = node.borrow().parent().upgrade();
+    while let Some(n) = current {
+        if n.borrow()
+            .get_element()
+            .is_some_and(|element| element.is_block_element())
+        {
+            return Some(n);
+        }
+        current = n.borrow().parent().upgrade();
+    }
+    None
+}
+
+// 連続する空白や改行を1つの空白にまとめ、前後の空白を取り除く
+fn collapse_whitespace(s: &str) -> String {
+    s.split([' ', '\n'])
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+    use alloc::string::ToString;
+
+    // 後ろから前に選択した場合も文書の順番の範囲になり、ブロック要素ごとに改行されるか
+    #[test]
+    fn test_selected_text() {
+        let html = "<html><head><style>p { color: red; }</style></head><body><p>hello\n   <a>big</a>world</p><p>second</p></body></html>"
+            .to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+        let texts = get_text_nodes(&document);
+        // <style>の中身は含まない
+        assert_eq!(4, texts.len());
+
+        let selection = Selection::new(
+            SelectionPoint::new(texts[3].clone(), 3),
+            SelectionPoint::new(texts[0].clone(), 2),
+        );
+        assert!(!selection.is_collapsed());
+        let ranges: Vec<(usize, usize)> = selection
+            .ranges(&document)
+            .iter()
+            .map(|(_, from, to)| (*from, *to))
+            .collect();
+        assert_eq!(alloc::vec![(2, 9), (0, 3), (0, 5), (0, 3)], ranges);
+        assert_eq!("llo bigworld\nsec", selection.selected_text(&document));
+
+        let collapsed = Selection::new(
+            SelectionPoint::new(texts[1].clone(), 1),
+            SelectionPoint::new(texts[1].clone(), 1),
+        );
+        assert!(collapsed.is_collapsed());
+        assert_eq!("", collapsed.selected_text(&document));
+    }
+}
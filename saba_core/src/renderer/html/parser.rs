@@ -37,6 +37,8 @@ pub struct HtmlParser {
     stack_of_open_elements: Vec<Rc<RefCell<Node>>>,
     // HtmlTokenizerの構造体　次のトークンはt.next()で取得
     t: HtmlTokenizer,
+    // 終了タグまでパースし、まだ実行されていない<script>要素
+    pending_script: Option<Rc<RefCell<Node>>>,
 }
 
 // -- HTMLファイルの中身 --
@@ -60,27 +62,48 @@ pub struct HtmlParser {
 
 impl HtmlParser {
     pub fn new(t: HtmlTokenizer) -> Self {
+        let window = Rc::new(RefCell::new(Window::new()));
+        // ドキュメントからウィンドウを辿れるようにする
+        window
+            .borrow()
+            .document()
+            .borrow_mut()
+            .set_window(Rc::downgrade(&window));
+
         Self {
-            window: Rc::new(RefCell::new(Window::new())),
+            window,
             mode: InsertionMode::Initial,
             original_insertion_mode: InsertionMode::Initial,
             stack_of_open_elements: Vec::new(),
             t,
+            pending_script: None,
         }
     }
 
+    // 作成中のDOMツリーを持つWindowオブジェクト
+    pub fn window(&self) -> Rc<RefCell<Window>> {
+        self.window.clone()
+    }
+
     fn create_element(&self, tag: &str, attributes: Vec<Attribute>) -> Node {
         Node::new(NodeKind::Element(Element::new(tag, attributes)))
     }
 
     fn insert_element(&mut self, tag: &str, attributes: Vec<Attribute>) {
-        let window = self.window.borrow();
+        // テキストノードは子要素を持たないので、スタックから取り除いておく
+        let current_is_text = match self.stack_of_open_elements.last() {
+            Some(n) => matches!(n.borrow().kind(), NodeKind::Text(_)),
+            None => false,
+        };
+        if current_is_text {
+            self.stack_of_open_elements.pop();
+        }
 
         // 現在の開いている要素スタックの最後のノードを取得
         // スタックがからの場合は、ルート要素が現在参照しているノード
         let current = match self.stack_of_open_elements.last() {
             Some(n) => n.clone(),
-            None => window.document(),
+            None => self.window.borrow().document(),
         };
 
         // 新しい要素ノードを作成
@@ -123,6 +146,9 @@ impl HtmlParser {
         // 新しいノードの親を現在参照しているノードに設定
         node.borrow_mut().set_parent(Rc::downgrade(&current));
 
+        // id/classの索引に登録
+        self.window.borrow_mut().register_node(&node);
+
         // 新しいノードを開いている要素スタックに追加
         self.stack_of_open_elements.push(node);
     }
@@ -162,6 +188,18 @@ impl HtmlParser {
         }
     }
 
+    // 一番内側のリスト（<ul>や<ol>）の中で<li>が開いているかどうか
+    fn is_list_item_open(&self) -> bool {
+        for element in self.stack_of_open_elements.iter().rev() {
+            match element.borrow().element_kind() {
+                Some(ElementKind::Li) => return true,
+                Some(ElementKind::Ul) | Some(ElementKind::Ol) => return false,
+                _ => {}
+            }
+        }
+        false
+    }
+
     // stack_of_open_elements スタックに存在する全ての要素を確認して、特定の種類の要素がある場合にtrueを返す
     fn contain_in_stack(&mut self, element_kind: ElementKind) -> bool {
         let contain = self
@@ -200,20 +238,11 @@ impl HtmlParser {
         let node = Rc::new(RefCell::new(self.create_char(c)));
 
         // すでに子要素がある場合
-        if current.borrow().first_child().is_some() {
-            // 新しいテキストノードをその直後に挿入
-            current
-                .borrow()
-                .first_child()
-                .unwrap()
-                .borrow_mut()
-                .set_next_sibling(Some(node.clone()));
-            // node.borrow_mut().set_previous_sibling(Rc::downgrade(
-            //     &current
-            //         .borrow()
-            //         .first_child()
-            //         .expect("failed to get a first child"),
-            // ));
+        let last_child = current.borrow().last_child().upgrade();
+        if let Some(last) = last_child {
+            // 新しいテキストノードを最後の子ノードの直後に挿入
+            last.borrow_mut().set_next_sibling(Some(node.clone()));
+            node.borrow_mut().set_previous_sibling(Rc::downgrade(&last));
         } else {
             // 新しいテキストノードを現在参照しているノードの最初の子要素として設定する
             current.borrow_mut().set_first_child(Some(node.clone()));
@@ -228,7 +257,25 @@ impl HtmlParser {
         self.stack_of_open_elements.push(node);
     }
 
+    // 次の</script>までパースを進めて、中身まで追加した<script>要素を返す
+    // スクリプトを実行してから、もう一度呼び出すと続きからパースする
+    // 文書の最後までパースした場合はNoneを返す
+    pub fn parse_until_script(&mut self) -> Option<Rc<RefCell<Node>>> {
+        self.build_tree();
+        self.pending_script.take()
+    }
+
     pub fn construct_tree(&mut self) -> Rc<RefCell<Window>> {
+        while self.parse_until_script().is_some() {}
+        let window = self.window.clone();
+
+        // トークナイザは1文字ずつ文字トークンを返すので、分かれたテキストノードをまとめる
+        window.borrow().document().borrow_mut().normalize();
+
+        window
+    }
+
+    fn build_tree(&mut self) -> Rc<RefCell<Window>> {
         let mut token = self.t.next();
 
         // tokenを取得できる限りloop
@@ -341,6 +388,16 @@ impl HtmlParser {
                                 continue;
                             }
 
+                            // <link>と<meta>は空要素なので、追加したらすぐにスタックから取り出す
+                            if tag == "link" || tag == "meta" {
+                                self.insert_element(tag, attributes.to_vec());
+                                if let Ok(element_kind) = ElementKind::from_str(tag) {
+                                    self.pop_current_node(element_kind);
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+
                             // 仕様書には定められていないが、このブラウザは仕様を
                             // 全て実装しているわけではないので、<head>が省略
                             // されているHTML文書を扱うために必要。これがないと
@@ -368,7 +425,7 @@ impl HtmlParser {
                         }
                         Some(HtmlToken::Eof) | None => return self.window.clone(),
                     }
-                    // <meta>や<title>などのサポートしていないタグは無視
+                    // <title>などのサポートしていないタグは無視
                     token = self.t.next();
                 }
 
@@ -413,7 +470,7 @@ impl HtmlParser {
                             ref attributes,
                         }) => match tag.as_str() {
                             // Task: ここ共通化できそう
-                            "p" => {
+                            "p" | "pre" => {
                                 // Elementノードを作成してDOMツリーに追加
                                 self.insert_element(tag, attributes.to_vec());
                                 token = self.t.next();
@@ -425,12 +482,66 @@ impl HtmlParser {
                                 token = self.t.next();
                                 continue;
                             }
-                            "a" => {
+                            "a" | "strong" | "em" => {
+                                // Elementノードを作成してDOMツリーに追加
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            "form" | "button" => {
+                                // Elementノードを作成してDOMツリーに追加
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            "ul" | "ol" => {
                                 // Elementノードを作成してDOMツリーに追加
                                 self.insert_element(tag, attributes.to_vec());
                                 token = self.t.next();
                                 continue;
                             }
+                            // </li>は省略できるので、同じリストの<li>が開いていれば閉じてから追加する
+                            "li" => {
+                                if self.is_list_item_open() {
+                                    self.pop_until(ElementKind::Li);
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            // 空要素なので、追加したらすぐにスタックから取り出す
+                            "br" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.pop_current_node(ElementKind::Br);
+                                token = self.t.next();
+                                continue;
+                            }
+                            "img" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.pop_current_node(ElementKind::Img);
+                                token = self.t.next();
+                                continue;
+                            }
+                            "hr" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.pop_current_node(ElementKind::Hr);
+                                token = self.t.next();
+                                continue;
+                            }
+                            "input" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.pop_current_node(ElementKind::Input);
+                                token = self.t.next();
+                                continue;
+                            }
+                            // <body>の中の<style>と<script>も、終了タグまでの文字を中身にする
+                            "style" | "script" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.original_insertion_mode = self.mode;
+                                self.mode = InsertionMode::Text;
+                                token = self.t.next();
+                                continue;
+                            }
                             _ => {
                                 token = self.t.next();
                             }
@@ -459,10 +570,14 @@ impl HtmlParser {
                                     continue;
                                 }
                                 // Task: ここ共通化できそう
-                                "p" => {
+                                "p" | "pre" => {
                                     let element_kind = ElementKind::from_str(tag)
                                         .expect("failed to convert string to ElementKind");
                                     token = self.t.next();
+                                    if !self.contain_in_stack(element_kind) {
+                                        // パースの失敗。トークンを無視する
+                                        continue;
+                                    }
                                     self.pop_until(element_kind);
                                     continue;
                                 }
@@ -473,13 +588,35 @@ impl HtmlParser {
                                     self.pop_until(element_kind);
                                     continue;
                                 }
-                                "a" => {
+                                "a" | "strong" | "em" => {
                                     let element_kind = ElementKind::from_str(tag)
                                         .expect("failed to convert string to ElementKind");
                                     token = self.t.next();
+                                    if !self.contain_in_stack(element_kind) {
+                                        // パースの失敗。トークンを無視する
+                                        continue;
+                                    }
                                     self.pop_until(element_kind);
                                     continue;
                                 }
+                                "ul" | "ol" | "li" | "form" | "button" => {
+                                    let element_kind = ElementKind::from_str(tag)
+                                        .expect("failed to convert string to ElementKind");
+                                    token = self.t.next();
+                                    if !self.contain_in_stack(element_kind) {
+                                        // パースの失敗。トークンを無視する
+                                        continue;
+                                    }
+                                    self.pop_until(element_kind);
+                                    continue;
+                                }
+                                // </br>はパースエラーだが、<br>として扱う
+                                "br" => {
+                                    self.insert_element(tag, Vec::new());
+                                    self.pop_current_node(ElementKind::Br);
+                                    token = self.t.next();
+                                    continue;
+                                }
                                 _ => {
                                     token = self.t.next();
                                 }
@@ -513,11 +650,19 @@ impl HtmlParser {
                                 continue;
                             }
 
+                            // スクリプトを実行できるように、次のトークンを読む前にパースを止める
                             if tag == "script" {
+                                self.pending_script = self
+                                    .stack_of_open_elements
+                                    .iter()
+                                    .rev()
+                                    .find(|node| {
+                                        node.borrow().element_kind() == Some(ElementKind::Script)
+                                    })
+                                    .cloned();
                                 self.pop_until(ElementKind::Script);
                                 self.mode = self.original_insertion_mode;
-                                token = self.t.next();
-                                continue;
+                                return self.window.clone();
                             }
                         }
                         Some(HtmlToken::Char(c)) => {
@@ -582,6 +727,8 @@ impl HtmlParser {
 mod tests {
     use super::*;
     use crate::alloc::string::ToString;
+    use crate::renderer::dom::api::get_inner_html;
+    use crate::renderer::dom::api::get_target_element_node;
     use alloc::vec;
 
     // 空文字のテスト
@@ -778,4 +925,229 @@ mod tests {
             text
         );
     }
+
+    // id/classの索引から要素を取得できるか
+    #[test]
+    fn test_id_and_class_index() {
+        let html = "<html><head></head><body><h1 id=\"title\" class=\"a b\">t</h1><p class=\"b\">p</p></body></html>"
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+
+        let title = window
+            .borrow()
+            .get_element_by_id("title")
+            .expect("failed to get an element by id");
+        assert_eq!(Some(ElementKind::H1), title.borrow().element_kind());
+        assert!(window.borrow().get_element_by_id("none").is_none());
+
+        assert_eq!(1, window.borrow().get_elements_by_class_name("a").len());
+        let b = window.borrow().get_elements_by_class_name("b");
+        assert_eq!(2, b.len());
+        assert_eq!(Some(ElementKind::H1), b[0].borrow().element_kind());
+        assert_eq!(Some(ElementKind::P), b[1].borrow().element_kind());
+    }
+
+    // テキストと要素が混在していても兄弟関係が保たれるか
+    #[test]
+    fn test_text_and_element_siblings() {
+        let html = "<html><head></head><body><p>a<a>b</a>c</p></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let p = window
+            .borrow()
+            .document()
+            .borrow()
+            .first_child()
+            .expect("failed to get html")
+            .borrow()
+            .last_child()
+            .upgrade()
+            .expect("failed to get body")
+            .borrow()
+            .first_child()
+            .expect("failed to get p");
+
+        let a_text = p.borrow().first_child().expect("failed to get text");
+        assert_eq!(NodeKind::Text("a".to_string()), a_text.borrow().kind());
+        let a = a_text.borrow().next_sibling().expect("failed to get a");
+        assert_eq!(Some(ElementKind::A), a.borrow().element_kind());
+        let c_text = a.borrow().next_sibling().expect("failed to get text");
+        assert!(c_text.borrow().next_sibling().is_none());
+    }
+
+    // 隣り合うテキストノードがまとめられ、空のテキストノードが取り除かれるか
+    #[test]
+    fn test_normalize() {
+        let parent = Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+            "p",
+            Vec::new(),
+        )))));
+        let mut previous: Option<Rc<RefCell<Node>>> = None;
+        for s in ["ab", "", "cd", "e"] {
+            let node = Rc::new(RefCell::new(Node::new(NodeKind::Text(s.to_string()))));
+            match previous {
+                Some(ref p) => p.borrow_mut().set_next_sibling(Some(node.clone())),
+                None => parent.borrow_mut().set_first_child(Some(node.clone())),
+            }
+            previous = Some(node);
+        }
+
+        parent.borrow_mut().normalize();
+
+        let text = parent
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child");
+        match text.borrow().kind() {
+            NodeKind::Text(s) => assert_eq!("abcde", s),
+            _ => panic!("text node should exist"),
+        }
+        assert!(text.borrow().next_sibling().is_none());
+        assert!(Rc::ptr_eq(
+            &text,
+            &parent
+                .borrow()
+                .last_child()
+                .upgrade()
+                .expect("failed to get a last child")
+        ));
+    }
+
+    // ドキュメントからウィンドウを辿れるか
+    #[test]
+    fn test_document_window() {
+        let t = HtmlTokenizer::new("<html></html>".to_string());
+        let window = HtmlParser::new(t).construct_tree();
+        let document_window = window
+            .borrow()
+            .document()
+            .borrow()
+            .window()
+            .upgrade()
+            .expect("failed to get a window from document");
+        assert!(Rc::ptr_eq(&window, &document_window));
+    }
+
+    // <br>は子要素を持たず、後ろのテキストは兄弟ノードになるか
+    #[test]
+    fn test_br() {
+        let html = "<html><head></head><body><p>a<br>b</br>c</p></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let p = window
+            .borrow()
+            .document()
+            .borrow()
+            .first_child()
+            .expect("failed to get html")
+            .borrow()
+            .last_child()
+            .upgrade()
+            .expect("failed to get body")
+            .borrow()
+            .first_child()
+            .expect("failed to get p");
+
+        let a = p.borrow().first_child().expect("failed to get text");
+        let br = a.borrow().next_sibling().expect("failed to get br");
+        assert_eq!(Some(ElementKind::Br), br.borrow().element_kind());
+        assert!(br.borrow().first_child().is_none());
+        let b = br.borrow().next_sibling().expect("failed to get text");
+        assert_eq!(NodeKind::Text("b".to_string()), b.borrow().kind());
+        let br = b.borrow().next_sibling().expect("failed to get br");
+        assert_eq!(Some(ElementKind::Br), br.borrow().element_kind());
+        let c = br.borrow().next_sibling().expect("failed to get text");
+        assert_eq!(NodeKind::Text("c".to_string()), c.borrow().kind());
+    }
+
+    // 閉じタグが省略された<li>は次の<li>の前で閉じられるか
+    #[test]
+    fn test_list() {
+        let html = "<html><head></head><body><ul><li>a<li>b</li></ul></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let ul = window
+            .borrow()
+            .document()
+            .borrow()
+            .first_child()
+            .expect("failed to get html")
+            .borrow()
+            .last_child()
+            .upgrade()
+            .expect("failed to get body")
+            .borrow()
+            .first_child()
+            .expect("failed to get ul");
+        assert_eq!(Some(ElementKind::Ul), ul.borrow().element_kind());
+
+        let li = ul.borrow().first_child().expect("failed to get li");
+        assert_eq!(Some(ElementKind::Li), li.borrow().element_kind());
+        let a = li.borrow().first_child().expect("failed to get text");
+        assert_eq!(NodeKind::Text("a".to_string()), a.borrow().kind());
+
+        let li = li.borrow().next_sibling().expect("failed to get li");
+        assert_eq!(Some(ElementKind::Li), li.borrow().element_kind());
+        let b = li.borrow().first_child().expect("failed to get text");
+        assert_eq!(NodeKind::Text("b".to_string()), b.borrow().kind());
+        assert!(li.borrow().next_sibling().is_none());
+    }
+
+    // </script>ごとにパースが止まり、その時点ではスクリプトより後の要素がまだないか
+    #[test]
+    fn test_parse_until_script() {
+        let html = "<html><head></head><body><script>a < b</script><p>x</p><script src=\"b.js\"></script></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let mut parser = HtmlParser::new(t);
+        let document = parser.window().borrow().document();
+
+        let script = parser.parse_until_script().expect("failed to get script");
+        assert_eq!(Some(ElementKind::Script), script.borrow().element_kind());
+        let text = script.borrow().first_child().expect("failed to get text");
+        assert_eq!(NodeKind::Text("a < b".to_string()), text.borrow().kind());
+        assert!(get_target_element_node(Some(document.clone()), ElementKind::P).is_none());
+
+        let script = parser.parse_until_script().expect("failed to get script");
+        assert_eq!(
+            Some("b.js".to_string()),
+            script
+                .borrow()
+                .get_element()
+                .and_then(|e| e.get_attribute("src"))
+        );
+        assert!(get_target_element_node(Some(document), ElementKind::P).is_some());
+        assert!(parser.parse_until_script().is_none());
+    }
+
+    // 開いていない</p>や</pre>は無視されるか
+    #[test]
+    fn test_stray_p_and_pre_end_tags() {
+        let html = "<html><head></head><body><p>a</p></p>b</pre><pre>c</pre></pre></body></html>"
+            .to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let body = get_target_element_node(Some(window.borrow().document()), ElementKind::Body)
+            .expect("failed to get body");
+        assert_eq!("<p>a</p>b<pre>c</pre>", get_inner_html(body));
+    }
+
+    // 入れ子が崩れた</a>、</strong>、</em>でパニックせず、開いていない終了タグは無視されるか
+    #[test]
+    fn test_misnested_inline_end_tags() {
+        let cases = [
+            ("<strong><em>x</strong></em>", "<strong><em>x</em></strong>"),
+            ("<p>a</em>b</p>", "<p>ab</p>"),
+            ("<em>a<p>b</em>c</p>", "<em>a<p>b</p></em>c"),
+            ("</a>x", "x"),
+        ];
+        for (input, expected) in cases {
+            let html = alloc::format!("<html><head></head><body>{}</body></html>", input);
+            let t = HtmlTokenizer::new(html);
+            let window = HtmlParser::new(t).construct_tree();
+            let body = get_target_element_node(Some(window.borrow().document()), ElementKind::Body)
+                .expect("failed to get body");
+            assert_eq!(expected, get_inner_html(body), "input: {}", input);
+        }
+    }
 }
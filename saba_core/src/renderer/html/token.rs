@@ -79,6 +79,18 @@ impl HtmlTokenizer {
         self.latest_token = None;
         assert!(self.latest_token.is_none());
 
+        // <script>の中身は</script>が出てくるまでJavaScriptとして扱い、タグを解釈しない
+        if let Some(HtmlToken::StartTag {
+            ref tag,
+            self_closing: false,
+            ..
+        }) = t
+        {
+            if tag == "script" {
+                self.state = State::ScriptData;
+            }
+        }
+
         t
     }
 
@@ -543,7 +555,8 @@ impl Iterator for HtmlTokenizer {
                 // JavaScriptの終了を表す</script>終了タグのタグ名部分(script)を解析している状態
                 State::ScriptDataEndTagName => {
                     // create_tagメソッドによって作成したlatest_tokenを返す
-                    if c == '>' {
+                    // </script>以外の終了タグ（文字列の中の"</p>"など）はスクリプトの文字として扱う
+                    if c == '>' && self.buf.eq_ignore_ascii_case("script") {
                         self.state = State::Data;
                         return self.take_latest_token();
                     }
@@ -555,9 +568,10 @@ impl Iterator for HtmlTokenizer {
                         continue;
                     }
 
+                    // ここまでの"</"とタグ名を文字トークンとして返し、今の文字はScriptData状態で処理し直す
+                    self.latest_token = None;
                     self.state = State::TemporaryBuffer;
-                    self.buf = String::from("</");
-                    self.buf.push(c);
+                    self.buf = alloc::format!("</{}", self.buf);
                     continue;
                 }
 
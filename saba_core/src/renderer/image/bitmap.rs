@@ -0,0 +1,106 @@
+use crate::error::Error;
+use crate::renderer::image::bmp::decode_bmp;
+use crate::renderer::image::png::decode_png;
+use crate::renderer::image::png::PNG_SIGNATURE;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+
+// デコードした画像
+// 画素は左上から行ごとに並び、1画素を0xAARRGGBBの形式で持つ
+#[derive(Clone, PartialEq, Eq)]
+pub struct Bitmap {
+    width: i64,
+    height: i64,
+    pixels: Vec<u32>,
+}
+
+impl Bitmap {
+    pub fn new(width: i64, height: i64, pixels: Vec<u32>) -> Self {
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> i64 {
+        self.width
+    }
+
+    pub fn height(&self) -> i64 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+
+    // (x, y)の画素の色
+    pub fn pixel(&self, x: i64, y: i64) -> Option<u32> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        self.pixels.get((y * self.width + x) as usize).copied()
+    }
+
+    // 画像をwidth x heightに拡大・縮小した時の(x, y)の画素の色
+    // 一番近い元の画素の色を使う（最近傍法）
+    pub fn scaled_pixel(&self, x: i64, y: i64, width: i64, height: i64) -> Option<u32> {
+        if x < 0 || y < 0 || x >= width || y >= height {
+            return None;
+        }
+        self.pixel(x * self.width / width, y * self.height / height)
+    }
+}
+
+// 画素の数が多いので、デバッグ出力には大きさだけを出す
+impl Debug for Bitmap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Bitmap")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+// 先頭のシグネチャから画像の形式を判断してデコードする
+// サポートしている形式はPNGとBMP
+pub fn decode_image(data: &[u8]) -> Result<Bitmap, Error> {
+    if data.starts_with(&PNG_SIGNATURE) {
+        decode_png(data)
+    } else if data.starts_with(b"BM") {
+        decode_bmp(data)
+    } else {
+        Err(Error::UnexpectedInput(
+            "unsupported image format".to_string(),
+        ))
+    }
+}
+
+// 8bitの各成分から0xAARRGGBBの画素を作る
+pub fn argb(a: u8, r: u8, g: u8, b: u8) -> u32 {
+    (a as u32) << 24 | (r as u32) << 16 | (g as u32) << 8 | b as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_scaled_pixel() {
+        let bitmap = Bitmap::new(2, 2, vec![1, 2, 3, 4]);
+
+        // 2倍に拡大すると、元の1画素が2x2画素になる
+        assert_eq!(Some(1), bitmap.scaled_pixel(1, 1, 4, 4));
+        assert_eq!(Some(2), bitmap.scaled_pixel(2, 0, 4, 4));
+        assert_eq!(Some(4), bitmap.scaled_pixel(3, 3, 4, 4));
+        assert_eq!(None, bitmap.scaled_pixel(4, 0, 4, 4));
+
+        // 縮小すると、左上の画素を使う
+        assert_eq!(Some(1), bitmap.scaled_pixel(0, 0, 1, 1));
+        assert_eq!(None, bitmap.scaled_pixel(0, 0, 0, 0));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::error::Error;
+use crate::renderer::image::bitmap::argb;
+use crate::renderer::image::bitmap::Bitmap;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+// 圧縮なし（BI_RGB）
+const BI_RGB: u32 = 0;
+// ビットフィールド（BI_BITFIELDS）
+// 32bitの画素がBGRAの順に並んでいるものだけをサポートする
+const BI_BITFIELDS: u32 = 3;
+
+// BMP形式の画像をデコードする
+// 圧縮されていない24bitと32bitの画素だけをサポートする
+// 高さが正の場合は下の行から、負の場合は上の行から並んでいる
+pub fn decode_bmp(data: &[u8]) -> Result<Bitmap, Error> {
+    if !data.starts_with(b"BM") {
+        return Err(Error::UnexpectedInput("invalid bmp signature".to_string()));
+    }
+    let pixel_offset = read_u32(data, 10)? as usize;
+    let width = read_u32(data, 18)? as i32 as i64;
+    let height = read_u32(data, 22)? as i32 as i64;
+    let bits_per_pixel = read_u16(data, 28)?;
+    let compression = read_u32(data, 30)?;
+
+    if width <= 0 || height == 0 {
+        return Err(Error::UnexpectedInput(format!(
+            "invalid bmp size {}x{}",
+            width, height
+        )));
+    }
+    let bytes_per_pixel = match (bits_per_pixel, compression) {
+        (24, BI_RGB) => 3,
+        (32, BI_RGB) | (32, BI_BITFIELDS) => 4,
+        _ => {
+            return Err(Error::UnexpectedInput(format!(
+                "unsupported bmp format: {}bit, compression {}",
+                bits_per_pixel, compression
+            )))
+        }
+    };
+
+    let top_down = height < 0;
+    let height = height.abs();
+    // 各行は4バイト単位に揃えられている
+    let stride = (bytes_per_pixel * width as usize).div_ceil(4) * 4;
+    let mut pixels = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        let row = if top_down { y } else { height - 1 - y };
+        let start = pixel_offset + row as usize * stride;
+        for x in 0..width as usize {
+            let i = start + x * bytes_per_pixel;
+            let p = data
+                .get(i..i + bytes_per_pixel)
+                .ok_or(Error::UnexpectedInput("bmp data is too short".to_string()))?;
+            // BI_RGBの32bitの画素の4バイト目は使われないので、不透明として扱う
+            let alpha = if compression == BI_BITFIELDS {
+                p[3]
+            } else {
+                0xff
+            };
+            pixels.push(argb(alpha, p[2], p[1], p[0]));
+        }
+    }
+
+    Ok(Bitmap::new(width, height, pixels))
+}
+
+// リトルエンディアンの値を読む
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, Error> {
+    match data.get(offset..offset + 2) {
+        Some(b) => Ok(u16::from_le_bytes([b[0], b[1]])),
+        None => Err(Error::UnexpectedInput(
+            "bmp header is too short".to_string(),
+        )),
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, Error> {
+    match data.get(offset..offset + 4) {
+        Some(b) => Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        None => Err(Error::UnexpectedInput(
+            "bmp header is too short".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 下の行から並んだ24bitの2x2の画像
+    #[test]
+    fn test_decode_bmp() {
+        let data = [
+            0x42, 0x4d, 0x46, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x36, 0x00, 0x00, 0x00,
+            0x28, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00,
+            0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x13, 0x0b, 0x00, 0x00,
+            0x13, 0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x00,
+            0x00, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0x00, 0xff, 0x00, 0x00, 0x00,
+        ];
+        let bitmap = decode_bmp(&data).expect("bmp should be decoded");
+        assert_eq!(2, bitmap.width());
+        assert_eq!(2, bitmap.height());
+        assert_eq!(
+            &[0xffff0000, 0xff00ff00, 0xff0000ff, 0xffffffff],
+            bitmap.pixels()
+        );
+
+        assert!(decode_bmp(&data[..60]).is_err());
+    }
+}
@@ -0,0 +1,326 @@
+use crate::error::Error;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// 長さの符号（257から285）ごとの基本の長さと追加のビット数
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+// 距離の符号（0から29）ごとの基本の距離と追加のビット数
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// 動的ハフマン符号で、符号長の符号長が並んでいる順番
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+// ハフマン符号の最大の長さ
+const MAX_BITS: usize = 15;
+
+// zlib形式（RFC 1950）のデータを展開する
+// 2バイトのヘッダの後にDEFLATEのデータが続く（末尾のAdler-32は確認しない）
+pub fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    if data.len() < 2 {
+        return Err(Error::UnexpectedInput("zlib data is too short".to_string()));
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0f != 8 || !((cmf as u16) << 8 | flg as u16).is_multiple_of(31) {
+        return Err(Error::UnexpectedInput("invalid zlib header".to_string()));
+    }
+    // プリセット辞書はサポートしていない
+    if flg & 0x20 != 0 {
+        return Err(Error::UnexpectedInput(
+            "zlib preset dictionary is not supported".to_string(),
+        ));
+    }
+    inflate(&data[2..])
+}
+
+// DEFLATE形式（RFC 1951）のデータを展開する
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut reader = BitReader::new(data);
+    let mut output = Vec::new();
+    loop {
+        let is_final = reader.read_bits(1)? == 1;
+        match reader.read_bits(2)? {
+            0 => inflate_stored(&mut reader, &mut output)?,
+            1 => {
+                let (literal, distance) = fixed_huffman();
+                inflate_huffman(&mut reader, &mut output, &literal, &distance)?;
+            }
+            2 => {
+                let (literal, distance) = dynamic_huffman(&mut reader)?;
+                inflate_huffman(&mut reader, &mut output, &literal, &distance)?;
+            }
+            block_type => {
+                return Err(Error::UnexpectedInput(format!(
+                    "invalid deflate block type {}",
+                    block_type
+                )))
+            }
+        }
+        if is_final {
+            return Ok(output);
+        }
+    }
+}
+
+// 圧縮されていないブロック
+fn inflate_stored(reader: &mut BitReader, output: &mut Vec<u8>) -> Result<(), Error> {
+    reader.align_to_byte();
+    let len = reader.read_bits(16)?;
+    let nlen = reader.read_bits(16)?;
+    if len != !nlen & 0xffff {
+        return Err(Error::UnexpectedInput(
+            "invalid stored block length".to_string(),
+        ));
+    }
+    for _ in 0..len {
+        output.push(reader.read_bits(8)? as u8);
+    }
+    Ok(())
+}
+
+// ハフマン符号で圧縮されたブロック
+// 256はブロックの終わり、それより小さい値はそのままのバイト、大きい値は前に出力した内容の繰り返し
+fn inflate_huffman(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    literal: &Huffman,
+    distance: &Huffman,
+) -> Result<(), Error> {
+    loop {
+        let symbol = literal.decode(reader)? as usize;
+        match symbol {
+            0..=255 => output.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let index = symbol - 257;
+                let length = LENGTH_BASE[index] as usize
+                    + reader.read_bits(LENGTH_EXTRA[index] as u32)? as usize;
+                let index = distance.decode(reader)? as usize;
+                if index >= DISTANCE_BASE.len() {
+                    return Err(Error::UnexpectedInput(
+                        "invalid deflate distance".to_string(),
+                    ));
+                }
+                let dist = DISTANCE_BASE[index] as usize
+                    + reader.read_bits(DISTANCE_EXTRA[index] as u32)? as usize;
+                if dist > output.len() {
+                    return Err(Error::UnexpectedInput(
+                        "deflate distance is too far".to_string(),
+                    ));
+                }
+                // 繰り返しは出力中の内容と重なることがあるので、1バイトずつコピーする
+                let start = output.len() - dist;
+                for i in 0..length {
+                    output.push(output[start + i]);
+                }
+            }
+            _ => return Err(Error::UnexpectedInput("invalid deflate symbol".to_string())),
+        }
+    }
+}
+
+// 固定ハフマン符号
+fn fixed_huffman() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    for (symbol, length) in lengths.iter_mut().enumerate() {
+        *length = match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+// 動的ハフマン符号
+// 符号長自体もハフマン符号で圧縮されている
+fn dynamic_huffman(reader: &mut BitReader) -> Result<(Huffman, Huffman), Error> {
+    let literal_count = reader.read_bits(5)? as usize + 257;
+    let distance_count = reader.read_bits(5)? as usize + 1;
+    let code_length_count = reader.read_bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for i in CODE_LENGTH_ORDER.iter().take(code_length_count) {
+        code_lengths[*i] = reader.read_bits(3)? as u8;
+    }
+    let code_length_huffman = Huffman::new(&code_lengths);
+
+    // 16は直前の長さの繰り返し、17と18は0の繰り返し
+    let mut lengths = vec![0u8; literal_count + distance_count];
+    let mut i = 0;
+    while i < lengths.len() {
+        let symbol = code_length_huffman.decode(reader)?;
+        let (length, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                if i == 0 {
+                    return Err(Error::UnexpectedInput(
+                        "no previous code length to repeat".to_string(),
+                    ));
+                }
+                (lengths[i - 1], 3 + reader.read_bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.read_bits(3)? as usize),
+            _ => (0, 11 + reader.read_bits(7)? as usize),
+        };
+        if i + repeat > lengths.len() {
+            return Err(Error::UnexpectedInput("too many code lengths".to_string()));
+        }
+        lengths[i..i + repeat].fill(length);
+        i += repeat;
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literal_count]),
+        Huffman::new(&lengths[literal_count..]),
+    ))
+}
+
+// 正規ハフマン符号の表
+// 符号の長さごとの数と、符号の長さと値の順に並べた値を持つ
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    // 値ごとの符号の長さから作る（長さ0の値は使われない）
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for length in lengths {
+            counts[*length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for length in 1..=MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0u16; offsets[MAX_BITS + 1] as usize];
+        for (symbol, length) in lengths.iter().enumerate() {
+            if *length != 0 {
+                symbols[offsets[*length as usize] as usize] = symbol as u16;
+                offsets[*length as usize] += 1;
+            }
+        }
+
+        Self { counts, symbols }
+    }
+
+    // 1ビットずつ読み、同じ長さの符号の範囲に入ったところで値を決める
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, Error> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for length in 1..=MAX_BITS {
+            code |= reader.read_bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(Error::UnexpectedInput("invalid huffman code".to_string()))
+    }
+}
+
+// 下位のビットから順にビット単位で読む
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bit_buffer: u32,
+    bit_count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            bit_buffer: 0,
+            bit_count: 0,
+        }
+    }
+
+    fn read_bits(&mut self, count: u32) -> Result<u32, Error> {
+        while self.bit_count < count {
+            let byte = match self.data.get(self.position) {
+                Some(byte) => *byte,
+                None => {
+                    return Err(Error::UnexpectedInput(
+                        "deflate data is too short".to_string(),
+                    ))
+                }
+            };
+            self.position += 1;
+            self.bit_buffer |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let value = self.bit_buffer & ((1u32 << count) - 1);
+        self.bit_buffer >>= count;
+        self.bit_count -= count;
+        Ok(value)
+    }
+
+    // 読みかけのバイトの残りのビットを捨てる
+    fn align_to_byte(&mut self) {
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 圧縮されていないブロック
+    #[test]
+    fn test_stored() {
+        let data = [
+            0x78, 0x01, 0x01, 0x05, 0x00, 0xfa, 0xff, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x06, 0x2c,
+            0x02, 0x15,
+        ];
+        assert_eq!(Ok(b"hello".to_vec()), zlib_decompress(&data));
+    }
+
+    // 固定ハフマン符号と、出力と重なる繰り返し
+    #[test]
+    fn test_fixed_huffman() {
+        let data = [0x4b, 0x4c, 0x4a, 0x4e, 0x84, 0x21, 0x00];
+        assert_eq!(Ok(b"abcabcabcabc".to_vec()), inflate(&data));
+    }
+
+    // 動的ハフマン符号
+    #[test]
+    fn test_dynamic_huffman() {
+        let data = [
+            0x1d, 0x8c, 0xb1, 0x15, 0x00, 0x51, 0x08, 0xc2, 0x56, 0xf9, 0xab, 0x51, 0xa4, 0xb0,
+            0x91, 0x86, 0xfd, 0xdf, 0xe1, 0x75, 0x81, 0x88, 0x2c, 0x41, 0x52, 0x40, 0x44, 0x8f,
+            0xd2, 0x55, 0x30, 0x27, 0x70, 0xe5, 0xd1, 0xf5, 0x85, 0x52, 0x2c, 0x93, 0xac, 0x9c,
+            0xfd, 0x37, 0x64, 0xea, 0x0c, 0x4e, 0x43, 0xe3, 0xdb, 0xed, 0xc0, 0xf7, 0xa3, 0xd3,
+            0x9e, 0x45, 0x99, 0x57, 0xc2, 0x53, 0xaf, 0xec, 0xf0, 0x01,
+        ];
+        let expected = b"eneteaaateeaeta eateneteeeieteaeoeaaieteeateaieetetoaoettnaotneaetaetiteaoeeottaeaet nnteeoeeeiietotntati etoeoieaeatnie";
+        assert_eq!(Ok(expected.to_vec()), inflate(&data));
+
+        // 途中で終わっているデータ
+        assert!(inflate(&data[..30]).is_err());
+    }
+}
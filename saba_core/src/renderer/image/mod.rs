@@ -0,0 +1,4 @@
+pub mod bitmap;
+pub mod bmp;
+pub mod inflate;
+pub mod png;
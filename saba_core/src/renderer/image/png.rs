@@ -0,0 +1,249 @@
+use crate::error::Error;
+use crate::renderer::image::bitmap::argb;
+use crate::renderer::image::bitmap::Bitmap;
+use crate::renderer::image::inflate::zlib_decompress;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// PNGファイルの先頭の8バイト
+pub const PNG_SIGNATURE: [u8; 8] = [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a];
+
+// カラータイプ
+const GRAYSCALE: u8 = 0;
+const TRUECOLOR: u8 = 2;
+const INDEXED: u8 = 3;
+const GRAYSCALE_ALPHA: u8 = 4;
+const TRUECOLOR_ALPHA: u8 = 6;
+
+// PNG形式の画像をデコードする
+// ビット深度が8で、インターレースされていない画像だけをサポートする
+pub fn decode_png(data: &[u8]) -> Result<Bitmap, Error> {
+    if !data.starts_with(&PNG_SIGNATURE) {
+        return Err(Error::UnexpectedInput("invalid png signature".to_string()));
+    }
+
+    let mut header = None;
+    let mut palette: Vec<u8> = Vec::new();
+    let mut transparency: Vec<u8> = Vec::new();
+    let mut compressed: Vec<u8> = Vec::new();
+    let mut offset = PNG_SIGNATURE.len();
+    // チャンクは長さ、種類、データ、CRCの順に並んでいる（CRCは確認しない）
+    loop {
+        let length = read_u32(data, offset)? as usize;
+        let kind = data
+            .get(offset + 4..offset + 8)
+            .ok_or(Error::UnexpectedInput("png data is too short".to_string()))?;
+        let body = data
+            .get(offset + 8..offset + 8 + length)
+            .ok_or(Error::UnexpectedInput("png data is too short".to_string()))?;
+        match kind {
+            b"IHDR" => header = Some(Header::new(body)?),
+            b"PLTE" => palette = body.to_vec(),
+            b"tRNS" => transparency = body.to_vec(),
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            // その他のチャンクは無視する
+            _ => {}
+        }
+        offset += 12 + length;
+    }
+
+    let header = match header {
+        Some(h) => h,
+        None => return Err(Error::UnexpectedInput("png has no IHDR".to_string())),
+    };
+    let channels = header.channels()?;
+    let stride = header.width as usize * channels;
+    let raw = zlib_decompress(&compressed)?;
+    let scanlines = unfilter(&raw, stride, header.height as usize, channels)?;
+
+    let mut pixels = Vec::with_capacity((header.width * header.height) as usize);
+    for p in scanlines.chunks_exact(channels) {
+        let pixel = match header.color_type {
+            GRAYSCALE => argb(0xff, p[0], p[0], p[0]),
+            TRUECOLOR => argb(0xff, p[0], p[1], p[2]),
+            INDEXED => {
+                let i = p[0] as usize;
+                let rgb = palette
+                    .get(i * 3..i * 3 + 3)
+                    .ok_or(Error::UnexpectedInput(format!(
+                        "png palette index {} is out of range",
+                        i
+                    )))?;
+                // tRNSがない、または足りない場合は不透明とする
+                let alpha = transparency.get(i).copied().unwrap_or(0xff);
+                argb(alpha, rgb[0], rgb[1], rgb[2])
+            }
+            GRAYSCALE_ALPHA => argb(p[1], p[0], p[0], p[0]),
+            _ => argb(p[3], p[0], p[1], p[2]),
+        };
+        pixels.push(pixel);
+    }
+
+    Ok(Bitmap::new(header.width, header.height, pixels))
+}
+
+// IHDRチャンクの内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Header {
+    width: i64,
+    height: i64,
+    bit_depth: u8,
+    color_type: u8,
+    interlace: u8,
+}
+
+impl Header {
+    fn new(body: &[u8]) -> Result<Self, Error> {
+        if body.len() < 13 {
+            return Err(Error::UnexpectedInput("png IHDR is too short".to_string()));
+        }
+        let header = Self {
+            width: read_u32(body, 0)? as i64,
+            height: read_u32(body, 4)? as i64,
+            bit_depth: body[8],
+            color_type: body[9],
+            interlace: body[12],
+        };
+        if header.width == 0 || header.height == 0 {
+            return Err(Error::UnexpectedInput(format!(
+                "invalid png size {}x{}",
+                header.width, header.height
+            )));
+        }
+        if header.bit_depth != 8 || header.interlace != 0 {
+            return Err(Error::UnexpectedInput(format!(
+                "unsupported png format: bit depth {}, interlace {}",
+                header.bit_depth, header.interlace
+            )));
+        }
+        Ok(header)
+    }
+
+    // 1画素あたりのバイト数
+    fn channels(&self) -> Result<usize, Error> {
+        match self.color_type {
+            GRAYSCALE | INDEXED => Ok(1),
+            GRAYSCALE_ALPHA => Ok(2),
+            TRUECOLOR => Ok(3),
+            TRUECOLOR_ALPHA => Ok(4),
+            _ => Err(Error::UnexpectedInput(format!(
+                "unsupported png color type {}",
+                self.color_type
+            ))),
+        }
+    }
+}
+
+// 各行の先頭にあるフィルタの種類に従って、フィルタをかける前の値に戻す
+fn unfilter(raw: &[u8], stride: usize, height: usize, bpp: usize) -> Result<Vec<u8>, Error> {
+    if raw.len() < (stride + 1) * height {
+        return Err(Error::UnexpectedInput("png data is too short".to_string()));
+    }
+    let mut output = vec![0u8; stride * height];
+    for y in 0..height {
+        let filter = raw[y * (stride + 1)];
+        let line = &raw[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (previous, current) = output.split_at_mut(y * stride);
+        let current = &mut current[..stride];
+        let above = if y == 0 {
+            None
+        } else {
+            Some(&previous[(y - 1) * stride..])
+        };
+        for x in 0..stride {
+            // a: 左、b: 上、c: 左上の画素の値
+            let a = if x >= bpp { current[x - bpp] } else { 0 };
+            let b = above.map_or(0, |above| above[x]);
+            let c = match above {
+                Some(above) if x >= bpp => above[x - bpp],
+                _ => 0,
+            };
+            let predictor = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => {
+                    return Err(Error::UnexpectedInput(format!(
+                        "invalid png filter type {}",
+                        filter
+                    )))
+                }
+            };
+            current[x] = line[x].wrapping_add(predictor);
+        }
+    }
+    Ok(output)
+}
+
+// 左、上、左上のうち、a + b - cに最も近い値を選ぶ
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// ビッグエンディアンの値を読む
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, Error> {
+    match data.get(offset..offset + 4) {
+        Some(b) => Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]])),
+        None => Err(Error::UnexpectedInput("png data is too short".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // SubとPaethのフィルタを使ったRGBの2x2の画像
+    #[test]
+    fn test_decode_truecolor() {
+        let data = [
+            0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x08, 0x02, 0x00, 0x00,
+            0x00, 0xfd, 0xd4, 0x9a, 0x73, 0x00, 0x00, 0x00, 0x15, 0x49, 0x44, 0x41, 0x54, 0x78,
+            0x9c, 0x63, 0xfc, 0xcf, 0xc0, 0xc0, 0xf8, 0x9f, 0x81, 0x85, 0x91, 0xe1, 0x3f, 0x90,
+            0x05, 0x00, 0x1d, 0x2b, 0x04, 0x04, 0x85, 0x9a, 0x1d, 0x05, 0x00, 0x00, 0x00, 0x00,
+            0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+        ];
+        let bitmap = decode_png(&data).expect("png should be decoded");
+        assert_eq!(2, bitmap.width());
+        assert_eq!(2, bitmap.height());
+        assert_eq!(
+            &[0xffff0000, 0xff00ff00, 0xff0000ff, 0xffffffff],
+            bitmap.pixels()
+        );
+    }
+
+    // パレットとtRNSを使った2x1の画像
+    #[test]
+    fn test_decode_indexed() {
+        let data = [
+            0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48,
+            0x44, 0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x08, 0x03, 0x00, 0x00,
+            0x00, 0xc3, 0xfc, 0x8f, 0xb8, 0x00, 0x00, 0x00, 0x06, 0x50, 0x4c, 0x54, 0x45, 0xff,
+            0x00, 0x00, 0x00, 0x00, 0xff, 0x6c, 0xa1, 0xfd, 0x8e, 0x00, 0x00, 0x00, 0x01, 0x74,
+            0x52, 0x4e, 0x53, 0x80, 0xad, 0x5e, 0x5b, 0x46, 0x00, 0x00, 0x00, 0x0b, 0x49, 0x44,
+            0x41, 0x54, 0x78, 0x9c, 0x63, 0x60, 0x60, 0x04, 0x00, 0x00, 0x04, 0x00, 0x02, 0xbf,
+            0x7a, 0x3f, 0x4a, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60,
+            0x82,
+        ];
+        let bitmap = decode_png(&data).expect("png should be decoded");
+        assert_eq!(&[0x80ff0000, 0xff0000ff], bitmap.pixels());
+
+        // IENDの前で終わっているデータ
+        assert!(decode_png(&data[..80]).is_err());
+    }
+}
@@ -0,0 +1,861 @@
+use crate::error::Error;
+use crate::renderer::js::token::JsLexer;
+use crate::renderer::js::token::Token;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// https://github.com/estree/estree/blob/master/es5.md
+// ESTreeの名前に合わせた抽象構文木のノード
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    // 式だけの文　`a = 1;`
+    ExpressionStatement(Rc<Node>),
+    // {}で囲まれた文の並び
+    BlockStatement {
+        body: Vec<Rc<Node>>,
+    },
+    ReturnStatement {
+        argument: Option<Rc<Node>>,
+    },
+    IfStatement {
+        test: Rc<Node>,
+        consequent: Rc<Node>,
+        alternate: Option<Rc<Node>>,
+    },
+    WhileStatement {
+        test: Rc<Node>,
+        body: Rc<Node>,
+    },
+    ForStatement {
+        init: Option<Rc<Node>>,
+        test: Option<Rc<Node>>,
+        update: Option<Rc<Node>>,
+        body: Rc<Node>,
+    },
+    BreakStatement,
+    ContinueStatement,
+    // var、let、constによる変数の宣言
+    // 初期値がない変数はundefinedになる
+    VariableDeclaration {
+        kind: VariableKind,
+        declarations: Vec<(String, Option<Rc<Node>>)>,
+    },
+    FunctionDeclaration {
+        id: String,
+        params: Vec<String>,
+        body: Rc<Node>,
+    },
+    FunctionExpression {
+        id: Option<String>,
+        params: Vec<String>,
+        body: Rc<Node>,
+    },
+    // アロー関数　`(a, b) => a + b`
+    // 本体が式の場合は、その式をreturnするブロックにする
+    // thisは関数を作った場所のthisのまま変わらない
+    ArrowFunctionExpression {
+        params: Vec<String>,
+        body: Rc<Node>,
+    },
+    // `a = b`、`a += b`など
+    AssignmentExpression {
+        operator: String,
+        left: Rc<Node>,
+        right: Rc<Node>,
+    },
+    // `a && b`、`a || b`
+    LogicalExpression {
+        operator: String,
+        left: Rc<Node>,
+        right: Rc<Node>,
+    },
+    BinaryExpression {
+        operator: String,
+        left: Rc<Node>,
+        right: Rc<Node>,
+    },
+    // `!a`、`-a`、`typeof a`など
+    UnaryExpression {
+        operator: String,
+        argument: Rc<Node>,
+    },
+    // `a++`、`--a`など
+    UpdateExpression {
+        operator: String,
+        prefix: bool,
+        argument: Rc<Node>,
+    },
+    // `test ? consequent : alternate`
+    ConditionalExpression {
+        test: Rc<Node>,
+        consequent: Rc<Node>,
+        alternate: Rc<Node>,
+    },
+    CallExpression {
+        callee: Rc<Node>,
+        arguments: Vec<Rc<Node>>,
+    },
+    // `object.property`、`object[property]`
+    // `object.property`の場合、propertyは名前の文字列リテラルにする
+    MemberExpression {
+        object: Rc<Node>,
+        property: Rc<Node>,
+    },
+    // `[a, b, c]`
+    ArrayExpression {
+        elements: Vec<Rc<Node>>,
+    },
+    // `{ a: 1, "b": 2 }`
+    // プロパティの名前は書かれた順に並べる
+    ObjectExpression {
+        properties: Vec<(String, Rc<Node>)>,
+    },
+    Identifier(String),
+    NumericLiteral(f64),
+    StringLiteral(String),
+    BooleanLiteral(bool),
+    NullLiteral,
+    UndefinedLiteral,
+    ThisExpression,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VariableKind {
+    Var,
+    Let,
+    Const,
+}
+
+// スクリプト全体
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Program {
+    body: Vec<Rc<Node>>,
+}
+
+impl Program {
+    pub fn new() -> Self {
+        Self { body: Vec::new() }
+    }
+
+    pub fn set_body(&mut self, body: Vec<Rc<Node>>) {
+        self.body = body;
+    }
+
+    pub fn body(&self) -> &Vec<Rc<Node>> {
+        &self.body
+    }
+}
+
+// トークンの並びから抽象構文木を作る再帰下降パーサ
+// 文法の誤りはError::UnexpectedInputで"SyntaxError: "から始まるメッセージを返す
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl JsParser {
+    pub fn new(lexer: JsLexer) -> Self {
+        Self {
+            tokens: lexer.collect(),
+            pos: 0,
+        }
+    }
+
+    pub fn parse_ast(&mut self) -> Result<Program, Error> {
+        let mut body = Vec::new();
+        while self.peek().is_some() {
+            body.push(self.statement()?);
+        }
+        let mut program = Program::new();
+        program.set_body(body);
+        Ok(program)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    // 次のトークンが記号sかどうか
+    fn is_punctuator(&self, s: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punctuator(p)) if p == s)
+    }
+
+    fn is_keyword(&self, s: &str) -> bool {
+        matches!(self.peek(), Some(Token::Keyword(k)) if k == s)
+    }
+
+    // 次のトークンが記号sであれば消費してtrueを返す
+    fn consume_punctuator(&mut self, s: &str) -> bool {
+        if self.is_punctuator(s) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_punctuator(&mut self, s: &str) -> Result<(), Error> {
+        if self.consume_punctuator(s) {
+            return Ok(());
+        }
+        Err(self.unexpected(s))
+    }
+
+    fn expect_identifier(&mut self) -> Result<String, Error> {
+        match self.peek() {
+            Some(Token::Identifier(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.unexpected("identifier")),
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> Error {
+        let found = match self.peek() {
+            Some(Token::Punctuator(s))
+            | Some(Token::Identifier(s))
+            | Some(Token::Keyword(s))
+            | Some(Token::StringLiteral(s)) => s.clone(),
+            Some(Token::Number(n)) => alloc::format!("{}", n),
+            None => String::from("end of input"),
+        };
+        Error::UnexpectedInput(alloc::format!(
+            "SyntaxError: expected {} but found {}",
+            expected,
+            found
+        ))
+    }
+
+    // 文の終わりの;は省略できる
+    fn consume_semicolon(&mut self) {
+        self.consume_punctuator(";");
+    }
+
+    fn statement(&mut self) -> Result<Rc<Node>, Error> {
+        if self.is_punctuator("{") {
+            return self.block_statement();
+        }
+        if self.consume_punctuator(";") {
+            return Ok(Rc::new(Node::BlockStatement { body: Vec::new() }));
+        }
+
+        let keyword = match self.peek() {
+            Some(Token::Keyword(keyword)) => keyword.clone(),
+            _ => String::new(),
+        };
+        let node = match keyword.as_str() {
+            "var" | "let" | "const" => {
+                let node = self.variable_declaration()?;
+                self.consume_semicolon();
+                node
+            }
+            "function" => {
+                self.pos += 1;
+                let id = self.expect_identifier()?;
+                let (params, body) = self.function_rest()?;
+                Node::FunctionDeclaration { id, params, body }
+            }
+            "return" => {
+                self.pos += 1;
+                let argument = if self.peek().is_none()
+                    || self.is_punctuator(";")
+                    || self.is_punctuator("}")
+                {
+                    None
+                } else {
+                    Some(self.expression()?)
+                };
+                self.consume_semicolon();
+                Node::ReturnStatement { argument }
+            }
+            "if" => {
+                self.pos += 1;
+                self.expect_punctuator("(")?;
+                let test = self.expression()?;
+                self.expect_punctuator(")")?;
+                let consequent = self.statement()?;
+                let alternate = if self.is_keyword("else") {
+                    self.pos += 1;
+                    Some(self.statement()?)
+                } else {
+                    None
+                };
+                Node::IfStatement {
+                    test,
+                    consequent,
+                    alternate,
+                }
+            }
+            "while" => {
+                self.pos += 1;
+                self.expect_punctuator("(")?;
+                let test = self.expression()?;
+                self.expect_punctuator(")")?;
+                let body = self.statement()?;
+                Node::WhileStatement { test, body }
+            }
+            "for" => self.for_statement()?,
+            "break" | "continue" => {
+                self.pos += 1;
+                self.consume_semicolon();
+                if keyword == "break" {
+                    Node::BreakStatement
+                } else {
+                    Node::ContinueStatement
+                }
+            }
+            _ => {
+                let expression = self.expression()?;
+                self.consume_semicolon();
+                Node::ExpressionStatement(expression)
+            }
+        };
+        Ok(Rc::new(node))
+    }
+
+    fn block_statement(&mut self) -> Result<Rc<Node>, Error> {
+        self.expect_punctuator("{")?;
+        let mut body = Vec::new();
+        while !self.consume_punctuator("}") {
+            if self.peek().is_none() {
+                return Err(self.unexpected("}"));
+            }
+            body.push(self.statement()?);
+        }
+        Ok(Rc::new(Node::BlockStatement { body }))
+    }
+
+    fn variable_declaration(&mut self) -> Result<Node, Error> {
+        let kind = match self.next() {
+            Some(Token::Keyword(k)) if k == "let" => VariableKind::Let,
+            Some(Token::Keyword(k)) if k == "const" => VariableKind::Const,
+            _ => VariableKind::Var,
+        };
+        let mut declarations = Vec::new();
+        loop {
+            let id = self.expect_identifier()?;
+            let init = if self.consume_punctuator("=") {
+                Some(self.assignment_expression()?)
+            } else {
+                None
+            };
+            declarations.push((id, init));
+            if !self.consume_punctuator(",") {
+                break;
+            }
+        }
+        Ok(Node::VariableDeclaration { kind, declarations })
+    }
+
+    fn for_statement(&mut self) -> Result<Node, Error> {
+        self.pos += 1;
+        self.expect_punctuator("(")?;
+        let init = if self.is_punctuator(";") {
+            None
+        } else if self.is_keyword("var") || self.is_keyword("let") || self.is_keyword("const") {
+            Some(Rc::new(self.variable_declaration()?))
+        } else {
+            Some(Rc::new(Node::ExpressionStatement(self.expression()?)))
+        };
+        self.expect_punctuator(";")?;
+        let test = if self.is_punctuator(";") {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.expect_punctuator(";")?;
+        let update = if self.is_punctuator(")") {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.expect_punctuator(")")?;
+        let body = self.statement()?;
+        Ok(Node::ForStatement {
+            init,
+            test,
+            update,
+            body,
+        })
+    }
+
+    // 関数の引数の並びと本体　`(a, b) { ... }`
+    fn function_rest(&mut self) -> Result<(Vec<String>, Rc<Node>), Error> {
+        self.expect_punctuator("(")?;
+        let mut params = Vec::new();
+        while !self.consume_punctuator(")") {
+            params.push(self.expect_identifier()?);
+            if !self.consume_punctuator(",") {
+                self.expect_punctuator(")")?;
+                break;
+            }
+        }
+        let body = self.block_statement()?;
+        Ok((params, body))
+    }
+
+    fn expression(&mut self) -> Result<Rc<Node>, Error> {
+        self.assignment_expression()
+    }
+
+    // 次のトークンからアロー関数が始まるかどうか
+    // `x =>`または、対応する)の次が=>の`(...)`
+    fn is_arrow_function(&self) -> bool {
+        match self.peek() {
+            Some(Token::Identifier(_)) => {
+                matches!(self.tokens.get(self.pos + 1), Some(Token::Punctuator(p)) if p == "=>")
+            }
+            Some(Token::Punctuator(p)) if p == "(" => {
+                let mut depth = 0;
+                for (i, token) in self.tokens.iter().enumerate().skip(self.pos) {
+                    match token {
+                        Token::Punctuator(p) if p == "(" => depth += 1,
+                        Token::Punctuator(p) if p == ")" => {
+                            depth -= 1;
+                            if depth == 0 {
+                                return matches!(
+                                    self.tokens.get(i + 1),
+                                    Some(Token::Punctuator(p)) if p == "=>"
+                                );
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn arrow_function(&mut self) -> Result<Rc<Node>, Error> {
+        let params = if self.consume_punctuator("(") {
+            let mut params = Vec::new();
+            while !self.consume_punctuator(")") {
+                params.push(self.expect_identifier()?);
+                if !self.consume_punctuator(",") {
+                    self.expect_punctuator(")")?;
+                    break;
+                }
+            }
+            params
+        } else {
+            alloc::vec![self.expect_identifier()?]
+        };
+        self.expect_punctuator("=>")?;
+        let body = if self.is_punctuator("{") {
+            self.block_statement()?
+        } else {
+            let argument = Some(self.assignment_expression()?);
+            Rc::new(Node::BlockStatement {
+                body: alloc::vec![Rc::new(Node::ReturnStatement { argument })],
+            })
+        };
+        Ok(Rc::new(Node::ArrowFunctionExpression { params, body }))
+    }
+
+    fn assignment_expression(&mut self) -> Result<Rc<Node>, Error> {
+        if self.is_arrow_function() {
+            return self.arrow_function();
+        }
+
+        let left = self.conditional_expression()?;
+        let operator = match self.peek() {
+            Some(Token::Punctuator(p))
+                if matches!(p.as_str(), "=" | "+=" | "-=" | "*=" | "/=" | "%=") =>
+            {
+                p.clone()
+            }
+            _ => return Ok(left),
+        };
+        if !matches!(
+            left.as_ref(),
+            Node::Identifier(_) | Node::MemberExpression { .. }
+        ) {
+            return Err(Error::UnexpectedInput(String::from(
+                "SyntaxError: invalid assignment target",
+            )));
+        }
+        self.pos += 1;
+        let right = self.assignment_expression()?;
+        Ok(Rc::new(Node::AssignmentExpression {
+            operator,
+            left,
+            right,
+        }))
+    }
+
+    fn conditional_expression(&mut self) -> Result<Rc<Node>, Error> {
+        let test = self.logical_expression(0)?;
+        if !self.consume_punctuator("?") {
+            return Ok(test);
+        }
+        let consequent = self.assignment_expression()?;
+        self.expect_punctuator(":")?;
+        let alternate = self.assignment_expression()?;
+        Ok(Rc::new(Node::ConditionalExpression {
+            test,
+            consequent,
+            alternate,
+        }))
+    }
+
+    // 二項演算子を優先順位の低いものから順に解析する
+    // levelはBINARY_OPERATORSの何番目の優先順位を解析しているか
+    fn logical_expression(&mut self, level: usize) -> Result<Rc<Node>, Error> {
+        let operators = match BINARY_OPERATORS.get(level) {
+            Some(operators) => operators,
+            None => return self.unary_expression(),
+        };
+
+        let mut left = self.logical_expression(level + 1)?;
+        loop {
+            let operator = match self.peek() {
+                Some(Token::Punctuator(p)) if operators.contains(&p.as_str()) => p.clone(),
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.logical_expression(level + 1)?;
+            left = Rc::new(if operator == "&&" || operator == "||" {
+                Node::LogicalExpression {
+                    operator,
+                    left,
+                    right,
+                }
+            } else {
+                Node::BinaryExpression {
+                    operator,
+                    left,
+                    right,
+                }
+            });
+        }
+    }
+
+    fn unary_expression(&mut self) -> Result<Rc<Node>, Error> {
+        let operator = match self.peek() {
+            Some(Token::Punctuator(p)) if matches!(p.as_str(), "!" | "-" | "+") => p.clone(),
+            Some(Token::Keyword(k)) if k == "typeof" => k.clone(),
+            Some(Token::Punctuator(p)) if p == "++" || p == "--" => {
+                let operator = p.clone();
+                self.pos += 1;
+                let argument = self.unary_expression()?;
+                return Ok(Rc::new(Node::UpdateExpression {
+                    operator,
+                    prefix: true,
+                    argument,
+                }));
+            }
+            _ => return self.postfix_expression(),
+        };
+        self.pos += 1;
+        let argument = self.unary_expression()?;
+        Ok(Rc::new(Node::UnaryExpression { operator, argument }))
+    }
+
+    fn postfix_expression(&mut self) -> Result<Rc<Node>, Error> {
+        let argument = self.call_expression()?;
+        match self.peek() {
+            Some(Token::Punctuator(p)) if p == "++" || p == "--" => {
+                let operator = p.clone();
+                self.pos += 1;
+                Ok(Rc::new(Node::UpdateExpression {
+                    operator,
+                    prefix: false,
+                    argument,
+                }))
+            }
+            _ => Ok(argument),
+        }
+    }
+
+    // メンバーへのアクセスと関数の呼び出し　`a.b[c](d)`
+    fn call_expression(&mut self) -> Result<Rc<Node>, Error> {
+        let mut node = self.primary_expression()?;
+        loop {
+            if self.consume_punctuator(".") {
+                let name = match self.next() {
+                    Some(Token::Identifier(name)) | Some(Token::Keyword(name)) => name,
+                    _ => {
+                        self.pos -= 1;
+                        return Err(self.unexpected("property name"));
+                    }
+                };
+                node = Rc::new(Node::MemberExpression {
+                    object: node,
+                    property: Rc::new(Node::StringLiteral(name)),
+                });
+            } else if self.consume_punctuator("[") {
+                let property = self.expression()?;
+                self.expect_punctuator("]")?;
+                node = Rc::new(Node::MemberExpression {
+                    object: node,
+                    property,
+                });
+            } else if self.consume_punctuator("(") {
+                let mut arguments = Vec::new();
+                while !self.consume_punctuator(")") {
+                    arguments.push(self.assignment_expression()?);
+                    if !self.consume_punctuator(",") {
+                        self.expect_punctuator(")")?;
+                        break;
+                    }
+                }
+                node = Rc::new(Node::CallExpression {
+                    callee: node,
+                    arguments,
+                });
+            } else {
+                return Ok(node);
+            }
+        }
+    }
+
+    fn primary_expression(&mut self) -> Result<Rc<Node>, Error> {
+        let node = match self.next() {
+            Some(Token::Number(n)) => Node::NumericLiteral(n),
+            Some(Token::StringLiteral(s)) => Node::StringLiteral(s),
+            Some(Token::Identifier(name)) => Node::Identifier(name),
+            Some(Token::Keyword(keyword)) => match keyword.as_str() {
+                "true" => Node::BooleanLiteral(true),
+                "false" => Node::BooleanLiteral(false),
+                "null" => Node::NullLiteral,
+                "undefined" => Node::UndefinedLiteral,
+                "this" => Node::ThisExpression,
+                "function" => {
+                    let id = match self.peek() {
+                        Some(Token::Identifier(_)) => Some(self.expect_identifier()?),
+                        _ => None,
+                    };
+                    let (params, body) = self.function_rest()?;
+                    Node::FunctionExpression { id, params, body }
+                }
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.unexpected("expression"));
+                }
+            },
+            Some(Token::Punctuator(p)) if p == "(" => {
+                let expression = self.expression()?;
+                self.expect_punctuator(")")?;
+                return Ok(expression);
+            }
+            Some(Token::Punctuator(p)) if p == "[" => {
+                let mut elements = Vec::new();
+                while !self.consume_punctuator("]") {
+                    elements.push(self.assignment_expression()?);
+                    if !self.consume_punctuator(",") {
+                        self.expect_punctuator("]")?;
+                        break;
+                    }
+                }
+                Node::ArrayExpression { elements }
+            }
+            Some(Token::Punctuator(p)) if p == "{" => self.object_expression()?,
+            Some(_) => {
+                self.pos -= 1;
+                return Err(self.unexpected("expression"));
+            }
+            None => return Err(self.unexpected("expression")),
+        };
+        Ok(Rc::new(node))
+    }
+
+    // オブジェクトリテラルの{の後ろから}まで
+    // プロパティの名前は識別子、文字列、数値のどれでもよく、`{ a }`は`{ a: a }`と同じ
+    fn object_expression(&mut self) -> Result<Node, Error> {
+        let mut properties = Vec::new();
+        while !self.consume_punctuator("}") {
+            let key = match self.next() {
+                Some(Token::Identifier(name)) | Some(Token::Keyword(name)) => name,
+                Some(Token::StringLiteral(s)) => s,
+                Some(Token::Number(n)) => alloc::format!("{}", n),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.unexpected("property name"));
+                }
+            };
+            let value = if self.consume_punctuator(":") {
+                self.assignment_expression()?
+            } else {
+                Rc::new(Node::Identifier(key.clone()))
+            };
+            properties.push((key, value));
+            if !self.consume_punctuator(",") {
+                self.expect_punctuator("}")?;
+                break;
+            }
+        }
+        Ok(Node::ObjectExpression { properties })
+    }
+}
+
+// 二項演算子（優先順位の低い順）
+static BINARY_OPERATORS: [&[&str]; 6] = [
+    &["||"],
+    &["&&"],
+    &["==", "!=", "===", "!=="],
+    &["<", ">", "<=", ">="],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn parse(js: &str) -> Result<Program, Error> {
+        JsParser::new(JsLexer::new(js.to_string())).parse_ast()
+    }
+
+    // 演算子の優先順位に従って木が作られるか
+    #[test]
+    fn test_binary_precedence() {
+        let program = parse("1 + 2 * 3;").expect("should parse");
+        let expected = Rc::new(Node::ExpressionStatement(Rc::new(Node::BinaryExpression {
+            operator: "+".to_string(),
+            left: Rc::new(Node::NumericLiteral(1.0)),
+            right: Rc::new(Node::BinaryExpression {
+                operator: "*".to_string(),
+                left: Rc::new(Node::NumericLiteral(2.0)),
+                right: Rc::new(Node::NumericLiteral(3.0)),
+            }),
+        })));
+        assert_eq!(&vec![expected], program.body());
+    }
+
+    // メンバーへの代入とアロー関数を含む呼び出し
+    #[test]
+    fn test_member_assignment_and_arrow() {
+        let program = parse("document.getElementById(\"t\").textContent = f(x => x + 1)")
+            .expect("should parse");
+        let statement = program.body()[0].clone();
+        let (left, right) = match statement.as_ref() {
+            Node::ExpressionStatement(expression) => match expression.as_ref() {
+                Node::AssignmentExpression { left, right, .. } => (left.clone(), right.clone()),
+                node => panic!("unexpected node {:?}", node),
+            },
+            node => panic!("unexpected node {:?}", node),
+        };
+        assert!(matches!(
+            left.as_ref(),
+            Node::MemberExpression { property, .. }
+                if property.as_ref() == &Node::StringLiteral("textContent".to_string())
+        ));
+        match right.as_ref() {
+            Node::CallExpression { arguments, .. } => assert!(matches!(
+                arguments[0].as_ref(),
+                Node::ArrowFunctionExpression { params, .. } if params == &vec!["x".to_string()]
+            )),
+            node => panic!("unexpected node {:?}", node),
+        }
+    }
+
+    // 文法の誤りはSyntaxErrorになるか
+    #[test]
+    fn test_syntax_error() {
+        assert!(matches!(
+            parse("var = 1;"),
+            Err(Error::UnexpectedInput(message)) if message.starts_with("SyntaxError")
+        ));
+        assert!(parse("if (a) { b();").is_err());
+        assert!(parse("1 = 2").is_err());
+    }
+
+    // 配列リテラルとオブジェクトリテラル（末尾のカンマと省略したプロパティの値を含む）
+    #[test]
+    fn test_array_and_object_literals() {
+        let program = parse("x = [1, 'a',]; ({ a: 1, 'b c': [], a2 })").expect("should parse");
+        let expressions: Vec<Rc<Node>> = program
+            .body()
+            .iter()
+            .map(|statement| match statement.as_ref() {
+                Node::ExpressionStatement(expression) => expression.clone(),
+                node => panic!("unexpected node {:?}", node),
+            })
+            .collect();
+        assert!(matches!(
+            expressions[0].as_ref(),
+            Node::AssignmentExpression { right, .. } if right.as_ref() == &Node::ArrayExpression {
+                elements: vec![
+                    Rc::new(Node::NumericLiteral(1.0)),
+                    Rc::new(Node::StringLiteral("a".to_string())),
+                ],
+            }
+        ));
+        assert_eq!(
+            &Node::ObjectExpression {
+                properties: vec![
+                    ("a".to_string(), Rc::new(Node::NumericLiteral(1.0))),
+                    (
+                        "b c".to_string(),
+                        Rc::new(Node::ArrayExpression { elements: vec![] })
+                    ),
+                    (
+                        "a2".to_string(),
+                        Rc::new(Node::Identifier("a2".to_string()))
+                    ),
+                ],
+            },
+            expressions[1].as_ref()
+        );
+        assert!(parse("({ a: 1 b: 2 })").is_err());
+    }
+
+    // whileとforの文（省略できる部分を省略したものを含む）
+    #[test]
+    fn test_loop_statements() {
+        let program =
+            parse("while (a) { break; } for (let i = 0; i < 3; i++) continue; for (;;) {}")
+                .expect("should parse");
+        let body = program.body();
+        assert_eq!(3, body.len());
+
+        assert_eq!(
+            &Node::WhileStatement {
+                test: Rc::new(Node::Identifier("a".to_string())),
+                body: Rc::new(Node::BlockStatement {
+                    body: vec![Rc::new(Node::BreakStatement)],
+                }),
+            },
+            body[0].as_ref()
+        );
+        match body[1].as_ref() {
+            Node::ForStatement {
+                init: Some(init),
+                test: Some(_),
+                update: Some(_),
+                body,
+            } => {
+                assert!(matches!(
+                    init.as_ref(),
+                    Node::VariableDeclaration { kind: VariableKind::Let, declarations }
+                        if declarations.len() == 1 && declarations[0].0 == "i"
+                ));
+                assert_eq!(&Node::ContinueStatement, body.as_ref());
+            }
+            node => panic!("unexpected node {:?}", node),
+        }
+        assert!(matches!(
+            body[2].as_ref(),
+            Node::ForStatement {
+                init: None,
+                test: None,
+                update: None,
+                ..
+            }
+        ));
+        assert!(parse("while (a {}").is_err());
+    }
+}
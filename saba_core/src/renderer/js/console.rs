@@ -0,0 +1,48 @@
+use crate::error::Error;
+use alloc::string::String;
+
+// https://console.spec.whatwg.org/
+
+// コンソールに出力されたメッセージの重要度
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConsoleLevel {
+    // console.log、console.info、console.debug
+    Log,
+    // console.warn
+    Warn,
+    // console.errorと、スクリプトの実行時のエラー
+    Error,
+}
+
+// コンソールに出力された1つのメッセージ
+// UIは開発者ツールのパネルに、出力された順に表示する
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleMessage {
+    level: ConsoleLevel,
+    text: String,
+}
+
+impl ConsoleMessage {
+    pub fn new(level: ConsoleLevel, text: String) -> Self {
+        Self { level, text }
+    }
+
+    // スクリプトのエラーを、エラーのメッセージとして出力する
+    pub fn from_error(error: &Error) -> Self {
+        let text = match error {
+            Error::Network(detail) => alloc::format!("NetworkError: {}", detail),
+            Error::UnexpectedInput(detail) | Error::InvalidUI(detail) | Error::Other(detail) => {
+                detail.clone()
+            }
+        };
+        Self::new(ConsoleLevel::Error, alloc::format!("Uncaught {}", text))
+    }
+
+    pub fn level(&self) -> ConsoleLevel {
+        self.level
+    }
+
+    pub fn text(&self) -> String {
+        self.text.clone()
+    }
+}
@@ -0,0 +1,5 @@
+pub mod ast;
+pub mod console;
+pub mod runtime;
+pub mod timer;
+pub mod token;
@@ -0,0 +1,1584 @@
+use crate::error::Error;
+use crate::renderer::dom::api::get_target_element_node;
+use crate::renderer::dom::api::get_text_content;
+use crate::renderer::dom::api::set_text_content;
+use crate::renderer::dom::event::Event;
+use crate::renderer::dom::event::EventCallback;
+use crate::renderer::dom::node::ElementKind;
+use crate::renderer::dom::node::Node as DomNode;
+use crate::renderer::dom::node::NodeKind;
+use crate::renderer::dom::node::Window;
+use crate::renderer::js::ast::JsParser;
+use crate::renderer::js::ast::Node;
+use crate::renderer::js::ast::Program;
+use crate::renderer::js::ast::VariableKind;
+use crate::renderer::js::console::ConsoleLevel;
+use crate::renderer::js::console::ConsoleMessage;
+use crate::renderer::js::timer::TimerQueue;
+use crate::renderer::js::token::JsLexer;
+use crate::renderer::page::resolve_url;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
+
+// 関数の呼び出しを入れ子にできる深さの上限
+// 終わらない再帰呼び出しでブラウザのスタックが溢れないようにする
+const MAX_CALL_DEPTH: usize = 100;
+
+// 1回の実行（スクリプト・イベントリスナー・タイマー）で繰り返し処理を回せる回数の上限
+// 終わらないループでブラウザ全体が止まらないようにする
+const MAX_LOOP_ITERATIONS: usize = 100_000;
+
+// スクリプトが扱う値
+#[derive(Debug, Clone)]
+pub enum RuntimeValue {
+    Undefined,
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    // スクリプトで定義した関数
+    Function(Rc<Function>),
+    // ブラウザが用意するオブジェクトのメソッド
+    // 呼び出す時に使うthisと、メソッドの名前を持つ
+    HostFunction {
+        this: Box<RuntimeValue>,
+        name: String,
+    },
+    // window
+    // グローバルの変数をプロパティとして持つ
+    Window,
+    // window.location
+    Location,
+    // document
+    Document,
+    // console
+    Console,
+    // DOMのノード（要素とテキスト）
+    HtmlElement(Rc<RefCell<DomNode>>),
+    // イベントリスナーに渡すイベント
+    Event(Rc<RefCell<Event>>),
+    // オブジェクトと配列
+    // 代入しても複製せず、同じ中身を参照する
+    Object(Rc<RefCell<BTreeMap<String, RuntimeValue>>>),
+    Array(Rc<RefCell<Vec<RuntimeValue>>>),
+}
+
+impl RuntimeValue {
+    // 条件式で真として扱われるかどうか
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            RuntimeValue::Undefined | RuntimeValue::Null => false,
+            RuntimeValue::Boolean(b) => *b,
+            RuntimeValue::Number(n) => *n != 0.0 && !n.is_nan(),
+            RuntimeValue::String(s) => !s.is_empty(),
+            _ => true,
+        }
+    }
+
+    // 数値に変換する
+    // 数値として解釈できない文字列はNaNになる
+    pub fn to_number(&self) -> f64 {
+        match self {
+            RuntimeValue::Null => 0.0,
+            RuntimeValue::Boolean(b) => f64::from(u8::from(*b)),
+            RuntimeValue::Number(n) => *n,
+            RuntimeValue::String(s) => match s.trim() {
+                "" => 0.0,
+                s => s.parse::<f64>().unwrap_or(f64::NAN),
+            },
+            // 配列は文字列に変換してから数値にする　[]は0、[5]は5
+            RuntimeValue::Array(_) => RuntimeValue::String(self.to_string()).to_number(),
+            _ => f64::NAN,
+        }
+    }
+
+    // typeof演算子の結果
+    pub fn type_of(&self) -> &'static str {
+        match self {
+            RuntimeValue::Undefined => "undefined",
+            RuntimeValue::Boolean(_) => "boolean",
+            RuntimeValue::Number(_) => "number",
+            RuntimeValue::String(_) => "string",
+            RuntimeValue::Function(_) | RuntimeValue::HostFunction { .. } => "function",
+            RuntimeValue::Null
+            | RuntimeValue::Window
+            | RuntimeValue::Location
+            | RuntimeValue::Document
+            | RuntimeValue::Console
+            | RuntimeValue::HtmlElement(_)
+            | RuntimeValue::Event(_)
+            | RuntimeValue::Object(_)
+            | RuntimeValue::Array(_) => "object",
+        }
+    }
+
+    fn is_primitive(&self) -> bool {
+        matches!(
+            self,
+            RuntimeValue::Undefined
+                | RuntimeValue::Null
+                | RuntimeValue::Boolean(_)
+                | RuntimeValue::Number(_)
+                | RuntimeValue::String(_)
+        )
+    }
+}
+
+// ===と同じ比較
+// 関数やDOMのノードは同じものかどうかで比較する
+impl PartialEq for RuntimeValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (RuntimeValue::Undefined, RuntimeValue::Undefined)
+            | (RuntimeValue::Null, RuntimeValue::Null)
+            | (RuntimeValue::Window, RuntimeValue::Window)
+            | (RuntimeValue::Location, RuntimeValue::Location)
+            | (RuntimeValue::Document, RuntimeValue::Document)
+            | (RuntimeValue::Console, RuntimeValue::Console) => true,
+            (RuntimeValue::Boolean(a), RuntimeValue::Boolean(b)) => a == b,
+            (RuntimeValue::Number(a), RuntimeValue::Number(b)) => a == b,
+            (RuntimeValue::String(a), RuntimeValue::String(b)) => a == b,
+            (RuntimeValue::Function(a), RuntimeValue::Function(b)) => Rc::ptr_eq(a, b),
+            (
+                RuntimeValue::HostFunction { this: a, name: m },
+                RuntimeValue::HostFunction { this: b, name: n },
+            ) => a == b && m == n,
+            (RuntimeValue::HtmlElement(a), RuntimeValue::HtmlElement(b)) => Rc::ptr_eq(a, b),
+            (RuntimeValue::Event(a), RuntimeValue::Event(b)) => Rc::ptr_eq(a, b),
+            (RuntimeValue::Object(a), RuntimeValue::Object(b)) => Rc::ptr_eq(a, b),
+            (RuntimeValue::Array(a), RuntimeValue::Array(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+// 文字列への変換（String(value)と同じ）
+impl Display for RuntimeValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RuntimeValue::Undefined => write!(f, "undefined"),
+            RuntimeValue::Null => write!(f, "null"),
+            RuntimeValue::Boolean(b) => write!(f, "{}", b),
+            RuntimeValue::Number(n) => write!(f, "{}", format_number(*n)),
+            RuntimeValue::String(s) => write!(f, "{}", s),
+            RuntimeValue::Function(function) => match &function.name {
+                Some(name) => write!(f, "function {}() {{ [code] }}", name),
+                None => write!(f, "function () {{ [code] }}"),
+            },
+            RuntimeValue::HostFunction { name, .. } => {
+                write!(f, "function {}() {{ [native code] }}", name)
+            }
+            RuntimeValue::Window => write!(f, "[object Window]"),
+            RuntimeValue::Location => write!(f, "[object Location]"),
+            RuntimeValue::Document => write!(f, "[object HTMLDocument]"),
+            RuntimeValue::Console => write!(f, "[object console]"),
+            RuntimeValue::HtmlElement(node) => match node.borrow().kind() {
+                NodeKind::Text(_) => write!(f, "[object Text]"),
+                _ => write!(f, "[object HTMLElement]"),
+            },
+            RuntimeValue::Event(_) => write!(f, "[object Event]"),
+            RuntimeValue::Object(_) => write!(f, "[object Object]"),
+            // 要素をカンマで区切る　nullとundefinedは空文字列にする
+            RuntimeValue::Array(elements) => {
+                for (i, element) in elements.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    if !matches!(element, RuntimeValue::Undefined | RuntimeValue::Null) {
+                        write!(f, "{}", element)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// 数値をJavaScriptと同じ形の文字列にする　1.0は"1"、0.5は"0.5"
+fn format_number(n: f64) -> String {
+    if n.is_nan() {
+        return String::from("NaN");
+    }
+    if n.is_infinite() {
+        return String::from(if n > 0.0 { "Infinity" } else { "-Infinity" });
+    }
+    if n == 0.0 {
+        // -0も"0"にする
+        return String::from("0");
+    }
+    alloc::format!("{}", n)
+}
+
+// スクリプトで定義した関数
+pub struct Function {
+    name: Option<String>,
+    params: Vec<String>,
+    body: Rc<Node>,
+    // アロー関数の場合はtrue
+    // アロー関数は呼び出し方によってthisが変わらない
+    arrow: bool,
+    // 関数を作った時の環境
+    // 関数の中からは、作った場所の変数を参照できる（クロージャ）
+    scope: Rc<RefCell<Environment>>,
+}
+
+// 環境は関数自身を含むことがあるので、環境を除いて表示する
+impl Debug for Function {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Function")
+            .field("name", &self.name)
+            .field("params", &self.params)
+            .field("arrow", &self.arrow)
+            .finish()
+    }
+}
+
+// 変数の名前と値の対応
+// ブロックや関数の呼び出しごとに作り、外側の環境をたどって変数を探す
+#[derive(Debug, Clone)]
+pub struct Environment {
+    variables: BTreeMap<String, RuntimeValue>,
+    outer: Option<Rc<RefCell<Environment>>>,
+    // 関数の本体かグローバルの環境かどうか（varで宣言した変数を置く場所）
+    function_scope: bool,
+    // 関数の中のthis
+    // アロー関数やブロックの環境では、外側の環境のthisを使う
+    this: Option<RuntimeValue>,
+}
+
+impl Environment {
+    fn new(outer: Option<Rc<RefCell<Environment>>>, function_scope: bool) -> Self {
+        Self {
+            variables: BTreeMap::new(),
+            outer,
+            function_scope,
+            this: None,
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<RuntimeValue> {
+        match self.variables.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.outer.as_ref()?.borrow().get(name),
+        }
+    }
+
+    fn define(&mut self, name: String, value: RuntimeValue) {
+        self.variables.insert(name, value);
+    }
+
+    // 宣言されている変数に代入する
+    // どこにも宣言されていない場合はfalseを返す
+    fn assign(&mut self, name: &str, value: RuntimeValue) -> bool {
+        if let Some(variable) = self.variables.get_mut(name) {
+            *variable = value;
+            return true;
+        }
+        match &self.outer {
+            Some(outer) => outer.borrow_mut().assign(name, value),
+            None => false,
+        }
+    }
+
+    fn this(&self) -> RuntimeValue {
+        match (&self.this, &self.outer) {
+            (Some(this), _) => this.clone(),
+            (None, Some(outer)) => outer.borrow().this(),
+            (None, None) => RuntimeValue::Undefined,
+        }
+    }
+}
+
+// varで宣言した変数を置く、一番近い関数の本体かグローバルの環境
+fn function_scope(env: &Rc<RefCell<Environment>>) -> Rc<RefCell<Environment>> {
+    let mut current = env.clone();
+    loop {
+        let outer = match (current.borrow().function_scope, &current.borrow().outer) {
+            (false, Some(outer)) => outer.clone(),
+            _ => return current.clone(),
+        };
+        current = outer;
+    }
+}
+
+// 文を実行した結果
+// returnやbreakで、残りの文を実行せずに抜ける
+enum Completion {
+    Normal(RuntimeValue),
+    Return(RuntimeValue),
+    Break,
+    Continue,
+}
+
+// 文書ごとのJavaScriptの実行環境
+// グローバルの変数は、同じ文書の全てのスクリプトで共有する
+#[derive(Debug, Clone)]
+pub struct JsRuntime {
+    window: Rc<RefCell<Window>>,
+    global: Rc<RefCell<Environment>>,
+    call_depth: usize,
+    // 今の実行で繰り返し処理を回した回数
+    loop_iterations: usize,
+    // consoleへの出力と実行時のエラー（古い順）
+    // イベントリスナーの中の実行環境とも共有する
+    console: Rc<RefCell<Vec<ConsoleMessage>>>,
+    // setTimeoutとsetIntervalで予約されたタイマー
+    timers: Rc<RefCell<TimerQueue>>,
+    // location.hrefへの代入などで、スクリプトが移動を求めた先の絶対URL
+    navigation: Rc<RefCell<Option<String>>>,
+}
+
+impl JsRuntime {
+    pub fn new(window: Rc<RefCell<Window>>) -> Self {
+        let mut global = Environment::new(None, true);
+        global.define(String::from("window"), RuntimeValue::Window);
+        global.define(String::from("location"), RuntimeValue::Location);
+        global.define(String::from("document"), RuntimeValue::Document);
+        global.define(String::from("console"), RuntimeValue::Console);
+        for name in ["setTimeout", "setInterval", "clearTimeout", "clearInterval"] {
+            global.define(
+                String::from(name),
+                RuntimeValue::HostFunction {
+                    this: Box::new(RuntimeValue::Undefined),
+                    name: String::from(name),
+                },
+            );
+        }
+        Self {
+            window,
+            global: Rc::new(RefCell::new(global)),
+            call_depth: 0,
+            loop_iterations: 0,
+            console: Rc::new(RefCell::new(Vec::new())),
+            timers: Rc::new(RefCell::new(TimerQueue::new())),
+            navigation: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    pub fn window(&self) -> Rc<RefCell<Window>> {
+        self.window.clone()
+    }
+
+    // consoleに出力されたメッセージを古い順に返す
+    pub fn console_messages(&self) -> Vec<ConsoleMessage> {
+        self.console.borrow().clone()
+    }
+
+    pub fn clear_console(&mut self) {
+        self.console.borrow_mut().clear();
+    }
+
+    // コンソールにメッセージを出力する
+    pub fn print(&self, message: ConsoleMessage) {
+        self.console.borrow_mut().push(message);
+    }
+
+    // スクリプトが移動を求めたURLを取り出す
+    // 移動はブラウザが行うので、スクリプトの実行が終わった後に呼び出す
+    // 何度も移動を求めた場合は最後のURLを返す
+    pub fn take_navigation(&mut self) -> Option<String> {
+        self.navigation.borrow_mut().take()
+    }
+
+    // 文書のURLを基準にhrefを解決し、移動先として記録する
+    fn navigate(&self, href: &RuntimeValue) {
+        let url = resolve_url(self.window.borrow().location().as_ref(), &href.to_string());
+        *self.navigation.borrow_mut() = Some(url);
+    }
+
+    // location.hrefの値
+    // URLのない文書ではabout:blankになる
+    fn location_href(&self) -> String {
+        match self.window.borrow().location() {
+            Some(url) => url.url(),
+            None => String::from("about:blank"),
+        }
+    }
+
+    // 現在の時刻（ミリ秒）を設定し、実行する時刻になったタイマーを順に実行する
+    // タイマーの中で新しく予約されたタイマーは、次に呼び出された時に実行する
+    // 実行時のエラーはコンソールに出力し、残りのタイマーの実行は続ける
+    // タイマーを1つでも実行した場合はtrueを返す
+    pub fn run_timers(&mut self, now: u64) -> bool {
+        self.timers.borrow_mut().set_now(now);
+        let due = self.timers.borrow().due_timers();
+        let mut ran = false;
+        for id in due {
+            let timer = match self.timers.borrow_mut().take(id) {
+                Some(timer) => timer,
+                None => continue,
+            };
+            ran = true;
+            let result = match timer.callback() {
+                RuntimeValue::String(source) => self.execute_script(&source),
+                callback => {
+                    self.call_function(&callback, RuntimeValue::Undefined, timer.arguments())
+                }
+            };
+            if let Err(error) = result {
+                self.print(ConsoleMessage::from_error(&error));
+            }
+        }
+        ran
+    }
+
+    // まだ実行されていないタイマーがあるかどうか
+    pub fn has_timers(&self) -> bool {
+        !self.timers.borrow().is_empty()
+    }
+
+    // スクリプトのソースをパースして実行する
+    pub fn execute_script(&mut self, source: &str) -> Result<RuntimeValue, Error> {
+        let program = JsParser::new(JsLexer::new(String::from(source))).parse_ast()?;
+        self.execute(&program)
+    }
+
+    // プログラムを実行し、最後に評価した式の値を返す
+    // 実行時のエラーはError::Otherで"TypeError: "などから始まるメッセージを返す
+    pub fn execute(&mut self, program: &Program) -> Result<RuntimeValue, Error> {
+        self.call_depth = 0;
+        self.loop_iterations = 0;
+        let global = self.global.clone();
+        match self.execute_statements(program.body(), &global)? {
+            Completion::Normal(value) | Completion::Return(value) => Ok(value),
+            Completion::Break | Completion::Continue => Ok(RuntimeValue::Undefined),
+        }
+    }
+
+    // 関数をthisと引数で呼び出す
+    pub fn call_function(
+        &mut self,
+        function: &RuntimeValue,
+        this: RuntimeValue,
+        arguments: Vec<RuntimeValue>,
+    ) -> Result<RuntimeValue, Error> {
+        match function {
+            RuntimeValue::Function(function) => {
+                if self.call_depth >= MAX_CALL_DEPTH {
+                    return Err(Error::Other(String::from(
+                        "RangeError: Maximum call stack size exceeded",
+                    )));
+                }
+                // イベントリスナーやタイマーから呼び出された場合は、新しい実行として数え直す
+                if self.call_depth == 0 {
+                    self.loop_iterations = 0;
+                }
+                let mut env = Environment::new(Some(function.scope.clone()), true);
+                if !function.arrow {
+                    env.this = Some(this);
+                }
+                for (i, param) in function.params.iter().enumerate() {
+                    let value = arguments.get(i).cloned().unwrap_or(RuntimeValue::Undefined);
+                    env.define(param.clone(), value);
+                }
+                let env = Rc::new(RefCell::new(env));
+                let body = match function.body.as_ref() {
+                    Node::BlockStatement { body } => body,
+                    _ => return Ok(RuntimeValue::Undefined),
+                };
+
+                self.call_depth += 1;
+                let result = self.execute_statements(body, &env);
+                self.call_depth -= 1;
+                match result? {
+                    Completion::Return(value) => Ok(value),
+                    _ => Ok(RuntimeValue::Undefined),
+                }
+            }
+            RuntimeValue::HostFunction { this, name } => {
+                self.call_host_function(this, name, arguments)
+            }
+            value => Err(Error::Other(alloc::format!(
+                "TypeError: {} is not a function",
+                value
+            ))),
+        }
+    }
+
+    // onclickなどのon〜属性を持つ全ての要素に、属性の値を実行するイベントリスナーを登録する
+    // 属性の値はeventを引数、要素をthisとする関数の本体として実行する
+    // パースできない属性は、エラーをコンソールに出力して無視する
+    pub fn register_event_handlers(&mut self) {
+        let document = self.window.borrow().document();
+        let mut elements = Vec::new();
+        collect_elements(&document, &mut elements);
+        for node in elements {
+            let attributes = match node.borrow().get_element() {
+                Some(element) => element.attributes(),
+                None => continue,
+            };
+            for attribute in attributes {
+                let event_type = match attribute.name().strip_prefix("on") {
+                    Some(event_type) if !event_type.is_empty() => String::from(event_type),
+                    _ => continue,
+                };
+                let program = match JsParser::new(JsLexer::new(attribute.value())).parse_ast() {
+                    Ok(program) => program,
+                    Err(error) => {
+                        self.print(ConsoleMessage::from_error(&error));
+                        continue;
+                    }
+                };
+                let body = Rc::new(Node::BlockStatement {
+                    body: program.body().clone(),
+                });
+                let handler = self.create_function(
+                    None,
+                    &[String::from("event")],
+                    &body,
+                    false,
+                    &self.global.clone(),
+                );
+                let callback = self.event_callback(handler, true);
+                node.borrow_mut()
+                    .add_event_listener(&event_type, callback, false);
+            }
+        }
+    }
+
+    // スクリプトの関数を呼び出すイベントリスナーを作る
+    // 関数は、リスナーを登録した要素をthis、イベントを引数として呼び出す
+    // on〜属性のハンドラ(handler)がfalseを返した場合は、デフォルトの動作をキャンセルする
+    // 実行時のエラーはコンソールに出力してそのリスナーだけを止め、イベントの伝搬は続ける
+    fn event_callback(&self, function: RuntimeValue, handler: bool) -> EventCallback {
+        let runtime = self.clone();
+        Rc::new(move |event: &mut Event| {
+            let mut runtime = runtime.clone();
+            let this = match event.current_target() {
+                Some(node) => RuntimeValue::HtmlElement(node),
+                None => RuntimeValue::Undefined,
+            };
+            let shared = Rc::new(RefCell::new(event.clone()));
+            let result = runtime.call_function(
+                &function,
+                this,
+                alloc::vec![RuntimeValue::Event(shared.clone())],
+            );
+            if let Err(error) = &result {
+                runtime.print(ConsoleMessage::from_error(error));
+            }
+
+            let shared = shared.borrow();
+            if shared.default_prevented() || (handler && result == Ok(RuntimeValue::Boolean(false)))
+            {
+                event.prevent_default();
+            }
+            if shared.propagation_stopped() {
+                event.stop_propagation();
+            }
+        })
+    }
+
+    // 文の並びを実行する
+    // 関数宣言は、宣言より前の文からも呼び出せるように先に定義する
+    fn execute_statements(
+        &mut self,
+        body: &[Rc<Node>],
+        env: &Rc<RefCell<Environment>>,
+    ) -> Result<Completion, Error> {
+        for node in body {
+            if let Node::FunctionDeclaration { id, params, body } = node.as_ref() {
+                let function = self.create_function(Some(id.clone()), params, body, false, env);
+                env.borrow_mut().define(id.clone(), function);
+            }
+        }
+
+        let mut last = RuntimeValue::Undefined;
+        for node in body {
+            match self.execute_statement(node, env)? {
+                Completion::Normal(value) => last = value,
+                completion => return Ok(completion),
+            }
+        }
+        Ok(Completion::Normal(last))
+    }
+
+    fn execute_statement(
+        &mut self,
+        node: &Rc<Node>,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Result<Completion, Error> {
+        match node.as_ref() {
+            Node::ExpressionStatement(expression) => {
+                Ok(Completion::Normal(self.evaluate(expression, env)?))
+            }
+            Node::BlockStatement { body } => {
+                let block_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()), false)));
+                self.execute_statements(body, &block_env)
+            }
+            Node::ReturnStatement { argument } => {
+                let value = match argument {
+                    Some(argument) => self.evaluate(argument, env)?,
+                    None => RuntimeValue::Undefined,
+                };
+                Ok(Completion::Return(value))
+            }
+            Node::IfStatement {
+                test,
+                consequent,
+                alternate,
+            } => {
+                if self.evaluate(test, env)?.is_truthy() {
+                    return self.execute_statement(consequent, env);
+                }
+                match alternate {
+                    Some(alternate) => self.execute_statement(alternate, env),
+                    None => Ok(Completion::Normal(RuntimeValue::Undefined)),
+                }
+            }
+            Node::WhileStatement { test, body } => {
+                while self.evaluate(test, env)?.is_truthy() {
+                    self.count_loop_iteration()?;
+                    match self.execute_statement(body, env)? {
+                        Completion::Break => break,
+                        Completion::Return(value) => return Ok(Completion::Return(value)),
+                        Completion::Normal(_) | Completion::Continue => {}
+                    }
+                }
+                Ok(Completion::Normal(RuntimeValue::Undefined))
+            }
+            Node::ForStatement {
+                init,
+                test,
+                update,
+                body,
+            } => {
+                let loop_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()), false)));
+                if let Some(init) = init {
+                    self.execute_statement(init, &loop_env)?;
+                }
+                loop {
+                    if let Some(test) = test {
+                        if !self.evaluate(test, &loop_env)?.is_truthy() {
+                            break;
+                        }
+                    }
+                    self.count_loop_iteration()?;
+                    match self.execute_statement(body, &loop_env)? {
+                        Completion::Break => break,
+                        Completion::Return(value) => return Ok(Completion::Return(value)),
+                        Completion::Normal(_) | Completion::Continue => {}
+                    }
+                    if let Some(update) = update {
+                        self.evaluate(update, &loop_env)?;
+                    }
+                }
+                Ok(Completion::Normal(RuntimeValue::Undefined))
+            }
+            Node::BreakStatement => Ok(Completion::Break),
+            Node::ContinueStatement => Ok(Completion::Continue),
+            Node::VariableDeclaration { kind, declarations } => {
+                for (name, init) in declarations {
+                    let value = match init {
+                        Some(init) => self.evaluate(init, env)?,
+                        None => RuntimeValue::Undefined,
+                    };
+                    let scope = match kind {
+                        VariableKind::Var => function_scope(env),
+                        VariableKind::Let | VariableKind::Const => env.clone(),
+                    };
+                    scope.borrow_mut().define(name.clone(), value);
+                }
+                Ok(Completion::Normal(RuntimeValue::Undefined))
+            }
+            // 関数宣言はexecute_statementsで定義済み
+            Node::FunctionDeclaration { .. } => Ok(Completion::Normal(RuntimeValue::Undefined)),
+            _ => Ok(Completion::Normal(self.evaluate(node, env)?)),
+        }
+    }
+
+    // 繰り返し処理を1回回したことを数え、上限を超えた場合は実行を止めるエラーを返す
+    fn count_loop_iteration(&mut self) -> Result<(), Error> {
+        self.loop_iterations += 1;
+        if self.loop_iterations > MAX_LOOP_ITERATIONS {
+            return Err(Error::Other(String::from(
+                "InternalError: too many loop iterations",
+            )));
+        }
+        Ok(())
+    }
+
+    fn create_function(
+        &self,
+        name: Option<String>,
+        params: &[String],
+        body: &Rc<Node>,
+        arrow: bool,
+        env: &Rc<RefCell<Environment>>,
+    ) -> RuntimeValue {
+        RuntimeValue::Function(Rc::new(Function {
+            name,
+            params: params.to_vec(),
+            body: body.clone(),
+            arrow,
+            scope: env.clone(),
+        }))
+    }
+
+    fn evaluate(
+        &mut self,
+        node: &Rc<Node>,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Result<RuntimeValue, Error> {
+        match node.as_ref() {
+            Node::NumericLiteral(n) => Ok(RuntimeValue::Number(*n)),
+            Node::StringLiteral(s) => Ok(RuntimeValue::String(s.clone())),
+            Node::BooleanLiteral(b) => Ok(RuntimeValue::Boolean(*b)),
+            Node::NullLiteral => Ok(RuntimeValue::Null),
+            Node::UndefinedLiteral => Ok(RuntimeValue::Undefined),
+            Node::ThisExpression => Ok(env.borrow().this()),
+            Node::Identifier(name) => env.borrow().get(name).ok_or_else(|| {
+                Error::Other(alloc::format!("ReferenceError: {} is not defined", name))
+            }),
+            Node::FunctionExpression { id, params, body } => {
+                Ok(self.create_function(id.clone(), params, body, false, env))
+            }
+            Node::ArrowFunctionExpression { params, body } => {
+                Ok(self.create_function(None, params, body, true, env))
+            }
+            Node::ArrayExpression { elements } => {
+                let mut values = Vec::new();
+                for element in elements {
+                    values.push(self.evaluate(element, env)?);
+                }
+                Ok(RuntimeValue::Array(Rc::new(RefCell::new(values))))
+            }
+            Node::ObjectExpression { properties } => {
+                let mut object = BTreeMap::new();
+                for (key, value) in properties {
+                    let value = self.evaluate(value, env)?;
+                    object.insert(key.clone(), value);
+                }
+                Ok(RuntimeValue::Object(Rc::new(RefCell::new(object))))
+            }
+            Node::AssignmentExpression {
+                operator,
+                left,
+                right,
+            } => {
+                let value = match operator.trim_end_matches('=') {
+                    "" => self.evaluate(right, env)?,
+                    operator => {
+                        let left_value = self.evaluate(left, env)?;
+                        let right_value = self.evaluate(right, env)?;
+                        binary_operation(operator, &left_value, &right_value)
+                    }
+                };
+                self.assign(left, value.clone(), env)?;
+                Ok(value)
+            }
+            Node::LogicalExpression {
+                operator,
+                left,
+                right,
+            } => {
+                let left = self.evaluate(left, env)?;
+                let short_circuit = match operator.as_str() {
+                    "&&" => !left.is_truthy(),
+                    _ => left.is_truthy(),
+                };
+                if short_circuit {
+                    return Ok(left);
+                }
+                self.evaluate(right, env)
+            }
+            Node::BinaryExpression {
+                operator,
+                left,
+                right,
+            } => {
+                let left = self.evaluate(left, env)?;
+                let right = self.evaluate(right, env)?;
+                Ok(binary_operation(operator, &left, &right))
+            }
+            Node::UnaryExpression { operator, argument } => {
+                // 宣言されていない変数のtypeofはエラーにしない
+                if let (true, Node::Identifier(name)) = (operator == "typeof", argument.as_ref()) {
+                    let value = env.borrow().get(name).unwrap_or(RuntimeValue::Undefined);
+                    return Ok(RuntimeValue::String(String::from(value.type_of())));
+                }
+                let value = self.evaluate(argument, env)?;
+                Ok(match operator.as_str() {
+                    "!" => RuntimeValue::Boolean(!value.is_truthy()),
+                    "-" => RuntimeValue::Number(-value.to_number()),
+                    "+" => RuntimeValue::Number(value.to_number()),
+                    _ => RuntimeValue::String(String::from(value.type_of())),
+                })
+            }
+            Node::UpdateExpression {
+                operator,
+                prefix,
+                argument,
+            } => {
+                let old = self.evaluate(argument, env)?.to_number();
+                let new = if operator == "++" {
+                    old + 1.0
+                } else {
+                    old - 1.0
+                };
+                self.assign(argument, RuntimeValue::Number(new), env)?;
+                Ok(RuntimeValue::Number(if *prefix { new } else { old }))
+            }
+            Node::ConditionalExpression {
+                test,
+                consequent,
+                alternate,
+            } => {
+                if self.evaluate(test, env)?.is_truthy() {
+                    self.evaluate(consequent, env)
+                } else {
+                    self.evaluate(alternate, env)
+                }
+            }
+            Node::CallExpression { callee, arguments } => {
+                // メソッドの呼び出しでは、メンバーを持つオブジェクトがthisになる
+                let (function, this) = match callee.as_ref() {
+                    Node::MemberExpression { object, property } => {
+                        let object = self.evaluate(object, env)?;
+                        let key = self.evaluate(property, env)?.to_string();
+                        (self.get_property(&object, &key)?, object)
+                    }
+                    _ => (self.evaluate(callee, env)?, RuntimeValue::Undefined),
+                };
+                let mut values = Vec::new();
+                for argument in arguments {
+                    values.push(self.evaluate(argument, env)?);
+                }
+                if matches!(
+                    function,
+                    RuntimeValue::Function(_) | RuntimeValue::HostFunction { .. }
+                ) {
+                    return self.call_function(&function, this, values);
+                }
+                Err(Error::Other(alloc::format!(
+                    "TypeError: {} is not a function",
+                    describe(callee)
+                )))
+            }
+            Node::MemberExpression { object, property } => {
+                let object = self.evaluate(object, env)?;
+                let key = self.evaluate(property, env)?.to_string();
+                self.get_property(&object, &key)
+            }
+            _ => Err(Error::Other(String::from(
+                "SyntaxError: unexpected statement",
+            ))),
+        }
+    }
+
+    // 変数かオブジェクトのプロパティに値を代入する
+    // 宣言されていない変数への代入は、グローバルの変数を作る
+    fn assign(
+        &mut self,
+        target: &Rc<Node>,
+        value: RuntimeValue,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Result<(), Error> {
+        match target.as_ref() {
+            Node::Identifier(name) => {
+                if !env.borrow_mut().assign(name, value.clone()) {
+                    self.global.borrow_mut().define(name.clone(), value);
+                }
+                Ok(())
+            }
+            Node::MemberExpression { object, property } => {
+                let object = self.evaluate(object, env)?;
+                let key = self.evaluate(property, env)?.to_string();
+                self.set_property(&object, &key, value)
+            }
+            _ => Err(Error::Other(String::from(
+                "SyntaxError: invalid assignment target",
+            ))),
+        }
+    }
+
+    fn get_property(&mut self, object: &RuntimeValue, key: &str) -> Result<RuntimeValue, Error> {
+        let method = |name: &str| RuntimeValue::HostFunction {
+            this: Box::new(object.clone()),
+            name: String::from(name),
+        };
+        let value = match object {
+            RuntimeValue::Undefined | RuntimeValue::Null => {
+                return Err(Error::Other(alloc::format!(
+                    "TypeError: Cannot read properties of {} (reading '{}')",
+                    object,
+                    key
+                )))
+            }
+            RuntimeValue::String(s) if key == "length" => {
+                RuntimeValue::Number(s.chars().count() as f64)
+            }
+            RuntimeValue::Document => match key {
+                "getElementById" => method(key),
+                "documentElement" | "body" => {
+                    let kind = if key == "body" {
+                        ElementKind::Body
+                    } else {
+                        ElementKind::Html
+                    };
+                    let document = self.window.borrow().document();
+                    match get_target_element_node(Some(document), kind) {
+                        Some(node) => RuntimeValue::HtmlElement(node),
+                        None => RuntimeValue::Null,
+                    }
+                }
+                _ => RuntimeValue::Undefined,
+            },
+            RuntimeValue::Window => match key {
+                "window" => RuntimeValue::Window,
+                _ => self
+                    .global
+                    .borrow()
+                    .get(key)
+                    .unwrap_or(RuntimeValue::Undefined),
+            },
+            RuntimeValue::Location => match key {
+                "href" => RuntimeValue::String(self.location_href()),
+                "assign" | "toString" => method(key),
+                _ => RuntimeValue::Undefined,
+            },
+            RuntimeValue::Object(object) => object
+                .borrow()
+                .get(key)
+                .cloned()
+                .unwrap_or(RuntimeValue::Undefined),
+            RuntimeValue::Array(elements) => match key {
+                "length" => RuntimeValue::Number(elements.borrow().len() as f64),
+                "push" => method(key),
+                _ => match array_index(key) {
+                    Some(i) => elements
+                        .borrow()
+                        .get(i)
+                        .cloned()
+                        .unwrap_or(RuntimeValue::Undefined),
+                    None => RuntimeValue::Undefined,
+                },
+            },
+            RuntimeValue::Console => match key {
+                "log" | "info" | "debug" | "warn" | "error" => method(key),
+                _ => RuntimeValue::Undefined,
+            },
+            RuntimeValue::HtmlElement(node) => match key {
+                "textContent" | "innerText" => RuntimeValue::String(get_text_content(node)),
+                "id" => RuntimeValue::String(
+                    node.borrow()
+                        .get_element()
+                        .and_then(|element| element.id())
+                        .unwrap_or_default(),
+                ),
+                "tagName" => match node.borrow().get_element() {
+                    Some(element) => {
+                        RuntimeValue::String(element.kind().to_string().to_uppercase())
+                    }
+                    None => RuntimeValue::Undefined,
+                },
+                "parentNode" => match node.borrow().parent().upgrade() {
+                    Some(parent) if parent.borrow().kind() == NodeKind::Document => {
+                        RuntimeValue::Document
+                    }
+                    Some(parent) => RuntimeValue::HtmlElement(parent),
+                    None => RuntimeValue::Null,
+                },
+                "getAttribute" | "addEventListener" => method(key),
+                _ => RuntimeValue::Undefined,
+            },
+            RuntimeValue::Event(event) => match key {
+                "type" => RuntimeValue::String(event.borrow().event_type()),
+                "target" | "currentTarget" => {
+                    let node = if key == "target" {
+                        event.borrow().target()
+                    } else {
+                        event.borrow().current_target()
+                    };
+                    match node {
+                        Some(node) => RuntimeValue::HtmlElement(node),
+                        None => RuntimeValue::Null,
+                    }
+                }
+                "defaultPrevented" => RuntimeValue::Boolean(event.borrow().default_prevented()),
+                "preventDefault" | "stopPropagation" => method(key),
+                _ => RuntimeValue::Undefined,
+            },
+            _ => RuntimeValue::Undefined,
+        };
+        Ok(value)
+    }
+
+    // プロパティに値を設定する
+    // 設定できないプロパティへの代入は無視する
+    fn set_property(
+        &mut self,
+        object: &RuntimeValue,
+        key: &str,
+        value: RuntimeValue,
+    ) -> Result<(), Error> {
+        match object {
+            RuntimeValue::Undefined | RuntimeValue::Null => Err(Error::Other(alloc::format!(
+                "TypeError: Cannot set properties of {} (setting '{}')",
+                object,
+                key
+            ))),
+            RuntimeValue::HtmlElement(node) if key == "textContent" || key == "innerText" => {
+                set_text_content(node, &value.to_string());
+                Ok(())
+            }
+            // window.locationとlocation.hrefへの代入は、そのURLへの移動になる
+            RuntimeValue::Window if key == "location" => {
+                self.navigate(&value);
+                Ok(())
+            }
+            RuntimeValue::Window => {
+                self.global.borrow_mut().define(String::from(key), value);
+                Ok(())
+            }
+            RuntimeValue::Location if key == "href" => {
+                self.navigate(&value);
+                Ok(())
+            }
+            RuntimeValue::Object(object) => {
+                object.borrow_mut().insert(String::from(key), value);
+                Ok(())
+            }
+            // 長さより後ろの要素に代入すると、間をundefinedで埋めて長くする
+            // lengthへの代入は、配列をその長さに切り詰めるか伸ばす
+            RuntimeValue::Array(elements) => {
+                let index = match key {
+                    "length" => {
+                        let length = value.to_number();
+                        if length < 0.0 || length as usize as f64 != length {
+                            return Err(Error::Other(String::from(
+                                "RangeError: Invalid array length",
+                            )));
+                        }
+                        elements
+                            .borrow_mut()
+                            .resize(length as usize, RuntimeValue::Undefined);
+                        return Ok(());
+                    }
+                    _ => match array_index(key) {
+                        Some(index) => index,
+                        None => return Ok(()),
+                    },
+                };
+                let mut elements = elements.borrow_mut();
+                if index >= elements.len() {
+                    elements.resize(index + 1, RuntimeValue::Undefined);
+                }
+                elements[index] = value;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // ブラウザが用意するメソッドを呼び出す
+    fn call_host_function(
+        &mut self,
+        this: &RuntimeValue,
+        name: &str,
+        arguments: Vec<RuntimeValue>,
+    ) -> Result<RuntimeValue, Error> {
+        let argument = |i: usize| {
+            arguments
+                .get(i)
+                .cloned()
+                .unwrap_or(RuntimeValue::Undefined)
+                .to_string()
+        };
+        match (this, name) {
+            (RuntimeValue::Document, "getElementById") => {
+                match self.window.borrow().get_element_by_id(&argument(0)) {
+                    Some(node) => Ok(RuntimeValue::HtmlElement(node)),
+                    None => Ok(RuntimeValue::Null),
+                }
+            }
+            (RuntimeValue::HtmlElement(node), "getAttribute") => {
+                match node
+                    .borrow()
+                    .get_element()
+                    .and_then(|element| element.get_attribute(&argument(0)))
+                {
+                    Some(value) => Ok(RuntimeValue::String(value)),
+                    None => Ok(RuntimeValue::Null),
+                }
+            }
+            (RuntimeValue::Location, "assign") => {
+                self.navigate(
+                    &arguments
+                        .first()
+                        .cloned()
+                        .unwrap_or(RuntimeValue::Undefined),
+                );
+                Ok(RuntimeValue::Undefined)
+            }
+            (RuntimeValue::Location, "toString") => Ok(RuntimeValue::String(self.location_href())),
+            // 1番目の引数の関数（または文字列のスクリプト）を、2番目の引数のミリ秒後に実行する
+            // 3番目以降の引数は関数に渡す
+            (RuntimeValue::Undefined, "setTimeout" | "setInterval") => {
+                let callback = arguments
+                    .first()
+                    .cloned()
+                    .unwrap_or(RuntimeValue::Undefined);
+                let delay = arguments.get(1).map(|d| d.to_number()).unwrap_or(0.0);
+                // NaNや負の数は0ミリ秒として扱う
+                let delay = if delay > 0.0 { delay as u64 } else { 0 };
+                let rest = arguments.iter().skip(2).cloned().collect();
+                let id = self
+                    .timers
+                    .borrow_mut()
+                    .add(callback, rest, delay, name == "setInterval");
+                Ok(RuntimeValue::Number(id as f64))
+            }
+            (RuntimeValue::Undefined, "clearTimeout" | "clearInterval") => {
+                let id = arguments.first().map(|id| id.to_number()).unwrap_or(0.0);
+                if id > 0.0 {
+                    self.timers.borrow_mut().remove(id as u64);
+                }
+                Ok(RuntimeValue::Undefined)
+            }
+            // 引数を順に末尾に追加し、新しい長さを返す
+            (RuntimeValue::Array(elements), "push") => {
+                let mut elements = elements.borrow_mut();
+                elements.extend(arguments);
+                Ok(RuntimeValue::Number(elements.len() as f64))
+            }
+            // 引数を文字列にして、空白で区切って出力する
+            (RuntimeValue::Console, _) => {
+                let level = match name {
+                    "warn" => ConsoleLevel::Warn,
+                    "error" => ConsoleLevel::Error,
+                    _ => ConsoleLevel::Log,
+                };
+                let text = arguments
+                    .iter()
+                    .map(|argument| argument.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                self.print(ConsoleMessage::new(level, text));
+                Ok(RuntimeValue::Undefined)
+            }
+            // 3番目の引数がtrueの場合は、キャプチャフェーズで呼び出す
+            (RuntimeValue::HtmlElement(node), "addEventListener") => {
+                if let Some(
+                    listener @ (RuntimeValue::Function(_) | RuntimeValue::HostFunction { .. }),
+                ) = arguments.get(1)
+                {
+                    let capture = arguments.get(2).is_some_and(|c| c.is_truthy());
+                    let callback = self.event_callback(listener.clone(), false);
+                    node.borrow_mut()
+                        .add_event_listener(&argument(0), callback, capture);
+                }
+                Ok(RuntimeValue::Undefined)
+            }
+            (RuntimeValue::Event(event), "preventDefault") => {
+                event.borrow_mut().prevent_default();
+                Ok(RuntimeValue::Undefined)
+            }
+            (RuntimeValue::Event(event), "stopPropagation") => {
+                event.borrow_mut().stop_propagation();
+                Ok(RuntimeValue::Undefined)
+            }
+            _ => Err(Error::Other(alloc::format!(
+                "TypeError: {} is not a function",
+                name
+            ))),
+        }
+    }
+}
+
+// 二項演算子の計算
+fn binary_operation(operator: &str, left: &RuntimeValue, right: &RuntimeValue) -> RuntimeValue {
+    let (l, r) = (left.to_number(), right.to_number());
+    match operator {
+        // どちらかが文字列（やオブジェクト）であれば、文字列として連結する
+        "+" if !left.is_primitive()
+            || !right.is_primitive()
+            || matches!(left, RuntimeValue::String(_))
+            || matches!(right, RuntimeValue::String(_)) =>
+        {
+            RuntimeValue::String(alloc::format!("{}{}", left, right))
+        }
+        "+" => RuntimeValue::Number(l + r),
+        "-" => RuntimeValue::Number(l - r),
+        "*" => RuntimeValue::Number(l * r),
+        "/" => RuntimeValue::Number(l / r),
+        "%" => RuntimeValue::Number(remainder(l, r)),
+        "===" => RuntimeValue::Boolean(left == right),
+        "!==" => RuntimeValue::Boolean(left != right),
+        "==" => RuntimeValue::Boolean(loose_equals(left, right)),
+        "!=" => RuntimeValue::Boolean(!loose_equals(left, right)),
+        "<" | ">" | "<=" | ">=" => {
+            let ordering = match (left, right) {
+                (RuntimeValue::String(a), RuntimeValue::String(b)) => Some(a.cmp(b)),
+                _ => l.partial_cmp(&r),
+            };
+            let result = match ordering {
+                Some(ordering) => match operator {
+                    "<" => ordering.is_lt(),
+                    ">" => ordering.is_gt(),
+                    "<=" => ordering.is_le(),
+                    _ => ordering.is_ge(),
+                },
+                // NaNとの比較は常にfalse
+                None => false,
+            };
+            RuntimeValue::Boolean(result)
+        }
+        _ => RuntimeValue::Undefined,
+    }
+}
+
+// 剰余（結果の符号は割られる数と同じ）
+// no_stdでは浮動小数点数の%が使えないので、商の整数部分から計算する
+fn remainder(a: f64, b: f64) -> f64 {
+    if b == 0.0 || a.is_nan() || b.is_nan() || a.is_infinite() {
+        return f64::NAN;
+    }
+    if b.is_infinite() {
+        return a;
+    }
+    let quotient = a / b;
+    let truncated = if quotient.abs() < 9.0e15 {
+        quotient as i64 as f64
+    } else {
+        quotient
+    };
+    a - b * truncated
+}
+
+// ==による比較
+// 型が違う場合は、nullとundefinedを等しいとし、それ以外は数値に変換して比べる
+fn loose_equals(left: &RuntimeValue, right: &RuntimeValue) -> bool {
+    match (left, right) {
+        (
+            RuntimeValue::Undefined | RuntimeValue::Null,
+            RuntimeValue::Undefined | RuntimeValue::Null,
+        ) => true,
+        (RuntimeValue::Undefined | RuntimeValue::Null, _)
+        | (_, RuntimeValue::Undefined | RuntimeValue::Null) => false,
+        _ if core::mem::discriminant(left) == core::mem::discriminant(right) => left == right,
+        _ if left.is_primitive() && right.is_primitive() => left.to_number() == right.to_number(),
+        // オブジェクトと値の比較は、オブジェクトを文字列に変換してから比べる　[] == 0はtrue
+        _ if left.is_primitive() => loose_equals(left, &RuntimeValue::String(right.to_string())),
+        _ if right.is_primitive() => loose_equals(&RuntimeValue::String(left.to_string()), right),
+        _ => false,
+    }
+}
+
+// 配列の添字として使えるプロパティの名前であれば、その数値を返す
+// "01"や"1.5"のような名前は添字ではなく、ただのプロパティとして扱う
+fn array_index(key: &str) -> Option<usize> {
+    let index = key.parse::<usize>().ok()?;
+    if alloc::format!("{}", index) == key {
+        Some(index)
+    } else {
+        None
+    }
+}
+
+// ノードとその子孫の要素を文書の順番に集める
+fn collect_elements(node: &Rc<RefCell<DomNode>>, elements: &mut Vec<Rc<RefCell<DomNode>>>) {
+    if node.borrow().get_element().is_some() {
+        elements.push(node.clone());
+    }
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        collect_elements(&c, elements);
+        child = c.borrow().next_sibling();
+    }
+}
+
+// エラーメッセージに使う、呼び出そうとした式の説明
+fn describe(node: &Rc<Node>) -> String {
+    match node.as_ref() {
+        Node::Identifier(name) => name.clone(),
+        Node::MemberExpression { object, property } => match property.as_ref() {
+            Node::StringLiteral(name) => alloc::format!("{}.{}", describe(object), name),
+            _ => alloc::format!("{}[...]", describe(object)),
+        },
+        Node::ThisExpression => String::from("this"),
+        _ => String::from("expression"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::dom::event::dispatch_event;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    fn runtime(html: &str) -> JsRuntime {
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        JsRuntime::new(window)
+    }
+
+    // 四則演算・文字列の連結・比較の結果が正しいか
+    #[test]
+    fn test_expressions() {
+        let mut runtime = runtime("");
+        let cases = [
+            ("1 + 2 * 3", RuntimeValue::Number(7.0)),
+            ("(1 + 2) * 3 - 10 / 4", RuntimeValue::Number(6.5)),
+            ("-7 % 3", RuntimeValue::Number(-1.0)),
+            ("'a' + 1 + 2", RuntimeValue::String("a12".to_string())),
+            ("0.5 + ''", RuntimeValue::String("0.5".to_string())),
+            ("1 < 2 && 'b' > 'a'", RuntimeValue::Boolean(true)),
+            ("1 == '1'", RuntimeValue::Boolean(true)),
+            ("1 === '1'", RuntimeValue::Boolean(false)),
+            ("null == undefined", RuntimeValue::Boolean(true)),
+            (
+                "0 || 'default'",
+                RuntimeValue::String("default".to_string()),
+            ),
+            (
+                "typeof missing",
+                RuntimeValue::String("undefined".to_string()),
+            ),
+            (
+                "true ? 'yes' : 'no'",
+                RuntimeValue::String("yes".to_string()),
+            ),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(Ok(expected), runtime.execute_script(source), "{}", source);
+        }
+    }
+
+    // 関数・クロージャ・ループ・変数のスコープ
+    #[test]
+    fn test_functions_and_control_flow() {
+        let mut runtime = runtime("");
+        let source = "
+            function fib(n) { return n < 2 ? n : fib(n - 1) + fib(n - 2); }
+            var counter = (function () {
+                let count = 0;
+                return () => ++count;
+            })();
+            counter();
+            counter();
+            let sum = 0;
+            for (let i = 0; i < 10; i++) {
+                if (i % 2 == 0) continue;
+                if (i > 7) break;
+                sum += i;
+            }
+            if (true) { var hoisted = 'var'; let scoped = 'let'; }
+            fib(10) + ',' + counter() + ',' + sum + ',' + hoisted + ',' + typeof scoped;
+        ";
+        assert_eq!(
+            Ok(RuntimeValue::String("55,3,16,var,undefined".to_string())),
+            runtime.execute_script(source)
+        );
+    }
+
+    // 実行時のエラーの種類とメッセージ
+    #[test]
+    fn test_runtime_errors() {
+        let mut runtime = runtime("");
+        assert_eq!(
+            Err(Error::Other(
+                "ReferenceError: missing is not defined".to_string()
+            )),
+            runtime.execute_script("missing + 1")
+        );
+        assert_eq!(
+            Err(Error::Other(
+                "TypeError: document.foo is not a function".to_string()
+            )),
+            runtime.execute_script("document.foo()")
+        );
+        assert_eq!(
+            Err(Error::Other(
+                "RangeError: Maximum call stack size exceeded".to_string()
+            )),
+            runtime.execute_script("function f() { return f(); } f();")
+        );
+        for source in [
+            "while (true) {}",
+            "for (;;) {}",
+            "for (var i = 0; ; i++) {}",
+        ] {
+            assert_eq!(
+                Err(Error::Other(
+                    "InternalError: too many loop iterations".to_string()
+                )),
+                runtime.execute_script(source),
+                "{}",
+                source
+            );
+        }
+        // 上限は実行ごとに数え直す
+        assert_eq!(
+            Ok(RuntimeValue::Number(10.0)),
+            runtime.execute_script("var n = 0; while (n < 10) { n++; } n")
+        );
+    }
+
+    // DOMの要素を取得して、テキストを読み書きできるか
+    #[test]
+    fn test_dom_binding() {
+        let mut runtime = runtime(
+            "<html><head></head><body><h1 id=\"title\" class=\"big\">hello</h1></body></html>",
+        );
+        let source = "
+            var title = document.getElementById('title');
+            title.textContent = title.textContent + ' world';
+            title.tagName + ':' + title.getAttribute('class') + ':' + document.getElementById('none');
+        ";
+        assert_eq!(
+            Ok(RuntimeValue::String("H1:big:null".to_string())),
+            runtime.execute_script(source)
+        );
+        let title = runtime
+            .window()
+            .borrow()
+            .get_element_by_id("title")
+            .expect("title should exist");
+        assert_eq!("hello world", get_text_content(&title));
+        assert!(title.borrow().is_dirty() || title.borrow().is_child_dirty());
+    }
+
+    // console.logなどの出力と、イベントリスナーの実行時のエラーがコンソールに記録されるか
+    #[test]
+    fn test_console() {
+        let mut runtime = runtime(
+            "<html><head></head><body><p id=\"p\" onclick=\"missing()\">x</p></body></html>",
+        );
+        let source = "
+            console.log('a', 1, null);
+            console.warn('b');
+            console.error(document);
+        ";
+        assert_eq!(Ok(RuntimeValue::Undefined), runtime.execute_script(source));
+        runtime.register_event_handlers();
+        let p = runtime
+            .window()
+            .borrow()
+            .get_element_by_id("p")
+            .expect("p should exist");
+        dispatch_event(&p, &mut Event::click());
+
+        assert_eq!(
+            alloc::vec![
+                ConsoleMessage::new(ConsoleLevel::Log, "a 1 null".to_string()),
+                ConsoleMessage::new(ConsoleLevel::Warn, "b".to_string()),
+                ConsoleMessage::new(ConsoleLevel::Error, "[object HTMLDocument]".to_string()),
+                ConsoleMessage::new(
+                    ConsoleLevel::Error,
+                    "Uncaught ReferenceError: missing is not defined".to_string()
+                ),
+            ],
+            runtime.console_messages()
+        );
+        runtime.clear_console();
+        assert!(runtime.console_messages().is_empty());
+    }
+
+    // オブジェクトと配列のリテラル、プロパティの読み書き、添字、length、push
+    #[test]
+    fn test_objects_and_arrays() {
+        let mut runtime = runtime("");
+        let source = "
+            var point = { x: 1, 'y': 2 };
+            point.x = point.x + 10;
+            point['z'] = point.y * 2;
+            var list = [1, 'two'];
+            var alias = list;
+            alias.push(point.x, point.z);
+            list[5] = 'last';
+            var sum = 0;
+            for (let i = 0; i < 4; i++) { if (typeof list[i] == 'number') sum += list[i]; }
+            list.length + ':' + sum + ':' + list + ':' + point.missing + ':' + typeof point;
+        ";
+        assert_eq!(
+            Ok(RuntimeValue::String(
+                "6:16:1,two,11,4,,last:undefined:object".to_string()
+            )),
+            runtime.execute_script(source)
+        );
+        assert_eq!(
+            Ok(RuntimeValue::Number(2.0)),
+            runtime.execute_script(
+                "list.length = 2; list.push(); [] + 1 == '1' && [] == 0 ? list.length : -1"
+            )
+        );
+        assert_eq!(
+            Err(Error::Other("RangeError: Invalid array length".to_string())),
+            runtime.execute_script("list.length = -1")
+        );
+    }
+
+    // タイマーが時刻の順に実行され、setIntervalは繰り返し、clearIntervalで止まるか
+    #[test]
+    fn test_timers() {
+        let mut runtime = runtime("");
+        let source = "
+            var log = [];
+            setTimeout(function (a, b) { log.push(a + b); }, 100, 1, 2);
+            setTimeout('log.push(\\'string\\')', 50);
+            var id = setInterval(() => {
+                log.push('tick');
+                if (log.length > 4) clearInterval(id);
+            }, 30);
+            setTimeout(() => { log.push('zero'); setTimeout(() => log.push('nested')); });
+        ";
+        runtime.execute_script(source).expect("script should run");
+        let log = |runtime: &mut JsRuntime| {
+            runtime
+                .execute_script("log + ''")
+                .expect("log should exist")
+                .to_string()
+        };
+
+        // タイマーの中で予約したタイマーは、次の呼び出しまで実行しない
+        assert!(runtime.run_timers(0));
+        assert_eq!("zero", log(&mut runtime));
+        assert!(runtime.run_timers(30));
+        assert_eq!("zero,nested,tick", log(&mut runtime));
+        assert!(runtime.run_timers(60));
+        assert_eq!("zero,nested,tick,string,tick", log(&mut runtime));
+        assert!(runtime.run_timers(100));
+        assert_eq!("zero,nested,tick,string,tick,3", log(&mut runtime));
+        assert!(!runtime.has_timers());
+        assert!(!runtime.run_timers(1000));
+    }
+
+    // location.hrefで文書のURLを読み、代入するとURLを解決して移動先として記録するか
+    #[test]
+    fn test_location() {
+        let mut runtime = runtime("");
+        assert_eq!(
+            Ok(RuntimeValue::String("about:blank".to_string())),
+            runtime.execute_script("location.href")
+        );
+
+        let url = crate::url::Url::new("http://example.com:80/dir/index.html".to_string())
+            .parse()
+            .expect("url should be valid");
+        runtime.window().borrow_mut().set_location(url);
+        assert_eq!(
+            Ok(RuntimeValue::Boolean(true)),
+            runtime.execute_script(
+                "var x = 1; window.location.href == 'http://example.com:80/dir/index.html' && window.x == 1 && window.document == document"
+            )
+        );
+        assert_eq!(None, runtime.take_navigation());
+        runtime
+            .execute_script("window.location.href = 'a.html'; location.assign('/b.html');")
+            .expect("script should run");
+        assert_eq!(
+            Some("http://example.com:80/b.html".to_string()),
+            runtime.take_navigation()
+        );
+        assert_eq!(None, runtime.take_navigation());
+    }
+}
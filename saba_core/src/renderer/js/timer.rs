@@ -0,0 +1,111 @@
+use crate::renderer::js::runtime::RuntimeValue;
+use alloc::vec::Vec;
+
+// https://html.spec.whatwg.org/multipage/timers-and-user-agent-apis.html#timers
+
+// setTimeoutとsetIntervalで予約された1つのタイマー
+#[derive(Debug, Clone)]
+pub struct Timer {
+    id: u64,
+    // 実行する時刻（ミリ秒）
+    due: u64,
+    // setIntervalの場合は繰り返す間隔（ミリ秒）
+    interval: Option<u64>,
+    // 呼び出す関数か、実行するスクリプトの文字列
+    callback: RuntimeValue,
+    // 関数に渡す引数
+    arguments: Vec<RuntimeValue>,
+}
+
+impl Timer {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn callback(&self) -> RuntimeValue {
+        self.callback.clone()
+    }
+
+    pub fn arguments(&self) -> Vec<RuntimeValue> {
+        self.arguments.clone()
+    }
+}
+
+// 予約されたタイマーの一覧と、タイマーの基準にする現在の時刻
+#[derive(Debug, Clone, Default)]
+pub struct TimerQueue {
+    // 最後に知らされた現在の時刻（ミリ秒）
+    now: u64,
+    next_id: u64,
+    timers: Vec<Timer>,
+}
+
+impl TimerQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    pub fn set_now(&mut self, now: u64) {
+        self.now = now;
+    }
+
+    // 現在の時刻からdelayミリ秒後に実行するタイマーを追加し、clearTimeoutなどに使うidを返す
+    // idは1から始まる
+    pub fn add(
+        &mut self,
+        callback: RuntimeValue,
+        arguments: Vec<RuntimeValue>,
+        delay: u64,
+        repeat: bool,
+    ) -> u64 {
+        self.next_id += 1;
+        self.timers.push(Timer {
+            id: self.next_id,
+            due: self.now.saturating_add(delay),
+            // 間隔が0の繰り返しで、同じ時刻に実行し続けないようにする
+            interval: if repeat { Some(delay.max(1)) } else { None },
+            callback,
+            arguments,
+        });
+        self.next_id
+    }
+
+    pub fn remove(&mut self, id: u64) {
+        self.timers.retain(|timer| timer.id != id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    // 現在の時刻までに実行する時刻になったタイマーのidを、実行する順に返す
+    // 時刻が同じタイマーは追加した順に実行する
+    pub fn due_timers(&self) -> Vec<u64> {
+        let mut due: Vec<&Timer> = self
+            .timers
+            .iter()
+            .filter(|timer| timer.due <= self.now)
+            .collect();
+        due.sort_by_key(|timer| (timer.due, timer.id));
+        due.iter().map(|timer| timer.id).collect()
+    }
+
+    // 実行するタイマーを取り出す
+    // setIntervalのタイマーは、現在の時刻から間隔だけ後に実行し直すように残す
+    // すでに削除されたタイマーの場合はNoneを返す
+    pub fn take(&mut self, id: u64) -> Option<Timer> {
+        let index = self.timers.iter().position(|timer| timer.id == id)?;
+        match self.timers[index].interval {
+            Some(interval) => {
+                let timer = self.timers[index].clone();
+                self.timers[index].due = self.now.saturating_add(interval);
+                Some(timer)
+            }
+            None => Some(self.timers.remove(index)),
+        }
+    }
+}
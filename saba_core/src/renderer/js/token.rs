@@ -0,0 +1,264 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// このブラウザが扱うJavaScriptの予約語
+static RESERVED_WORDS: [&str; 17] = [
+    "var",
+    "let",
+    "const",
+    "function",
+    "return",
+    "if",
+    "else",
+    "while",
+    "for",
+    "break",
+    "continue",
+    "true",
+    "false",
+    "null",
+    "undefined",
+    "typeof",
+    "this",
+];
+
+// 記号と演算子
+// 長いものから順に一致を調べる（"==="を"=="と"="に分けないように）
+static PUNCTUATORS: [&str; 39] = [
+    "===", "!==", "=>", "==", "!=", "<=", ">=", "&&", "||", "++", "--", "+=", "-=", "*=", "/=",
+    "%=", "{", "}", "(", ")", "[", "]", ";", ",", ".", ":", "?", "+", "-", "*", "/", "%", "=", "<",
+    ">", "!", "&", "|", "^",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    // 記号と演算子　'(' '{' ';' '+' '===' など
+    Punctuator(String),
+    // 数値リテラル
+    Number(f64),
+    // 文字列リテラル（エスケープシーケンスを処理した後の中身）
+    StringLiteral(String),
+    // 変数名や関数名、プロパティ名
+    Identifier(String),
+    // 予約語　'var' 'function' など
+    Keyword(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsLexer {
+    pos: usize,
+    input: Vec<char>,
+}
+
+impl JsLexer {
+    pub fn new(js: String) -> Self {
+        Self {
+            pos: 0,
+            input: js.chars().collect(),
+        }
+    }
+
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.input.get(self.pos + offset).copied()
+    }
+
+    // 空白と改行、コメント（//と/* */）を読み飛ばす
+    fn skip_whitespace_and_comments(&mut self) {
+        while let Some(c) = self.peek(0) {
+            if c.is_whitespace() {
+                self.pos += 1;
+            } else if c == '/' && self.peek(1) == Some('/') {
+                while self.peek(0).is_some_and(|c| c != '\n') {
+                    self.pos += 1;
+                }
+            } else if c == '/' && self.peek(1) == Some('*') {
+                self.pos += 2;
+                while self.pos < self.input.len()
+                    && !(self.peek(0) == Some('*') && self.peek(1) == Some('/'))
+                {
+                    self.pos += 1;
+                }
+                self.pos = (self.pos + 2).min(self.input.len());
+            } else {
+                return;
+            }
+        }
+    }
+
+    // 10進数の数値を消費する（小数と指数を含む）
+    fn consume_number(&mut self) -> f64 {
+        let start = self.pos;
+        while self.peek(0).is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.pos += 1;
+        }
+        if self.peek(0).is_some_and(|c| c == 'e' || c == 'E') {
+            let sign = usize::from(self.peek(1).is_some_and(|c| c == '+' || c == '-'));
+            if self.peek(1 + sign).is_some_and(|c| c.is_ascii_digit()) {
+                self.pos += 1 + sign;
+                while self.peek(0).is_some_and(|c| c.is_ascii_digit()) {
+                    self.pos += 1;
+                }
+            }
+        }
+        let s: String = self.input[start..self.pos].iter().collect();
+        s.parse::<f64>().unwrap_or(f64::NAN)
+    }
+
+    // 開始と同じ"か'が出てくるまで、文字列を消費する
+    // 閉じられないまま入力が終わった場合は、そこまでを文字列とする
+    fn consume_string(&mut self) -> String {
+        let quote = self.input[self.pos];
+        self.pos += 1;
+        let mut s = String::new();
+        while let Some(c) = self.peek(0) {
+            self.pos += 1;
+            if c == quote {
+                break;
+            }
+            if c != '\\' {
+                s.push(c);
+                continue;
+            }
+            let escaped = match self.peek(0) {
+                Some(escaped) => escaped,
+                None => break,
+            };
+            self.pos += 1;
+            match escaped {
+                'n' => s.push('\n'),
+                't' => s.push('\t'),
+                'r' => s.push('\r'),
+                '0' => s.push('\0'),
+                'u' => {
+                    let hex: String = self.input[self.pos..(self.pos + 4).min(self.input.len())]
+                        .iter()
+                        .collect();
+                    match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        Some(c) if hex.len() == 4 => {
+                            s.push(c);
+                            self.pos += 4;
+                        }
+                        _ => s.push('u'),
+                    }
+                }
+                // 行末の\は行の継続
+                '\n' => {}
+                c => s.push(c),
+            }
+        }
+        s
+    }
+
+    // 識別子または予約語を消費する
+    fn consume_identifier(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek(0)
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        {
+            self.pos += 1;
+        }
+        self.input[start..self.pos].iter().collect()
+    }
+
+    fn consume_punctuator(&mut self) -> String {
+        for punctuator in PUNCTUATORS {
+            let len = punctuator.chars().count();
+            if self.pos + len > self.input.len() {
+                continue;
+            }
+            if self.input[self.pos..self.pos + len]
+                .iter()
+                .copied()
+                .eq(punctuator.chars())
+            {
+                self.pos += len;
+                return String::from(punctuator);
+            }
+        }
+        // 知らない記号は1文字のトークンにして、構文解析でエラーにする
+        let c = self.input[self.pos];
+        self.pos += 1;
+        c.into()
+    }
+}
+
+impl Iterator for JsLexer {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.skip_whitespace_and_comments();
+        let c = self.peek(0)?;
+
+        let token = if c.is_ascii_digit()
+            || (c == '.' && self.peek(1).is_some_and(|c| c.is_ascii_digit()))
+        {
+            Token::Number(self.consume_number())
+        } else if c == '"' || c == '\'' {
+            Token::StringLiteral(self.consume_string())
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            let word = self.consume_identifier();
+            if RESERVED_WORDS.contains(&word.as_str()) {
+                Token::Keyword(word)
+            } else {
+                Token::Identifier(word)
+            }
+        } else {
+            Token::Punctuator(self.consume_punctuator())
+        };
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    // 予約語・識別子・数値・文字列・演算子に分けられ、コメントは読み飛ばされるか
+    #[test]
+    fn test_tokens() {
+        let js =
+            "var a = 1.5e1; // comment\n/* block */ a === 'it\\'s' && b.c(\"\\u0041\")".to_string();
+        let tokens: Vec<Token> = JsLexer::new(js).collect();
+        let expected = vec![
+            Token::Keyword("var".to_string()),
+            Token::Identifier("a".to_string()),
+            Token::Punctuator("=".to_string()),
+            Token::Number(15.0),
+            Token::Punctuator(";".to_string()),
+            Token::Identifier("a".to_string()),
+            Token::Punctuator("===".to_string()),
+            Token::StringLiteral("it's".to_string()),
+            Token::Punctuator("&&".to_string()),
+            Token::Identifier("b".to_string()),
+            Token::Punctuator(".".to_string()),
+            Token::Identifier("c".to_string()),
+            Token::Punctuator("(".to_string()),
+            Token::StringLiteral("A".to_string()),
+            Token::Punctuator(")".to_string()),
+        ];
+        assert_eq!(expected, tokens);
+    }
+
+    // 記号は長いものから一致し、予約語で始まる名前は識別子になるか
+    #[test]
+    fn test_punctuators_and_keywords() {
+        let js = "a !== b => c++ += while whiles _this$".to_string();
+        let tokens: Vec<Token> = JsLexer::new(js).collect();
+        let expected = vec![
+            Token::Identifier("a".to_string()),
+            Token::Punctuator("!==".to_string()),
+            Token::Identifier("b".to_string()),
+            Token::Punctuator("=>".to_string()),
+            Token::Identifier("c".to_string()),
+            Token::Punctuator("++".to_string()),
+            Token::Punctuator("+=".to_string()),
+            Token::Keyword("while".to_string()),
+            Token::Identifier("whiles".to_string()),
+            Token::Identifier("_this$".to_string()),
+        ];
+        assert_eq!(expected, tokens);
+    }
+}
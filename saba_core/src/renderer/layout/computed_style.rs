@@ -1,11 +1,19 @@
+use crate::constants::CHAR_HEIGHT;
 use crate::error::Error;
+use crate::renderer::css::cssom::CascadeOrigin;
+use crate::renderer::css::cssom::ComponentValue;
+use crate::renderer::css::cssom::Declaration;
+use crate::renderer::css::cssom::QualifiedRule;
+use crate::renderer::css::cssom::StyleSheet;
 use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
 use crate::renderer::dom::node::NodeKind;
+use crate::renderer::layout::font_metrics::FontMetrics;
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::string::ToString;
+use alloc::vec::Vec;
 use core::cell::RefCell;
 use core::str::FromStr;
 
@@ -18,8 +26,34 @@ pub struct ComputedStyle {
     display: Option<DisplayType>,
     font_size: Option<FontSize>,
     text_decoration: Option<TextDecoration>,
-    height: Option<f64>,
-    width: Option<f64>,
+    font_weight: Option<FontWeight>,
+    font_style: Option<FontStyle>,
+    font_family: Option<String>,
+    text_align: Option<TextAlign>,
+    white_space: Option<WhiteSpace>,
+    line_height: Option<LineHeight>,
+    margin: Option<EdgeSizes>,
+    padding: Option<EdgeSizes>,
+    border_width: Option<EdgeSizes>,
+    border_color: Option<EdgeColors>,
+    // 左右のマージンにautoが指定されているか
+    // marginの値では0として扱う
+    margin_auto: AutoMargins,
+    height: Option<Dimension>,
+    width: Option<Dimension>,
+    max_width: Option<Dimension>,
+    min_width: Option<Dimension>,
+    min_height: Option<Dimension>,
+    position: Option<Position>,
+    z_index: Option<ZIndex>,
+    overflow: Option<Overflow>,
+    // 角の丸みの半径（ピクセル）
+    border_radius: Option<i64>,
+    // 不透明度 0.0（透明）から1.0（不透明）
+    opacity: Option<f64>,
+    // ルート要素の文字の大きさ（ピクセル）
+    // remの単位の基準になる
+    root_font_size: Option<i64>,
 }
 
 impl Default for ComputedStyle {
@@ -36,8 +70,28 @@ impl ComputedStyle {
             display: None,
             font_size: None,
             text_decoration: None,
+            font_weight: None,
+            font_style: None,
+            font_family: None,
+            text_align: None,
+            white_space: None,
+            line_height: None,
+            margin: None,
+            padding: None,
+            border_width: None,
+            border_color: None,
+            margin_auto: AutoMargins::default(),
             height: None,
             width: None,
+            max_width: None,
+            min_width: None,
+            min_height: None,
+            position: None,
+            z_index: None,
+            overflow: None,
+            border_radius: None,
+            opacity: None,
+            root_font_size: None,
         }
     }
 
@@ -70,87 +124,1186 @@ impl ComputedStyle {
             .expect("failed to access CSS property: display")
     }
 
+    pub fn set_font_size(&mut self, font_size: FontSize) {
+        self.font_size = Some(font_size);
+    }
+
     pub fn font_size(&self) -> FontSize {
         self.font_size
             .expect("failed to access CSS property: font_size")
     }
 
+    pub fn set_text_decoration(&mut self, text_decoration: TextDecoration) {
+        self.text_decoration = Some(text_decoration);
+    }
+
     pub fn text_decoration(&self) -> TextDecoration {
         self.text_decoration
             .expect("failed to access CSS property: text_decoration")
     }
 
-    pub fn set_height(&mut self, height: f64) {
+    pub fn set_font_weight(&mut self, font_weight: FontWeight) {
+        self.font_weight = Some(font_weight);
+    }
+
+    pub fn font_weight(&self) -> FontWeight {
+        self.font_weight
+            .expect("failed to access CSS property: font_weight")
+    }
+
+    pub fn set_font_style(&mut self, font_style: FontStyle) {
+        self.font_style = Some(font_style);
+    }
+
+    pub fn font_style(&self) -> FontStyle {
+        self.font_style
+            .expect("failed to access CSS property: font_style")
+    }
+
+    pub fn set_font_family(&mut self, font_family: String) {
+        self.font_family = Some(font_family);
+    }
+
+    pub fn font_family(&self) -> String {
+        self.font_family
+            .clone()
+            .expect("failed to access CSS property: font_family")
+    }
+
+    pub fn set_text_align(&mut self, text_align: TextAlign) {
+        self.text_align = Some(text_align);
+    }
+
+    pub fn text_align(&self) -> TextAlign {
+        self.text_align
+            .expect("failed to access CSS property: text_align")
+    }
+
+    pub fn set_white_space(&mut self, white_space: WhiteSpace) {
+        self.white_space = Some(white_space);
+    }
+
+    pub fn white_space(&self) -> WhiteSpace {
+        self.white_space
+            .expect("failed to access CSS property: white_space")
+    }
+
+    pub fn set_line_height(&mut self, line_height: LineHeight) {
+        self.line_height = Some(line_height);
+    }
+
+    pub fn line_height(&self) -> LineHeight {
+        self.line_height
+            .expect("failed to access CSS property: line_height")
+    }
+
+    pub fn set_margin(&mut self, margin: EdgeSizes) {
+        self.margin = Some(margin);
+    }
+
+    pub fn margin(&self) -> EdgeSizes {
+        self.margin.expect("failed to access CSS property: margin")
+    }
+
+    pub fn set_border_color(&mut self, border_color: EdgeColors) {
+        self.border_color = Some(border_color);
+    }
+
+    pub fn border_color(&self) -> EdgeColors {
+        self.border_color
+            .clone()
+            .expect("failed to access CSS property: border_color")
+    }
+
+    // 左右のマージンがautoかどうか
+    pub fn margin_auto(&self) -> AutoMargins {
+        self.margin_auto
+    }
+
+    pub fn set_padding(&mut self, padding: EdgeSizes) {
+        self.padding = Some(padding);
+    }
+
+    pub fn padding(&self) -> EdgeSizes {
+        self.padding
+            .expect("failed to access CSS property: padding")
+    }
+
+    pub fn set_border_width(&mut self, border_width: EdgeSizes) {
+        self.border_width = Some(border_width);
+    }
+
+    pub fn border_width(&self) -> EdgeSizes {
+        self.border_width
+            .expect("failed to access CSS property: border_width")
+    }
+
+    // margin、padding、border-widthのいずれかの値
+    fn edges(&self, property: &str) -> Option<EdgeSizes> {
+        match property {
+            "margin" => self.margin,
+            "padding" => self.padding,
+            "border-width" => self.border_width,
+            _ => None,
+        }
+    }
+
+    // marginの場合、autoの指定は取り消される
+    fn set_edges(&mut self, property: &str, edges: Option<EdgeSizes>) {
+        match property {
+            "margin" => {
+                self.margin = edges;
+                self.margin_auto = AutoMargins::default();
+            }
+            "padding" => self.padding = edges,
+            "border-width" => self.border_width = edges,
+            _ => {}
+        }
+    }
+
+    // margin-topなど1辺だけの値を変更する
+    // 他の辺がまだ決まっていない場合は0にする
+    fn set_edge(&mut self, property: &str, side: Side, value: i64) {
+        let mut edges = self.edges(property).unwrap_or_default();
+        edges.set_side(side, value);
+        let mut margin_auto = self.margin_auto;
+        self.set_edges(property, Some(edges));
+        if property == "margin" {
+            margin_auto.set_side(side, false);
+            self.margin_auto = margin_auto;
+        }
+    }
+
+    pub fn set_height(&mut self, height: Dimension) {
         self.height = Some(height);
     }
 
-    pub fn height(&self) -> f64 {
+    pub fn height(&self) -> Dimension {
         self.height.expect("failed to access CSS property: height")
     }
 
-    pub fn set_width(&mut self, width: f64) {
+    pub fn set_width(&mut self, width: Dimension) {
         self.width = Some(width);
     }
 
-    pub fn width(&self) -> f64 {
+    pub fn width(&self) -> Dimension {
         self.width.expect("failed to access CSS property: width")
     }
 
+    pub fn set_max_width(&mut self, max_width: Dimension) {
+        self.max_width = Some(max_width);
+    }
+
+    pub fn max_width(&self) -> Dimension {
+        self.max_width
+            .expect("failed to access CSS property: max_width")
+    }
+
+    pub fn set_min_width(&mut self, min_width: Dimension) {
+        self.min_width = Some(min_width);
+    }
+
+    pub fn min_width(&self) -> Dimension {
+        self.min_width
+            .expect("failed to access CSS property: min_width")
+    }
+
+    pub fn set_min_height(&mut self, min_height: Dimension) {
+        self.min_height = Some(min_height);
+    }
+
+    pub fn min_height(&self) -> Dimension {
+        self.min_height
+            .expect("failed to access CSS property: min_height")
+    }
+
+    pub fn set_position(&mut self, position: Position) {
+        self.position = Some(position);
+    }
+
+    pub fn position(&self) -> Position {
+        self.position
+            .expect("failed to access CSS property: position")
+    }
+
+    pub fn set_z_index(&mut self, z_index: ZIndex) {
+        self.z_index = Some(z_index);
+    }
+
+    pub fn z_index(&self) -> ZIndex {
+        self.z_index
+            .expect("failed to access CSS property: z_index")
+    }
+
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = Some(overflow);
+    }
+
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+            .expect("failed to access CSS property: overflow")
+    }
+
+    pub fn set_border_radius(&mut self, border_radius: i64) {
+        self.border_radius = Some(border_radius);
+    }
+
+    pub fn border_radius(&self) -> i64 {
+        self.border_radius
+            .expect("failed to access CSS property: border_radius")
+    }
+
+    pub fn set_opacity(&mut self, opacity: f64) {
+        self.opacity = Some(opacity);
+    }
+
+    pub fn opacity(&self) -> f64 {
+        self.opacity
+            .expect("failed to access CSS property: opacity")
+    }
+
+    // 位置指定された要素（position: static以外）かどうか
+    // 位置指定された要素は、重なりの順番（z-index）に従って描画される
+    pub fn is_positioned(&self) -> bool {
+        self.position() != Position::Static
+    }
+
+    // ノードの計算値を求める
+    // 1. 全てのスタイルシートからノードにマッチするルールを集める
+    // 2. 詳細度とソースコード上の順番で並べて宣言を適用する（カスケード）
+    // 3. 値が指定されていないプロパティを埋める（デフォルト値の設定）
+    pub fn resolve(
+        node: &Rc<RefCell<Node>>,
+        styles: &[StyleSheet],
+        parent_style: Option<ComputedStyle>,
+    ) -> Self {
+        let mut matched_rules: Vec<(CascadeOrigin, &QualifiedRule)> = styles
+            .iter()
+            .flat_map(|style| style.rules.iter().map(move |rule| (style.origin, rule)))
+            .filter(|(_, rule)| rule.selector.matches(&node.borrow()))
+            .collect();
+
+        // オリジン、詳細度の低い順に並べる
+        // 安定ソートなので、詳細度が同じ場合はスタイルシートの順番とその中での順番が保たれる
+        matched_rules.sort_by_key(|(origin, rule)| (*origin, rule.selector.specificity()));
+
+        // 後から適用した宣言が優先される
+        // 他のプロパティのemは要素自身の文字の大きさを基準にするので、font-sizeを先に決める
+        let (font_size_declarations, declarations): (Vec<&Declaration>, Vec<&Declaration>) =
+            matched_rules
+                .iter()
+                .flat_map(|(_, rule)| rule.declarations.iter())
+                .partition(|declaration| declaration.property == "font-size");
+
+        let mut style = Self::new();
+        style.cascading(font_size_declarations, parent_style.as_ref());
+        style.default_font_size(node, parent_style.as_ref());
+        style.cascading(declarations, parent_style.as_ref());
+
+        style.defaulting(node, parent_style);
+        style
+    }
+
+    // 匿名ブロックボックスのスタイル
+    // 対応する要素がないので、継承されるプロパティは親の値、それ以外は初期値になる
+    pub fn anonymous_block(node: &Rc<RefCell<Node>>, parent_style: &ComputedStyle) -> Self {
+        let mut style = Self::new();
+        style.set_display(DisplayType::Block);
+        style.root_font_size = parent_style.root_font_size;
+        style.defaulting(node, Some(parent_style.clone()));
+        style
+    }
+
+    // 文字の大きさを決める
+    // 指定がない場合は要素ごとのデフォルトの値、親の値、初期値の順に使う
+    // ルート要素の場合はremの基準として文字の大きさを覚えておく
+    fn default_font_size(
+        &mut self,
+        node: &Rc<RefCell<Node>>,
+        parent_style: Option<&ComputedStyle>,
+    ) {
+        if self.font_size.is_none() {
+            self.font_size = FontSize::default(node);
+        }
+        if self.font_size.is_none() {
+            self.font_size = Some(match parent_style {
+                Some(parent) => parent.font_size(),
+                None => FontSize::Medium,
+            });
+        }
+        self.root_font_size = match parent_style {
+            Some(parent) => parent.root_font_size,
+            None => Some(self.font_size().px()),
+        };
+    }
+
+    // 相対的な単位をピクセルに変換するための基準
+    // emとremは要素自身とルート要素の文字の大きさ
+    fn length_base(&self, percentage: Option<i64>) -> LengthBase {
+        let em = self.font_size.unwrap_or(FontSize::Medium).px();
+        LengthBase {
+            em,
+            rem: self.root_font_size.unwrap_or(em),
+            percentage,
+        }
+    }
+
+    // CSSの宣言を適用する
+    // inherit、initial、unsetのキーワードは親のスタイルを使って解決する
+    pub fn cascading<'a>(
+        &mut self,
+        declarations: impl IntoIterator<Item = &'a Declaration>,
+        parent_style: Option<&ComputedStyle>,
+    ) {
+        for declaration in declarations {
+            if let ComponentValue::Ident(keyword) = &declaration.value {
+                let property = declaration.property.as_str();
+                // unsetは継承されるプロパティならinherit、それ以外はinitialとして扱う
+                let inherit = match keyword.as_str() {
+                    "inherit" => true,
+                    "initial" => false,
+                    "unset" => is_inherited_property(property),
+                    _ => {
+                        self.apply_declaration(declaration, parent_style);
+                        continue;
+                    }
+                };
+                match parent_style {
+                    Some(parent) if inherit => self.inherit_property(property, parent),
+                    _ => self.set_initial(property),
+                }
+                continue;
+            }
+            self.apply_declaration(declaration, parent_style);
+        }
+    }
+
+    // 1つの宣言をスタイルに反映する
+    // 相対的な単位（em、rem、%）はここでピクセルに変換し、レイアウトではピクセルの値だけを扱う
+    fn apply_declaration(
+        &mut self,
+        declaration: &Declaration,
+        parent_style: Option<&ComputedStyle>,
+    ) {
+        match declaration.property.as_str() {
+            // font-sizeのemと%は親の文字の大きさを基準にする
+            "font-size" => {
+                let parent_px = parent_style
+                    .map(|parent| parent.font_size().px())
+                    .unwrap_or(FontSize::Medium.px());
+                let base = LengthBase {
+                    em: parent_px,
+                    rem: parent_style
+                        .and_then(|parent| parent.root_font_size)
+                        .unwrap_or(parent_px),
+                    percentage: Some(parent_px),
+                };
+                if let Ok(font_size) = FontSize::from_component_values(&declaration.values, base) {
+                    self.set_font_size(font_size);
+                }
+            }
+            "font-weight" => {
+                if let Ok(font_weight) = FontWeight::from_component_value(&declaration.value) {
+                    self.set_font_weight(font_weight);
+                }
+            }
+            "font-style" => {
+                if let ComponentValue::Ident(value) = &declaration.value {
+                    if let Ok(font_style) = FontStyle::from_str(value) {
+                        self.set_font_style(font_style);
+                    }
+                }
+            }
+            // text-decoration: underline solid redのような指定では、先頭の線の種類だけを使う
+            "text-decoration" => {
+                if let ComponentValue::Ident(value) = &declaration.value {
+                    if let Ok(text_decoration) = TextDecoration::from_str(value) {
+                        self.set_text_decoration(text_decoration);
+                    }
+                }
+            }
+            "text-align" => {
+                if let ComponentValue::Ident(value) = &declaration.value {
+                    if let Ok(text_align) = TextAlign::from_str(value) {
+                        self.set_text_align(text_align);
+                    }
+                }
+            }
+            "white-space" => {
+                if let ComponentValue::Ident(value) = &declaration.value {
+                    if let Ok(white_space) = WhiteSpace::from_str(value) {
+                        self.set_white_space(white_space);
+                    }
+                }
+            }
+            "line-height" => {
+                // %は要素自身の文字の大きさを基準にする
+                let base = self.length_base(Some(self.font_size.unwrap_or(FontSize::Medium).px()));
+                if let Ok(line_height) =
+                    LineHeight::from_component_values(&declaration.values, base)
+                {
+                    self.set_line_height(line_height);
+                }
+            }
+            // 1つから4つの値で上下左右をまとめて指定する
+            // paddingとborder-widthは負の値を取らない
+            // %は包含ブロックの幅が基準だが、スタイルの計算時には分からないのでまだサポートしていない
+            // marginのautoは0として扱い、左右のautoはレイアウトの時に残りの幅で決める
+            "margin" | "padding" | "border-width" => {
+                let property = declaration.property.as_str();
+                let base = self.length_base(None);
+                if let Ok(values) = lengths_or_auto_from_component_values(&declaration.values, base)
+                {
+                    let lengths: Vec<i64> = values.iter().map(|v| v.unwrap_or(0)).collect();
+                    let allow_negative = property == "margin";
+                    if allow_negative || lengths.iter().all(|l| *l >= 0) {
+                        if let Some(edges) = EdgeSizes::from_lengths(&lengths) {
+                            self.set_edges(property, Some(edges));
+                            if allow_negative {
+                                let flags: Vec<bool> = values.iter().map(|v| v.is_none()).collect();
+                                self.margin_auto = AutoMargins::from_flags(&flags);
+                            }
+                        }
+                    }
+                }
+            }
+            // %は包含ブロックの大きさが基準なので、レイアウトの時に解決する
+            // max-widthだけはautoの代わりにnone（制限しない）を取る
+            "width" | "height" | "max-width" | "min-width" | "min-height" => {
+                let property = declaration.property.as_str();
+                let keyword = if property == "max-width" {
+                    "none"
+                } else {
+                    "auto"
+                };
+                let base = self.length_base(None);
+                if let Ok(dimension) =
+                    Dimension::from_component_values(&declaration.values, base, keyword)
+                {
+                    match property {
+                        "width" => self.set_width(dimension),
+                        "height" => self.set_height(dimension),
+                        "max-width" => self.set_max_width(dimension),
+                        "min-width" => self.set_min_width(dimension),
+                        _ => self.set_min_height(dimension),
+                    }
+                }
+            }
+            // 複数のフォントが指定されている場合は先頭のフォントを使う
+            "font-family" => match &declaration.value {
+                ComponentValue::Ident(family) | ComponentValue::StringToken(family) => {
+                    self.set_font_family(family.clone())
+                }
+                _ => {}
+            },
+            "background-color" => {
+                let color = match Color::from_component_value(&declaration.value) {
+                    Ok(color) => color,
+                    Err(_) => Color::white(),
+                };
+                self.set_background_color(color);
+            }
+            "color" => {
+                let color = match Color::from_component_value(&declaration.value) {
+                    Ok(color) => color,
+                    Err(_) => Color::black(),
+                };
+                self.set_color(color);
+            }
+            "position" => {
+                if let ComponentValue::Ident(value) = &declaration.value {
+                    if let Ok(position) = Position::from_str(value) {
+                        self.set_position(position);
+                    }
+                }
+            }
+            "z-index" => {
+                if let Ok(z_index) = ZIndex::from_component_value(&declaration.value) {
+                    self.set_z_index(z_index);
+                }
+            }
+            "overflow" => {
+                if let ComponentValue::Ident(value) = &declaration.value {
+                    if let Ok(overflow) = Overflow::from_str(value) {
+                        self.set_overflow(overflow);
+                    }
+                }
+            }
+            // 数字または%で指定し、0.0から1.0の範囲に丸める
+            "opacity" => {
+                let opacity = match declaration.values.as_slice() {
+                    [ComponentValue::Number(n)] => Some(*n),
+                    [ComponentValue::Number(n), ComponentValue::Delim('%')] => Some(*n / 100.0),
+                    _ => None,
+                };
+                if let Some(opacity) = opacity {
+                    self.set_opacity(opacity.clamp(0.0, 1.0));
+                }
+            }
+            // 角ごとの指定と楕円の指定はまだサポートしていないので、先頭の値を全ての角に使う
+            // %は要素の大きさが基準なので、まだサポートしていない
+            "border-radius" => {
+                let base = self.length_base(None);
+                if let Ok(lengths) = lengths_from_component_values(&declaration.values, base) {
+                    if let Some(radius) = lengths.first() {
+                        if *radius >= 0 {
+                            self.set_border_radius(*radius);
+                        }
+                    }
+                }
+            }
+            // 1つから4つの色で上下左右をまとめて指定する
+            "border-color" => {
+                let colors: Result<Vec<Color>, Error> = declaration
+                    .values
+                    .iter()
+                    .map(Color::from_component_value)
+                    .collect();
+                if let Some(border_color) = colors.ok().and_then(|c| EdgeColors::from_colors(&c)) {
+                    self.set_border_color(border_color);
+                }
+            }
+            // border: 1px solid redのように、太さ、線の種類、色を順不同で指定する
+            // 線の種類はnoneとhidden（線を描かない）以外は実線として扱う
+            "border" => self.apply_border_shorthand(&declaration.values),
+            "display" => {
+                if let ComponentValue::Ident(value) = &declaration.value {
+                    let display_type = match DisplayType::from_str(value) {
+                        Ok(display_type) => display_type,
+                        Err(_) => DisplayType::DisplayNone,
+                    };
+                    self.set_display(display_type)
+                }
+            }
+            property => {
+                if let Some((shorthand, side)) = split_edge_property(property) {
+                    let base = self.length_base(None);
+                    if let Ok(lengths) =
+                        lengths_or_auto_from_component_values(&declaration.values, base)
+                    {
+                        match lengths.as_slice() {
+                            [None] if shorthand == "margin" => {
+                                self.set_edge(shorthand, side, 0);
+                                self.margin_auto.set_side(side, true);
+                            }
+                            [Some(length)] if shorthand == "margin" || *length >= 0 => {
+                                self.set_edge(shorthand, side, *length)
+                            }
+                            _ => {}
+                        }
+                    }
+                } else if let Some(side) = border_color_side(property) {
+                    if let Ok(color) = Color::from_component_value(&declaration.value) {
+                        let mut border_color = self
+                            .border_color
+                            .clone()
+                            .unwrap_or_else(|| EdgeColors::all(self.current_color()));
+                        border_color.set_side(side, color);
+                        self.set_border_color(border_color);
+                    }
+                }
+            }
+        }
+    }
+
+    // borderの値を太さ、線の種類、色に分けて、4辺全てに適用する
+    // 指定されなかった値は初期値に戻る
+    fn apply_border_shorthand(&mut self, values: &[ComponentValue]) {
+        let mut width = None;
+        let mut line_style = None;
+        let mut color = None;
+        let mut iter = values.iter().peekable();
+        while let Some(value) = iter.next() {
+            match value {
+                ComponentValue::Number(_) => {
+                    // 単位が続く場合は、単位と合わせて1つの長さにする
+                    let mut length = alloc::vec![value.clone()];
+                    if let Some(ComponentValue::Ident(unit)) = iter.peek() {
+                        if matches!(unit.as_str(), "px" | "em" | "rem") {
+                            length.push(ComponentValue::Ident(unit.clone()));
+                            iter.next();
+                        }
+                    }
+                    match lengths_from_component_values(&length, self.length_base(None)) {
+                        Ok(lengths) if width.is_none() && lengths[0] >= 0 => {
+                            width = Some(lengths[0])
+                        }
+                        _ => return,
+                    }
+                }
+                ComponentValue::Ident(keyword)
+                    if BORDER_STYLES.contains(&keyword.as_str()) && line_style.is_none() =>
+                {
+                    line_style = Some(keyword.clone())
+                }
+                _ => match Color::from_component_value(value) {
+                    Ok(c) if color.is_none() => color = Some(c),
+                    _ => return,
+                },
+            }
+        }
+
+        // 線の種類が指定されていない場合の初期値はnoneだが、太さだけの指定も線を描くようにする
+        let width = match line_style.as_deref() {
+            Some("none") | Some("hidden") => 0,
+            _ => width.unwrap_or(BORDER_MEDIUM_WIDTH),
+        };
+        self.set_edges(
+            "border-width",
+            Some(EdgeSizes::new(width, width, width, width)),
+        );
+        self.border_color = color.map(EdgeColors::all);
+    }
+
+    // currentcolor（文字の色）
+    // 色がまだ決まっていない場合は初期値の黒にする
+    fn current_color(&self) -> Color {
+        self.color.clone().unwrap_or(Color::black())
+    }
+
+    // 値が指定されていないプロパティを埋める
+    // 1. 要素ごとのデフォルトの値（h1の文字の大きさなど）
+    // 2. 継承されるプロパティは親の値
+    // 3. それ以外は初期値
     pub fn defaulting(&mut self, node: &Rc<RefCell<Node>>, parent_style: Option<ComputedStyle>) {
-        // 親ノードが存在する場合
+        if self.display.is_none() {
+            self.display = Some(DisplayType::default(node));
+        }
+
+        // 親ノードが存在する場合、継承されるプロパティは親の値を使う
         if let Some(parent_style) = parent_style {
-            // CSSの値が初期値と異なる場合、初期値を継承する
-            // バックグラウンドカラーが初期値(白)ではない場合
-            if self.background_color.is_none() && parent_style.background_color() != Color::white()
-            {
-                self.background_color = Some(parent_style.background_color());
+            for property in INHERITED_PROPERTIES {
+                if !self.is_specified(property) {
+                    self.inherit_property(property, &parent_style);
+                }
             }
-            // 色が初期値(黒)ではない場合
-            if self.color.is_none() && parent_style.color() != Color::black() {
-                self.color = Some(parent_style.color());
+        }
+
+        // 各プロパティの初期値
+        for property in ALL_PROPERTIES {
+            if !self.is_specified(property) {
+                self.set_initial(property);
             }
-            // 文字の大きさが初期値(通常の大きさ)ではない場合
-            if self.font_size.is_none() && parent_style.font_size() != FontSize::Medium {
-                self.font_size = Some(parent_style.font_size());
+        }
+    }
+
+    // プロパティの値が既に決まっているか
+    fn is_specified(&self, property: &str) -> bool {
+        match property {
+            "background-color" => self.background_color.is_some(),
+            "color" => self.color.is_some(),
+            "display" => self.display.is_some(),
+            "font-size" => self.font_size.is_some(),
+            "text-decoration" => self.text_decoration.is_some(),
+            "font-weight" => self.font_weight.is_some(),
+            "font-style" => self.font_style.is_some(),
+            "font-family" => self.font_family.is_some(),
+            "text-align" => self.text_align.is_some(),
+            "white-space" => self.white_space.is_some(),
+            "line-height" => self.line_height.is_some(),
+            "margin" => self.margin.is_some(),
+            "padding" => self.padding.is_some(),
+            "border-width" => self.border_width.is_some(),
+            "border-color" => self.border_color.is_some(),
+            "height" => self.height.is_some(),
+            "width" => self.width.is_some(),
+            "max-width" => self.max_width.is_some(),
+            "min-width" => self.min_width.is_some(),
+            "min-height" => self.min_height.is_some(),
+            "position" => self.position.is_some(),
+            "z-index" => self.z_index.is_some(),
+            "overflow" => self.overflow.is_some(),
+            "border-radius" => self.border_radius.is_some(),
+            "opacity" => self.opacity.is_some(),
+            _ => true,
+        }
+    }
+
+    // 親の値を引き継ぐ（inheritキーワード、または継承されるプロパティの既定の動作）
+    pub fn inherit_property(&mut self, property: &str, parent_style: &ComputedStyle) {
+        match property {
+            "background-color" => self.background_color = parent_style.background_color.clone(),
+            "color" => self.color = parent_style.color.clone(),
+            "display" => self.display = parent_style.display,
+            "font-size" => self.font_size = parent_style.font_size,
+            "text-decoration" => self.text_decoration = parent_style.text_decoration,
+            "font-weight" => self.font_weight = parent_style.font_weight,
+            "font-style" => self.font_style = parent_style.font_style,
+            "font-family" => self.font_family = parent_style.font_family.clone(),
+            "text-align" => self.text_align = parent_style.text_align,
+            "white-space" => self.white_space = parent_style.white_space,
+            "line-height" => self.line_height = parent_style.line_height,
+            "border-color" => self.border_color = parent_style.border_color.clone(),
+            "margin" | "padding" | "border-width" => {
+                self.set_edges(property, parent_style.edges(property));
+                if property == "margin" {
+                    self.margin_auto = parent_style.margin_auto;
+                }
             }
-            if self.text_decoration.is_none()
-                && parent_style.text_decoration() != TextDecoration::None
-            {
-                self.text_decoration = Some(parent_style.text_decoration());
+            "height" => self.height = parent_style.height,
+            "width" => self.width = parent_style.width,
+            "max-width" => self.max_width = parent_style.max_width,
+            "min-width" => self.min_width = parent_style.min_width,
+            "min-height" => self.min_height = parent_style.min_height,
+            "position" => self.position = parent_style.position,
+            "z-index" => self.z_index = parent_style.z_index,
+            "overflow" => self.overflow = parent_style.overflow,
+            "border-radius" => self.border_radius = parent_style.border_radius,
+            "opacity" => self.opacity = parent_style.opacity,
+            property => {
+                if let Some((shorthand, side)) = split_edge_property(property) {
+                    let value = parent_style.edges(shorthand).unwrap_or_default().side(side);
+                    self.set_edge(shorthand, side, value);
+                    if shorthand == "margin" {
+                        self.margin_auto
+                            .set_side(side, parent_style.margin_auto.side(side));
+                    }
+                } else if let Some(side) = border_color_side(property) {
+                    let color = parent_style.border_color().side(side);
+                    let mut border_color = self
+                        .border_color
+                        .clone()
+                        .unwrap_or_else(|| EdgeColors::all(self.current_color()));
+                    border_color.set_side(side, color);
+                    self.set_border_color(border_color);
+                }
             }
         }
+    }
 
-        // 各プロパティの初期値
-        if self.background_color.is_none() {
-            self.background_color = Some(Color::white());
+    // CSSの仕様で決められた初期値にする（initialキーワード）
+    pub fn set_initial(&mut self, property: &str) {
+        match property {
+            "background-color" => self.background_color = Some(Color::transparent()),
+            "color" => self.color = Some(Color::black()),
+            "display" => self.display = Some(DisplayType::Inline),
+            "font-size" => self.font_size = Some(FontSize::Medium),
+            "text-decoration" => self.text_decoration = Some(TextDecoration::None),
+            "font-weight" => self.font_weight = Some(FontWeight::Normal),
+            "font-style" => self.font_style = Some(FontStyle::Normal),
+            "font-family" => self.font_family = Some(DEFAULT_FONT_FAMILY.to_string()),
+            "text-align" => self.text_align = Some(TextAlign::Left),
+            "white-space" => self.white_space = Some(WhiteSpace::Normal),
+            "line-height" => self.line_height = Some(LineHeight::Normal),
+            "margin" | "padding" | "border-width" => {
+                self.set_edges(property, Some(EdgeSizes::default()))
+            }
+            // 初期値はcurrentcolor（文字の色）
+            "border-color" => self.border_color = Some(EdgeColors::all(self.current_color())),
+            "height" => self.height = Some(Dimension::Auto),
+            "width" => self.width = Some(Dimension::Auto),
+            "max-width" => self.max_width = Some(Dimension::None),
+            "min-width" => self.min_width = Some(Dimension::Auto),
+            "min-height" => self.min_height = Some(Dimension::Auto),
+            "position" => self.position = Some(Position::Static),
+            "z-index" => self.z_index = Some(ZIndex::Auto),
+            "overflow" => self.overflow = Some(Overflow::Visible),
+            "border-radius" => self.border_radius = Some(0),
+            "opacity" => self.opacity = Some(1.0),
+            property => {
+                if let Some((shorthand, side)) = split_edge_property(property) {
+                    self.set_edge(shorthand, side, 0);
+                } else if let Some(side) = border_color_side(property) {
+                    let mut border_color = self
+                        .border_color
+                        .clone()
+                        .unwrap_or_else(|| EdgeColors::all(self.current_color()));
+                    border_color.set_side(side, self.current_color());
+                    self.set_border_color(border_color);
+                }
+            }
         }
-        if self.color.is_none() {
-            self.color = Some(Color::black());
+    }
+}
+
+// 継承されるプロパティ
+// 値が指定されていない場合、親の計算値を使う
+// https://www.w3.org/TR/css-cascade-4/#inheriting
+pub const INHERITED_PROPERTIES: [&str; 9] = [
+    "color",
+    "font-size",
+    "text-decoration",
+    "font-weight",
+    "font-style",
+    "font-family",
+    "text-align",
+    "white-space",
+    "line-height",
+];
+
+// ComputedStyleが持つ全てのプロパティ
+const ALL_PROPERTIES: [&str; 25] = [
+    "background-color",
+    "color",
+    "display",
+    "font-size",
+    "text-decoration",
+    "font-weight",
+    "font-style",
+    "font-family",
+    "text-align",
+    "white-space",
+    "line-height",
+    "margin",
+    "padding",
+    "border-width",
+    "border-color",
+    "height",
+    "width",
+    "max-width",
+    "min-width",
+    "min-height",
+    "position",
+    "z-index",
+    "overflow",
+    "border-radius",
+    "opacity",
+];
+
+// font-familyの初期値
+const DEFAULT_FONT_FAMILY: &str = "sans-serif";
+
+// プロパティが継承されるかどうか
+pub fn is_inherited_property(property: &str) -> bool {
+    INHERITED_PROPERTIES.contains(&property)
+}
+
+// margin-topのような1辺だけのプロパティを、まとめて指定するプロパティと辺に分ける
+fn split_edge_property(property: &str) -> Option<(&'static str, Side)> {
+    let (shorthand, side) = match property {
+        "margin-top" => ("margin", Side::Top),
+        "margin-right" => ("margin", Side::Right),
+        "margin-bottom" => ("margin", Side::Bottom),
+        "margin-left" => ("margin", Side::Left),
+        "padding-top" => ("padding", Side::Top),
+        "padding-right" => ("padding", Side::Right),
+        "padding-bottom" => ("padding", Side::Bottom),
+        "padding-left" => ("padding", Side::Left),
+        "border-top-width" => ("border-width", Side::Top),
+        "border-right-width" => ("border-width", Side::Right),
+        "border-bottom-width" => ("border-width", Side::Bottom),
+        "border-left-width" => ("border-width", Side::Left),
+        _ => return None,
+    };
+    Some((shorthand, side))
+}
+
+// border-top-colorのような1辺だけの色のプロパティの辺
+fn border_color_side(property: &str) -> Option<Side> {
+    match property {
+        "border-top-color" => Some(Side::Top),
+        "border-right-color" => Some(Side::Right),
+        "border-bottom-color" => Some(Side::Bottom),
+        "border-left-color" => Some(Side::Left),
+        _ => None,
+    }
+}
+
+// borderの線の種類のキーワード
+const BORDER_STYLES: [&str; 10] = [
+    "none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset", "outset",
+];
+
+// borderで太さが指定されていない場合の太さ（medium）
+const BORDER_MEDIUM_WIDTH: i64 = 3;
+
+// 相対的な長さの単位をピクセルに変換する時の基準の値（ピクセル）
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct LengthBase {
+    em: i64,
+    rem: i64,
+    // %の基準がないプロパティではNone
+    percentage: Option<i64>,
+}
+
+// 長さの値の並び（10px 1.5em 50% autoなど）をピクセルの値にする
+// 単位のない数字はpxとして扱い、autoは0として扱う
+fn lengths_from_component_values(
+    values: &[ComponentValue],
+    base: LengthBase,
+) -> Result<Vec<i64>, Error> {
+    Ok(lengths_or_auto_from_component_values(values, base)?
+        .into_iter()
+        .map(|length| length.unwrap_or(0))
+        .collect())
+}
+
+// 長さの値の並びをピクセルの値にする
+// autoはNoneとして残す
+fn lengths_or_auto_from_component_values(
+    values: &[ComponentValue],
+    base: LengthBase,
+) -> Result<Vec<Option<i64>>, Error> {
+    let mut lengths = Vec::new();
+    let mut iter = values.iter().peekable();
+    while let Some(value) = iter.next() {
+        match value {
+            ComponentValue::Number(n) => {
+                // 単位のない数字の後に続くautoなどのキーワードは、次の値として扱う
+                let px = match iter.peek() {
+                    Some(ComponentValue::Ident(unit)) if unit != "auto" => {
+                        let px = match unit.as_str() {
+                            "px" => *n,
+                            "em" => *n * base.em as f64,
+                            "rem" => *n * base.rem as f64,
+                            _ => {
+                                return Err(Error::UnexpectedInput(format!(
+                                    "unit {:?} is not supported yet",
+                                    unit
+                                )))
+                            }
+                        };
+                        iter.next();
+                        px
+                    }
+                    Some(ComponentValue::Delim('%')) => {
+                        let percentage = base.percentage.ok_or(Error::UnexpectedInput(
+                            "percentage is not supported for this property".to_string(),
+                        ))?;
+                        iter.next();
+                        *n * percentage as f64 / 100.0
+                    }
+                    _ => *n,
+                };
+                lengths.push(Some(px as i64));
+            }
+            ComponentValue::Ident(keyword) if keyword == "auto" => lengths.push(None),
+            _ => {
+                return Err(Error::UnexpectedInput(format!(
+                    "length {:?} is not supported yet",
+                    value
+                )))
+            }
         }
-        if self.display.is_none() {
-            self.display = Some(DisplayType::default(node));
+    }
+    Ok(lengths)
+}
+
+// 上下左右の辺
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Side {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+// margin、padding、border-widthの上下左右の4辺の大きさ（ピクセル）
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct EdgeSizes {
+    top: i64,
+    right: i64,
+    bottom: i64,
+    left: i64,
+}
+
+impl EdgeSizes {
+    pub fn new(top: i64, right: i64, bottom: i64, left: i64) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
         }
-        if self.font_size.is_none() {
-            self.font_size = Some(FontSize::default(node));
+    }
+
+    // 値の数によって上下左右に割り当てる
+    // 1つ: 全て、2つ: 上下と左右、3つ: 上と左右と下、4つ: 上、右、下、左
+    pub fn from_lengths(lengths: &[i64]) -> Option<Self> {
+        match *lengths {
+            [all] => Some(Self::new(all, all, all, all)),
+            [vertical, horizontal] => Some(Self::new(vertical, horizontal, vertical, horizontal)),
+            [top, horizontal, bottom] => Some(Self::new(top, horizontal, bottom, horizontal)),
+            [top, right, bottom, left] => Some(Self::new(top, right, bottom, left)),
+            _ => None,
+        }
+    }
+
+    pub fn top(&self) -> i64 {
+        self.top
+    }
+
+    pub fn right(&self) -> i64 {
+        self.right
+    }
+
+    pub fn bottom(&self) -> i64 {
+        self.bottom
+    }
+
+    pub fn left(&self) -> i64 {
+        self.left
+    }
+
+    pub fn side(&self, side: Side) -> i64 {
+        match side {
+            Side::Top => self.top,
+            Side::Right => self.right,
+            Side::Bottom => self.bottom,
+            Side::Left => self.left,
+        }
+    }
+
+    pub fn set_side(&mut self, side: Side, value: i64) {
+        match side {
+            Side::Top => self.top = value,
+            Side::Right => self.right = value,
+            Side::Bottom => self.bottom = value,
+            Side::Left => self.left = value,
+        }
+    }
+
+    // 左右の合計
+    pub fn horizontal(&self) -> i64 {
+        self.left + self.right
+    }
+
+    // 上下の合計
+    pub fn vertical(&self) -> i64 {
+        self.top + self.bottom
+    }
+}
+
+// ボーダーの上下左右の4辺の色
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeColors {
+    top: Color,
+    right: Color,
+    bottom: Color,
+    left: Color,
+}
+
+impl EdgeColors {
+    pub fn new(top: Color, right: Color, bottom: Color, left: Color) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
         }
-        if self.text_decoration.is_none() {
-            self.text_decoration = Some(TextDecoration::default(node));
+    }
+
+    pub fn all(color: Color) -> Self {
+        Self::new(color.clone(), color.clone(), color.clone(), color)
+    }
+
+    // 値の数によって上下左右に割り当てる（EdgeSizes::from_lengthsと同じ）
+    pub fn from_colors(colors: &[Color]) -> Option<Self> {
+        match colors {
+            [all] => Some(Self::all(all.clone())),
+            [vertical, horizontal] => Some(Self::new(
+                vertical.clone(),
+                horizontal.clone(),
+                vertical.clone(),
+                horizontal.clone(),
+            )),
+            [top, horizontal, bottom] => Some(Self::new(
+                top.clone(),
+                horizontal.clone(),
+                bottom.clone(),
+                horizontal.clone(),
+            )),
+            [top, right, bottom, left] => Some(Self::new(
+                top.clone(),
+                right.clone(),
+                bottom.clone(),
+                left.clone(),
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn top(&self) -> Color {
+        self.top.clone()
+    }
+
+    pub fn right(&self) -> Color {
+        self.right.clone()
+    }
+
+    pub fn bottom(&self) -> Color {
+        self.bottom.clone()
+    }
+
+    pub fn left(&self) -> Color {
+        self.left.clone()
+    }
+
+    pub fn side(&self, side: Side) -> Color {
+        match side {
+            Side::Top => self.top(),
+            Side::Right => self.right(),
+            Side::Bottom => self.bottom(),
+            Side::Left => self.left(),
+        }
+    }
+
+    pub fn set_side(&mut self, side: Side, color: Color) {
+        match side {
+            Side::Top => self.top = color,
+            Side::Right => self.right = color,
+            Side::Bottom => self.bottom = color,
+            Side::Left => self.left = color,
+        }
+    }
+}
+
+// 左右のマージンのautoの指定
+// 上下のautoは0と同じなので持たない
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct AutoMargins {
+    left: bool,
+    right: bool,
+}
+
+impl AutoMargins {
+    pub fn new(left: bool, right: bool) -> Self {
+        Self { left, right }
+    }
+
+    // marginの値の並びのうち、どれがautoかを左右に割り当てる
+    fn from_flags(flags: &[bool]) -> Self {
+        match *flags {
+            [all] => Self::new(all, all),
+            [_, horizontal] | [_, horizontal, _] => Self::new(horizontal, horizontal),
+            [_, right, _, left] => Self::new(left, right),
+            _ => Self::default(),
         }
-        if self.height.is_none() {
-            self.height = Some(0.0);
+    }
+
+    pub fn left(&self) -> bool {
+        self.left
+    }
+
+    pub fn right(&self) -> bool {
+        self.right
+    }
+
+    fn side(&self, side: Side) -> bool {
+        match side {
+            Side::Left => self.left,
+            Side::Right => self.right,
+            Side::Top | Side::Bottom => false,
         }
-        if self.width.is_none() {
-            self.width = Some(0.0);
+    }
+
+    fn set_side(&mut self, side: Side, auto: bool) {
+        match side {
+            Side::Left => self.left = auto,
+            Side::Right => self.right = auto,
+            Side::Top | Side::Bottom => {}
         }
     }
 }
 
 // CSSの色の値を表す構造体
-// 名前とカラーコードを表す値をフィールドに持つ
+// 名前とカラーコードを表す値、不透明度をフィールドに持つ
 #[derive(Debug, Clone, PartialEq)]
 pub struct Color {
     name: Option<String>,
     code: String,
+    // 不透明度 0.0（透明）から1.0（不透明）
+    alpha: f64,
 }
 
 // Task: 新しい色の実装
@@ -176,6 +1329,8 @@ impl Color {
             "aqua" => "#00ffff".to_string(),
             "orange" => "#ffa500".to_string(),
             "lightgray" => "#d3d3d3".to_string(),
+            "khaki" => "#f0e68c".to_string(),
+            "transparent" => return Ok(Self::transparent()),
             _ => {
                 return Err(Error::UnexpectedInput(format!(
                     "color name {:?} is not suppored yet",
@@ -187,20 +1342,109 @@ impl Color {
         Ok(Self {
             name: Some(name.to_string()),
             code,
+            alpha: 1.0,
         })
     }
 
     // color code -> name
+    // #rgbの3桁と#rrggbbの6桁の形式をサポートする
     pub fn from_code(code: &str) -> Result<Self, Error> {
-        // codeの0文字目が'#'ではない、または長さが7でなければエラー
-        if code.chars().nth(0) != Some('#') || code.len() != 7 {
-            return Err(Error::UnexpectedInput(format!(
-                "invalid color code {}",
-                code
-            )));
+        let hex = match code.strip_prefix('#') {
+            Some(hex) if hex.chars().all(|c| c.is_ascii_hexdigit()) => hex.to_lowercase(),
+            _ => {
+                return Err(Error::UnexpectedInput(format!(
+                    "invalid color code {}",
+                    code
+                )))
+            }
+        };
+
+        // 3桁の場合は各桁を2回繰り返す #f0a => #ff00aa
+        let code = match hex.len() {
+            3 => {
+                let mut expanded = String::from("#");
+                for c in hex.chars() {
+                    expanded.push(c);
+                    expanded.push(c);
+                }
+                expanded
+            }
+            6 => format!("#{}", hex),
+            _ => {
+                return Err(Error::UnexpectedInput(format!(
+                    "invalid color code {}",
+                    code
+                )))
+            }
+        };
+
+        Ok(Self {
+            name: Self::name_from_code(&code),
+            code,
+            alpha: 1.0,
+        })
+    }
+
+    // rgb(r, g, b)とrgba(r, g, b, a)の値から生成する
+    // 範囲外の値は0から255（不透明度は0.0から1.0）に丸める
+    pub fn from_rgba(r: f64, g: f64, b: f64, alpha: f64) -> Self {
+        let clamp = |v: f64| -> u32 {
+            if v < 0.0 {
+                0
+            } else if v > 255.0 {
+                255
+            } else {
+                (v + 0.5) as u32
+            }
+        };
+        let code = format!("#{:02x}{:02x}{:02x}", clamp(r), clamp(g), clamp(b));
+
+        Self {
+            name: Self::name_from_code(&code),
+            code,
+            alpha: alpha.clamp(0.0, 1.0),
         }
+    }
+
+    // CSSの値（色の名前、カラーコード、rgb()/rgba()関数）から生成する
+    pub fn from_component_value(value: &ComponentValue) -> Result<Self, Error> {
+        match value {
+            ComponentValue::Ident(name) => Self::from_name(name),
+            ComponentValue::HashToken(code) => Self::from_code(code),
+            ComponentValue::FunctionValue { name, arguments }
+                if name == "rgb" || name == "rgba" =>
+            {
+                // カンマなどの区切り文字は無視して数字だけを取り出す
+                let numbers: Vec<f64> = arguments
+                    .iter()
+                    .filter_map(|arg| match arg {
+                        ComponentValue::Number(n) => Some(*n),
+                        _ => None,
+                    })
+                    .collect();
 
-        let name = match code.to_lowercase().as_str() {
+                match numbers.len() {
+                    3 => Ok(Self::from_rgba(numbers[0], numbers[1], numbers[2], 1.0)),
+                    4 => Ok(Self::from_rgba(
+                        numbers[0], numbers[1], numbers[2], numbers[3],
+                    )),
+                    _ => Err(Error::UnexpectedInput(format!(
+                        "invalid arguments for {}(): {:?}",
+                        name, arguments
+                    ))),
+                }
+            }
+            _ => Err(Error::UnexpectedInput(format!(
+                "color value {:?} is not supported yet",
+                value
+            ))),
+        }
+    }
+
+    // カラーコードに対応する色の名前
+    // 名前のない色の場合はNone
+    fn name_from_code(code: &str) -> Option<String> {
+        let name = match code {
             "#000000" => "black",
             "#c0c0c0" => "silver",
             "#808080" => "gray",
@@ -219,18 +1463,10 @@ impl Color {
             "#00ffff" => "aqua",
             "#ffa500" => "orange",
             "#d3d3d3" => "lightgray",
-            _ => {
-                return Err(Error::UnexpectedInput(format!(
-                    "color code {} is not supported yet",
-                    code
-                )));
-            }
+            "#f0e68c" => "khaki",
+            _ => return None,
         };
-
-        Ok(Self {
-            name: Some(name.to_string()),
-            code: code.to_string(),
-        })
+        Some(name.to_string())
     }
 
     // whiteを表すColorオブジェクトを生成
@@ -238,6 +1474,17 @@ impl Color {
         Self {
             name: Some("white".to_string()),
             code: "#ffffff".to_string(),
+            alpha: 1.0,
+        }
+    }
+
+    // 透明な色（background-colorの初期値）
+    // 描画する時は不透明度が0の色は塗らない
+    pub fn transparent() -> Self {
+        Self {
+            name: Some("transparent".to_string()),
+            code: "#000000".to_string(),
+            alpha: 0.0,
         }
     }
 
@@ -246,9 +1493,14 @@ impl Color {
         Self {
             name: Some("black".to_string()),
             code: "#000000".to_string(),
+            alpha: 1.0,
         }
     }
 
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
     // color code を u32型で返す
     pub fn code_u32(&self) -> u32 {
         // '#'は取り除いてu32に変換
@@ -266,14 +1518,65 @@ pub enum FontSize {
 }
 
 impl FontSize {
-    fn default(node: &Rc<RefCell<Node>>) -> Self {
+    // キーワード（medium、x-largeなど）または長さから決める
+    // UIのフォントは3種類の大きさしかないので、長さは一番近い大きさにまとめる
+    fn from_component_values(values: &[ComponentValue], base: LengthBase) -> Result<Self, Error> {
+        if let [ComponentValue::Ident(keyword)] = values {
+            return match keyword.as_str() {
+                "xx-small" | "x-small" | "small" | "medium" | "large" => Ok(Self::Medium),
+                "x-large" => Ok(Self::XLarge),
+                "xx-large" | "xxx-large" => Ok(Self::XXLarge),
+                _ => Err(Error::UnexpectedInput(format!(
+                    "font-size {:?} is not supported yet",
+                    keyword
+                ))),
+            };
+        }
+
+        match lengths_from_component_values(values, base)?.as_slice() {
+            [px] if *px >= 0 => Ok(Self::from_px(*px)),
+            _ => Err(Error::UnexpectedInput(format!(
+                "font-size {:?} is not supported yet",
+                values
+            ))),
+        }
+    }
+
+    // ピクセルの値に一番近い大きさ
+    fn from_px(px: i64) -> Self {
+        if px < (Self::Medium.px() + Self::XLarge.px()) / 2 {
+            Self::Medium
+        } else if px < (Self::XLarge.px() + Self::XXLarge.px()) / 2 {
+            Self::XLarge
+        } else {
+            Self::XXLarge
+        }
+    }
+
+    // 文字の大きさ（ピクセル）
+    pub fn px(&self) -> i64 {
+        CHAR_HEIGHT * self.ratio()
+    }
+
+    // 通常の文字に対する大きさの倍率
+    pub fn ratio(&self) -> i64 {
+        match self {
+            FontSize::Medium => 1,
+            FontSize::XLarge => 2,
+            FontSize::XXLarge => 3,
+        }
+    }
+
+    // 要素ごとのデフォルトの文字の大きさ
+    // 決まっていない要素は親から継承する
+    fn default(node: &Rc<RefCell<Node>>) -> Option<Self> {
         match &node.borrow().kind() {
             NodeKind::Element(element) => match element.kind() {
-                ElementKind::H1 => FontSize::XXLarge,
-                ElementKind::H2 => FontSize::XLarge,
-                _ => FontSize::Medium,
+                ElementKind::H1 => Some(FontSize::XXLarge),
+                ElementKind::H2 => Some(FontSize::XLarge),
+                _ => None,
             },
-            _ => FontSize::Medium,
+            _ => None,
         }
     }
 }
@@ -283,6 +1586,7 @@ impl FontSize {
 pub enum DisplayType {
     Block,       // ブロック要素
     Inline,      // インライン要素
+    InlineBlock, // インライン要素として並び、中身はブロック要素として配置する
     DisplayNone, // 要素を非表示
 }
 
@@ -310,6 +1614,7 @@ impl FromStr for DisplayType {
         match s {
             "block" => Ok(Self::Block),
             "inline" => Ok(Self::Inline),
+            "inline-block" => Ok(Self::InlineBlock),
             "none" => Ok(Self::DisplayNone),
             _ => Err(Error::UnexpectedInput(format!(
                 "display {:?} is not supported yrt",
@@ -319,6 +1624,296 @@ impl FromStr for DisplayType {
     }
 }
 
+// CSSの font-weightプロパティに対応する値を表す列挙型
+// UIのフォントは通常と太字しかないので2種類にまとめる
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FontWeight {
+    Normal,
+    Bold,
+}
+
+impl FontWeight {
+    // normal、bold、または100から900の数字
+    // 600以上の数字は太字として扱う
+    fn from_component_value(value: &ComponentValue) -> Result<Self, Error> {
+        match value {
+            ComponentValue::Ident(value) => match value.as_str() {
+                "normal" | "lighter" => Ok(Self::Normal),
+                "bold" | "bolder" => Ok(Self::Bold),
+                _ => Err(Error::UnexpectedInput(format!(
+                    "font-weight {:?} is not supported yet",
+                    value
+                ))),
+            },
+            ComponentValue::Number(n) if *n >= 600.0 => Ok(Self::Bold),
+            ComponentValue::Number(_) => Ok(Self::Normal),
+            _ => Err(Error::UnexpectedInput(format!(
+                "font-weight {:?} is not supported yet",
+                value
+            ))),
+        }
+    }
+}
+
+// CSSの font-styleプロパティに対応する値を表す列挙型
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+}
+
+impl FromStr for FontStyle {
+    type Err = Error;
+
+    // obliqueは斜体と同じように扱う
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "italic" | "oblique" => Ok(Self::Italic),
+            _ => Err(Error::UnexpectedInput(format!(
+                "font-style {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+// CSSの text-alignプロパティに対応する値を表す列挙型
+// ブロック要素の中で行をどこに寄せるか
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+impl FromStr for TextAlign {
+    type Err = Error;
+
+    // 左から右に書く言語のみサポートしているので、startはleft、endはrightとして扱う
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" | "start" => Ok(Self::Left),
+            "center" => Ok(Self::Center),
+            "right" | "end" => Ok(Self::Right),
+            _ => Err(Error::UnexpectedInput(format!(
+                "text-align {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+impl TextAlign {
+    // 幅がwidthの行を、幅がcontainer_widthの領域に置く時の左端からのずれ
+    pub fn offset(&self, width: i64, container_width: i64) -> i64 {
+        let space = container_width - width;
+        if space <= 0 {
+            return 0;
+        }
+        match self {
+            Self::Left => 0,
+            Self::Center => space / 2,
+            Self::Right => space,
+        }
+    }
+}
+
+// CSSの positionプロパティに対応する値を表す列挙型
+// absoluteやfixedのような、通常の配置から外れる指定はまだサポートしていない
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Position {
+    Static,
+    Relative,
+}
+
+impl FromStr for Position {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "static" => Ok(Self::Static),
+            "relative" => Ok(Self::Relative),
+            _ => Err(Error::UnexpectedInput(format!(
+                "position {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+// CSSの z-indexプロパティに対応する値を表す列挙型
+// 位置指定された要素の重なりの順番を決める
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ZIndex {
+    // 新しい重ね合わせコンテキストを作らず、0として並べる
+    Auto,
+    // 重ね合わせコンテキストを作り、値の小さい順に描画する
+    Integer(i64),
+}
+
+impl ZIndex {
+    fn from_component_value(value: &ComponentValue) -> Result<Self, Error> {
+        match value {
+            ComponentValue::Ident(keyword) if keyword == "auto" => Ok(Self::Auto),
+            ComponentValue::Number(n) if *n == (*n as i64) as f64 => Ok(Self::Integer(*n as i64)),
+            _ => Err(Error::UnexpectedInput(format!(
+                "z-index {:?} is not supported yet",
+                value
+            ))),
+        }
+    }
+
+    // 描画の順番を決める値
+    pub fn order(&self) -> i64 {
+        match self {
+            Self::Auto => 0,
+            Self::Integer(n) => *n,
+        }
+    }
+}
+
+// CSSの overflowプロパティに対応する値を表す列挙型
+// スクロールバーはないので、scrollやautoはまだサポートしていない
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Overflow {
+    // はみ出した子孫もそのまま描画する
+    Visible,
+    // 子孫をパディングの外側の矩形で切り取る
+    Hidden,
+}
+
+impl FromStr for Overflow {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "visible" => Ok(Self::Visible),
+            "hidden" => Ok(Self::Hidden),
+            _ => Err(Error::UnexpectedInput(format!(
+                "overflow {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
+// CSSの line-heightプロパティに対応する値を表す列挙型
+// 1行の高さ（行ボックスの高さ）を決める
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LineHeight {
+    // 文字の高さに少し余白を足した高さ
+    Normal,
+    // 文字の大きさに対する倍率
+    // 継承した要素では、その要素の文字の大きさに掛ける
+    Number(f64),
+    // ピクセルで指定された高さ
+    Length(i64),
+}
+
+impl LineHeight {
+    // normal、数字、または長さ（10pxなど）
+    fn from_component_values(values: &[ComponentValue], base: LengthBase) -> Result<Self, Error> {
+        match values {
+            [ComponentValue::Ident(value)] if value == "normal" => Ok(Self::Normal),
+            [ComponentValue::Number(n)] if *n >= 0.0 => Ok(Self::Number(*n)),
+            _ => match lengths_from_component_values(values, base)?.as_slice() {
+                [length] if *length >= 0 => Ok(Self::Length(*length)),
+                _ => Err(Error::UnexpectedInput(format!(
+                    "line-height {:?} is not supported yet",
+                    values
+                ))),
+            },
+        }
+    }
+
+    // 文字の大きさがfont_sizeの時の1行の高さ（ピクセル）
+    pub fn to_px(&self, font_size: FontSize, font_metrics: &dyn FontMetrics) -> i64 {
+        match self {
+            Self::Normal => font_metrics.line_height(font_size),
+            Self::Number(n) => (font_size.px() as f64 * n) as i64,
+            Self::Length(length) => *length,
+        }
+    }
+}
+
+// CSSの width、height、max-width、min-width、min-heightプロパティに対応する値を表す列挙型
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Dimension {
+    // 中身や画像の大きさに合わせる（min-widthとmin-heightでは0）
+    Auto,
+    // 大きさを制限しない（max-widthのnone）
+    None,
+    // ピクセルで指定された大きさ
+    Length(i64),
+    // 包含ブロックの大きさに対する割合
+    Percentage(f64),
+}
+
+impl Dimension {
+    // キーワード（autoまたはnone）、負でない長さ（10pxなど）、または%
+    fn from_component_values(
+        values: &[ComponentValue],
+        base: LengthBase,
+        keyword: &str,
+    ) -> Result<Self, Error> {
+        match values {
+            [ComponentValue::Ident(value)] if value == keyword && keyword == "auto" => {
+                Ok(Self::Auto)
+            }
+            [ComponentValue::Ident(value)] if value == keyword && keyword == "none" => {
+                Ok(Self::None)
+            }
+            [ComponentValue::Number(n), ComponentValue::Delim('%')] if *n >= 0.0 => {
+                Ok(Self::Percentage(*n))
+            }
+            [ComponentValue::Ident(value)] if value == "auto" || value == "none" => Err(
+                Error::UnexpectedInput(format!("keyword {:?} is not allowed", value)),
+            ),
+            _ => match lengths_from_component_values(values, base)?.as_slice() {
+                [length] if *length >= 0 => Ok(Self::Length(*length)),
+                _ => Err(Error::UnexpectedInput(format!(
+                    "size {:?} is not supported yet",
+                    values
+                ))),
+            },
+        }
+    }
+
+    // ピクセルの値にする
+    // %の基準になる包含ブロックの大きさが分からない場合と、auto、noneはNoneを返す
+    pub fn to_px(&self, percentage_base: Option<i64>) -> Option<i64> {
+        match self {
+            Self::Auto | Self::None => None,
+            Self::Length(length) => Some(*length),
+            Self::Percentage(p) => percentage_base.map(|base| (base as f64 * p / 100.0) as i64),
+        }
+    }
+}
+
+// 空白と改行の扱い（CSSのwhite-space）
+// normalは連続する空白や改行を1つの空白にまとめて折り返し、preはそのまま表示して折り返さない
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WhiteSpace {
+    Normal,
+    Pre,
+}
+
+impl FromStr for WhiteSpace {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "pre" => Ok(Self::Pre),
+            _ => Err(Error::UnexpectedInput(format!(
+                "white-space {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
 // CSSの text-decorationプロパティに対応する値を表す列挙型
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum TextDecoration {
@@ -326,14 +1921,125 @@ pub enum TextDecoration {
     Underline, // テキストの下線
 }
 
-impl TextDecoration {
-    fn default(node: &Rc<RefCell<Node>>) -> Self {
-        match &node.borrow().kind() {
-            NodeKind::Element(element) => match element.kind() {
-                ElementKind::A => TextDecoration::Underline,
-                _ => TextDecoration::None,
-            },
-            _ => TextDecoration::None,
+impl FromStr for TextDecoration {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "underline" => Ok(Self::Underline),
+            _ => Err(Error::UnexpectedInput(format!(
+                "text-decoration {:?} is not supported yet",
+                s
+            ))),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::cssom::CssParser;
+    use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::dom::api::get_target_element_node;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    fn parse_color(value: &str) -> Result<Color, Error> {
+        let style = format!("p {{ color: {}; }}", value);
+        let cssom = CssParser::new(CssTokenizer::new(style)).parse_stylesheet();
+        Color::from_component_value(&cssom.rules[0].declarations[0].value)
+    }
+
+    // 3桁と6桁のカラーコード
+    #[test]
+    fn test_hex() {
+        let color = parse_color("#F00").expect("color should be valid");
+        assert_eq!("#ff0000", color.code);
+        assert_eq!(Some("red".to_string()), color.name);
+
+        let color = parse_color("#123abc").expect("color should be valid");
+        assert_eq!(0x123abc, color.code_u32());
+        assert_eq!(None, color.name);
+
+        assert!(parse_color("#12345").is_err());
+        assert!(parse_color("#xyz").is_err());
+    }
+
+    // rgb()とrgba()
+    #[test]
+    fn test_rgb() {
+        let color = parse_color("rgb(255, 0, 0)").expect("color should be valid");
+        assert_eq!(Color::from_name("red").expect("red should exist"), color);
+
+        let color = parse_color("rgba(0, 128, 300, 0.5)").expect("color should be valid");
+        assert_eq!("#0080ff", color.code);
+        assert_eq!(0.5, color.alpha());
+
+        assert!(parse_color("rgb(1, 2)").is_err());
+    }
+
+    // カスケードとデフォルト値の設定で計算値が決まるか
+    #[test]
+    fn test_resolve() {
+        let html = "<html><head></head><body><h1 class=\"a\">x</h1></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+        let h1 = get_target_element_node(Some(document), ElementKind::H1).expect("h1 should exist");
+        let styles = [
+            CssParser::new(CssTokenizer::new(".a { color: red; }".to_string())).parse_stylesheet(),
+        ];
+
+        let style = ComputedStyle::resolve(&h1, &styles, None);
+        assert_eq!(
+            Color::from_name("red").expect("red should exist"),
+            style.color()
+        );
+        assert_eq!(FontSize::XXLarge, style.font_size());
+        assert_eq!(DisplayType::Block, style.display());
+        assert_eq!(Color::transparent(), style.background_color());
+    }
+
+    // margin、padding、border-widthのまとめた指定と1辺だけの指定
+    #[test]
+    fn test_edge_sizes() {
+        let html = "<html><head></head><body><p>x</p></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+        let p = get_target_element_node(Some(document), ElementKind::P).expect("p should exist");
+        let css = "p { margin: 1px 2px 3px; margin-left: 4px; padding: 5px -1px; border-top-width: 6px; }";
+        let styles = [CssParser::new(CssTokenizer::new(css.to_string())).parse_stylesheet()];
+
+        let style = ComputedStyle::resolve(&p, &styles, None);
+        assert_eq!(EdgeSizes::new(1, 2, 3, 4), style.margin());
+        // 負の値のpaddingは無効
+        assert_eq!(EdgeSizes::default(), style.padding());
+        assert_eq!(EdgeSizes::new(6, 0, 0, 0), style.border_width());
+        assert_eq!(None, EdgeSizes::from_lengths(&[1, 2, 3, 4, 5]));
+    }
+
+    // em、rem、%がピクセルの値に変換されるか
+    #[test]
+    fn test_relative_length() {
+        let html = "<html><head></head><body><p>x<a>y</a></p></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+        let p = get_target_element_node(Some(document.clone()), ElementKind::P)
+            .expect("p should exist");
+        let a = get_target_element_node(Some(document), ElementKind::A).expect("a should exist");
+        let css =
+            "p { font-size: 200%; } a { margin: 1em 0.5rem; line-height: 150%; padding: 10%; }";
+        let styles = [CssParser::new(CssTokenizer::new(css.to_string())).parse_stylesheet()];
+
+        // 親がない場合は初期値の大きさが基準になる
+        let p_style = ComputedStyle::resolve(&p, &styles, None);
+        assert_eq!(FontSize::XLarge, p_style.font_size());
+
+        let a_style = ComputedStyle::resolve(&a, &styles, Some(p_style));
+        assert_eq!(FontSize::XLarge, a_style.font_size());
+        assert_eq!(EdgeSizes::new(32, 16, 32, 16), a_style.margin());
+        assert_eq!(LineHeight::Length(48), a_style.line_height());
+        // paddingの%はまだサポートしていない
+        assert_eq!(EdgeSizes::default(), a_style.padding());
+    }
+}
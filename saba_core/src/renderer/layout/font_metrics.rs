@@ -0,0 +1,35 @@
+use crate::constants::CHAR_HEIGHT_WITH_PADDING;
+use crate::constants::CHAR_WIDTH;
+use crate::renderer::layout::computed_style::FontSize;
+use core::fmt::Debug;
+
+// 文字の大きさごとのフォントの寸法
+// レイアウトはこのトレイトを通して文字の幅と行の高さを知る
+// UIは描画に使うフォントに合わせて実装する
+pub trait FontMetrics: Debug {
+    // 1文字の送り幅
+    fn char_width(&self, font_size: FontSize) -> i64;
+
+    // line-height: normalの時の1行の高さ
+    fn line_height(&self, font_size: FontSize) -> i64;
+
+    // 文字列を1行に並べた時の横幅
+    fn text_width(&self, text: &str, font_size: FontSize) -> i64 {
+        self.char_width(font_size) * text.chars().count() as i64
+    }
+}
+
+// 固定幅のビットマップフォントを想定したデフォルトの寸法
+// 通常の文字の大きさで1文字が8x16ピクセル
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultFontMetrics;
+
+impl FontMetrics for DefaultFontMetrics {
+    fn char_width(&self, font_size: FontSize) -> i64 {
+        CHAR_WIDTH * font_size.ratio()
+    }
+
+    fn line_height(&self, font_size: FontSize) -> i64 {
+        CHAR_HEIGHT_WITH_PADDING * font_size.ratio()
+    }
+}
@@ -1,22 +1,22 @@
-use crate::alloc::string::ToString;
-use crate::constants::CHAR_HEIGHT_WITH_PADDING;
-use crate::constants::CHAR_WIDTH;
-use crate::constants::CONTENT_AREA_WIDTH;
-use crate::renderer::css::cssom::ComponentValue;
-use crate::renderer::css::cssom::Declaration;
+use crate::display_item::DisplayItem;
 use crate::renderer::css::cssom::Selector;
 use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
 use crate::renderer::dom::node::NodeKind;
-use crate::renderer::layout::computed_style::Color;
 use crate::renderer::layout::computed_style::ComputedStyle;
 use crate::renderer::layout::computed_style::DisplayType;
-use crate::renderer::layout::computed_style::FontSize;
+use crate::renderer::layout::computed_style::EdgeSizes;
+use crate::renderer::layout::computed_style::Side;
+use crate::renderer::layout::computed_style::TextDecoration;
+use crate::renderer::layout::computed_style::WhiteSpace;
+use crate::renderer::layout::font_metrics::FontMetrics;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
+use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
 use core::cell::RefCell;
-use core::str::FromStr;
 
 // レイアウトツリーの一つのノードになり、描画に必要な情報を全て持った構造体
 #[derive(Debug, Clone)]
@@ -29,6 +29,13 @@ pub struct LayoutObject {
     style: ComputedStyle,
     point: LayoutPoint,
     size: LayoutSize,
+    // リストの項目（<li>）の先頭に付けるマーカー（"* "や"1. "）
+    marker: Option<String>,
+    // 左右のautoのマージンを、残りの幅から決めたもの
+    used_margin: Option<EdgeSizes>,
+    // 匿名ブロックボックスかどうか
+    // 匿名ブロックボックスは対応する要素を持たないので、nodeは親と同じノードになる
+    anonymous: bool,
 }
 
 impl LayoutObject {
@@ -47,13 +54,34 @@ impl LayoutObject {
             style: ComputedStyle::new(),
             point: LayoutPoint::new(0, 0),
             size: LayoutSize::new(0, 0),
+            marker: None,
+            used_margin: None,
+            anonymous: false,
         }
     }
 
+    // インライン要素とブロック要素が混ざった子ノードのうち、連続するインライン要素を包むブロックボックス
+    pub fn new_anonymous_block(parent: &Rc<RefCell<LayoutObject>>) -> Self {
+        let node = parent.borrow().node();
+        let style = ComputedStyle::anonymous_block(&node, &parent.borrow().style);
+        let mut object = Self::new(node, &Some(parent.clone()));
+        object.set_style(style);
+        object.anonymous = true;
+        object
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        self.anonymous
+    }
+
     pub fn kind(&self) -> LayoutObjectKind {
         self.kind
     }
 
+    pub fn node(&self) -> Rc<RefCell<Node>> {
+        self.node.clone()
+    }
+
     pub fn node_kind(&self) -> NodeKind {
         self.node.borrow().kind().clone()
     }
@@ -81,110 +109,80 @@ impl LayoutObject {
         self.parent.clone()
     }
 
+    pub fn set_parent(&mut self, parent: Weak<RefCell<Self>>) {
+        self.parent = parent;
+    }
+
     pub fn style(&self) -> ComputedStyle {
         self.style.clone()
     }
 
+    pub fn set_style(&mut self, style: ComputedStyle) {
+        self.style = style;
+    }
+
     pub fn point(&self) -> LayoutPoint {
         self.point
     }
 
+    pub fn set_point(&mut self, point: LayoutPoint) {
+        self.point = point;
+    }
+
     pub fn size(&self) -> LayoutSize {
         self.size
     }
 
-    // ノードがセレクタにマッチしているかを確認する
-    pub fn is_node_selected(&self, selector: &Selector) -> bool {
-        match &self.node_kind() {
-            NodeKind::Element(e) => match selector {
-                Selector::TypeSelector(type_name) => {
-                    if e.kind().to_string() == *type_name {
-                        return true;
-                    }
-                    false
-                }
-                Selector::ClassSelector(class_name) => {
-                    for attr in &e.attributes() {
-                        if attr.name() == "class" && attr.value() == *class_name {
-                            return true;
-                        }
-                    }
-                    false
-                }
-                Selector::IdSelector(id_name) => {
-                    for attr in &e.attributes() {
-                        if attr.name() == "id" && attr.value() == *id_name {
-                            return true;
-                        }
-                    }
-                    false
-                }
-                Selector::UnknownSelector => false,
-            },
-            _ => false,
+    // レイアウトに使う余白の大きさ
+    // インライン要素の上下の余白は行の高さに影響しないので0として扱う
+    fn used_edges(&self, edges: EdgeSizes) -> EdgeSizes {
+        match self.kind {
+            LayoutObjectKind::Block | LayoutObjectKind::InlineBlock | LayoutObjectKind::Image => {
+                edges
+            }
+            LayoutObjectKind::Inline => EdgeSizes::new(0, edges.right(), 0, edges.left()),
+            LayoutObjectKind::Text | LayoutObjectKind::LineBreak => EdgeSizes::default(),
         }
     }
 
-    // CSSの宣言を適用する
-    pub fn cascading_style(&mut self, declarations: Vec<Declaration>) {
-        for declaration in declarations {
-            match declaration.property.as_str() {
-                // 関数化できそう
-                "background-color" => {
-                    if let ComponentValue::Ident(value) = &declaration.value {
-                        let color = match Color::from_name(value) {
-                            Ok(color) => color,
-                            Err(_) => Color::white(),
-                        };
-                        self.style.set_background_color(color);
-                        continue;
-                    }
-                    if let ComponentValue::HashToken(color_code) = &declaration.value {
-                        let color = match Color::from_code(color_code) {
-                            Ok(color) => color,
-                            Err(_) => Color::white(),
-                        };
-                        self.style.set_background_color(color);
-                        continue;
-                    }
-                }
-                "color" => {
-                    if let ComponentValue::Ident(value) = &declaration.value {
-                        let color = match Color::from_name(value) {
-                            Ok(color) => color,
-                            Err(_) => Color::black(),
-                        };
-                        self.style.set_background_color(color);
-                    }
-                    if let ComponentValue::HashToken(color_code) = &declaration.value {
-                        let color = match Color::from_code(color_code) {
-                            Ok(color) => color,
-                            Err(_) => Color::black(),
-                        };
-                        self.style.set_color(color);
-                    }
-                }
-                "display" => {
-                    if let ComponentValue::Ident(value) = declaration.value {
-                        let display_type = match DisplayType::from_str(&value) {
-                            Ok(display_type) => display_type,
-                            Err(_) => DisplayType::DisplayNone,
-                        };
-                        self.style.set_display(display_type)
-                    }
-                }
-                _ => {}
-            }
-        }
+    pub fn margin(&self) -> EdgeSizes {
+        self.used_margin
+            .unwrap_or_else(|| self.used_edges(self.style.margin()))
     }
 
-    // 初期値を設定する
-    pub fn defaulting_style(
-        &mut self,
-        node: &Rc<RefCell<Node>>,
-        parent_style: Option<ComputedStyle>,
-    ) {
-        self.style.defaulting(node, parent_style);
+    pub fn padding(&self) -> EdgeSizes {
+        self.used_edges(self.style.padding())
+    }
+
+    pub fn border_width(&self) -> EdgeSizes {
+        self.used_edges(self.style.border_width())
+    }
+
+    // ボーダーの外側までの矩形
+    // pointとsizeはこの矩形の位置と大きさ
+    pub fn border_box(&self) -> LayoutRect {
+        LayoutRect::new(self.point, self.size)
+    }
+
+    // パディングの外側までの矩形
+    pub fn padding_box(&self) -> LayoutRect {
+        self.border_box().shrink(self.border_width())
+    }
+
+    // 子要素やテキストを配置する矩形
+    pub fn content_box(&self) -> LayoutRect {
+        self.padding_box().shrink(self.padding())
+    }
+
+    // マージンを含めた矩形
+    // 兄弟要素はこの矩形の外側に並ぶ
+    pub fn margin_box(&self) -> LayoutRect {
+        self.border_box().expand(self.margin())
+    }
+
+    // ノードがセレクタにマッチしているかを確認する
+    pub fn is_node_selected(&self, selector: &Selector) -> bool {
+        selector.matches(&self.node.borrow())
     }
 
     // LayoutObjectの種類を更新する
@@ -192,11 +190,22 @@ impl LayoutObject {
     pub fn update_kind(&mut self) {
         match self.node_kind() {
             NodeKind::Document => panic!("should not create a layout object for aDocumentnode"),
+            // <br>は行を終わらせる
+            NodeKind::Element(e) if e.kind() == ElementKind::Br => {
+                self.kind = LayoutObjectKind::LineBreak
+            }
+            // 画像と入力欄は中身を持たず、自身の大きさを持つインライン要素として扱う
+            NodeKind::Element(e)
+                if e.kind() == ElementKind::Img || e.kind() == ElementKind::Input =>
+            {
+                self.kind = LayoutObjectKind::Image
+            }
             NodeKind::Element(_) => {
                 let display = self.style.display();
                 match display {
                     DisplayType::Block => self.kind = LayoutObjectKind::Block,
                     DisplayType::Inline => self.kind = LayoutObjectKind::Inline,
+                    DisplayType::InlineBlock => self.kind = LayoutObjectKind::InlineBlock,
                     DisplayType::DisplayNone => {
                         panic!("should not create alayout object for display:none")
                     }
@@ -206,36 +215,125 @@ impl LayoutObject {
         }
     }
 
+    // リストの項目のマーカーを決める
+    // <ul>の中は記号、<ol>の中は何番目の<li>かを表す番号になる
+    pub fn update_marker(&mut self) {
+        if self.kind != LayoutObjectKind::Block
+            || self.node.borrow().element_kind() != Some(ElementKind::Li)
+        {
+            return;
+        }
+
+        let parent = match self.node.borrow().parent().upgrade() {
+            Some(p) => p,
+            None => return,
+        };
+        let list_kind = parent.borrow().element_kind();
+        self.marker = match list_kind {
+            // フォントがASCIIの文字しか持たないため、黒丸の代わりに*を使う
+            Some(ElementKind::Ul) => Some("* ".to_string()),
+            Some(ElementKind::Ol) => {
+                let mut index = 1;
+                let mut sibling = self.node.borrow().previous_sibling().upgrade();
+                while let Some(s) = sibling {
+                    if s.borrow().element_kind() == Some(ElementKind::Li) {
+                        index += 1;
+                    }
+                    sibling = s.borrow().previous_sibling().upgrade();
+                }
+                Some(alloc::format!("{}. ", index))
+            }
+            _ => None,
+        };
+    }
+
+    pub fn marker(&self) -> Option<String> {
+        self.marker.clone()
+    }
+
     // ノードのサイズを計算する
-    pub fn compute_size(&mut self, parent_size: LayoutSize) {
+    pub fn compute_size(&mut self, parent_size: LayoutSize, font_metrics: &dyn FontMetrics) {
         let mut size = LayoutSize::new(0, 0);
 
         match self.kind() {
             // ブロック要素の場合
-            // 横幅は親要素と同じ
-            LayoutObjectKind::Block => {
-                size.set_width(parent_size.width());
+            // 横幅は親要素のコンテンツの幅から左右のマージンを引いたもの
+            // inline-blockの要素は中身に合わせた横幅で、親要素の幅を超えない
+            // widthが指定されている場合はその値を中身の横幅にする
+            LayoutObjectKind::Block | LayoutObjectKind::InlineBlock => {
+                // autoのマージンを決める前の値（autoは0）
+                let mut margin = self.used_edges(self.style.margin());
+                let available_width = parent_size.width() - margin.horizontal();
+                let horizontal_edges =
+                    self.padding().horizontal() + self.border_width().horizontal();
+                let width = match self.style.width().to_px(Some(parent_size.width())) {
+                    Some(width) => width + horizontal_edges,
+                    None if self.kind == LayoutObjectKind::InlineBlock => {
+                        self.preferred_width(font_metrics).min(available_width)
+                    }
+                    None => available_width,
+                };
+                size.set_width(
+                    self.constrain_width(width - horizontal_edges, Some(parent_size.width()))
+                        + horizontal_edges,
+                );
+
+                // ブロック要素の左右のマージンがautoの場合、残りの幅を割り当てる
+                // 両方がautoの場合は半分ずつにして、中央に寄せる
+                if self.kind == LayoutObjectKind::Block {
+                    let margin_auto = self.style.margin_auto();
+                    let remaining = (available_width - size.width()).max(0);
+                    match (margin_auto.left(), margin_auto.right()) {
+                        (true, true) => {
+                            margin.set_side(Side::Left, margin.left() + remaining / 2);
+                            margin
+                                .set_side(Side::Right, margin.right() + remaining - remaining / 2);
+                        }
+                        (true, false) => margin.set_side(Side::Left, margin.left() + remaining),
+                        (false, true) => margin.set_side(Side::Right, margin.right() + remaining),
+                        (false, false) => {}
+                    }
+                    self.used_margin = Some(margin);
+                }
 
                 let mut height = 0;
+                // 連続するインライン要素が並ぶ行の高さ
+                // 行の中で一番高い要素に合わせる
+                let mut line_height = 0;
                 let mut child = self.first_child();
-                let mut previous_child_kind = LayoutObjectKind::Block;
                 while child.is_some() {
                     let c = match child {
                         Some(c) => c,
                         None => panic!("first child should exist"),
                     };
 
-                    if previous_child_kind == LayoutObjectKind::Block
-                        || c.borrow().kind() == LayoutObjectKind::Block
-                    {
-                        // 高さは子ノードの高さを足していく
-                        height += c.borrow().size.height();
+                    // 高さは子ノードのマージンを含めた高さを足していく
+                    let child_height = c.borrow().margin_box().size().height();
+                    let kind = c.borrow().kind();
+                    match kind {
+                        LayoutObjectKind::Block => {
+                            height += line_height + child_height;
+                            line_height = 0;
+                        }
+                        // 改行までを1行とする
+                        LayoutObjectKind::LineBreak => {
+                            height += line_height.max(child_height);
+                            line_height = 0;
+                        }
+                        _ => line_height = line_height.max(child_height),
                     }
 
-                    previous_child_kind = c.borrow().kind();
                     child = c.borrow().next_sibling();
                 }
-                size.set_height(height);
+                height += line_height;
+                if let Some(specified_height) = self.style.height().to_px(None) {
+                    height = specified_height;
+                }
+                size.set_height(
+                    self.constrain_height(height)
+                        + self.padding().vertical()
+                        + self.border_width().vertical(),
+                );
             }
             // インライン要素の場合
             LayoutObjectKind::Inline => {
@@ -248,40 +346,75 @@ impl LayoutObject {
                         None => panic!("first child should exist"),
                     };
 
-                    // 高さも横幅も子ノードのサイズを足していく
-                    width += c.borrow().size.width();
-                    height += c.borrow().size.height();
+                    // 横幅は子ノードのサイズを足していき、高さは一番高い子ノードに合わせる
+                    width += c.borrow().margin_box().size().width();
+                    height = height.max(c.borrow().size.height());
 
                     child = c.borrow().next_sibling();
                 }
 
-                size.set_width(width);
+                size.set_width(
+                    width + self.padding().horizontal() + self.border_width().horizontal(),
+                );
                 size.set_height(height);
             }
+            // 画像の場合
+            // 中身の大きさに、パディングとボーダーを足す
+            LayoutObjectKind::Image => {
+                let content = self.image_size(font_metrics, Some(parent_size.width()));
+                size.set_width(
+                    self.constrain_width(content.width(), Some(parent_size.width()))
+                        + self.padding().horizontal()
+                        + self.border_width().horizontal(),
+                );
+                size.set_height(
+                    self.constrain_height(content.height())
+                        + self.padding().vertical()
+                        + self.border_width().vertical(),
+                );
+            }
+            // 改行の場合
+            // 横幅はなく、高さは空の行の高さ
+            LayoutObjectKind::LineBreak => {
+                let font_size = self.style.font_size();
+                size.set_height(self.style.line_height().to_px(font_size, font_metrics));
+            }
             // テキストノードの場合
+            // parent_sizeは包含ブロック（テキストを含むブロック要素）のコンテンツのサイズ
             LayoutObjectKind::Text => {
                 if let NodeKind::Text(t) = self.node_kind() {
-                    let ratio = match self.style.font_size() {
-                        FontSize::Medium => 1,
-                        FontSize::XLarge => 2,
-                        FontSize::XXLarge => 3,
-                    };
-                    let width = CHAR_WIDTH * ratio * t.len() as i64;
-                    // ブラウザの描画エリアに収まるかどうかで場合分け
+                    let font_size = self.style.font_size();
+                    let line_height = self.style.line_height().to_px(font_size, font_metrics);
+                    // white-space: preの場合は折り返さず、改行の位置だけで行を分ける
+                    if self.style.white_space() == WhiteSpace::Pre {
+                        let lines = pre_lines(&t);
+                        let width = lines
+                            .iter()
+                            .map(|(line, _)| font_metrics.text_width(line, font_size))
+                            .max()
+                            .unwrap_or(0);
+                        size.set_width(width);
+                        size.set_height(line_height * lines.len() as i64);
+                        self.size = size;
+                        return;
+                    }
+                    let width = font_metrics.text_width(&t, font_size);
+                    let max_width = parent_size.width().max(1);
+                    // 包含ブロックの横幅に収まるかどうかで場合分け
                     // 収まらない場合は複数行にする
-                    if width > CONTENT_AREA_WIDTH {
+                    if width > max_width {
                         // テキストが複数行のとき
-                        size.set_width(CONTENT_AREA_WIDTH);
-                        let line_num = if width.wrapping_rem(CONTENT_AREA_WIDTH) == 0 {
-                            width.wrapping_div(CONTENT_AREA_WIDTH)
+                        size.set_width(max_width);
+                        let line_num = if width.wrapping_rem(max_width) == 0 {
+                            width.wrapping_div(max_width)
                         } else {
-                            width.wrapping_div(CONTENT_AREA_WIDTH) + 1 // 最後の行を考慮して1行追加
+                            width.wrapping_div(max_width) + 1 // 最後の行を考慮して1行追加
                         };
-                        size.set_height(CHAR_HEIGHT_WITH_PADDING * ratio * line_num);
+                        size.set_height(line_height * line_num);
                     } else {
                         // テキストが1行に収まるとき
                         size.set_width(width);
-                        size.set_height(CHAR_HEIGHT_WITH_PADDING * ratio);
+                        size.set_height(line_height);
                     }
                 }
             }
@@ -290,6 +423,424 @@ impl LayoutObject {
         self.size = size;
     }
 
+    // 中身を折り返さずに並べた時の横幅（ボーダーの外側まで）
+    // inline-blockの要素の横幅を中身に合わせて決めるために使う
+    fn preferred_width(&self, font_metrics: &dyn FontMetrics) -> i64 {
+        let edges = self.padding().horizontal() + self.border_width().horizontal();
+
+        if self.kind == LayoutObjectKind::Text {
+            let font_size = self.style.font_size();
+            return match self.node_kind() {
+                NodeKind::Text(t) if self.style.white_space() == WhiteSpace::Pre => pre_lines(&t)
+                    .iter()
+                    .map(|(line, _)| font_metrics.text_width(line, font_size))
+                    .max()
+                    .unwrap_or(0),
+                NodeKind::Text(t) => font_metrics.text_width(&t, font_size),
+                _ => 0,
+            };
+        }
+
+        if self.kind == LayoutObjectKind::Image {
+            let width = self.image_size(font_metrics, None).width();
+            return self.constrain_width(width, None) + edges;
+        }
+
+        // 連続するインライン要素を1行として、一番長い行の横幅にする
+        // インライン要素の場合は子要素を全て1行に並べる
+        let mut width = 0;
+        let mut line_width = 0;
+        let mut child = self.first_child();
+        while let Some(c) = child {
+            let child_width =
+                c.borrow().preferred_width(font_metrics) + c.borrow().margin().horizontal();
+            let kind = c.borrow().kind();
+            if (kind == LayoutObjectKind::Block || kind == LayoutObjectKind::LineBreak)
+                && self.kind != LayoutObjectKind::Inline
+            {
+                width = width.max(line_width).max(child_width);
+                line_width = 0;
+            } else {
+                line_width += child_width;
+            }
+            child = c.borrow().next_sibling();
+        }
+        width = width.max(line_width);
+
+        width + edges
+    }
+
+    // min-widthとmax-widthで中身の横幅を制限する
+    // 両方を満たせない場合はmin-widthを優先する
+    fn constrain_width(&self, width: i64, containing_width: Option<i64>) -> i64 {
+        let mut width = width;
+        if let Some(max_width) = self.style.max_width().to_px(containing_width) {
+            width = width.min(max_width);
+        }
+        if let Some(min_width) = self.style.min_width().to_px(containing_width) {
+            width = width.max(min_width);
+        }
+        width
+    }
+
+    // min-heightで中身の高さを制限する
+    // 包含ブロックの高さは中身から決まるので、%の指定は無視する
+    fn constrain_height(&self, height: i64) -> i64 {
+        match self.style.min_height().to_px(None) {
+            Some(min_height) => height.max(min_height),
+            None => height,
+        }
+    }
+
+    // 画像の中身の大きさ
+    // CSSのwidthとheight、次にwidthとheight属性の値を使う
+    // 指定がない場合は読み込んだ画像の大きさにし、片方だけの指定では縦横比を保つ
+    // 画像がない（まだ読み込んでいない、または読み込めなかった）場合は代わりに描画するテキストの大きさにする
+    fn image_size(
+        &self,
+        font_metrics: &dyn FontMetrics,
+        containing_width: Option<i64>,
+    ) -> LayoutSize {
+        let element = match self.node_kind() {
+            NodeKind::Element(e) => e,
+            _ => return LayoutSize::new(0, 0),
+        };
+        let font_size = self.style.font_size();
+        let alt = self.replaced_text();
+        let dimension = |css: Option<i64>, attribute: &str| match css {
+            Some(length) => Some(length),
+            None => element
+                .get_attribute(attribute)
+                .and_then(|value| value.trim().parse::<i64>().ok())
+                .filter(|value| *value >= 0),
+        };
+        let width = dimension(self.style.width().to_px(containing_width), "width");
+        let height = dimension(self.style.height().to_px(None), "height");
+
+        if let Some(image) = self.node.borrow().image() {
+            let (image_width, image_height) = (image.width(), image.height());
+            return match (width, height) {
+                (Some(w), Some(h)) => LayoutSize::new(w, h),
+                (Some(w), None) if image_width > 0 => {
+                    LayoutSize::new(w, w * image_height / image_width)
+                }
+                (None, Some(h)) if image_height > 0 => {
+                    LayoutSize::new(h * image_width / image_height, h)
+                }
+                _ => LayoutSize::new(width.unwrap_or(image_width), height.unwrap_or(image_height)),
+            };
+        }
+
+        let alt_height = if alt.is_empty() {
+            0
+        } else {
+            self.style.line_height().to_px(font_size, font_metrics)
+        };
+        LayoutSize::new(
+            width.unwrap_or(font_metrics.text_width(&alt, font_size)),
+            height.unwrap_or(alt_height),
+        )
+    }
+
+    // 画像の代わりに描画するテキスト
+    // <img>は代替テキスト（alt属性）、<input>は入力された値（value属性）か、なければplaceholder属性
+    // 値のない送信ボタンは"Submit"と表示する
+    fn replaced_text(&self) -> String {
+        let element = match self.node_kind() {
+            NodeKind::Element(e) => e,
+            _ => return String::new(),
+        };
+        if element.kind() == ElementKind::Img {
+            return element.get_attribute("alt").unwrap_or_default();
+        }
+        if let Some(value) = element.get_attribute("value") {
+            return value;
+        }
+        match element.get_attribute("type") {
+            Some(t) if t.eq_ignore_ascii_case("submit") => String::from("Submit"),
+            _ => element.get_attribute("placeholder").unwrap_or_default(),
+        }
+    }
+
+    // ボーダーの外側の角の丸みの半径
+    // 丸みが重ならないように、短い方の辺の半分までにする
+    fn border_radius(&self) -> i64 {
+        let size = self.border_box().size();
+        let max_radius = size.width().min(size.height()) / 2;
+        self.style.border_radius().min(max_radius).max(0)
+    }
+
+    // 描画する内容を作る
+    // ボーダーは中身より先に（奥に）描画する
+    pub fn paint(&mut self, font_metrics: &dyn FontMetrics) -> Vec<DisplayItem> {
+        if self.style.display() == DisplayType::DisplayNone {
+            return Vec::new();
+        }
+
+        let mut items = Vec::new();
+        let border_width = self.border_width();
+        let radius = self.border_radius();
+        // 背景はボーダーと中身より先に、パディングの外側まで塗る
+        // 透明な場合は何も描画しない
+        // パディングの外側の角の丸みは、ボーダーの太さの分だけ小さくなる
+        let background_color = self.style.background_color();
+        if background_color.alpha() > 0.0 {
+            let padding_box = self.padding_box();
+            items.push(DisplayItem::Rect {
+                layout_point: padding_box.point(),
+                size: padding_box.size(),
+                color: background_color,
+                radius: (radius - border_width.left()).max(0),
+            });
+        }
+
+        if border_width != EdgeSizes::default() {
+            let border_box = self.border_box();
+            items.push(DisplayItem::Border {
+                layout_point: border_box.point(),
+                size: border_box.size(),
+                widths: border_width,
+                colors: self.style.border_color(),
+                radius,
+            });
+        }
+        items.extend(self.paint_content(font_metrics));
+        items
+    }
+
+    fn paint_content(&mut self, font_metrics: &dyn FontMetrics) -> Vec<DisplayItem> {
+        match self.kind {
+            // マーカーはコンテンツの左側の余白（<ul>や<ol>のpadding）に、最初の行に揃えて描画する
+            LayoutObjectKind::Block => match self.marker() {
+                Some(marker) => {
+                    let font_size = self.style.font_size();
+                    let line_height = self.style.line_height().to_px(font_size, font_metrics);
+                    let half_leading = (line_height - font_metrics.line_height(font_size)) / 2;
+                    let content_point = self.content_box().point();
+                    let width = font_metrics.text_width(&marker, font_size);
+
+                    alloc::vec![DisplayItem::Text {
+                        text: marker,
+                        layout_point: LayoutPoint::new(
+                            content_point.x() - width,
+                            content_point.y() + half_leading,
+                        ),
+                        font_size: self.style.font_size(),
+                        font_weight: self.style.font_weight(),
+                        font_style: self.style.font_style(),
+                        font_family: self.style.font_family(),
+                        color: self.style.color(),
+                        text_decoration: self.style.text_decoration(),
+                    }]
+                }
+                None => Vec::new(),
+            },
+            // 画像を中身の大きさに合わせて描画する
+            // 画像がない場合は、代わりに代替テキストを描画する
+            LayoutObjectKind::Image => {
+                if let Some(bitmap) = self.node.borrow().image() {
+                    let content_box = self.content_box();
+                    return alloc::vec![DisplayItem::Image {
+                        layout_point: content_box.point(),
+                        size: content_box.size(),
+                        bitmap,
+                    }];
+                }
+
+                let alt = self.replaced_text();
+                if alt.is_empty() {
+                    return Vec::new();
+                }
+
+                let font_size = self.style.font_size();
+                let line_height = self.style.line_height().to_px(font_size, font_metrics);
+                let half_leading = (line_height - font_metrics.line_height(font_size)) / 2;
+                let content_point = self.content_box().point();
+                alloc::vec![DisplayItem::Text {
+                    text: alt,
+                    layout_point: LayoutPoint::new(
+                        content_point.x(),
+                        content_point.y() + half_leading
+                    ),
+                    font_size: self.style.font_size(),
+                    font_weight: self.style.font_weight(),
+                    font_style: self.style.font_style(),
+                    font_family: self.style.font_family(),
+                    color: self.style.color(),
+                    text_decoration: self.style.text_decoration(),
+                }]
+            }
+            LayoutObjectKind::Inline
+            | LayoutObjectKind::InlineBlock
+            | LayoutObjectKind::LineBreak => Vec::new(),
+            LayoutObjectKind::Text => {
+                let mut v = Vec::new();
+                let mut underlines = Vec::new();
+                let font_size = self.style.font_size();
+                for line in self.text_lines(font_metrics) {
+                    let point = line.point();
+                    // 下線は文字の下端に、文字と同じ色で引く
+                    if self.style.text_decoration() == TextDecoration::Underline {
+                        let width = font_metrics.text_width(line.text(), font_size);
+                        let y = point.y() + font_size.px();
+                        underlines.push(DisplayItem::Line {
+                            layout_point: LayoutPoint::new(point.x(), y),
+                            end_point: LayoutPoint::new(point.x() + width, y),
+                            width: 1,
+                            color: self.style.color(),
+                        });
+                    }
+                    v.push(DisplayItem::Text {
+                        text: line.text,
+                        layout_point: point,
+                        font_size: self.style.font_size(),
+                        font_weight: self.style.font_weight(),
+                        font_style: self.style.font_style(),
+                        font_family: self.style.font_family(),
+                        color: self.style.color(),
+                        text_decoration: self.style.text_decoration(),
+                    });
+                }
+                v.extend(underlines);
+                v
+            }
+        }
+    }
+
+    // テキストノードを折り返した各行の内容と位置
+    // 各文字がDOMのテキストの何文字目かも持つので、テキストの選択に使える
+    pub fn text_lines(&self, font_metrics: &dyn FontMetrics) -> Vec<TextLine> {
+        let t = match (self.kind, self.node_kind()) {
+            (LayoutObjectKind::Text, NodeKind::Text(t)) => t,
+            _ => return Vec::new(),
+        };
+        let font_size = self.style.font_size();
+        let line_height = self.style.line_height().to_px(font_size, font_metrics);
+        // 文字は行の高さの中で上下中央に置く
+        let half_leading = (line_height - font_metrics.line_height(font_size)) / 2;
+
+        // white-space: preの場合は空白や改行をまとめずに、改行の位置で行を分ける
+        let lines = if self.style.white_space() == WhiteSpace::Pre {
+            pre_lines(&t)
+        } else {
+            self.wrapped_lines(&t, font_metrics)
+        };
+
+        // 複数行の場合、1行ずつtext-alignに従って寄せる
+        // 1行の場合はLayoutViewで行全体の位置を決めている
+        let max_width = self.size.width();
+        let is_multiline = lines.len() > 1;
+        let mut result = Vec::new();
+        for (i, (line, offsets)) in lines.into_iter().enumerate() {
+            let width = font_metrics.text_width(&line, font_size);
+            let offset = if is_multiline {
+                self.style.text_align().offset(width, max_width)
+            } else {
+                0
+            };
+            let top = self.point().y() + line_height * i as i64;
+            result.push(TextLine {
+                text: line,
+                point: LayoutPoint::new(self.point().x() + offset, top + half_leading),
+                rect: LayoutRect::new(
+                    LayoutPoint::new(self.point().x() + offset, top),
+                    LayoutSize::new(width, line_height),
+                ),
+                offsets,
+            });
+        }
+        result
+    }
+
+    // 連続する空白や改行を1つの空白にまとめ、横幅に収まるように折り返した各行と、各文字のDOMのテキストでの位置
+    fn wrapped_lines(&self, t: &str, font_metrics: &dyn FontMetrics) -> Vec<(String, Vec<usize>)> {
+        // まとめた後の各文字が、元のテキストの何文字目かを覚えておく
+        let mut plain_text = String::new();
+        let mut offsets = Vec::new();
+        for (i, c) in t.chars().enumerate() {
+            if c == ' ' || c == '\n' {
+                if !plain_text.is_empty() && !plain_text.ends_with(' ') {
+                    plain_text.push(' ');
+                    offsets.push(i);
+                }
+                continue;
+            }
+            plain_text.push(c);
+            offsets.push(i);
+        }
+        if plain_text.ends_with(' ') {
+            plain_text.pop();
+            offsets.pop();
+        }
+
+        // 複数行のテキストの横幅は折り返す幅と同じ
+        let char_width = font_metrics.char_width(self.style.font_size());
+        let chars: Vec<char> = plain_text.chars().collect();
+        let mut start = 0;
+        let mut result = Vec::new();
+        for line in split_text(plain_text, char_width, self.size.width()) {
+            let end = (start + line.chars().count()).min(offsets.len());
+            let line_offsets = offsets[start..end].to_vec();
+            start = end;
+            // 折り返した位置の空白は、どちらの行にも含まれない
+            if chars.get(start) == Some(&' ') {
+                start += 1;
+            }
+            result.push((line, line_offsets));
+        }
+        result
+    }
+
+    // DOMのテキストのfrom文字目からto文字目の前までを、行ごとに囲む矩形
+    // テキストの選択範囲を強調表示するために使う
+    pub fn text_range_rects(
+        &self,
+        from: usize,
+        to: usize,
+        font_metrics: &dyn FontMetrics,
+    ) -> Vec<LayoutRect> {
+        let char_width = font_metrics.char_width(self.style.font_size());
+        self.text_lines(font_metrics)
+            .iter()
+            .filter_map(|line| {
+                let first = line.offsets().iter().position(|o| *o >= from)?;
+                let count = line.offsets()[first..]
+                    .iter()
+                    .take_while(|o| **o < to)
+                    .count() as i64;
+                if count == 0 {
+                    return None;
+                }
+                let rect = line.rect();
+                Some(LayoutRect::new(
+                    LayoutPoint::new(
+                        rect.point().x() + char_width * first as i64,
+                        rect.point().y(),
+                    ),
+                    LayoutSize::new(char_width * count, rect.size().height()),
+                ))
+            })
+            .collect()
+    }
+
+    // ページ上の点(x, y)に一番近い文字の境目が、DOMのテキストの何文字目かを返す
+    // 行の上下にはみ出している場合は最初と最後の行、左右にはみ出している場合は行の先頭と末尾にする
+    pub fn text_offset_at(&self, x: i64, y: i64, font_metrics: &dyn FontMetrics) -> Option<usize> {
+        let lines = self.text_lines(font_metrics);
+        let line = lines
+            .iter()
+            .find(|line| y < line.rect().point().y() + line.rect().size().height())
+            .or(lines.last())?;
+        let char_width = font_metrics.char_width(self.style.font_size()).max(1);
+        let index = ((x - line.rect().point().x() + char_width / 2) / char_width)
+            .clamp(0, line.offsets().len() as i64) as usize;
+        match line.offsets().get(index) {
+            Some(offset) => Some(*offset),
+            // 行の末尾は最後の文字の次
+            None => line.offsets().last().map(|offset| offset + 1),
+        }
+    }
+
     // 一つのノードの位置を計算するメソッド
     pub fn compute_position(
         &mut self,
@@ -300,49 +851,155 @@ impl LayoutObject {
     ) {
         let mut point = LayoutPoint::new(0, 0);
 
+        let margin = self.margin();
+
+        // parent_pointは親のコンテンツの位置、兄弟ノードの位置とサイズはマージンを含めた矩形
+        // 自身の位置はマージンの内側（ボーダーの外側）になる
         match (self.kind, previous_sibling_kind) {
             // ブロック要素が兄弟ノードの場合、Y軸方向に進む
-            (LayoutObjectKind::Block, _) | (_, LayoutObjectKind::Block) => {
+            // 改行の後も次の行の先頭に置く
+            // 上下のマージンの相殺はまだサポートしていない
+            (LayoutObjectKind::Block, _)
+            | (_, LayoutObjectKind::Block)
+            | (_, LayoutObjectKind::LineBreak) => {
                 if let (Some(size), Some(pos)) = (previous_sibling_size, previous_sibling_point) {
                     // 兄弟ノードのYの位置と高さを足し合わせた値を設定
-                    point.set_y(pos.y() + size.height());
+                    point.set_y(pos.y() + size.height() + margin.top());
                 } else {
                     // 親のY座標をセット
-                    point.set_y(parent_point.y());
+                    point.set_y(parent_point.y() + margin.top());
                 }
-                // X座標は常に親の値
-                point.set_x(parent_point.x());
+                // X座標は親の値
+                point.set_x(parent_point.x() + margin.left());
             }
-            // 対象と兄弟ノードがインライン要素の場合、X軸方向に進む
-            (LayoutObjectKind::Inline, LayoutObjectKind::Inline) => {
+            // 対象と兄弟ノードがインライン要素（テキスト、inline-block、画像を含む）の場合、X軸方向に進む
+            (
+                LayoutObjectKind::Inline
+                | LayoutObjectKind::Text
+                | LayoutObjectKind::InlineBlock
+                | LayoutObjectKind::LineBreak
+                | LayoutObjectKind::Image,
+                LayoutObjectKind::Inline
+                | LayoutObjectKind::Text
+                | LayoutObjectKind::InlineBlock
+                | LayoutObjectKind::Image,
+            ) => {
                 if let (Some(size), Some(pos)) = (previous_sibling_size, previous_sibling_point) {
                     // 兄弟ノードのX座標と横幅を足したものが次の位置に
-                    point.set_x(pos.x() + size.width());
+                    point.set_x(pos.x() + size.width() + margin.left());
                     // 兄弟ノードのY座標が、対象のY座標に
-                    point.set_y(pos.y());
+                    point.set_y(pos.y() + margin.top());
                 } else {
                     //兄弟ノードがなければ
                     // 親の座標をセット
-                    point.set_x(parent_point.x());
-                    point.set_y(parent_point.y());
+                    point.set_x(parent_point.x() + margin.left());
+                    point.set_y(parent_point.y() + margin.top());
                 }
             }
-            _ => {
-                point.set_x(parent_point.x());
-                point.set_y(parent_point.y());
-            }
         }
         self.point = point;
     }
 }
 
+// white-space: preのテキストを改行の位置で分けた各行と、各文字のDOMのテキストでの位置
+// 改行の前の\rは表示せず、タブは空白1つとして表示する
+fn pre_lines(t: &str) -> Vec<(String, Vec<usize>)> {
+    let mut lines = alloc::vec![(String::new(), Vec::new())];
+    for (i, c) in t.chars().enumerate() {
+        match c {
+            '\n' => lines.push((String::new(), Vec::new())),
+            '\r' => {}
+            c => {
+                if let Some((line, offsets)) = lines.last_mut() {
+                    line.push(if c == '\t' { ' ' } else { c });
+                    offsets.push(i);
+                }
+            }
+        }
+    }
+    // 最後の改行の後に何もない場合は、空の行を作らない
+    if lines.len() > 1 && lines.last().is_some_and(|(line, _)| line.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+// max_widthの横幅に収まるように、テキストを単語の区切りで複数行に分ける
+fn split_text(line: String, char_width: i64, max_width: i64) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    if line.chars().count() as i64 * char_width > max_width {
+        // 1文字も入らない幅でも、1行に1文字は置く
+        let max_index = (max_width / char_width).max(1) as usize;
+        // 何文字目で分けるかを、日本語などの複数バイトの文字の途中にならないようにバイトの位置にする
+        let index = find_index_for_line_break(line.clone(), max_index);
+        let index = line
+            .char_indices()
+            .nth(index)
+            .map(|(i, _)| i)
+            .unwrap_or(line.len());
+        let s = line.split_at(index);
+        result.push(s.0.to_string());
+        result.extend(split_text(s.1.trim().to_string(), char_width, max_width))
+    } else {
+        result.push(line);
+    }
+    result
+}
+
+// max_index以下で最後に出てくる空白の位置を返す
+// 空白がない場合は単語の途中で分ける
+fn find_index_for_line_break(line: String, max_index: usize) -> usize {
+    for i in (0..max_index).rev() {
+        if line.chars().collect::<Vec<char>>()[i] == ' ' {
+            return i;
+        }
+    }
+    max_index
+}
+
+// テキストを折り返した1行
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    text: String,
+    // 文字を描画する位置
+    point: LayoutPoint,
+    // 行の高さの矩形（選択範囲の強調表示に使う）
+    rect: LayoutRect,
+    // 行の各文字が、DOMのテキストの何文字目か
+    offsets: Vec<usize>,
+}
+
+impl TextLine {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn point(&self) -> LayoutPoint {
+        self.point
+    }
+
+    pub fn rect(&self) -> LayoutRect {
+        self.rect
+    }
+
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum LayoutObjectKind {
     // ブロック要素
     Block,
     // インライン要素
     Inline,
+    // インライン要素として並び、中身はブロック要素として配置する要素
+    InlineBlock,
     Text,
+    // 改行（<br>）。幅を持たず、その行を終わらせる
+    LineBreak,
+    // 画像（<img>）
+    Image,
 }
 
 // LayoutObjectオブジェクトの位置を表すデータ構造
@@ -375,6 +1032,88 @@ impl LayoutPoint {
     }
 }
 
+// 位置とサイズを合わせた矩形を表すデータ構造
+// ボックスモデルのコンテンツ、パディング、ボーダー、マージンの各領域を表す
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub struct LayoutRect {
+    point: LayoutPoint,
+    size: LayoutSize,
+}
+
+impl LayoutRect {
+    pub fn new(point: LayoutPoint, size: LayoutSize) -> Self {
+        Self { point, size }
+    }
+
+    pub fn point(&self) -> LayoutPoint {
+        self.point
+    }
+
+    pub fn size(&self) -> LayoutSize {
+        self.size
+    }
+
+    // 点(x, y)が矩形の中にあるか
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        x >= self.point.x()
+            && x < self.point.x() + self.size.width()
+            && y >= self.point.y()
+            && y < self.point.y() + self.size.height()
+    }
+
+    // 上下左右をedgesの分だけ内側に縮めた矩形
+    pub fn shrink(&self, edges: EdgeSizes) -> Self {
+        Self::new(
+            LayoutPoint::new(self.point.x() + edges.left(), self.point.y() + edges.top()),
+            LayoutSize::new(
+                (self.size.width() - edges.horizontal()).max(0),
+                (self.size.height() - edges.vertical()).max(0),
+            ),
+        )
+    }
+
+    // 両方の矩形を含む一番小さい矩形
+    pub fn union(&self, other: &LayoutRect) -> Self {
+        let left = self.point.x().min(other.point.x());
+        let top = self.point.y().min(other.point.y());
+        let right = (self.point.x() + self.size.width()).max(other.point.x() + other.size.width());
+        let bottom =
+            (self.point.y() + self.size.height()).max(other.point.y() + other.size.height());
+        Self::new(
+            LayoutPoint::new(left, top),
+            LayoutSize::new(right - left, bottom - top),
+        )
+    }
+
+    // 両方の矩形が重なっている部分の矩形
+    // 重なっていない場合はNone
+    pub fn intersection(&self, other: &LayoutRect) -> Option<Self> {
+        let left = self.point.x().max(other.point.x());
+        let top = self.point.y().max(other.point.y());
+        let right = (self.point.x() + self.size.width()).min(other.point.x() + other.size.width());
+        let bottom =
+            (self.point.y() + self.size.height()).min(other.point.y() + other.size.height());
+        if left >= right || top >= bottom {
+            return None;
+        }
+        Some(Self::new(
+            LayoutPoint::new(left, top),
+            LayoutSize::new(right - left, bottom - top),
+        ))
+    }
+
+    // 上下左右をedgesの分だけ外側に広げた矩形
+    pub fn expand(&self, edges: EdgeSizes) -> Self {
+        Self::new(
+            LayoutPoint::new(self.point.x() - edges.left(), self.point.y() - edges.top()),
+            LayoutSize::new(
+                self.size.width() + edges.horizontal(),
+                self.size.height() + edges.vertical(),
+            ),
+        )
+    }
+}
+
 // LayoutObjectオブジェクトのサイズを表すデータ構造
 // レイアウトツリーを構築する際に、各要素のサイズも計算する
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -408,25 +1147,17 @@ impl LayoutSize {
 pub fn create_layout_object(
     node: &Option<Rc<RefCell<Node>>>,
     parent_obj: &Option<Rc<RefCell<LayoutObject>>>,
-    cssom: &StyleSheet,
+    styles: &[StyleSheet],
 ) -> Option<Rc<RefCell<LayoutObject>>> {
     if let Some(n) = node {
         // LayoutObjectを生成する
         let layout_object = Rc::new(RefCell::new(LayoutObject::new(n.clone(), parent_obj)));
 
-        // CSSのルールをノードに適用する
-        for rule in &cssom.rules {
-            if layout_object.borrow().is_node_selected(&rule.selector) {
-                layout_object
-                    .borrow_mut()
-                    .cascading_style(rule.declarations.clone());
-            }
-        }
-
-        // 初期値を設定する
-        // 親のノードかデフォルトの値を使う
+        // カスケードとデフォルト値の設定を行い、計算値を決める
         let parent_style = parent_obj.as_ref().map(|parent| parent.borrow().style());
-        layout_object.borrow_mut().defaulting_style(n, parent_style);
+        layout_object
+            .borrow_mut()
+            .set_style(ComputedStyle::resolve(n, styles, parent_style));
 
         // display:noneの場合、nodeを返さない
         if layout_object.borrow().style().display() == DisplayType::DisplayNone {
@@ -435,6 +1166,7 @@ pub fn create_layout_object(
 
         // LayoutObjectの種類を更新する
         layout_object.borrow_mut().update_kind();
+        layout_object.borrow_mut().update_marker();
         return Some(layout_object);
     }
     None
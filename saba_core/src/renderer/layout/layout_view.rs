@@ -1,30 +1,36 @@
-use crate::constants::CONTENT_AREA_WIDTH;
+use crate::display_item::DisplayItem;
 use crate::renderer::css::cssom::StyleSheet;
 use crate::renderer::dom::api::get_target_element_node;
 use crate::renderer::dom::node::ElementKind;
 use crate::renderer::dom::node::Node;
+use crate::renderer::layout::computed_style::Overflow;
+use crate::renderer::layout::computed_style::TextAlign;
+use crate::renderer::layout::font_metrics::DefaultFontMetrics;
+use crate::renderer::layout::font_metrics::FontMetrics;
 use crate::renderer::layout::layout_object::create_layout_object;
 use crate::renderer::layout::layout_object::LayoutObject;
 use crate::renderer::layout::layout_object::LayoutObjectKind;
 use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutRect;
 use crate::renderer::layout::layout_object::LayoutSize;
 use alloc::rc::Rc;
+use alloc::vec::Vec;
 use core::cell::RefCell;
 
 fn build_layout_tree(
     node: &Option<Rc<RefCell<Node>>>,               // 現在のDOMノード
     parent_obj: &Option<Rc<RefCell<LayoutObject>>>, // 親のレイアウトオブジェクト
-    cssom: &StyleSheet,                             // CSSのスタイルシート
+    styles: &[StyleSheet],                          // CSSのスタイルシート（文書内の順番）
 ) -> Option<Rc<RefCell<LayoutObject>>> {
     // ノードとなるLayoutObjectの作成を試みる。CSSでdisplay:Noneならノードは作成されない
     let mut target_node = node.clone();
-    let mut layout_object = create_layout_object(node, parent_obj, cssom);
+    let mut layout_object = create_layout_object(node, parent_obj, styles);
 
     // ノードが作成されなかった時にDOMノードの兄弟のノードを走査する。
     while layout_object.is_none() {
         if let Some(n) = target_node {
             target_node = n.borrow().next_sibling().clone();
-            layout_object = create_layout_object(&target_node, parent_obj, cssom);
+            layout_object = create_layout_object(&target_node, parent_obj, styles);
         } else {
             // 兄弟ノードがない場合
             return layout_object;
@@ -34,8 +40,8 @@ fn build_layout_tree(
     if let Some(n) = target_node {
         let original_first_child = n.borrow().first_child();
         let original_next_sibling = n.borrow().next_sibling();
-        let mut first_child = build_layout_tree(&original_first_child, &layout_object, cssom);
-        let mut next_sibling = build_layout_tree(&original_next_sibling, &None, cssom);
+        let mut first_child = build_layout_tree(&original_first_child, &layout_object, styles);
+        let mut next_sibling = build_layout_tree(&original_next_sibling, parent_obj, styles);
 
         if first_child.is_none() && original_first_child.is_some() {
             let mut original_dom_node = original_first_child
@@ -43,7 +49,7 @@ fn build_layout_tree(
                 .borrow()
                 .next_sibling();
             loop {
-                first_child = build_layout_tree(&original_dom_node, &layout_object, cssom);
+                first_child = build_layout_tree(&original_dom_node, &layout_object, styles);
                 if first_child.is_none() && original_dom_node.is_some() {
                     original_dom_node = original_dom_node
                         .expect("next sibling should exist")
@@ -62,7 +68,7 @@ fn build_layout_tree(
                 .next_sibling();
 
             loop {
-                next_sibling = build_layout_tree(&original_dom_node, &None, cssom);
+                next_sibling = build_layout_tree(&original_dom_node, parent_obj, styles);
 
                 if next_sibling.is_none() && original_dom_node.is_some() {
                     original_dom_node = original_dom_node
@@ -85,18 +91,107 @@ fn build_layout_tree(
     layout_object
 }
 
+// ブロック要素とインライン要素が混ざった子ノードを持つブロック要素の場合、
+// 連続するインライン要素を匿名ブロックボックスで包み、子ノードを全てブロック要素にする
+// https://www.w3.org/TR/CSS2/visuren.html#anonymous-block-level
+fn wrap_inline_runs(node: &Option<Rc<RefCell<LayoutObject>>>) {
+    let n = match node {
+        Some(n) => n,
+        None => return,
+    };
+
+    let mut children = Vec::new();
+    let mut child = n.borrow().first_child();
+    while let Some(c) = child {
+        child = c.borrow().next_sibling();
+        children.push(c);
+    }
+
+    let kind = n.borrow().kind();
+    let is_container = kind == LayoutObjectKind::Block || kind == LayoutObjectKind::InlineBlock;
+    let is_block = |c: &Rc<RefCell<LayoutObject>>| c.borrow().kind() == LayoutObjectKind::Block;
+    if is_container && children.iter().any(is_block) && !children.iter().all(is_block) {
+        let mut wrapped = Vec::new();
+        let mut run = Vec::new();
+        for c in children {
+            if is_block(&c) {
+                if !run.is_empty() {
+                    wrapped.push(wrap_in_anonymous_block(n, run));
+                    run = Vec::new();
+                }
+                wrapped.push(c);
+            } else {
+                run.push(c);
+            }
+        }
+        if !run.is_empty() {
+            wrapped.push(wrap_in_anonymous_block(n, run));
+        }
+        link_children(n, &wrapped);
+    }
+
+    let mut child = n.borrow().first_child();
+    while let Some(c) = child {
+        wrap_inline_runs(&Some(c.clone()));
+        child = c.borrow().next_sibling();
+    }
+}
+
+fn wrap_in_anonymous_block(
+    parent: &Rc<RefCell<LayoutObject>>,
+    children: Vec<Rc<RefCell<LayoutObject>>>,
+) -> Rc<RefCell<LayoutObject>> {
+    let anonymous = Rc::new(RefCell::new(LayoutObject::new_anonymous_block(parent)));
+    for c in &children {
+        c.borrow_mut().set_parent(Rc::downgrade(&anonymous));
+    }
+    link_children(&anonymous, &children);
+    anonymous
+}
+
+// childrenを順番に兄弟ノードとしてつなぎ、parentの子ノードにする
+fn link_children(parent: &Rc<RefCell<LayoutObject>>, children: &[Rc<RefCell<LayoutObject>>]) {
+    for (i, c) in children.iter().enumerate() {
+        c.borrow_mut()
+            .set_next_sibling(children.get(i + 1).cloned());
+    }
+    parent
+        .borrow_mut()
+        .set_first_child(children.first().cloned());
+}
+
 #[derive(Debug, Clone)]
 pub struct LayoutView {
     root: Option<Rc<RefCell<LayoutObject>>>,
+    // ページを表示する領域（ビューポート）のサイズ
+    // ルートの要素の横幅はビューポートの横幅になる
+    viewport: LayoutSize,
+    // 文字の幅と行の高さを決めるフォントの寸法
+    font_metrics: Rc<dyn FontMetrics>,
 }
 
 impl LayoutView {
-    pub fn new(root: Rc<RefCell<Node>>, cssom: &StyleSheet) -> Self {
+    pub fn new(root: Rc<RefCell<Node>>, styles: &[StyleSheet], viewport: LayoutSize) -> Self {
+        Self::with_font_metrics(root, styles, viewport, Rc::new(DefaultFontMetrics))
+    }
+
+    // UIのフォントに合わせた寸法でレイアウトする
+    pub fn with_font_metrics(
+        root: Rc<RefCell<Node>>,
+        styles: &[StyleSheet],
+        viewport: LayoutSize,
+        font_metrics: Rc<dyn FontMetrics>,
+    ) -> Self {
         // レイアウトツリーは描画される要素だけを持つツリーなので、<body>タグを取得し、その子要素以下をレイアウトツリーのノードに変換する
         let body_root = get_target_element_node(Some(root), ElementKind::Body);
 
+        let root = build_layout_tree(&body_root, &None, styles);
+        wrap_inline_runs(&root);
+
         let mut tree = Self {
-            root: build_layout_tree(&body_root, &None, cssom),
+            root,
+            viewport,
+            font_metrics,
         };
 
         tree.update_layout();
@@ -106,7 +201,7 @@ impl LayoutView {
 
     // レイアウトツリーの各ノードのサイズと位置を計算する関数
     fn update_layout(&mut self) {
-        Self::calculate_node_size(&self.root, LayoutSize::new(CONTENT_AREA_WIDTH, 0));
+        Self::calculate_node_size(&self.root, self.viewport, self.font_metrics.as_ref());
 
         Self::calculate_node_position(
             &self.root,
@@ -115,26 +210,389 @@ impl LayoutView {
             None,
             None,
         );
+
+        Self::align_inline_content(&self.root);
+    }
+
+    // text-alignに従って、ブロック要素の中のインライン要素の並びを横方向に寄せる
+    fn align_inline_content(node: &Option<Rc<RefCell<LayoutObject>>>) {
+        if let Some(n) = node {
+            let kind = n.borrow().kind();
+            if kind == LayoutObjectKind::Block || kind == LayoutObjectKind::InlineBlock {
+                let text_align = n.borrow().style().text_align();
+                let container_width = n.borrow().content_box().size().width();
+
+                // ブロック要素で区切られた、連続するインライン要素を1行として扱う
+                let mut line = Vec::new();
+                let mut child = n.borrow().first_child();
+                while let Some(c) = child {
+                    let kind = c.borrow().kind();
+                    match kind {
+                        LayoutObjectKind::Block => {
+                            Self::align_line(&line, text_align, container_width);
+                            line.clear();
+                        }
+                        // 改行までを1行として寄せる
+                        LayoutObjectKind::LineBreak => {
+                            line.push(c.clone());
+                            Self::align_line(&line, text_align, container_width);
+                            line.clear();
+                        }
+                        _ => line.push(c.clone()),
+                    }
+                    child = c.borrow().next_sibling();
+                }
+                Self::align_line(&line, text_align, container_width);
+            }
+
+            let first_child = n.borrow().first_child();
+            Self::align_inline_content(&first_child);
+
+            let next_sibling = n.borrow().next_sibling();
+            Self::align_inline_content(&next_sibling);
+        }
+    }
+
+    fn align_line(line: &[Rc<RefCell<LayoutObject>>], text_align: TextAlign, container_width: i64) {
+        let (first, last) = match (line.first(), line.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return,
+        };
+
+        // 複数行にまたがる場合は、テキストの描画時に1行ずつ寄せる
+        let start = first.borrow().margin_box();
+        let end = last.borrow().margin_box();
+        if start.point().y() != end.point().y() {
+            return;
+        }
+        let width = end.point().x() + end.size().width() - start.point().x();
+        if width >= container_width {
+            return;
+        }
+
+        let offset = text_align.offset(width, container_width);
+        if offset == 0 {
+            return;
+        }
+        for node in line {
+            Self::shift_subtree(node, offset);
+        }
+    }
+
+    // ノードとその子孫をx方向にずらす
+    fn shift_subtree(node: &Rc<RefCell<LayoutObject>>, dx: i64) {
+        let point = node.borrow().point();
+        node.borrow_mut()
+            .set_point(LayoutPoint::new(point.x() + dx, point.y()));
+
+        let mut child = node.borrow().first_child();
+        while let Some(c) = child {
+            Self::shift_subtree(&c, dx);
+            child = c.borrow().next_sibling();
+        }
     }
 
     pub fn root(&self) -> Option<Rc<RefCell<LayoutObject>>> {
         self.root.clone()
     }
 
+    pub fn viewport(&self) -> LayoutSize {
+        self.viewport
+    }
+
+    // ビューポートのサイズを変えて、レイアウトをやり直す
+    pub fn set_viewport(&mut self, viewport: LayoutSize) {
+        self.viewport = viewport;
+        self.update_layout();
+    }
+
+    // ページ全体の高さ
+    // ビューポートより高い場合はスクロールが必要になる
+    pub fn content_height(&self) -> i64 {
+        match &self.root {
+            Some(root) => {
+                let margin_box = root.borrow().margin_box();
+                (margin_box.point().y() + margin_box.size().height()).max(0)
+            }
+            None => 0,
+        }
+    }
+
+    // ページ上の点(x, y)にあるレイアウトオブジェクトを返す
+    // 重なっている場合は、最も深い（手前に描画される）ものを選ぶ
+    // スクロールしている場合、呼び出し側で画面上の位置にスクロール量を足す
+    pub fn hit_test(&self, x: i64, y: i64) -> Option<Rc<RefCell<LayoutObject>>> {
+        Self::hit_test_node(&self.root, x, y)
+    }
+
+    // nodeとその後ろの兄弟ノードから探す
+    // 後ろの兄弟ノードほど後に描画されるので優先する
+    fn hit_test_node(
+        node: &Option<Rc<RefCell<LayoutObject>>>,
+        x: i64,
+        y: i64,
+    ) -> Option<Rc<RefCell<LayoutObject>>> {
+        let mut result = None;
+        let mut current = node.clone();
+        while let Some(n) = current {
+            let contains = n.borrow().border_box().contains(x, y);
+            // インライン要素の子孫（複数行のテキストなど）は、インライン要素の矩形の外にはみ出すことがある
+            if contains || n.borrow().kind() == LayoutObjectKind::Inline {
+                let first_child = n.borrow().first_child();
+                match Self::hit_test_node(&first_child, x, y) {
+                    Some(hit) => result = Some(hit),
+                    None if contains => result = Some(n.clone()),
+                    None => {}
+                }
+            }
+            current = n.borrow().next_sibling();
+        }
+        result
+    }
+
+    // DOMツリーのノードに対応するレイアウトオブジェクトを返す
+    // display: noneなどでレイアウトツリーにない場合はNone
+    pub fn find_layout_object(
+        &self,
+        node: &Rc<RefCell<Node>>,
+    ) -> Option<Rc<RefCell<LayoutObject>>> {
+        Self::find_layout_object_internal(&self.root, node)
+    }
+
+    // nodeのレイアウトオブジェクトとその子孫のボーダーの外側の矩形を全て含む矩形
+    // インライン要素の中身（テキスト）の範囲も含むので、リンクのフォーカスリングなどに使う
+    pub fn node_bounds(&self, node: &Rc<RefCell<Node>>) -> Option<LayoutRect> {
+        let layout_object = self.find_layout_object(node)?;
+        let bounds = layout_object.borrow().border_box();
+        let first_child = layout_object.borrow().first_child();
+        Some(Self::descendant_bounds(&first_child, bounds))
+    }
+
+    fn descendant_bounds(
+        layout_object: &Option<Rc<RefCell<LayoutObject>>>,
+        bounds: LayoutRect,
+    ) -> LayoutRect {
+        let mut bounds = bounds;
+        let mut current = layout_object.clone();
+        while let Some(o) = current {
+            bounds = bounds.union(&o.borrow().border_box());
+            let first_child = o.borrow().first_child();
+            bounds = Self::descendant_bounds(&first_child, bounds);
+            current = o.borrow().next_sibling();
+        }
+        bounds
+    }
+
+    fn find_layout_object_internal(
+        layout_object: &Option<Rc<RefCell<LayoutObject>>>,
+        node: &Rc<RefCell<Node>>,
+    ) -> Option<Rc<RefCell<LayoutObject>>> {
+        let mut current = layout_object.clone();
+        while let Some(o) = current {
+            if Rc::ptr_eq(&o.borrow().node(), node) {
+                return Some(o);
+            }
+            let first_child = o.borrow().first_child();
+            if let Some(found) = Self::find_layout_object_internal(&first_child, node) {
+                return Some(found);
+            }
+            current = o.borrow().next_sibling();
+        }
+        None
+    }
+
+    // レイアウトツリーを行きがけ順にたどり、描画する内容のリストを作る
+    // 位置指定された要素はz-indexの順番に並べ替える
+    pub fn paint(&self) -> Vec<DisplayItem> {
+        self.paint_in(None)
+    }
+
+    // scroll_yだけスクロールした時に、ビューポートに見えている部分だけの描画する内容を作る
+    // 位置はビューポートの左上を原点にする
+    // クリップと不透明度の操作は対応が崩れないように全て残す
+    pub fn paint_visible(&self, scroll_y: i64) -> Vec<DisplayItem> {
+        let visible = (scroll_y, scroll_y + self.viewport.height());
+        self.paint_in(Some(visible))
+            .into_iter()
+            .map(|item| item.translated(0, -scroll_y))
+            .filter(|item| match item {
+                DisplayItem::PushClip(_)
+                | DisplayItem::PopClip
+                | DisplayItem::PushOpacity(_)
+                | DisplayItem::PopOpacity => true,
+                item => item
+                    .layout_point()
+                    .is_some_and(|point| point.y() < self.viewport.height()),
+            })
+            .collect()
+    }
+
+    // ルートの重ね合わせコンテキストを描画する
+    fn paint_in(&self, visible: Option<(i64, i64)>) -> Vec<DisplayItem> {
+        let mut display_items = Vec::new();
+        let mut flow_items = Vec::new();
+        let mut layers = Vec::new();
+        Self::paint_node(
+            &self.root,
+            &mut flow_items,
+            &mut layers,
+            self.font_metrics.as_ref(),
+            visible,
+        );
+        Self::paint_layers(
+            &mut display_items,
+            flow_items,
+            layers,
+            self.font_metrics.as_ref(),
+            visible,
+        );
+        display_items
+    }
+
+    // 重ね合わせコンテキストの中身を、次の順番でdisplay_itemsに追加する
+    // 1. z-indexが負の位置指定された要素
+    // 2. 位置指定されていない要素（ツリーの順番）
+    // 3. z-indexがautoか0以上の位置指定された要素
+    // z-indexが同じ場合はツリーの順番になる
+    fn paint_layers(
+        display_items: &mut Vec<DisplayItem>,
+        flow_items: Vec<DisplayItem>,
+        mut layers: Vec<Rc<RefCell<LayoutObject>>>,
+        font_metrics: &dyn FontMetrics,
+        visible: Option<(i64, i64)>,
+    ) {
+        // 安定ソートなので、同じz-indexの要素はツリーの順番のまま
+        layers.sort_by_key(|layer| layer.borrow().style().z_index().order());
+        let (negative, positive): (Vec<_>, Vec<_>) = layers
+            .into_iter()
+            .partition(|layer| layer.borrow().style().z_index().order() < 0);
+
+        for layer in negative {
+            Self::paint_stacking_context(&layer, display_items, font_metrics, visible);
+        }
+        display_items.extend(flow_items);
+        for layer in positive {
+            Self::paint_stacking_context(&layer, display_items, font_metrics, visible);
+        }
+    }
+
+    // 位置指定された要素とその子孫を、1つの重ね合わせコンテキストとして描画する
+    // z-index: autoの要素の子孫も、このコンテキストの中で並べ替える
+    fn paint_stacking_context(
+        node: &Rc<RefCell<LayoutObject>>,
+        display_items: &mut Vec<DisplayItem>,
+        font_metrics: &dyn FontMetrics,
+        visible: Option<(i64, i64)>,
+    ) {
+        let mut flow_items = Vec::new();
+        let mut layers = Vec::new();
+        Self::paint_object(node, &mut flow_items, &mut layers, font_metrics, visible);
+        Self::paint_layers(display_items, flow_items, layers, font_metrics, visible);
+    }
+
+    // ノードと兄弟ノードを描画する
+    // 位置指定された要素は後で描画するためにlayersに集める
+    fn paint_node(
+        node: &Option<Rc<RefCell<LayoutObject>>>,
+        display_items: &mut Vec<DisplayItem>,
+        layers: &mut Vec<Rc<RefCell<LayoutObject>>>,
+        font_metrics: &dyn FontMetrics,
+        visible: Option<(i64, i64)>,
+    ) {
+        if let Some(n) = node {
+            if n.borrow().style().is_positioned() {
+                layers.push(n.clone());
+            } else {
+                Self::paint_object(n, display_items, layers, font_metrics, visible);
+            }
+
+            let next_sibling = n.borrow().next_sibling();
+            Self::paint_node(&next_sibling, display_items, layers, font_metrics, visible);
+        }
+    }
+
+    // ノード自身と子孫を描画する
+    // visibleは描画する範囲のy座標（上端, 下端）で、Noneの場合は全てを描画する
+    fn paint_object(
+        n: &Rc<RefCell<LayoutObject>>,
+        display_items: &mut Vec<DisplayItem>,
+        layers: &mut Vec<Rc<RefCell<LayoutObject>>>,
+        font_metrics: &dyn FontMetrics,
+        visible: Option<(i64, i64)>,
+    ) {
+        let border_box = n.borrow().border_box();
+        let top = border_box.point().y();
+        let bottom = top + border_box.size().height();
+        let is_visible = match visible {
+            Some((visible_top, visible_bottom)) => bottom > visible_top && top < visible_bottom,
+            None => true,
+        };
+
+        // opacityが1より小さい要素は、子孫も含めて1つのグループとして半透明にする
+        let opacity = n.borrow().style().opacity();
+        if opacity < 1.0 {
+            display_items.push(DisplayItem::PushOpacity(opacity));
+        }
+        if is_visible {
+            display_items.extend(n.borrow_mut().paint(font_metrics));
+        }
+
+        // ブロック要素の子孫はブロック要素の中に収まるので、見えない場合はまとめて飛ばす
+        let kind = n.borrow().kind();
+        let contains_children =
+            kind == LayoutObjectKind::Block || kind == LayoutObjectKind::InlineBlock;
+        if is_visible || !contains_children {
+            // overflow: hiddenの要素の子孫は、パディングの外側の矩形で切り取る
+            // 位置指定された子孫は後で別に描画するので切り取らない
+            let clip = match n.borrow().style().overflow() {
+                Overflow::Hidden => Some(n.borrow().padding_box()),
+                Overflow::Visible => None,
+            };
+            if let Some(rect) = clip {
+                display_items.push(DisplayItem::PushClip(rect));
+            }
+            let first_child = n.borrow().first_child();
+            Self::paint_node(&first_child, display_items, layers, font_metrics, visible);
+            if clip.is_some() {
+                display_items.push(DisplayItem::PopClip);
+            }
+        }
+        if opacity < 1.0 {
+            display_items.push(DisplayItem::PopOpacity);
+        }
+    }
+
     // レイアウトツリーの各ノードのサイズを再帰的に計算する関数
-    fn calculate_node_size(node: &Option<Rc<RefCell<LayoutObject>>>, parent_size: LayoutSize) {
+    fn calculate_node_size(
+        node: &Option<Rc<RefCell<LayoutObject>>>,
+        parent_size: LayoutSize,
+        font_metrics: &dyn FontMetrics,
+    ) {
         if let Some(n) = node {
-            if n.borrow().kind() == LayoutObjectKind::Block {
-                n.borrow_mut().compute_size(parent_size);
+            // ブロック要素とinline-blockの要素は、子ノードより先に横幅を決める
+            let kind = n.borrow().kind();
+            if kind == LayoutObjectKind::Block || kind == LayoutObjectKind::InlineBlock {
+                n.borrow_mut().compute_size(parent_size, font_metrics);
             }
 
+            // 子ノードは親のコンテンツの領域に配置される
+            // インライン要素の子ノードは、インライン要素を含むブロック要素の領域を使う
             let first_child = n.borrow().first_child();
-            Self::calculate_node_size(&first_child, n.borrow().size());
+            let content_size = match kind {
+                LayoutObjectKind::Block | LayoutObjectKind::InlineBlock => {
+                    n.borrow().content_box().size()
+                }
+                LayoutObjectKind::Inline
+                | LayoutObjectKind::Text
+                | LayoutObjectKind::LineBreak
+                | LayoutObjectKind::Image => parent_size,
+            };
+            Self::calculate_node_size(&first_child, content_size, font_metrics);
 
             let next_sibling = n.borrow().next_sibling();
-            Self::calculate_node_size(&next_sibling, parent_size);
+            Self::calculate_node_size(&next_sibling, parent_size, font_metrics);
 
-            n.borrow_mut().compute_size(parent_size);
+            n.borrow_mut().compute_size(parent_size, font_metrics);
         }
     }
 
@@ -157,22 +615,36 @@ impl LayoutView {
 
             // 再帰的にノードの子ノードの位置を計算する
             let first_child = n.borrow().first_child();
+            let content_point = n.borrow().content_box().point();
             Self::calculate_node_position(
                 &first_child,
-                n.borrow().point(),
+                content_point,
                 LayoutObjectKind::Block,
                 None,
                 None,
             );
 
             // 再帰的にノードの兄弟ノードの位置を計算する
+            // 兄弟ノードはマージンの外側に配置される
             let next_sibling = n.borrow().next_sibling();
+            let margin_box = n.borrow().margin_box();
+            let mut sibling_size = margin_box.size();
+            // 同じ行に並ぶインライン要素の中で一番高いものに合わせて、次の行やブロック要素を置く
+            // 改行の直後は新しい行なので、前の行の高さは含めない
+            if n.borrow().kind() != LayoutObjectKind::Block
+                && previous_sibling_kind != LayoutObjectKind::Block
+                && previous_sibling_kind != LayoutObjectKind::LineBreak
+            {
+                if let Some(size) = previous_sibling_size {
+                    sibling_size.set_height(sibling_size.height().max(size.height()));
+                }
+            }
             Self::calculate_node_position(
                 &next_sibling,
                 parent_point,
                 n.borrow().kind(),
-                Some(n.borrow().point()),
-                Some(n.borrow().size()),
+                Some(margin_box.point()),
+                Some(sibling_size),
             );
         }
     }
@@ -182,24 +654,45 @@ impl LayoutView {
 mod tests {
     use super::*;
     use crate::alloc::string::ToString;
+    use crate::constants::CHAR_HEIGHT;
+    use crate::constants::CHAR_HEIGHT_WITH_PADDING;
+    use crate::constants::CHAR_WIDTH;
+    use crate::constants::CONTENT_AREA_HEIGHT;
+    use crate::constants::CONTENT_AREA_WIDTH;
     use crate::renderer::css::cssom::CssParser;
     use crate::renderer::css::token::CssTokenizer;
-    use crate::renderer::dom::api::get_style_content;
+    use crate::renderer::css::ua_stylesheet::ua_stylesheet;
+    use crate::renderer::dom::api::get_style_contents;
     use crate::renderer::dom::node::Element;
     use crate::renderer::dom::node::NodeKind;
     use crate::renderer::html::parser::HtmlParser;
     use crate::renderer::html::token::HtmlTokenizer;
+    use crate::renderer::layout::computed_style::Color;
+    use crate::renderer::layout::computed_style::EdgeColors;
+    use crate::renderer::layout::computed_style::EdgeSizes;
+    use crate::renderer::layout::computed_style::FontSize;
+    use crate::renderer::layout::computed_style::FontStyle;
+    use crate::renderer::layout::computed_style::FontWeight;
+    use crate::renderer::layout::computed_style::TextDecoration;
+    use crate::renderer::layout::layout_object::LayoutRect;
     use alloc::string::String;
-    use alloc::vec::Vec;
+    use alloc::vec;
 
     fn create_layout_view(html: String) -> LayoutView {
         let t = HtmlTokenizer::new(html);
         let window = HtmlParser::new(t).construct_tree();
         let dom = window.borrow().document();
-        let style = get_style_content(dom.clone());
-        let css_tokenizer = CssTokenizer::new(style);
-        let cssom = CssParser::new(css_tokenizer).parse_stylesheet();
-        LayoutView::new(dom, &cssom)
+        let mut styles = vec![ua_stylesheet()];
+        styles.extend(
+            get_style_contents(dom.clone())
+                .into_iter()
+                .map(|style| CssParser::new(CssTokenizer::new(style)).parse_stylesheet()),
+        );
+        LayoutView::new(
+            dom,
+            &styles,
+            LayoutSize::new(CONTENT_AREA_WIDTH, CONTENT_AREA_HEIGHT),
+        )
     }
 
     // 空文字の場合
@@ -335,4 +828,814 @@ mod tests {
             .next_sibling()
             .is_none());
     }
+
+    // 詳細度の高いルールがソースコード上の順番に関わらず優先されるか
+    #[test]
+    fn test_specificity() {
+        let html = r#"<html>
+            <head>
+            <style>
+            #title { color: red; }
+            p { color: blue; }
+            p { color: green; }
+            </style>
+            </head>
+            <body><p id="title">a</p><p>b</p></body>
+            </html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let first = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("first p should exist");
+        assert_eq!(
+            Color::from_name("red").expect("red should exist"),
+            first.borrow().style().color()
+        );
+
+        // 詳細度が同じ場合は後に書かれたルールが優先される
+        let second = first
+            .borrow()
+            .next_sibling()
+            .expect("second p should exist");
+        assert_eq!(
+            Color::from_name("green").expect("green should exist"),
+            second.borrow().style().color()
+        );
+    }
+
+    // 複数の<style>にまたがってカスケードされるか
+    #[test]
+    fn test_multiple_style_blocks() {
+        let html = r#"<html>
+            <head>
+            <style>p { color: red; } #a { color: blue; }</style>
+            <style>p { color: green; }</style>
+            </head>
+            <body><p id="a">a</p><p>b</p></body>
+            </html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let first = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("first p should exist");
+        assert_eq!(
+            Color::from_name("blue").expect("blue should exist"),
+            first.borrow().style().color()
+        );
+
+        // 後の<style>のルールが優先される
+        let second = first
+            .borrow()
+            .next_sibling()
+            .expect("second p should exist");
+        assert_eq!(
+            Color::from_name("green").expect("green should exist"),
+            second.borrow().style().color()
+        );
+    }
+
+    // 継承されるプロパティとされないプロパティ、inherit/initialキーワード
+    #[test]
+    fn test_inheritance() {
+        let html = r#"<html>
+            <head>
+            <style>
+            body { color: red; background-color: blue; }
+            h1 { color: initial; background-color: inherit; }
+            </style>
+            </head>
+            <body><p>a</p><h1>b</h1></body>
+            </html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let p = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("p should exist");
+        // colorは継承され、background-colorは継承されない
+        assert_eq!(
+            Color::from_name("red").expect("red should exist"),
+            p.borrow().style().color()
+        );
+        assert_eq!(Color::transparent(), p.borrow().style().background_color());
+        let text = p.borrow().first_child().expect("text should exist");
+        assert_eq!(
+            Color::from_name("red").expect("red should exist"),
+            text.borrow().style().color()
+        );
+
+        let h1 = p.borrow().next_sibling().expect("h1 should exist");
+        assert_eq!(Color::black(), h1.borrow().style().color());
+        assert_eq!(
+            Color::from_name("blue").expect("blue should exist"),
+            h1.borrow().style().background_color()
+        );
+    }
+
+    // UAスタイルシートで<strong>が太字、<em>が斜体になり、描画内容に反映されるか
+    #[test]
+    fn test_font_weight_and_style() {
+        let html = r#"<html>
+            <head>
+            <style>p { font-family: serif; } em { font-weight: 700; }</style>
+            </head>
+            <body><p><strong>a</strong><em>b</em></p></body>
+            </html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let items = layout_view.paint();
+        assert_eq!(
+            vec![
+                DisplayItem::Text {
+                    text: "a".to_string(),
+                    layout_point: LayoutPoint::new(0, 0),
+                    font_size: FontSize::Medium,
+                    font_weight: FontWeight::Bold,
+                    font_style: FontStyle::Normal,
+                    font_family: "serif".to_string(),
+                    color: Color::black(),
+                    text_decoration: TextDecoration::None,
+                },
+                DisplayItem::Text {
+                    text: "b".to_string(),
+                    layout_point: LayoutPoint::new(8, 0),
+                    font_size: FontSize::Medium,
+                    font_weight: FontWeight::Bold,
+                    font_style: FontStyle::Italic,
+                    font_family: "serif".to_string(),
+                    color: Color::black(),
+                    text_decoration: TextDecoration::None,
+                },
+            ],
+            items
+        );
+    }
+
+    // text-alignで行が寄せられるか
+    #[test]
+    fn test_text_align() {
+        let html = r#"<html>
+            <head>
+            <style>#c { text-align: center; } #r { text-align: right; }</style>
+            </head>
+            <body><p id="c">abcd</p><p id="r">ab<a>cd</a></p><p>ab</p></body>
+            </html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let center = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("first p should exist");
+        let text = center.borrow().first_child().expect("text should exist");
+        assert_eq!((CONTENT_AREA_WIDTH - 32) / 2, text.borrow().point().x());
+
+        // インライン要素とその子孫もまとめて寄せられる
+        let right = center
+            .borrow()
+            .next_sibling()
+            .expect("second p should exist");
+        let text = right.borrow().first_child().expect("text should exist");
+        assert_eq!(CONTENT_AREA_WIDTH - 32, text.borrow().point().x());
+        let a = text.borrow().next_sibling().expect("a should exist");
+        let a_text = a.borrow().first_child().expect("text in a should exist");
+        assert_eq!(CONTENT_AREA_WIDTH - 16, a_text.borrow().point().x());
+
+        let left = right.borrow().next_sibling().expect("third p should exist");
+        let text = left.borrow().first_child().expect("text should exist");
+        assert_eq!(0, text.borrow().point().x());
+    }
+
+    // マージン、パディング、ボーダーの幅を考慮して位置とサイズが決まるか
+    #[test]
+    fn test_box_model() {
+        let html = r#"<html>
+            <head>
+            <style>#a { margin: 10px 20px; padding: 5px; border-width: 1px 2px 3px 4px; }</style>
+            </head>
+            <body><p id="a">ab</p><p>cd</p></body>
+            </html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let p = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("first p should exist");
+        assert_eq!(LayoutPoint::new(20, 10), p.borrow().point());
+        assert_eq!(
+            LayoutSize::new(CONTENT_AREA_WIDTH - 40, CHAR_HEIGHT_WITH_PADDING + 10 + 4),
+            p.borrow().size()
+        );
+        assert_eq!(LayoutPoint::new(29, 16), p.borrow().content_box().point());
+        assert_eq!(
+            LayoutSize::new(CONTENT_AREA_WIDTH - 40 - 16, CHAR_HEIGHT_WITH_PADDING),
+            p.borrow().content_box().size()
+        );
+
+        // テキストはコンテンツの領域に置かれる
+        let text = p.borrow().first_child().expect("text should exist");
+        assert_eq!(LayoutPoint::new(29, 16), text.borrow().point());
+
+        // 次の兄弟要素はマージンの外側に置かれる
+        let next = p.borrow().next_sibling().expect("second p should exist");
+        assert_eq!(
+            LayoutPoint::new(0, 10 + CHAR_HEIGHT_WITH_PADDING + 14 + 10),
+            next.borrow().point()
+        );
+    }
+
+    // line-heightに従って行の高さが決まり、次の要素がその下に置かれるか
+    #[test]
+    fn test_line_height() {
+        let html = r#"<html>
+            <head>
+            <style>#a { line-height: 2; } strong { line-height: 40px; }</style>
+            </head>
+            <body><p id="a">ab</p><p>ab<strong>cd</strong></p><p>ef</p></body>
+            </html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let first = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("first p should exist");
+        let text = first.borrow().first_child().expect("text should exist");
+        assert_eq!(CHAR_HEIGHT * 2, text.borrow().size().height());
+        assert_eq!(CHAR_HEIGHT * 2, first.borrow().size().height());
+
+        // 行の高さは一番高いインライン要素に合わせる
+        let second = first
+            .borrow()
+            .next_sibling()
+            .expect("second p should exist");
+        assert_eq!(CHAR_HEIGHT * 2, second.borrow().point().y());
+        assert_eq!(40, second.borrow().size().height());
+
+        let third = second
+            .borrow()
+            .next_sibling()
+            .expect("third p should exist");
+        assert_eq!(CHAR_HEIGHT * 2 + 40, third.borrow().point().y());
+
+        // 文字は行の中で上下中央に描画される
+        let items = layout_view.paint();
+        match &items[0] {
+            DisplayItem::Text { layout_point, .. } => assert_eq!(
+                LayoutPoint::new(0, (CHAR_HEIGHT * 2 - CHAR_HEIGHT_WITH_PADDING) / 2),
+                *layout_point
+            ),
+            item => panic!("unexpected display item {:?}", item),
+        }
+    }
+
+    // inline-blockの要素は横に並び、中身に合わせた大きさになるか
+    #[test]
+    fn test_inline_block() {
+        let html = r#"<html>
+            <head>
+            <style>#x { display: inline-block; padding: 2px; margin: 3px; }</style>
+            </head>
+            <body><p>ab<a id="x">cd</a>ef</p></body>
+            </html>"#
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let p = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("p should exist");
+        let text = p.borrow().first_child().expect("text should exist");
+        let a = text.borrow().next_sibling().expect("a should exist");
+        assert_eq!(LayoutObjectKind::InlineBlock, a.borrow().kind());
+        assert_eq!(LayoutPoint::new(16 + 3, 3), a.borrow().point());
+        assert_eq!(
+            LayoutSize::new(16 + 4, CHAR_HEIGHT_WITH_PADDING + 4),
+            a.borrow().size()
+        );
+
+        // 中身はパディングの内側に置かれる
+        let a_text = a.borrow().first_child().expect("text in a should exist");
+        assert_eq!(LayoutPoint::new(16 + 3 + 2, 3 + 2), a_text.borrow().point());
+
+        // 後ろのテキストはマージンの外側に並ぶ
+        let last = a.borrow().next_sibling().expect("last text should exist");
+        assert_eq!(LayoutPoint::new(16 + 3 + 20 + 3, 0), last.borrow().point());
+
+        // 行の高さはinline-blockの要素のマージンを含めた高さになる
+        assert_eq!(CHAR_HEIGHT_WITH_PADDING + 4 + 6, p.borrow().size().height());
+    }
+
+    // テスト用の寸法
+    // 1文字が10ピクセル、1行が12ピクセル
+    #[derive(Debug)]
+    struct TestFontMetrics;
+
+    impl FontMetrics for TestFontMetrics {
+        fn char_width(&self, font_size: FontSize) -> i64 {
+            10 * font_size.ratio()
+        }
+
+        fn line_height(&self, font_size: FontSize) -> i64 {
+            12 * font_size.ratio()
+        }
+    }
+
+    // フォントの寸法に従ってテキストのサイズと折り返しが決まるか
+    #[test]
+    fn test_font_metrics() {
+        let long_text = "abcde ".repeat(20);
+        let html = alloc::format!(
+            "<html><head></head><body><p>abc</p><p>{}</p></body></html>",
+            long_text
+        );
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = window.borrow().document();
+        let layout_view = LayoutView::with_font_metrics(
+            dom,
+            &[ua_stylesheet()],
+            LayoutSize::new(CONTENT_AREA_WIDTH, CONTENT_AREA_HEIGHT),
+            Rc::new(TestFontMetrics),
+        );
+
+        let p = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("p should exist");
+        let text = p.borrow().first_child().expect("text should exist");
+        assert_eq!(LayoutSize::new(30, 12), text.borrow().size());
+
+        // 120文字（1200ピクセル）は描画エリアに収まらないので3行になる
+        let long = p.borrow().next_sibling().expect("second p should exist");
+        let long_text = long.borrow().first_child().expect("text should exist");
+        assert_eq!(
+            LayoutSize::new(CONTENT_AREA_WIDTH, 36),
+            long_text.borrow().size()
+        );
+        assert_eq!(12, long.borrow().point().y());
+
+        let items = layout_view.paint();
+        assert_eq!(4, items.len());
+        match &items[2] {
+            DisplayItem::Text { layout_point, .. } => {
+                assert_eq!(LayoutPoint::new(0, 24), *layout_point)
+            }
+            item => panic!("unexpected display item {:?}", item),
+        }
+    }
+
+    // 点の位置にある一番深いレイアウトオブジェクトが見つかるか
+    #[test]
+    fn test_hit_test() {
+        let html = "<html><head></head><body><p>ab<a>cd</a></p><p>ef</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let hit = layout_view
+            .hit_test(20, 5)
+            .expect("text in a should be hit");
+        assert_eq!(NodeKind::Text("cd".to_string()), hit.borrow().node_kind());
+
+        // テキストのない場所は、それを含むブロック要素
+        let hit = layout_view.hit_test(100, 5).expect("p should be hit");
+        assert_eq!(
+            NodeKind::Element(Element::new("p", Vec::new())),
+            hit.borrow().node_kind()
+        );
+
+        let hit = layout_view
+            .hit_test(0, 25)
+            .expect("second text should be hit");
+        assert_eq!(NodeKind::Text("ef".to_string()), hit.borrow().node_kind());
+
+        assert!(layout_view.hit_test(0, 1000).is_none());
+    }
+
+    #[test]
+    fn test_line_break() {
+        // <br>の後のテキストは次の行の先頭に置かれる
+        let html = "<html><head></head><body><p>ab<br>cd</p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let p = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("p should exist");
+        assert_eq!(2 * CHAR_HEIGHT_WITH_PADDING, p.borrow().size().height());
+
+        let ab = p.borrow().first_child().expect("text should exist");
+        let br = ab.borrow().next_sibling().expect("br should exist");
+        assert_eq!(LayoutObjectKind::LineBreak, br.borrow().kind());
+        assert_eq!(0, br.borrow().size().width());
+        assert!(br.borrow_mut().paint(&DefaultFontMetrics).is_empty());
+
+        let cd = br.borrow().next_sibling().expect("text should exist");
+        assert_eq!(
+            LayoutPoint::new(0, CHAR_HEIGHT_WITH_PADDING),
+            cd.borrow().point()
+        );
+    }
+
+    #[test]
+    fn test_image() {
+        // 画像の大きさはCSS、width・height属性、代替テキストの順に決まる
+        let html = "<html><head><style>.wide { width: 50px; }</style></head><body><p>a<img width=\"30\" height=\"40\" alt=\"x\">b</p><p><img class=\"wide\" width=\"30\" height=\"40\"><img alt=\"cat\"></p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let p1 = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("p should exist");
+        assert_eq!(40, p1.borrow().size().height());
+        let a = p1.borrow().first_child().expect("text should exist");
+        let img = a.borrow().next_sibling().expect("img should exist");
+        assert_eq!(LayoutObjectKind::Image, img.borrow().kind());
+        assert_eq!(LayoutPoint::new(CHAR_WIDTH, 0), img.borrow().point());
+        assert_eq!(LayoutSize::new(30, 40), img.borrow().size());
+        let b = img.borrow().next_sibling().expect("text should exist");
+        assert_eq!(LayoutPoint::new(CHAR_WIDTH + 30, 0), b.borrow().point());
+
+        let p2 = p1.borrow().next_sibling().expect("p should exist");
+        let wide = p2.borrow().first_child().expect("img should exist");
+        assert_eq!(LayoutSize::new(50, 40), wide.borrow().size());
+        assert!(wide.borrow_mut().paint(&DefaultFontMetrics).is_empty());
+
+        // 画像のデータがないので、代替テキストが描画される
+        let alt = wide.borrow().next_sibling().expect("img should exist");
+        assert_eq!(
+            LayoutSize::new(3 * CHAR_WIDTH, CHAR_HEIGHT_WITH_PADDING),
+            alt.borrow().size()
+        );
+        let items = alt.borrow_mut().paint(&DefaultFontMetrics);
+        assert_eq!(1, items.len());
+        match &items[0] {
+            DisplayItem::Text {
+                text, layout_point, ..
+            } => {
+                assert_eq!("cat", text);
+                assert_eq!(LayoutPoint::new(50, 40), *layout_point);
+            }
+            item => panic!("unexpected display item {:?}", item),
+        }
+    }
+
+    #[test]
+    fn test_size_constraints() {
+        // max-width、min-width、min-heightで大きさが制限される
+        let html = "<html><head><style>p { max-width: 100px; min-height: 50px; } img { max-width: 50%; } a { display: inline-block; min-width: 30px; }</style></head><body><p>x</p><p><img width=\"1000\" height=\"10\"></p><p><a>y</a></p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let p1 = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("p should exist");
+        assert_eq!(LayoutSize::new(100, 50), p1.borrow().size());
+
+        // %は包含ブロックの横幅が基準になる
+        let p2 = p1.borrow().next_sibling().expect("p should exist");
+        let img = p2.borrow().first_child().expect("img should exist");
+        assert_eq!(LayoutSize::new(50, 10), img.borrow().size());
+
+        let p3 = p2.borrow().next_sibling().expect("p should exist");
+        let a = p3.borrow().first_child().expect("a should exist");
+        assert_eq!(30, a.borrow().size().width());
+    }
+
+    #[test]
+    fn test_margin_auto() {
+        // 横幅が決まっているブロック要素は、autoのマージンで寄せられる
+        let html = "<html><head><style>p { width: 200px; margin: 0 auto; } h1 { width: 100px; margin-left: auto; }</style></head><body><p>x</p><h1>y</h1></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let p = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("p should exist");
+        let center = (CONTENT_AREA_WIDTH - 200) / 2;
+        assert_eq!(LayoutPoint::new(center, 0), p.borrow().point());
+        assert_eq!(200, p.borrow().size().width());
+        let x = p.borrow().first_child().expect("text should exist");
+        assert_eq!(center, x.borrow().point().x());
+
+        let h1 = p.borrow().next_sibling().expect("h1 should exist");
+        assert_eq!(CONTENT_AREA_WIDTH - 100, h1.borrow().point().x());
+        assert_eq!(CONTENT_AREA_WIDTH, h1.borrow().margin_box().size().width());
+    }
+
+    #[test]
+    fn test_paint_order() {
+        // 位置指定された要素はz-indexの順番、同じ場合はツリーの順番で描画される
+        let html = "<html><head><style>.front { position: relative; z-index: 2; } .back { position: relative; z-index: -1; } .auto { position: relative; }</style></head><body><p class=\"front\">a<strong class=\"back\">b</strong></p><p>c</p><p class=\"auto\">d</p><p class=\"back\">e</p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let texts: Vec<String> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect();
+        // bは.frontの重ね合わせコンテキストの中で、aより前に描画される
+        assert_eq!(vec!["e", "c", "d", "b", "a"], texts);
+    }
+
+    #[test]
+    fn test_anonymous_block() {
+        // ブロック要素と混ざったインライン要素は匿名ブロックボックスに包まれる
+        let html = "<html><head></head><body>a<em>b</em><p>c</p>d</body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let body = layout_view.root().expect("root should exist");
+        let anonymous = body
+            .borrow()
+            .first_child()
+            .expect("first child should exist");
+        assert!(anonymous.borrow().is_anonymous());
+        assert_eq!(LayoutObjectKind::Block, anonymous.borrow().kind());
+        assert_eq!(
+            LayoutSize::new(CONTENT_AREA_WIDTH, CHAR_HEIGHT_WITH_PADDING),
+            anonymous.borrow().size()
+        );
+        let a = anonymous.borrow().first_child().expect("a should exist");
+        assert_eq!(NodeKind::Text("a".to_string()), a.borrow().node_kind());
+        let em = a.borrow().next_sibling().expect("em should exist");
+        assert_eq!(LayoutPoint::new(CHAR_WIDTH, 0), em.borrow().point());
+        assert!(em.borrow().next_sibling().is_none());
+
+        let p = anonymous.borrow().next_sibling().expect("p should exist");
+        assert!(!p.borrow().is_anonymous());
+        assert_eq!(
+            LayoutPoint::new(0, CHAR_HEIGHT_WITH_PADDING),
+            p.borrow().point()
+        );
+
+        let anonymous = p.borrow().next_sibling().expect("last child should exist");
+        assert!(anonymous.borrow().is_anonymous());
+        let d = anonymous.borrow().first_child().expect("d should exist");
+        assert_eq!(
+            LayoutPoint::new(0, 2 * CHAR_HEIGHT_WITH_PADDING),
+            d.borrow().point()
+        );
+    }
+
+    #[test]
+    fn test_border_and_underline() {
+        // ボーダー、<hr>、下線が描画されるか
+        let html = "<html><head><style>p { border: 2px solid red; border-bottom-color: blue; }</style></head><body><p><a href=\"/\">ab</a></p><hr></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let red = Color::from_name("red").expect("red should be a color");
+        let blue = Color::from_name("blue").expect("blue should be a color");
+        let gray = Color::from_name("gray").expect("gray should be a color");
+        let p_height = CHAR_HEIGHT_WITH_PADDING + 4;
+        assert_eq!(
+            vec![
+                DisplayItem::Border {
+                    layout_point: LayoutPoint::new(0, 0),
+                    size: LayoutSize::new(CONTENT_AREA_WIDTH, p_height),
+                    widths: EdgeSizes::new(2, 2, 2, 2),
+                    colors: EdgeColors::new(red.clone(), red.clone(), blue.clone(), red),
+                    radius: 0,
+                },
+                DisplayItem::Text {
+                    text: "ab".to_string(),
+                    layout_point: LayoutPoint::new(2, 2),
+                    font_size: FontSize::Medium,
+                    font_weight: FontWeight::Normal,
+                    font_style: FontStyle::Normal,
+                    font_family: "sans-serif".to_string(),
+                    color: blue.clone(),
+                    text_decoration: TextDecoration::Underline,
+                },
+                DisplayItem::Line {
+                    layout_point: LayoutPoint::new(2, 2 + CHAR_HEIGHT),
+                    end_point: LayoutPoint::new(2 + 2 * CHAR_WIDTH, 2 + CHAR_HEIGHT),
+                    width: 1,
+                    color: blue,
+                },
+                DisplayItem::Border {
+                    layout_point: LayoutPoint::new(0, p_height + 8),
+                    size: LayoutSize::new(CONTENT_AREA_WIDTH, 2),
+                    widths: EdgeSizes::new(1, 1, 1, 1),
+                    colors: EdgeColors::all(gray),
+                    radius: 0,
+                },
+            ],
+            layout_view.paint()
+        );
+    }
+
+    #[test]
+    fn test_background() {
+        // 背景色はパディングの外側まで、ボーダーと文字より先に描画される
+        let html = "<html><head><style>body { background-color: khaki; } p { padding: 4px; border: 2px solid red; background-color: #00ff00; }</style></head><body><p>a</p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let khaki = Color::from_name("khaki").expect("khaki should be a color");
+        let green = Color::from_code("#00ff00").expect("#00ff00 should be a color");
+        let p_height = CHAR_HEIGHT_WITH_PADDING + 12;
+        let rects: Vec<(LayoutPoint, LayoutSize, Color)> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Rect {
+                    layout_point,
+                    size,
+                    color,
+                    ..
+                } => Some((layout_point, size, color)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    LayoutPoint::new(0, 0),
+                    LayoutSize::new(CONTENT_AREA_WIDTH, p_height),
+                    khaki
+                ),
+                (
+                    LayoutPoint::new(2, 2),
+                    LayoutSize::new(CONTENT_AREA_WIDTH - 4, p_height - 4),
+                    green
+                ),
+            ],
+            rects
+        );
+        assert!(matches!(layout_view.paint()[2], DisplayItem::Border { .. }));
+    }
+
+    #[test]
+    fn test_border_radius() {
+        // 背景の角の丸みはボーダーの太さの分だけ小さくなり、短い辺の半分を超えない
+        let html = "<html><head><style>p { border: 2px solid red; border-radius: 6px; background-color: blue; } h1 { border-radius: 100px; background-color: blue; }</style></head><body><p>a</p><h1>b</h1></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let radii: Vec<(&str, i64)> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Rect { radius, .. } => Some(("rect", radius)),
+                DisplayItem::Border { radius, .. } => Some(("border", radius)),
+                _ => None,
+            })
+            .collect();
+        let h1_height = layout_view
+            .root()
+            .expect("body should exist")
+            .borrow()
+            .first_child()
+            .expect("p should exist")
+            .borrow()
+            .next_sibling()
+            .expect("h1 should exist")
+            .borrow()
+            .size()
+            .height();
+        assert_eq!(
+            vec![("rect", 4), ("border", 6), ("rect", h1_height / 2)],
+            radii
+        );
+    }
+
+    #[test]
+    fn test_opacity() {
+        // opacityが1より小さい要素は、子孫も含めて不透明度のグループで囲まれる
+        let html = "<html><head><style>p { opacity: 50%; } a { opacity: 0.25; }</style></head><body><p>a<a>b</a></p><h1>c</h1></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let items: Vec<String> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::PushOpacity(opacity) => Some(alloc::format!("push {}", opacity)),
+                DisplayItem::PopOpacity => Some("pop".to_string()),
+                DisplayItem::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec!["push 0.5", "a", "push 0.25", "b", "pop", "pop", "c"],
+            items
+        );
+    }
+
+    #[test]
+    fn test_overflow_clip() {
+        // overflow: hiddenの要素の子孫は、パディングの外側の矩形で切り取られる
+        let html = "<html><head><style>ul { overflow: hidden; height: 10px; padding: 2px; }</style></head><body><ul><li>a</li></ul><p>b</p></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let items: Vec<Option<String>> = layout_view
+            .paint()
+            .into_iter()
+            .map(|item| match item {
+                DisplayItem::PushClip(rect) => {
+                    assert_eq!(
+                        LayoutRect::new(
+                            LayoutPoint::new(0, 0),
+                            LayoutSize::new(CONTENT_AREA_WIDTH, 14)
+                        ),
+                        rect
+                    );
+                    Some("push".to_string())
+                }
+                DisplayItem::PopClip => Some("pop".to_string()),
+                DisplayItem::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                Some("push".to_string()),
+                Some("* ".to_string()),
+                Some("a".to_string()),
+                Some("pop".to_string()),
+                Some("b".to_string()),
+            ],
+            items
+        );
+    }
+
+    #[test]
+    fn test_list_marker() {
+        // <ol>の項目には番号、<ul>の項目には記号がコンテンツの左側に描画される
+        let html = "<html><head></head><body><ol><li>a</li><li>b</li></ol><ul><li>c</li></ul></body></html>"
+            .to_string();
+        let layout_view = create_layout_view(html);
+
+        let texts: Vec<(String, LayoutPoint)> = layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    text, layout_point, ..
+                } => Some((text, layout_point)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("1. ".to_string(), LayoutPoint::new(40 - 3 * CHAR_WIDTH, 0)),
+                ("a".to_string(), LayoutPoint::new(40, 0)),
+                (
+                    "2. ".to_string(),
+                    LayoutPoint::new(40 - 3 * CHAR_WIDTH, CHAR_HEIGHT_WITH_PADDING)
+                ),
+                (
+                    "b".to_string(),
+                    LayoutPoint::new(40, CHAR_HEIGHT_WITH_PADDING)
+                ),
+                (
+                    "* ".to_string(),
+                    LayoutPoint::new(40 - 2 * CHAR_WIDTH, 2 * CHAR_HEIGHT_WITH_PADDING)
+                ),
+                (
+                    "c".to_string(),
+                    LayoutPoint::new(40, 2 * CHAR_HEIGHT_WITH_PADDING)
+                ),
+            ],
+            texts
+        );
+    }
 }
@@ -1,3 +1,4 @@
 pub mod computed_style;
+pub mod font_metrics;
 pub mod layout_object;
 pub mod layout_view;
@@ -1,5 +1,7 @@
 pub mod css;
 pub mod dom;
 pub mod html;
+pub mod image;
+pub mod js;
 pub mod layout;
 pub mod page;
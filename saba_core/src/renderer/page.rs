@@ -1,19 +1,169 @@
+use crate::bookmark::Bookmark;
 use crate::browser::Browser;
+use crate::constants::CONTENT_AREA_HEIGHT;
+use crate::constants::CONTENT_AREA_WIDTH;
+use crate::cookie::CookieJar;
+use crate::display_item::bounding_rect;
+use crate::display_item::changed_display_items;
+use crate::display_item::DisplayItem;
+use crate::encoding;
+use crate::encoding::percent;
+use crate::error::Error;
+use crate::http::Header;
 use crate::http::HttpResponse;
+use crate::http_cache::CacheLookup;
+use crate::http_cache::HttpCache;
+use crate::renderer::css::cssom::CssParser;
+use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::token::CssTokenizer;
+use crate::renderer::css::ua_stylesheet::ua_stylesheet;
+use crate::renderer::dom::api::clear_dirty;
+use crate::renderer::dom::api::get_focusable_nodes;
+use crate::renderer::dom::api::get_style_sources;
+use crate::renderer::dom::api::get_target_element_node;
+use crate::renderer::dom::api::get_target_element_nodes;
+use crate::renderer::dom::api::get_text_content;
+use crate::renderer::dom::api::is_focusable;
+use crate::renderer::dom::api::needs_update;
+use crate::renderer::dom::api::set_active;
+use crate::renderer::dom::api::set_focus;
+use crate::renderer::dom::api::set_hover;
+use crate::renderer::dom::api::set_text_content;
+use crate::renderer::dom::api::set_visited;
+use crate::renderer::dom::api::StyleSource;
+use crate::renderer::dom::event::dispatch_event;
+use crate::renderer::dom::event::Event;
+use crate::renderer::dom::form::form_data;
+use crate::renderer::dom::form::form_owner;
+use crate::renderer::dom::form::is_submit_button;
+use crate::renderer::dom::form::url_encode;
+use crate::renderer::dom::form::FormMethod;
+use crate::renderer::dom::form::FormSubmission;
+use crate::renderer::dom::node::ElementKind;
+use crate::renderer::dom::node::Node;
 use crate::renderer::dom::node::Window;
+use crate::renderer::dom::selection::Selection;
+use crate::renderer::dom::selection::SelectionPoint;
 use crate::renderer::html::parser::HtmlParser;
 use crate::renderer::html::token::HtmlTokenizer;
+use crate::renderer::image::bitmap::decode_image;
+use crate::renderer::image::bitmap::Bitmap;
+use crate::renderer::js::console::ConsoleLevel;
+use crate::renderer::js::console::ConsoleMessage;
+use crate::renderer::js::runtime::JsRuntime;
+use crate::renderer::layout::computed_style::Color;
+use crate::renderer::layout::computed_style::EdgeColors;
+use crate::renderer::layout::computed_style::EdgeSizes;
+use crate::renderer::layout::font_metrics::DefaultFontMetrics;
+use crate::renderer::layout::font_metrics::FontMetrics;
+use crate::renderer::layout::layout_object::LayoutObjectKind;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutRect;
+use crate::renderer::layout::layout_object::LayoutSize;
+use crate::renderer::layout::layout_view::LayoutView;
+use crate::resource_loader::ResourceLoader;
+use crate::url::Scheme;
+use crate::url::Url;
 use crate::utils::convert_dom_to_string;
+use alloc::collections::BTreeMap;
+use alloc::collections::VecDeque;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
 use alloc::string::String;
 use alloc::string::ToString;
+use alloc::vec::Vec;
 use core::cell::RefCell;
 
+// @importが入れ子になっている場合に読み込む深さの上限
+// 循環しているインポートで無限に取得し続けないようにする
+const MAX_IMPORT_DEPTH: usize = 4;
+
+// フォーカスリングの線の太さと色
+const FOCUS_RING_WIDTH: i64 = 2;
+const FOCUS_RING_COLOR: &str = "#1a73e8";
+
+// 選択範囲の強調表示の色と不透明度
+// 半透明にして、選択したテキストの手前に重ねて描画する
+const SELECTION_COLOR: &str = "#3390ff";
+const SELECTION_OPACITY: f64 = 0.4;
+
+// 文書の読み込みの段階を知らせるイベント
+// UIは読み込み中の表示に使い、テストでは読み込みの各段階が実行されたことを確かめる
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadEvent {
+    // URLの文書の読み込みを始めた
+    Started(String),
+    // 文書をパースしてDOMツリーを作り終えた
+    DomReady,
+    // スタイルシートと画像を全て読み込み終えた
+    SubresourcesLoaded,
+    // 読み込みが全て終わった
+    Finished,
+}
+
 #[derive(Debug, Clone)]
 pub struct Page {
     browser: Weak<RefCell<Browser>>,
+    // 表示しているページのURL
+    url: Option<Url>,
     frame: Option<Rc<RefCell<Window>>>,
+    // 文書内の順番に並んだスタイルシート
+    // 後のスタイルシートのルールほど優先される
+    styles: Vec<StyleSheet>,
+    // UIのフォントの寸法
+    font_metrics: Rc<dyn FontMetrics>,
+    // 外部のリソースを取得する方法
+    // 設定されていない場合はネットワークにアクセスしない
+    resource_loader: Option<Rc<dyn ResourceLoader>>,
+    // リクエストに付けるクッキーと、レスポンスで受け取ったクッキーを保存する場所
+    // ブラウザの全てのページで共有する
+    cookie_jar: Option<Rc<RefCell<CookieJar>>>,
+    // ネットワークにアクセスする前に確認するHTTPキャッシュ
+    // ブラウザの全てのページで共有する
+    http_cache: Option<Rc<RefCell<HttpCache>>>,
+    // trueの間はHTTPキャッシュを使わずに取得する（キャッシュを無視した再読み込み）
+    bypass_cache: bool,
+    // ページを表示する領域のサイズ
+    viewport: LayoutSize,
+    // 縦方向のスクロール量（ページの上端からの距離）
+    scroll_offset: i64,
+    layout_view: Option<LayoutView>,
+    // UIが描画する内容
+    display_items: Vec<DisplayItem>,
+    // 前のフレームから変わった描画内容
+    // 新しく描画するものと、前のフレームから消えたものの両方を含む
+    changed_display_items: Vec<DisplayItem>,
+    // 変わった描画内容を全て含む矩形（ビューポート上の描き直しが必要な領域）
+    // 何も変わっていない場合はNone
+    damaged_rect: Option<LayoutRect>,
+    // マウスカーソルが乗っているノード
+    hovered_node: Option<Rc<RefCell<Node>>>,
+    // マウスのボタンが押されているノード
+    active_node: Option<Rc<RefCell<Node>>>,
+    // キーボードの入力先になっているノード
+    // Tabキーでリンクや入力欄の間を移動し、Enterキーで実行する
+    focused_node: Option<Rc<RefCell<Node>>>,
+    // マウスのドラッグで選択したテキストの範囲
+    selection: Option<Selection>,
+    // 訪問済みのURL
+    // リンク先が含まれる<a>要素は:visitedにマッチする
+    visited_urls: Vec<String>,
+    // 最後に受け取ったレスポンス
+    // 再読み込みの時にキャッシュとして使う
+    response: Option<HttpResponse>,
+    // まだ読み込んでいない画像の<img>要素と、画像の絶対URL
+    pending_images: VecDeque<(Rc<RefCell<Node>>, String)>,
+    // 読み込んだ画像のURLごとのキャッシュ
+    // 取得やデコードに失敗した画像はNoneにして、何度も取得しないようにする
+    images: BTreeMap<String, Option<Rc<Bitmap>>>,
+    // まだ取り出されていない読み込みのイベント（古い順）
+    load_events: Vec<LoadEvent>,
+    // 文書を読み込んでいる途中かどうか
+    loading: bool,
+    // 今の文書のサブリソースを全て読み込み終えたかどうか
+    subresources_loaded: bool,
+    // 今の文書のスクリプトを実行する環境
+    js_runtime: Option<JsRuntime>,
 }
 
 impl Default for Page {
@@ -26,7 +176,32 @@ impl Page {
     pub fn new() -> Self {
         Self {
             browser: Weak::new(),
+            url: None,
             frame: None,
+            styles: Vec::new(),
+            font_metrics: Rc::new(DefaultFontMetrics),
+            resource_loader: None,
+            cookie_jar: None,
+            http_cache: None,
+            bypass_cache: false,
+            viewport: LayoutSize::new(CONTENT_AREA_WIDTH, CONTENT_AREA_HEIGHT),
+            scroll_offset: 0,
+            layout_view: None,
+            display_items: Vec::new(),
+            changed_display_items: Vec::new(),
+            damaged_rect: None,
+            hovered_node: None,
+            active_node: None,
+            focused_node: None,
+            selection: None,
+            visited_urls: Vec::new(),
+            response: None,
+            pending_images: VecDeque::new(),
+            images: BTreeMap::new(),
+            load_events: Vec::new(),
+            loading: false,
+            subresources_loaded: false,
+            js_runtime: None,
         }
     }
 
@@ -34,8 +209,217 @@ impl Page {
         self.browser = browser;
     }
 
+    pub fn set_resource_loader(&mut self, resource_loader: Rc<dyn ResourceLoader>) {
+        self.resource_loader = Some(resource_loader);
+    }
+
+    pub fn set_cookie_jar(&mut self, cookie_jar: Rc<RefCell<CookieJar>>) {
+        self.cookie_jar = Some(cookie_jar);
+    }
+
+    pub fn set_http_cache(&mut self, http_cache: Rc<RefCell<HttpCache>>) {
+        self.http_cache = Some(http_cache);
+    }
+
+    // URLのリソースをリソースローダーで取得する
+    // HTTPキャッシュが設定されている場合は、新鮮なレスポンスがあればネットワークにアクセスせずに使い、
+    // 古くなったレスポンスは条件付きリクエストで確かめる
+    // クッキーの保存場所が設定されている場合は、一致するクッキーを送り、受け取ったクッキーを保存する
+    pub fn fetch(&self, url: String) -> Result<HttpResponse, Error> {
+        let loader = self
+            .resource_loader
+            .as_ref()
+            .ok_or(Error::Other("resource loader is not set".to_string()))?;
+        let url = Url::new(url).parse()?;
+
+        let mut headers = Vec::new();
+        if let (Some(http_cache), false) = (&self.http_cache, self.bypass_cache) {
+            match http_cache.borrow().lookup(&url) {
+                CacheLookup::Fresh(response) => return Ok(response),
+                CacheLookup::Stale(validators) => headers.extend(validators),
+                CacheLookup::Miss => {}
+            }
+        }
+        headers.extend(self.cookie_headers(&url));
+        headers.extend(authorization_header(&url));
+
+        let response = loader.fetch_with_headers(url.clone(), headers)?;
+        self.store_cookies(&url, &response);
+        match &self.http_cache {
+            Some(http_cache) => Ok(http_cache.borrow_mut().store(&url, response)),
+            None => Ok(response),
+        }
+    }
+
+    // フォームの内容をURLにPOSTする
+    // POSTのレスポンスはHTTPキャッシュに保存しない
+    pub fn post(&self, url: String, body: String) -> Result<HttpResponse, Error> {
+        let loader = self
+            .resource_loader
+            .as_ref()
+            .ok_or(Error::Other("resource loader is not set".to_string()))?;
+        let url = Url::new(url).parse()?;
+
+        let mut headers = self.cookie_headers(&url);
+        headers.extend(authorization_header(&url));
+        headers.push(Header::new(
+            "Content-Type".to_string(),
+            "application/x-www-form-urlencoded".to_string(),
+        ));
+        let response = loader.post(url.clone(), headers, body)?;
+        self.store_cookies(&url, &response);
+        Ok(response)
+    }
+
+    // urlへのリクエストに付けるCookieヘッダ
+    fn cookie_headers(&self, url: &Url) -> Vec<Header> {
+        self.cookie_jar
+            .as_ref()
+            .and_then(|cookie_jar| cookie_jar.borrow().cookie_header(url))
+            .map(|cookie| Header::new("Cookie".to_string(), cookie))
+            .into_iter()
+            .collect()
+    }
+
+    // レスポンスのSet-Cookieヘッダのクッキーを保存する
+    fn store_cookies(&self, url: &Url, response: &HttpResponse) {
+        if let Some(cookie_jar) = &self.cookie_jar {
+            for set_cookie in response.set_cookies() {
+                cookie_jar.borrow_mut().set_cookie(url, &set_cookie);
+            }
+        }
+    }
+
+    // 次に受け取るレスポンスのURLを設定する
+    pub fn set_url(&mut self, url: Url) {
+        self.url = Some(url);
+    }
+
+    pub fn url(&self) -> Option<Url> {
+        self.url.clone()
+    }
+
+    // UIが描画に使うフォントの寸法を設定する
+    // 次にレイアウトする時から使われる
+    pub fn set_font_metrics(&mut self, font_metrics: Rc<dyn FontMetrics>) {
+        self.font_metrics = font_metrics;
+    }
+
+    pub fn viewport(&self) -> LayoutSize {
+        self.viewport
+    }
+
+    // ページを表示する領域のサイズが変わった時に、レイアウトと描画する内容を作り直す
+    pub fn resize(&mut self, width: i64, height: i64) {
+        self.viewport = LayoutSize::new(width, height);
+        if let Some(layout_view) = &mut self.layout_view {
+            layout_view.set_viewport(self.viewport);
+        }
+        // ページの高さが変わるので、スクロールできる範囲に収め直す
+        self.scroll_by(0);
+    }
+
+    pub fn scroll_offset(&self) -> i64 {
+        self.scroll_offset
+    }
+
+    // ページ全体の高さ（スクロールバーのつまみの大きさに使う）
+    pub fn content_height(&self) -> i64 {
+        match &self.layout_view {
+            Some(layout_view) => layout_view.content_height(),
+            None => 0,
+        }
+    }
+
+    // 縦方向にdyだけスクロールし、描画する内容を作り直す
+    // ページの上端より上や、下端より下にはスクロールしない
+    pub fn scroll_by(&mut self, dy: i64) {
+        let max_offset = (self.content_height() - self.viewport.height()).max(0);
+        self.scroll_offset = (self.scroll_offset + dy).clamp(0, max_offset);
+        self.paint_tree();
+    }
+
+    // ページの上端からyの位置までスクロールする
+    pub fn scroll_to(&mut self, y: i64) {
+        self.scroll_by(y - self.scroll_offset);
+    }
+
+    // idがfragmentの要素の上端までスクロールする
+    // 見つからない場合は、パーセントエンコードを戻したfragmentで探す
+    // fragmentが空か"top"で、一致する要素がない場合はページの先頭に戻る
+    // 要素が見つからない、またはレイアウトツリーにない場合はスクロールせずにfalseを返す
+    // https://html.spec.whatwg.org/multipage/browsing-the-web.html#find-a-potential-indicated-element
+    pub fn scroll_to_fragment(&mut self, fragment: &str) -> bool {
+        if fragment.is_empty() {
+            self.scroll_to(0);
+            return true;
+        }
+
+        let decoded = String::from_utf8(percent::decode(fragment)).ok();
+        let target = match &self.frame {
+            Some(frame) => {
+                let frame = frame.borrow();
+                frame.get_element_by_id(fragment).or_else(|| {
+                    decoded
+                        .as_deref()
+                        .and_then(|decoded| frame.get_element_by_id(decoded))
+                })
+            }
+            None => None,
+        };
+        if target.is_none() && decoded.is_some_and(|decoded| decoded.eq_ignore_ascii_case("top")) {
+            self.scroll_to(0);
+            return true;
+        }
+        let layout_object = match (target, &self.layout_view) {
+            (Some(target), Some(layout_view)) => layout_view.find_layout_object(&target),
+            _ => None,
+        };
+        match layout_object {
+            Some(layout_object) => {
+                let y = layout_object.borrow().border_box().point().y();
+                self.scroll_to(y);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn frame(&self) -> Option<Rc<RefCell<Window>>> {
+        self.frame.clone()
+    }
+
+    pub fn styles(&self) -> Vec<StyleSheet> {
+        self.styles.clone()
+    }
+
+    pub fn layout_view(&self) -> Option<LayoutView> {
+        self.layout_view.clone()
+    }
+
+    pub fn display_items(&self) -> Vec<DisplayItem> {
+        self.display_items.clone()
+    }
+
+    // UIはこれらの描画内容とdamaged_rectを使って、変わった部分だけを描き直せる
+    pub fn changed_display_items(&self) -> Vec<DisplayItem> {
+        self.changed_display_items.clone()
+    }
+
+    pub fn damaged_rect(&self) -> Option<LayoutRect> {
+        self.damaged_rect
+    }
+
     pub fn receive_response(&mut self, response: HttpResponse) -> String {
-        self.create_frame(response.body());
+        self.create_document(&response);
+        self.subresources_loaded = false;
+        self.load_events.push(LoadEvent::DomReady);
+        self.response = Some(response);
+        self.update_visited_links();
+        self.collect_images();
+        self.set_styles();
+        self.set_layout_view();
+        self.paint_tree();
 
         // デバッグ用にDOMツリーを文字列として返す
         if let Some(frame) = &self.frame {
@@ -48,9 +432,1934 @@ impl Page {
         "".to_string()
     }
 
+    // 文書の読み込みに失敗した時に、エラーの内容を説明する文書を表示する
+    // 再読み込みで取得し直せるように、エラーの文書はキャッシュしない
+    pub fn receive_error(&mut self, url: String, error: &Error) {
+        let html = error_document(&url, error);
+        self.receive_html(url, html);
+    }
+
+    // ブックマークの一覧を表示する
+    // 一覧が変わった時に作り直せるように、この文書もキャッシュしない
+    pub fn receive_bookmarks(&mut self, url: String, bookmarks: &[Bookmark]) {
+        let html = bookmarks_document(bookmarks);
+        self.receive_html(url, html);
+    }
+
+    // ブラウザが作ったHTMLの文書を表示する
+    fn receive_html(&mut self, url: String, html: String) {
+        self.url = Url::new(url).parse().ok();
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("failed to create an internal document");
+        self.receive_response(response);
+        self.response = None;
+    }
+
+    // 表示している文書を読み込み直し、DOMツリー・スタイル・レイアウト・描画内容を作り直す
+    // bypass_cacheがfalseの場合は、前回受け取ったレスポンスがあればそれを使う
+    // bypass_cacheがtrueの場合は、スタイルシートや画像もHTTPキャッシュを使わずに取得し直す
+    // スクロールの位置は読み込み直す前の位置に戻す
+    pub fn reload(&mut self, bypass_cache: bool) -> Result<(), Error> {
+        self.bypass_cache = bypass_cache;
+        let result = self.reload_document(bypass_cache);
+        self.bypass_cache = false;
+        result
+    }
+
+    fn reload_document(&mut self, bypass_cache: bool) -> Result<(), Error> {
+        let response = match (&self.response, &self.url) {
+            (Some(response), _) if !bypass_cache => response.clone(),
+            (_, Some(url)) => self.fetch(url.url())?,
+            (_, None) => return Err(Error::Other("no document to reload".to_string())),
+        };
+        if bypass_cache {
+            self.images.clear();
+        }
+
+        let scroll_offset = self.scroll_offset;
+        if let Some(url) = &self.url {
+            self.start_load(url.url());
+        }
+        self.receive_response(response);
+        self.load_imports();
+        self.load_images();
+        self.scroll_by(scroll_offset);
+        self.finish_load();
+        Ok(())
+    }
+
+    // 文書の読み込みを始めたことを記録する
+    // 前の読み込みのまだ取り出されていないイベントは捨てる
+    pub fn start_load(&mut self, url: String) {
+        self.load_events.clear();
+        self.loading = true;
+        self.load_events.push(LoadEvent::Started(url));
+    }
+
+    // 文書の読み込みが全て終わったことを記録する
+    pub fn finish_load(&mut self) {
+        self.check_subresources_loaded();
+        if self.loading {
+            self.loading = false;
+            self.load_events.push(LoadEvent::Finished);
+        }
+    }
+
+    // 文書を読み込んでいる途中かどうか
+    pub fn is_loading(&self) -> bool {
+        self.loading
+    }
+
+    // まだ取り出されていない読み込みのイベントを古い順に取り出す
+    pub fn take_load_events(&mut self) -> Vec<LoadEvent> {
+        core::mem::take(&mut self.load_events)
+    }
+
+    // 読み込む画像が残っていなければ、サブリソースを読み込み終えたことを1回だけ記録する
+    fn check_subresources_loaded(&mut self) {
+        if self.frame.is_some() && !self.subresources_loaded && self.pending_images.is_empty() {
+            self.subresources_loaded = true;
+            self.load_events.push(LoadEvent::SubresourcesLoaded);
+        }
+    }
+
+    // DOMツリーが変更され、スタイル・レイアウト・描画のやり直しが必要かどうか
+    pub fn needs_update(&self) -> bool {
+        match &self.frame {
+            Some(frame) => needs_update(&frame.borrow().document()),
+            None => false,
+        }
+    }
+
+    // DOMツリーの変更を反映し終えたことを記録する
+    pub fn clear_dirty(&mut self) {
+        if let Some(frame) = &self.frame {
+            clear_dirty(&frame.borrow().document());
+        }
+    }
+
+    // スクリプトなどによるDOMツリーの変更を画面に反映する
+    // 例えばidが"title"の要素のinnerTextを変えた後に呼び出すと、新しいテキストが描画される
+    // <style>や<link>が変更された場合はスタイルシートを作り直し、追加された<img>は読み込む画像に加える
+    // 変更がなかった場合は何もせずにfalseを返す
+    pub fn update_rendering(&mut self) -> bool {
+        let dom = match &self.frame {
+            Some(frame) if self.needs_update() => frame.borrow().document(),
+            _ => return false,
+        };
+
+        let styles_changed = [ElementKind::Style, ElementKind::Link]
+            .into_iter()
+            .flat_map(|kind| get_target_element_nodes(Some(dom.clone()), kind))
+            .any(|node| needs_update(&node));
+        if styles_changed {
+            self.set_styles();
+            self.load_imports();
+        }
+        self.update_visited_links();
+        self.collect_images();
+        self.set_layout_view();
+        self.paint_tree();
+        self.clear_dirty();
+        true
+    }
+
+    // ビューポート上の点(x, y)がクリックされた時に、その位置にあるDOMノードを返す
+    // テキストの上の場合はテキストノードを返すので、リンクなどは呼び出し側で祖先をたどる
+    pub fn clicked(&self, x: i64, y: i64) -> Option<Rc<RefCell<Node>>> {
+        let layout_view = self.layout_view.as_ref()?;
+        let hit = layout_view.hit_test(x, y + self.scroll_offset)?;
+        let node = hit.borrow().node();
+        Some(node)
+    }
+
+    // 現在の時刻（ミリ秒）までに実行する時刻になった、setTimeoutとsetIntervalのタスクを実行する
+    // UIのイベントループから繰り返し呼び出し、その後update_renderingでDOMツリーの変更を画面に反映する
+    // タスクを1つでも実行した場合はtrueを返す
+    pub fn run_pending_tasks(&mut self, now: u64) -> bool {
+        match &mut self.js_runtime {
+            Some(runtime) => runtime.run_timers(now),
+            None => false,
+        }
+    }
+
+    // location.hrefへの代入などで、スクリプトが移動を求めたURLを取り出す
+    // 移動はBrowserが行う
+    pub fn take_navigation(&mut self) -> Option<String> {
+        self.js_runtime.as_mut()?.take_navigation()
+    }
+
+    // 今の文書のconsoleに出力されたメッセージとスクリプトのエラーを古い順に返す
+    // UIは開発者ツールのパネルにこれを表示する
+    pub fn console_messages(&self) -> Vec<ConsoleMessage> {
+        match &self.js_runtime {
+            Some(runtime) => runtime.console_messages(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn clear_console(&mut self) {
+        if let Some(runtime) = &mut self.js_runtime {
+            runtime.clear_console();
+        }
+    }
+
+    // ビューポート上の点(x, y)がクリックされた時の処理
+    // クリックされた要素にclickイベントを送り、onclick属性やaddEventListenerで登録されたスクリプトを実行する
+    // リンクの上の場合は、リンク先のURLを現在のURLで解決し、移動先としてBrowserに返す
+    // スクリプトがデフォルトの動作をキャンセルした場合は移動しない
+    pub fn on_click(&self, x: i64, y: i64) -> Option<String> {
+        let mut node = self.clicked(x, y)?;
+        // テキストの上の場合は、テキストを含む要素をターゲットにする
+        if node.borrow().get_element().is_none() {
+            let parent = node.borrow().parent().upgrade()?;
+            node = parent;
+        }
+        if !dispatch_event(&node, &mut Event::click()) {
+            return None;
+        }
+        let href = Self::link_href(&node)?;
+        Some(resolve_url(self.url.as_ref(), &href))
+    }
+
+    // <meta http-equiv="refresh">で指定された、移動するまでの秒数と移動先のURL
+    // URLの指定がない場合は、表示している文書を読み込み直す
+    pub fn refresh(&self) -> Option<(u64, String)> {
+        let dom = self.frame.as_ref()?.borrow().document();
+        get_target_element_nodes(Some(dom), ElementKind::Meta)
+            .iter()
+            .find_map(|meta| {
+                let element = meta.borrow().get_element()?;
+                let http_equiv = element.get_attribute("http-equiv")?;
+                if !http_equiv.trim().eq_ignore_ascii_case("refresh") {
+                    return None;
+                }
+                parse_refresh(&element.get_attribute("content")?)
+            })
+            .map(|(delay, url)| {
+                let url = match url {
+                    Some(url) => resolve_url(self.url.as_ref(), &url),
+                    None => self.url.as_ref().map(|url| url.url()).unwrap_or_default(),
+                };
+                (delay, url)
+            })
+    }
+
+    // ビューポート上の点(x, y)がクリックされた時に送信するフォームの内容
+    // 送信ボタン（またはその中身）がクリックされた場合だけSomeを返す
+    pub fn form_submission(&self, x: i64, y: i64) -> Option<FormSubmission> {
+        let mut current = Some(self.clicked(x, y)?);
+        while let Some(n) = current {
+            if is_submit_button(&n) {
+                let form = form_owner(&n)?;
+                return Some(self.submit_form(&form, Some(&n)));
+            }
+            current = n.borrow().parent().upgrade();
+        }
+        None
+    }
+
+    pub fn focused_node(&self) -> Option<Rc<RefCell<Node>>> {
+        self.focused_node.clone()
+    }
+
+    // フォーカスを持つノードを設定し、フォーカスリングを描き直す
+    // 以前のノードのフォーカスは解除する
+    pub fn set_focused_node(&mut self, node: Option<Rc<RefCell<Node>>>) {
+        if let Some(old) = self.focused_node.take() {
+            set_focus(&old, false);
+        }
+        if let Some(new) = &node {
+            set_focus(new, true);
+        }
+        self.focused_node = node;
+
+        // :focusのスタイルが変わる場合はレイアウトからやり直す
+        if self.needs_update() {
+            self.set_layout_view();
+        }
+        self.paint_tree();
+    }
+
+    // ビューポート上の点(x, y)がクリックされた時に、その位置のリンクや入力欄にフォーカスを移す
+    // フォーカスを移せる要素の外をクリックした場合は、フォーカスを外す
+    pub fn focus_at(&mut self, x: i64, y: i64) {
+        let mut current = self.clicked(x, y);
+        while let Some(n) = current {
+            if is_focusable(&n) {
+                self.set_focused_node(Some(n));
+                return;
+            }
+            current = n.borrow().parent().upgrade();
+        }
+        self.set_focused_node(None);
+    }
+
+    // 文書の順番で次の（reverseの場合は前の）リンクや入力欄にフォーカスを移す（TabキーとShift+Tabキー）
+    // 最後の要素の次は最初の要素に戻る。フォーカスを移した要素が画面の外にある場合はスクロールする
+    // フォーカスを移せる要素がない場合はfalseを返す
+    pub fn focus_next(&mut self, reverse: bool) -> bool {
+        let nodes: Vec<Rc<RefCell<Node>>> = match (&self.frame, &self.layout_view) {
+            (Some(frame), Some(layout_view)) => get_focusable_nodes(&frame.borrow().document())
+                .into_iter()
+                // display: noneなどで表示されていない要素は飛ばす
+                .filter(|node| layout_view.find_layout_object(node).is_some())
+                .collect(),
+            _ => Vec::new(),
+        };
+        if nodes.is_empty() {
+            return false;
+        }
+
+        let current = self
+            .focused_node
+            .as_ref()
+            .and_then(|focused| nodes.iter().position(|node| Rc::ptr_eq(node, focused)));
+        let index = match (current, reverse) {
+            (None, false) => 0,
+            (None, true) => nodes.len() - 1,
+            (Some(i), false) => (i + 1) % nodes.len(),
+            (Some(i), true) => (i + nodes.len() - 1) % nodes.len(),
+        };
+        self.set_focused_node(Some(nodes[index].clone()));
+        self.scroll_into_view(&nodes[index]);
+        true
+    }
+
+    // フォーカスを持つリンクのリンク先のURL（Enterキーで移動する）
+    pub fn focused_link(&self) -> Option<String> {
+        let href = Self::link_href(self.focused_node.as_ref()?)?;
+        Some(resolve_url(self.url.as_ref(), &href))
+    }
+
+    // Enterキーで送信するフォームの内容
+    // 送信ボタンにフォーカスがある場合はそのボタンで、入力欄にフォーカスがある場合はボタンなしで送信する
+    pub fn focused_form_submission(&self) -> Option<FormSubmission> {
+        let node = self.focused_node.as_ref()?;
+        let form = form_owner(node)?;
+        if is_submit_button(node) {
+            return Some(self.submit_form(&form, Some(node)));
+        }
+        if node.borrow().element_kind() == Some(ElementKind::Input) {
+            return Some(self.submit_form(&form, None));
+        }
+        None
+    }
+
+    pub fn selection(&self) -> Option<Selection> {
+        self.selection.clone()
+    }
+
+    // ビューポート上の点(x, y)から選択を始める（マウスのボタンを押した時）
+    // テキストの上でない場合は、選択を解除する
+    pub fn start_selection(&mut self, x: i64, y: i64) {
+        self.selection = self
+            .text_position(x, y)
+            .map(|point| Selection::new(point.clone(), point));
+        self.paint_tree();
+    }
+
+    // 選択範囲の終わりをビューポート上の点(x, y)まで広げる（マウスをドラッグした時）
+    // テキストの上でない場合は、選択範囲を変えない
+    pub fn extend_selection(&mut self, x: i64, y: i64) {
+        let point = match (&self.selection, self.text_position(x, y)) {
+            (Some(_), Some(point)) => point,
+            _ => return,
+        };
+        if let Some(selection) = &mut self.selection {
+            selection.set_focus(point);
+        }
+        self.paint_tree();
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+        self.paint_tree();
+    }
+
+    // 選択されたテキスト（クリップボードにコピーする内容）
+    pub fn selected_text(&self) -> String {
+        match (&self.selection, &self.frame) {
+            (Some(selection), Some(frame)) => selection.selected_text(&frame.borrow().document()),
+            _ => String::new(),
+        }
+    }
+
+    // ビューポート上の点(x, y)にある文字の境目の位置
+    fn text_position(&self, x: i64, y: i64) -> Option<SelectionPoint> {
+        let layout_view = self.layout_view.as_ref()?;
+        let y = y + self.scroll_offset;
+        let hit = layout_view.hit_test(x, y)?;
+        if hit.borrow().kind() != LayoutObjectKind::Text {
+            return None;
+        }
+        let offset = hit
+            .borrow()
+            .text_offset_at(x, y, self.font_metrics.as_ref())?;
+        let node = hit.borrow().node();
+        Some(SelectionPoint::new(node, offset))
+    }
+
+    // nodeがビューポートの外にある場合は、ビューポートに入るようにスクロールする
+    fn scroll_into_view(&mut self, node: &Rc<RefCell<Node>>) {
+        let bounds = match self
+            .layout_view
+            .as_ref()
+            .and_then(|layout_view| layout_view.node_bounds(node))
+        {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        let top = bounds.point().y();
+        let bottom = top + bounds.size().height();
+        if top < self.scroll_offset {
+            self.scroll_to(top);
+        } else if bottom > self.scroll_offset + self.viewport.height() {
+            self.scroll_to(bottom - self.viewport.height());
+        }
+    }
+
+    // formの内容をエンコードし、送信先と送信方法を決める
+    // action属性がない場合は、表示している文書のURLに送信する
+    // GETの場合は、送信先のURLのクエリをフォームの内容で置き換える
+    pub fn submit_form(
+        &self,
+        form: &Rc<RefCell<Node>>,
+        submitter: Option<&Rc<RefCell<Node>>>,
+    ) -> FormSubmission {
+        let (action, method) = match form.borrow().get_element() {
+            Some(element) => (
+                element.get_attribute("action").unwrap_or_default(),
+                element.get_attribute("method").unwrap_or_default(),
+            ),
+            None => (String::new(), String::new()),
+        };
+        let action = match action.trim() {
+            "" => self.url.as_ref().map(|url| url.url()).unwrap_or_default(),
+            action => resolve_url(self.url.as_ref(), action),
+        };
+        let action = action
+            .split_once('#')
+            .map_or(action.as_str(), |(action, _)| action)
+            .to_string();
+        let data = form_data(form, submitter);
+
+        if method.trim().eq_ignore_ascii_case("post") {
+            return FormSubmission::new(FormMethod::Post, action, url_encode(&data));
+        }
+        let url = match Url::new(action.clone()).parse() {
+            Ok(url) => url.set_query_pairs(&data).url(),
+            Err(_) => {
+                let base = action
+                    .split_once('?')
+                    .map_or(action.as_str(), |(base, _)| base);
+                alloc::format!("{}?{}", base, url_encode(&data))
+            }
+        };
+        FormSubmission::new(FormMethod::Get, url, String::new())
+    }
+
+    // ノード自身か一番近い祖先の、href属性を持つ<a>要素のhrefを返す
+    fn link_href(node: &Rc<RefCell<Node>>) -> Option<String> {
+        let mut current = Some(node.clone());
+        while let Some(n) = current {
+            if n.borrow().element_kind() == Some(ElementKind::A) {
+                let href = n
+                    .borrow()
+                    .get_element()
+                    .and_then(|element| element.get_attribute("href"));
+                if href.is_some() {
+                    return href;
+                }
+            }
+            current = n.borrow().parent().upgrade();
+        }
+        None
+    }
+
+    // UIからマウスカーソルが乗っているノードを設定する
+    // 以前のノードのホバー状態は解除する
+    // 同じノードの上でマウスが動いた時は、スタイルを計算し直さないように何もしない
+    pub fn set_hovered_node(&mut self, node: Option<Rc<RefCell<Node>>>) {
+        if is_same_node(&self.hovered_node, &node) {
+            return;
+        }
+        if let Some(old) = self.hovered_node.take() {
+            set_hover(&old, false);
+        }
+        if let Some(new) = &node {
+            set_hover(new, true);
+        }
+        self.hovered_node = node;
+    }
+
+    // UIからマウスのボタンが押されているノードを設定する
+    pub fn set_active_node(&mut self, node: Option<Rc<RefCell<Node>>>) {
+        if is_same_node(&self.active_node, &node) {
+            return;
+        }
+        if let Some(old) = self.active_node.take() {
+            set_active(&old, false);
+        }
+        if let Some(new) = &node {
+            set_active(new, true);
+        }
+        self.active_node = node;
+    }
+
+    // 訪問済みのURLを記録し、そのURLへのリンクのスタイルを更新する
+    // 履歴の機能からページを訪れるたびに呼び出すことを想定している
+    pub fn mark_visited(&mut self, url: String) {
+        if self.is_visited(&url) {
+            return;
+        }
+        self.visited_urls.push(url);
+
+        self.update_visited_links();
+        if self.needs_update() {
+            self.set_layout_view();
+            self.paint_tree();
+        }
+    }
+
+    pub fn is_visited(&self, url: &str) -> bool {
+        self.visited_urls.iter().any(|visited| visited == url)
+    }
+
+    // href属性を持つ<a>要素の訪問済みの状態を、訪問済みのURLに合わせる
+    fn update_visited_links(&mut self) {
+        let dom = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return,
+        };
+        for link in get_target_element_nodes(Some(dom), ElementKind::A) {
+            let href = match link
+                .borrow()
+                .get_element()
+                .and_then(|element| element.get_attribute("href"))
+            {
+                Some(href) => href,
+                None => continue,
+            };
+            let url = resolve_url(self.url.as_ref(), &href);
+            set_visited(&link, self.is_visited(&url));
+        }
+    }
+
+    // @importで指定されたスタイルシートを取得し、書かれた順にマージする
+    // スタイルを計算する前に呼び出す
+    // リソースローダーが設定されていない場合は何もしない
+    pub fn load_imports(&mut self) {
+        if self.resource_loader.is_none() {
+            return;
+        }
+        let styles = core::mem::take(&mut self.styles);
+        let base = self.url.clone();
+        self.styles = styles
+            .into_iter()
+            .map(|style| self.resolve_imports(style, base.clone(), 0))
+            .collect();
+        self.set_layout_view();
+        self.paint_tree();
+    }
+
+    fn resolve_imports(
+        &self,
+        mut style: StyleSheet,
+        base: Option<Url>,
+        depth: usize,
+    ) -> StyleSheet {
+        if depth >= MAX_IMPORT_DEPTH {
+            style.set_imports(Vec::new());
+            return style;
+        }
+
+        let mut imported = Vec::new();
+        for href in &style.imports {
+            let url = resolve_url(base.as_ref(), href);
+            // 取得に失敗したスタイルシートは無視する
+            let sheet_url = match Url::new(url).parse() {
+                Ok(url) => url,
+                Err(_) => continue,
+            };
+            let response = match self.fetch(sheet_url.url()) {
+                Ok(response) => response,
+                Err(_) => continue,
+            };
+            let sheet = CssParser::new(CssTokenizer::new(response.body())).parse_stylesheet();
+            imported.push(self.resolve_imports(sheet, Some(sheet_url), depth + 1));
+        }
+
+        style.merge_imports(imported);
+        style
+    }
+
+    // 全ての<style>要素と<link rel="stylesheet">をそれぞれ文書順にスタイルシートにする
+    // 先頭はUAスタイルシート
+    // 外部のスタイルシートはリソースローダーで取得し、取得に失敗したものは無視する
+    fn set_styles(&mut self) {
+        let dom = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return,
+        };
+        self.styles = alloc::vec![ua_stylesheet()];
+        for source in get_style_sources(dom) {
+            let sheet = match source {
+                StyleSource::Inline(style) => {
+                    CssParser::new(CssTokenizer::new(style)).parse_stylesheet()
+                }
+                StyleSource::Link(href) => match self.fetch_stylesheet(&href) {
+                    Some(sheet) => sheet,
+                    None => continue,
+                },
+            };
+            self.styles.push(sheet);
+        }
+    }
+
+    // <link>で参照されている外部のスタイルシートを取得してパースする
+    // @importはスタイルシートのURLを基準に解決するので、ここで読み込んでおく
+    fn fetch_stylesheet(&self, href: &str) -> Option<StyleSheet> {
+        let url = Url::new(resolve_url(self.url.as_ref(), href))
+            .parse()
+            .ok()?;
+        let response = self.fetch(url.url()).ok()?;
+        let sheet = CssParser::new(CssTokenizer::new(response.body())).parse_stylesheet();
+        Some(self.resolve_imports(sheet, Some(url), 0))
+    }
+
+    // src属性を持つ全ての<img>要素を、読み込む画像として文書順に並べる
+    // キャッシュにある画像はすぐに設定し、レイアウトの前に大きさが決まるようにする
+    fn collect_images(&mut self) {
+        self.pending_images.clear();
+        let dom = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return,
+        };
+        for img in get_target_element_nodes(Some(dom), ElementKind::Img) {
+            let src = match img.borrow().get_element() {
+                Some(element) => element.get_attribute("src"),
+                None => None,
+            };
+            let url = match src {
+                Some(src) if !src.trim().is_empty() => resolve_url(self.url.as_ref(), src.trim()),
+                _ => continue,
+            };
+            match self.images.get(&url) {
+                Some(image) => img.borrow_mut().set_image(image.clone()),
+                None => self.pending_images.push_back((img, url)),
+            }
+        }
+    }
+
+    // まだ読み込んでいない画像を1つ取得してデコードし、レイアウトと描画をやり直す
+    // 取得やデコードに失敗した場合は代替テキストのまま表示する
+    // 読み込む画像が残っていない場合はfalseを返す
+    pub fn load_next_image(&mut self) -> bool {
+        let (img, url) = match self.pending_images.pop_front() {
+            Some(pending) => pending,
+            None => {
+                self.check_subresources_loaded();
+                return false;
+            }
+        };
+
+        let image = match self.images.get(&url) {
+            Some(image) => image.clone(),
+            None => {
+                let image = self
+                    .fetch(url.clone())
+                    .and_then(|response| decode_image(&response.body_bytes()))
+                    .ok()
+                    .map(Rc::new);
+                self.images.insert(url, image.clone());
+                image
+            }
+        };
+        if image.is_some() {
+            img.borrow_mut().set_image(image);
+            self.set_layout_view();
+            self.paint_tree();
+        }
+        self.check_subresources_loaded();
+        true
+    }
+
+    // 残っている全ての画像を順に読み込む
+    pub fn load_images(&mut self) {
+        while self.load_next_image() {}
+    }
+
+    fn set_layout_view(&mut self) {
+        let dom = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return,
+        };
+        self.layout_view = Some(LayoutView::with_font_metrics(
+            dom,
+            &self.styles,
+            self.viewport,
+            self.font_metrics.clone(),
+        ));
+    }
+
+    fn paint_tree(&mut self) {
+        if let Some(layout_view) = &self.layout_view {
+            let mut display_items = layout_view.paint_visible(self.scroll_offset);
+            display_items.extend(self.paint_selection(layout_view));
+            display_items.extend(self.paint_focus_ring(layout_view));
+            self.update_damage(&display_items);
+            self.display_items = display_items;
+        }
+    }
+
+    // 選択範囲の強調表示
+    // 選択したテキストの行ごとの矩形を、半透明の色で全ての内容の手前に描画する
+    fn paint_selection(&self, layout_view: &LayoutView) -> Vec<DisplayItem> {
+        let (selection, frame) = match (&self.selection, &self.frame) {
+            (Some(selection), Some(frame)) => (selection, frame),
+            _ => return Vec::new(),
+        };
+        let color = Color::from_code(SELECTION_COLOR).expect("selection color is valid");
+        let rects: Vec<DisplayItem> = selection
+            .ranges(&frame.borrow().document())
+            .iter()
+            .filter_map(|(node, from, to)| {
+                let layout_object = layout_view.find_layout_object(node)?;
+                let rects =
+                    layout_object
+                        .borrow()
+                        .text_range_rects(*from, *to, self.font_metrics.as_ref());
+                Some(rects)
+            })
+            .flatten()
+            .map(|rect| DisplayItem::Rect {
+                layout_point: LayoutPoint::new(
+                    rect.point().x(),
+                    rect.point().y() - self.scroll_offset,
+                ),
+                size: rect.size(),
+                color: color.clone(),
+                radius: 0,
+            })
+            .collect();
+        if rects.is_empty() {
+            return Vec::new();
+        }
+
+        let mut items = alloc::vec![DisplayItem::PushOpacity(SELECTION_OPACITY)];
+        items.extend(rects);
+        items.push(DisplayItem::PopOpacity);
+        items
+    }
+
+    // フォーカスリングは全ての内容の手前に、要素を囲むように描画する
+    fn paint_focus_ring(&self, layout_view: &LayoutView) -> Option<DisplayItem> {
+        let bounds = layout_view.node_bounds(self.focused_node.as_ref()?)?;
+        let widths = EdgeSizes::new(
+            FOCUS_RING_WIDTH,
+            FOCUS_RING_WIDTH,
+            FOCUS_RING_WIDTH,
+            FOCUS_RING_WIDTH,
+        );
+        let ring = bounds.expand(widths);
+        Some(DisplayItem::Border {
+            layout_point: LayoutPoint::new(ring.point().x(), ring.point().y() - self.scroll_offset),
+            size: ring.size(),
+            widths,
+            colors: EdgeColors::all(
+                Color::from_code(FOCUS_RING_COLOR).expect("focus ring color is valid"),
+            ),
+            radius: 0,
+        })
+    }
+
+    // 前のフレームの描画内容と比べて、変わった描画内容と描き直しが必要な領域を求める
+    fn update_damage(&mut self, display_items: &[DisplayItem]) {
+        self.changed_display_items = changed_display_items(&self.display_items, display_items);
+        self.damaged_rect = bounding_rect(&self.changed_display_items, self.font_metrics.as_ref());
+    }
+
+    // レスポンスのContent-Typeに合わせて、表示する文書を作る
+    // HTMLはそのまま、テキストは<pre>で囲んだ文書、画像は画像だけの文書にする
+    // それ以外の種類は表示できないので、説明する文書にする
+    // Content-Typeがない場合はHTMLとして扱う
+    fn create_document(&mut self, response: &HttpResponse) {
+        let media_type = media_type(response);
+        match media_type.as_str() {
+            "" | "text/html" | "application/xhtml+xml" => self.create_frame(html_text(response)),
+            t if t.starts_with("text/") || t == "application/json" => {
+                self.create_frame("<html><head></head><body><pre></pre></body></html>".to_string());
+                // テキストをHTMLとして解釈しないように、DOMツリーに直接テキストノードを追加する
+                let dom = self.frame.as_ref().map(|frame| frame.borrow().document());
+                if let Some(pre) = get_target_element_node(dom, ElementKind::Pre) {
+                    set_text_content(&pre, &response.body());
+                }
+            }
+            t if t.starts_with("image/") => match decode_image(&response.body_bytes()) {
+                Ok(bitmap) => {
+                    self.create_frame("<html><head></head><body><img></body></html>".to_string());
+                    let dom = self.frame.as_ref().map(|frame| frame.borrow().document());
+                    if let Some(img) = get_target_element_node(dom, ElementKind::Img) {
+                        img.borrow_mut().set_image(Some(Rc::new(bitmap)));
+                    }
+                }
+                Err(_) => self.create_unsupported_document(response, t),
+            },
+            t => self.create_unsupported_document(response, t),
+        }
+    }
+
+    fn create_unsupported_document(&mut self, response: &HttpResponse, media_type: &str) {
+        let url = self.url.as_ref().map(|url| url.url()).unwrap_or_default();
+        self.create_frame(unsupported_document(
+            &url,
+            media_type,
+            response.body_bytes().len(),
+        ));
+    }
+
+    // HTMLをパースしてDOMツリーを作る
+    // <script>は終了タグまでパースした時点で実行し、DOMツリーの続きはその後にパースする
+    // async属性を持つ外部スクリプトはパースを止めずに、文書の最後までパースしてから実行する
     fn create_frame(&mut self, html: String) {
         let html_tokenizer = HtmlTokenizer::new(html);
-        let frame = HtmlParser::new(html_tokenizer).construct_tree();
+        let mut parser = HtmlParser::new(html_tokenizer);
+        if let Some(url) = &self.url {
+            parser.window().borrow_mut().set_location(url.clone());
+        }
+        let mut runtime = JsRuntime::new(parser.window());
+        let mut async_scripts = Vec::new();
+        while let Some(script) = parser.parse_until_script() {
+            let (src, is_async) = match script.borrow().get_element() {
+                Some(element) => (
+                    element.get_attribute("src"),
+                    element.get_attribute("async").is_some(),
+                ),
+                None => continue,
+            };
+            match src {
+                Some(src) if is_async => async_scripts.push(src),
+                Some(src) => self.execute_external_script(&mut runtime, &src),
+                None => execute_script(&mut runtime, &get_text_content(&script)),
+            }
+        }
+        let frame = parser.construct_tree();
+        runtime.register_event_handlers();
+        for src in async_scripts {
+            self.execute_external_script(&mut runtime, &src);
+        }
         self.frame = Some(frame);
+        self.js_runtime = Some(runtime);
+        self.hovered_node = None;
+        self.active_node = None;
+        self.focused_node = None;
+        self.selection = None;
+        self.scroll_offset = 0;
+    }
+
+    // src属性で指定されたスクリプトをリソースローダーで取得して実行する
+    // 取得できなかったスクリプトは実行せず、コンソールにエラーを出力する
+    fn execute_external_script(&self, runtime: &mut JsRuntime, src: &str) {
+        if src.trim().is_empty() {
+            return;
+        }
+        let url = resolve_url(self.url.as_ref(), src.trim());
+        match self.fetch(url.clone()) {
+            Ok(response) => execute_script(runtime, &response.body()),
+            Err(_) => runtime.print(ConsoleMessage::new(
+                ConsoleLevel::Error,
+                alloc::format!("Failed to load script: {}", url),
+            )),
+        }
+    }
+}
+
+// スクリプトを実行する
+// 実行時のエラーはコンソールに出力してそのスクリプトだけを止め、文書のパースは続ける
+// 2つのノードが両方ともない、または同じノードか
+fn is_same_node(a: &Option<Rc<RefCell<Node>>>, b: &Option<Rc<RefCell<Node>>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn execute_script(runtime: &mut JsRuntime, source: &str) {
+    if let Err(error) = runtime.execute_script(source) {
+        runtime.print(ConsoleMessage::from_error(&error));
+    }
+}
+
+// 読み込みに失敗した時に表示する文書のHTML
+// エラーの種類ごとの見出しと、読み込もうとしたURL、エラーの詳細を表示する
+fn error_document(url: &str, error: &Error) -> String {
+    let (title, detail) = match error {
+        Error::Network(detail) => ("Network error", detail),
+        Error::UnexpectedInput(detail) => ("Invalid address", detail),
+        Error::InvalidUI(detail) | Error::Other(detail) => ("Failed to load", detail),
+    };
+    alloc::format!(
+        "<html><head><style>h1 {{ color: #b00020; }}</style></head><body><h1>{}</h1><p>{}</p><p>{}</p></body></html>",
+        title,
+        strip_tags(url),
+        strip_tags(detail)
+    )
+}
+
+// ブックマークの一覧を、タイトルをテキストにしたリンクとして表示する文書のHTML
+fn bookmarks_document(bookmarks: &[Bookmark]) -> String {
+    let items: String = if bookmarks.is_empty() {
+        String::from("<p>No bookmarks yet.</p>")
+    } else {
+        bookmarks
+            .iter()
+            .map(|bookmark| {
+                alloc::format!(
+                    "<p><a href=\"{}\">{}</a></p>",
+                    strip_tags(&bookmark.url()).replace('"', ""),
+                    strip_tags(&bookmark.title())
+                )
+            })
+            .collect()
+    };
+    alloc::format!(
+        "<html><head></head><body><h1>Bookmarks</h1>{}</body></html>",
+        items
+    )
+}
+
+// 表示できない種類のファイルを受け取った時に表示する文書のHTML
+// ファイルを保存する機能はないので、種類と大きさだけを表示する
+fn unsupported_document(url: &str, content_type: &str, size: usize) -> String {
+    alloc::format!(
+        "<html><head></head><body><h1>Cannot display file</h1><p>{}</p><p>{} ({} bytes)</p><p>Downloading files is not supported.</p></body></html>",
+        strip_tags(url),
+        strip_tags(content_type),
+        size
+    )
+}
+
+// レスポンスのContent-Typeのメディアタイプ（小文字、パラメータを除く）
+// Content-Typeがない場合は空文字列
+fn media_type(response: &HttpResponse) -> String {
+    match response.content_type() {
+        Some(value) => value
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase(),
+        None => String::new(),
+    }
+}
+
+// HTMLのボディを文字列にする
+// 文字エンコーディングはContent-Typeのcharset、<meta charset>、UTF-8の順に決める
+// https://html.spec.whatwg.org/multipage/parsing.html#determining-the-character-encoding
+fn html_text(response: &HttpResponse) -> String {
+    let bytes = response.body_bytes();
+    let encoding = response
+        .charset()
+        .or_else(|| encoding::prescan_meta_charset(&bytes));
+    encoding::decode(&bytes, encoding)
+}
+
+// 文字参照はサポートしていないので、タグとして解釈されないように<と>を取り除く
+fn strip_tags(s: &str) -> String {
+    s.chars().filter(|c| *c != '<' && *c != '>').collect()
+}
+
+// <meta http-equiv="refresh">のcontent属性（"5; url=next.html"など）を解析する
+// 秒数の小数部分は切り捨てる。URLは引用符で囲まれていてもよい
+// https://html.spec.whatwg.org/multipage/semantics.html#shared-declarative-refresh-steps
+fn parse_refresh(content: &str) -> Option<(u64, Option<String>)> {
+    let content = content.trim_start();
+    let digits = content
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(content.len());
+    let delay = content[..digits].parse::<u64>().ok()?;
+
+    let rest = content[digits..].trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let rest = rest.trim_start();
+    let rest = rest
+        .strip_prefix(';')
+        .or_else(|| rest.strip_prefix(','))
+        .unwrap_or(rest)
+        .trim_start();
+    if rest.is_empty() {
+        return Some((delay, None));
+    }
+
+    let mut url = rest;
+    if url.len() >= 3 && url[..3].eq_ignore_ascii_case("url") {
+        if let Some(value) = url[3..].trim_start().strip_prefix('=') {
+            url = value.trim_start();
+        }
+    }
+    let url = match url.chars().next() {
+        Some(quote) if quote == '"' || quote == '\'' => {
+            let url = &url[1..];
+            url.split_once(quote).map_or(url, |(url, _)| url)
+        }
+        _ => url,
+    };
+    Some((delay, Some(url.trim().to_string())))
+}
+
+// URLにuserinfoがある場合は、Basic認証のAuthorizationヘッダ
+fn authorization_header(url: &Url) -> Option<Header> {
+    url.basic_authorization()
+        .map(|value| Header::new("Authorization".to_string(), value))
+}
+
+// 相対URLをページのURLを基準に絶対URLにする
+pub fn resolve_url(base: Option<&Url>, href: &str) -> String {
+    // サポートしているschemeで始まる場合は絶対URL
+    if href
+        .split_once(':')
+        .is_some_and(|(scheme, _)| Scheme::from_name(scheme).is_some())
+    {
+        return href.to_string();
+    }
+
+    let base = match base {
+        Some(base) => base,
+        None => return href.to_string(),
+    };
+
+    // fragmentだけの場合は同じ文書の中の位置
+    if href.starts_with('#') {
+        let url = base.url();
+        let document = url
+            .split_once('#')
+            .map_or(url.as_str(), |(document, _)| document);
+        return alloc::format!("{}{}", document, href);
+    }
+    let origin = base.origin();
+
+    // 絶対パスの場合はホストの直下
+    if let Some(path) = href.strip_prefix('/') {
+        return alloc::format!("{}/{}", origin, path);
+    }
+
+    // 相対パスの場合は基準のURLのディレクトリからの相対位置
+    let base_path = base.raw_path();
+    let directory = match base_path.rfind('/') {
+        Some(index) => &base_path[..index + 1],
+        None => "",
+    };
+    alloc::format!("{}/{}{}", origin, directory, href)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::CHAR_HEIGHT_WITH_PADDING;
+    use crate::constants::CHAR_WIDTH;
+    use crate::renderer::css::cssom::Selector;
+    use crate::renderer::dom::node::NodeKind;
+    use crate::renderer::layout::computed_style::Color;
+    use crate::renderer::layout::computed_style::TextDecoration;
+    use crate::renderer::layout::layout_object::LayoutPoint;
+
+    // 下の行から並んだ24bitの2x2のBMP画像
+    const TEST_BMP: [u8; 70] = [
+        0x42, 0x4d, 0x46, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x36, 0x00, 0x00, 0x00, 0x28,
+        0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x18, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x13, 0x0b, 0x00, 0x00, 0x13, 0x0b, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0xff, 0xff, 0xff,
+        0x00, 0x00, 0x00, 0x00, 0xff, 0x00, 0xff, 0x00, 0x00, 0x00,
+    ];
+
+    #[derive(Debug)]
+    struct TestLoader;
+
+    impl ResourceLoader for TestLoader {
+        fn fetch(&self, url: Url) -> Result<HttpResponse, Error> {
+            let url = url.url();
+            if url == "http://example.com:80/img/a.bmp" {
+                let mut raw = b"HTTP/1.1 200 OK\n\n".to_vec();
+                raw.extend_from_slice(&TEST_BMP);
+                return HttpResponse::from_bytes(raw);
+            }
+            let body = match url.as_str() {
+                "http://example.com:80/css/a.css" => "@import 'b.css'; h1 { color: red; }",
+                "http://example.com:80/css/b.css" => "h2 { color: red; }",
+                "http://example.com:80/index.html" => "<html><head></head><body>new</body></html>",
+                "http://example.com:80/js/a.js" => "log = log + 'a';",
+                "http://example.com:80/js/b.js" => {
+                    "log = log + 'b'; document.getElementById('p').textContent = log;"
+                }
+                _ => return Err(Error::Network(url)),
+            };
+            HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", body))
+        }
+    }
+
+    // ネットワークにアクセスした回数を数えるローダー
+    #[derive(Debug, Default)]
+    struct CountingLoader {
+        fetches: core::cell::Cell<usize>,
+    }
+
+    impl ResourceLoader for CountingLoader {
+        fn fetch(&self, url: Url) -> Result<HttpResponse, Error> {
+            self.fetches.set(self.fetches.get() + 1);
+            let raw = match url.url().as_str() {
+                "http://example.com:80/index.html" => {
+                    "HTTP/1.1 200 OK\n\n<html><head><link rel=\"stylesheet\" href=\"style.css\"></head></html>"
+                }
+                "http://example.com:80/style.css" => {
+                    "HTTP/1.1 200 OK\nCache-Control: max-age=60\n\np { color: red; }"
+                }
+                _ => return Err(Error::Network(url.url())),
+            };
+            HttpResponse::new(raw.to_string())
+        }
+    }
+
+    // 相対URLの解決
+    #[test]
+    fn test_resolve_url() {
+        let base = Url::new("http://example.com/dir/index.html".to_string())
+            .parse()
+            .expect("url should be valid");
+        assert_eq!(
+            "http://example.com:80/dir/a.css",
+            resolve_url(Some(&base), "a.css")
+        );
+        assert_eq!(
+            "http://example.com:80/a.css",
+            resolve_url(Some(&base), "/a.css")
+        );
+        assert_eq!(
+            "http://other.com/a.css",
+            resolve_url(Some(&base), "http://other.com/a.css")
+        );
+        assert_eq!(
+            "http://example.com/dir/index.html#top",
+            resolve_url(Some(&base), "#top")
+        );
+
+        let base = Url::new("https://example.com/dir/index.html".to_string())
+            .parse()
+            .expect("url should be valid");
+        assert_eq!(
+            "https://example.com:443/a.css",
+            resolve_url(Some(&base), "/a.css")
+        );
+        assert_eq!(
+            "https://other.com/a.css",
+            resolve_url(Some(&base), "https://other.com/a.css")
+        );
+    }
+
+    // <meta http-equiv="refresh">のcontent属性の解析と、移動先のURLの解決
+    #[test]
+    fn test_refresh() {
+        assert_eq!(Some((0, None)), parse_refresh("0"));
+        assert_eq!(
+            Some((5, Some("next.html".to_string()))),
+            parse_refresh(" 5.5; URL = 'next.html' ")
+        );
+        assert_eq!(
+            Some((3, Some("a.html".to_string()))),
+            parse_refresh("3,a.html")
+        );
+        assert_eq!(None, parse_refresh("soon; url=a.html"));
+
+        let mut page = Page::new();
+        page.set_url(
+            Url::new("http://example.com/dir/index.html".to_string())
+                .parse()
+                .expect("url should be valid"),
+        );
+        page.receive_response(
+            HttpResponse::new(
+                "HTTP/1.1 200 OK\n\n<html><head><meta charset=\"utf-8\"><meta http-equiv=\"Refresh\" content=\"2; url=next.html\"></head><body>text</body></html>"
+                    .to_string(),
+            )
+            .expect("response should be valid"),
+        );
+        assert_eq!(
+            Some((2, "http://example.com:80/dir/next.html".to_string())),
+            page.refresh()
+        );
+    }
+
+    // @importで指定されたスタイルシートが入れ子も含めて順番通りにマージされるか
+    #[test]
+    fn test_load_imports() {
+        let mut page = Page::new();
+        page.set_url(
+            Url::new("http://example.com/index.html".to_string())
+                .parse()
+                .expect("url should be valid"),
+        );
+        let html = "<html><head><style>@import url(css/a.css); @import 'missing.css'; p { color: red; }</style></head></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+        page.set_resource_loader(Rc::new(TestLoader));
+        page.load_imports();
+
+        // 先頭はUAスタイルシート
+        let styles = page.styles();
+        assert_eq!(2, styles.len());
+        let selectors: Vec<Selector> = styles[1].rules.iter().map(|r| r.selector.clone()).collect();
+        assert_eq!(
+            alloc::vec![
+                Selector::TypeSelector("h2".to_string()),
+                Selector::TypeSelector("h1".to_string()),
+                Selector::TypeSelector("p".to_string()),
+            ],
+            selectors
+        );
+    }
+
+    // 訪問済みのURLへのリンクが:visitedにマッチするか
+    #[test]
+    fn test_mark_visited() {
+        let mut page = Page::new();
+        page.set_url(
+            Url::new("http://example.com/index.html".to_string())
+                .parse()
+                .expect("url should be valid"),
+        );
+        let html = "<html><head><style>a:visited { color: red; }</style></head><body><a href=\"/a.html\">x</a><a href=\"b.html\">y</a></body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+        page.mark_visited("http://example.com:80/a.html".to_string());
+        assert!(page.is_visited("http://example.com:80/a.html"));
+
+        let body = page
+            .layout_view()
+            .expect("layout view should exist")
+            .root()
+            .expect("root should exist");
+        let visited = body
+            .borrow()
+            .first_child()
+            .expect("first link should exist");
+        assert_eq!(
+            Color::from_name("red").expect("red should exist"),
+            visited.borrow().style().color()
+        );
+        assert_eq!(
+            TextDecoration::Underline,
+            visited.borrow().style().text_decoration()
+        );
+
+        // 訪問していないリンクはUAスタイルシートの色
+        let unvisited = visited
+            .borrow()
+            .next_sibling()
+            .expect("second link should exist");
+        assert_eq!(
+            Color::from_name("blue").expect("blue should exist"),
+            unvisited.borrow().style().color()
+        );
+    }
+
+    // ビューポートのサイズを変えると、レイアウトと描画する内容が作り直されるか
+    #[test]
+    fn test_resize() {
+        let mut page = Page::new();
+        let html = "<html><head></head><body><p>aaaa bbbb cccc dddd</p></body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+        assert_eq!(1, page.display_items().len());
+
+        // 1行に10文字（80ピクセル）までしか入らない
+        page.resize(80, 100);
+        assert_eq!(LayoutSize::new(80, 100), page.viewport());
+        let body = page
+            .layout_view()
+            .expect("layout view should exist")
+            .root()
+            .expect("root should exist");
+        assert_eq!(80, body.borrow().size().width());
+        assert_eq!(2, page.display_items().len());
+    }
+
+    // スクロールした分だけ描画する位置がずれ、見えない部分は描画されないか
+    #[test]
+    fn test_scroll_by() {
+        let mut page = Page::new();
+        let html = alloc::format!(
+            "<html><head></head><body>{}</body></html>",
+            "<p>x</p>".repeat(30)
+        );
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+        page.resize(100, 200);
+
+        // 1行20ピクセルの段落が30個で、ページの高さは600ピクセル
+        assert_eq!(10, page.display_items().len());
+        assert_eq!(600, page.content_height());
+
+        page.scroll_by(100);
+        assert_eq!(100, page.scroll_offset());
+        let items = page.display_items();
+        assert_eq!(10, items.len());
+        assert_eq!(
+            0,
+            items[0]
+                .layout_point()
+                .expect("text should have a position")
+                .y()
+        );
+
+        // 下端より下にはスクロールしない
+        page.scroll_by(1000);
+        assert_eq!(400, page.scroll_offset());
+        page.scroll_by(-1000);
+        assert_eq!(0, page.scroll_offset());
+    }
+
+    // 前のフレームから変わった描画内容と、描き直しが必要な領域が分かるか
+    #[test]
+    fn test_damaged_rect() {
+        let mut page = Page::new();
+        page.set_url(
+            Url::new("http://example.com/index.html".to_string())
+                .parse()
+                .expect("url should be valid"),
+        );
+        let html = "<html><head><style>a:visited { color: red; }</style></head><body><p>x</p><p><a href=\"/a.html\">y</a></p></body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+        // 最初のフレームは全てが変わっている
+        assert_eq!(3, page.changed_display_items().len());
+
+        // 同じ内容で描き直した場合は何も変わらない
+        page.scroll_by(0);
+        assert!(page.changed_display_items().is_empty());
+        assert_eq!(None, page.damaged_rect());
+
+        // リンクの色が変わるので、リンクの文字と下線だけを描き直す
+        // 下線は太さの1px分だけ右にはみ出す
+        page.mark_visited("http://example.com:80/a.html".to_string());
+        assert_eq!(4, page.changed_display_items().len());
+        assert_eq!(
+            Some(LayoutRect::new(
+                LayoutPoint::new(0, CHAR_HEIGHT_WITH_PADDING),
+                LayoutSize::new(CHAR_WIDTH + 1, CHAR_HEIGHT_WITH_PADDING)
+            )),
+            page.damaged_rect()
+        );
+    }
+
+    // スクリプトのようにDOMツリーを変更した後、テキストとスタイルの変更が描画内容に反映されるか
+    #[test]
+    fn test_update_rendering() {
+        let mut page = Page::new();
+        let html = "<html><head><style>h1 { color: blue; }</style></head><body><h1 id=\"title\">hello</h1></body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+        page.clear_dirty();
+        assert!(!page.update_rendering());
+
+        let text_color = |page: &Page| {
+            page.display_items()
+                .into_iter()
+                .find_map(|item| match item {
+                    DisplayItem::Text { text, color, .. } => Some((text, color)),
+                    _ => None,
+                })
+        };
+        let frame = page.frame().expect("frame should exist");
+        let title = frame
+            .borrow()
+            .get_element_by_id("title")
+            .expect("title should exist");
+        set_text_content(&title, "hi");
+        // 反映するまでは描画内容は変わらない
+        assert_eq!(
+            Some("hello".to_string()),
+            text_color(&page).map(|(text, _)| text)
+        );
+        assert!(page.update_rendering());
+        assert_eq!(
+            Some((
+                "hi".to_string(),
+                Color::from_code("#0000ff").expect("color should be valid")
+            )),
+            text_color(&page)
+        );
+        assert!(!page.needs_update());
+
+        let style = get_target_element_node(Some(frame.borrow().document()), ElementKind::Style)
+            .expect("style should exist");
+        set_text_content(&style, "h1 { color: red; }");
+        assert!(page.update_rendering());
+        assert_eq!(
+            Some(Color::from_code("#ff0000").expect("color should be valid")),
+            text_color(&page).map(|(_, color)| color)
+        );
+    }
+
+    // UIがマウスの位置で当たり判定をしたノードを設定すると、:hoverと:activeのスタイルが反映されるか
+    // 同じノードを設定し直しても、スタイルを計算し直さないか
+    #[test]
+    fn test_hover_and_active() {
+        let mut page = Page::new();
+        let html = "<html><head><style>a:hover { color: red; } a:active { color: blue; }</style></head><body><a href=\"next.html\">link</a></body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+        page.clear_dirty();
+
+        let text_color = |page: &Page| {
+            page.display_items()
+                .into_iter()
+                .find_map(|item| match item {
+                    DisplayItem::Text { color, .. } => Some(color),
+                    _ => None,
+                })
+        };
+        let link = page.clicked(1, 1);
+        assert!(link.is_some());
+
+        page.set_hovered_node(link.clone());
+        assert!(page.update_rendering());
+        assert_eq!(
+            Some(Color::from_name("red").expect("red should exist")),
+            text_color(&page)
+        );
+        page.set_hovered_node(link.clone());
+        assert!(!page.update_rendering());
+
+        page.set_active_node(link);
+        assert!(page.update_rendering());
+        assert_eq!(
+            Some(Color::from_name("blue").expect("blue should exist")),
+            text_color(&page)
+        );
+
+        page.set_active_node(None);
+        page.set_hovered_node(None);
+        assert!(page.update_rendering());
+        assert_ne!(
+            Some(Color::from_name("red").expect("red should exist")),
+            text_color(&page)
+        );
+    }
+
+    // setTimeoutで予約したタスクが時刻になると実行され、DOMツリーの変更が描画内容に反映されるか
+    #[test]
+    fn test_run_pending_tasks() {
+        let mut page = Page::new();
+        assert!(!page.run_pending_tasks(0));
+        let html = "<html><head></head><body><p id=\"p\">wait</p><script>setTimeout(() => document.getElementById('p').textContent = 'done', 500);</script></body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+        page.clear_dirty();
+
+        let texts = |page: &Page| -> Vec<String> {
+            page.display_items()
+                .into_iter()
+                .filter_map(|item| match item {
+                    DisplayItem::Text { text, .. } => Some(text),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert!(!page.run_pending_tasks(499));
+        assert!(!page.update_rendering());
+        assert!(page.run_pending_tasks(500));
+        assert!(page.update_rendering());
+        assert_eq!(alloc::vec!["done".to_string()], texts(&page));
+    }
+
+    // スクロールしている時に、クリックした位置のノードが見つかるか
+    #[test]
+    fn test_clicked() {
+        let mut page = Page::new();
+        let paragraphs: String = (0..30).map(|i| alloc::format!("<p>{}</p>", i)).collect();
+        let html = alloc::format!("<html><head></head><body>{}</body></html>", paragraphs);
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+        assert!(page.clicked(0, 5).is_some());
+
+        page.scroll_by(100);
+        let node = page.clicked(0, 5).expect("node should be clicked");
+        assert_eq!(NodeKind::Text("5".to_string()), node.borrow().kind());
+    }
+
+    // <link rel="stylesheet">のスタイルシートが文書順に取得され、失敗したものは無視されるか
+    #[test]
+    fn test_external_stylesheets() {
+        let mut page = Page::new();
+        page.set_resource_loader(Rc::new(TestLoader));
+        page.set_url(
+            Url::new("http://example.com/index.html".to_string())
+                .parse()
+                .expect("url should be valid"),
+        );
+        let html = "<html><head><link rel=\"stylesheet\" href=\"css/a.css\"><link rel=\"stylesheet\" href=\"missing.css\"><style>p { color: red; }</style></head></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+
+        let styles = page.styles();
+        assert_eq!(3, styles.len());
+        let selectors: Vec<Vec<Selector>> = styles[1..]
+            .iter()
+            .map(|sheet| sheet.rules.iter().map(|r| r.selector.clone()).collect())
+            .collect();
+        assert_eq!(
+            alloc::vec![
+                alloc::vec![
+                    Selector::TypeSelector("h2".to_string()),
+                    Selector::TypeSelector("h1".to_string()),
+                ],
+                alloc::vec![Selector::TypeSelector("p".to_string())],
+            ],
+            selectors
+        );
+    }
+
+    // インラインと外部のスクリプトが文書順に実行され、async属性を持つスクリプトは最後に実行されるか
+    // 取得に失敗したスクリプトは無視される
+    #[test]
+    fn test_scripts() {
+        let mut page = Page::new();
+        page.set_resource_loader(Rc::new(TestLoader));
+        page.set_url(
+            Url::new("http://example.com/index.html".to_string())
+                .parse()
+                .expect("url should be valid"),
+        );
+        let html = "<html><head><script>var log = '';</script><script src=\"js/b.js\" async></script><script src=\"js/a.js\"></script><script src=\"missing.js\"></script></head><body><p id=\"p\">x</p><script>log = log + document.getElementById('p').textContent;</script></body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+
+        let texts: Vec<String> = page
+            .display_items()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(alloc::vec!["axb".to_string()], texts);
+        assert_eq!(
+            alloc::vec![ConsoleMessage::new(
+                ConsoleLevel::Error,
+                "Failed to load script: http://example.com:80/missing.js".to_string()
+            )],
+            page.console_messages()
+        );
+    }
+
+    // 再読み込みで、キャッシュを使う場合は同じ文書、使わない場合は取得し直した文書になるか
+    #[test]
+    fn test_reload() {
+        let mut page = Page::new();
+        page.set_resource_loader(Rc::new(TestLoader));
+        assert!(page.reload(true).is_err());
+
+        page.set_url(
+            Url::new("http://example.com:80/index.html".to_string())
+                .parse()
+                .expect("url should be valid"),
+        );
+        let html = "<html><head></head><body>old</body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+
+        let texts = |page: &Page| -> Vec<String> {
+            page.display_items()
+                .into_iter()
+                .filter_map(|item| match item {
+                    DisplayItem::Text { text, .. } => Some(text),
+                    _ => None,
+                })
+                .collect()
+        };
+        page.reload(false).expect("reload should succeed");
+        assert_eq!(alloc::vec!["old".to_string()], texts(&page));
+
+        page.reload(true).expect("reload should succeed");
+        assert_eq!(alloc::vec!["new".to_string()], texts(&page));
+    }
+
+    // リンクの中をクリックすると、現在のURLで解決したリンク先が返るか
+    #[test]
+    fn test_on_click() {
+        let mut page = Page::new();
+        page.set_url(
+            Url::new("http://example.com/dir/index.html".to_string())
+                .parse()
+                .expect("url should be valid"),
+        );
+        let html = "<html><head></head><body><p>x</p><p><a href=\"a.html\">y</a></p></body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+
+        assert_eq!(None, page.on_click(0, 5));
+        assert_eq!(
+            Some("http://example.com:80/dir/a.html".to_string()),
+            page.on_click(0, CHAR_HEIGHT_WITH_PADDING + 5)
+        );
+    }
+
+    // クリックでonclick属性とaddEventListenerのリスナーが実行され、キャンセルされたリンクには移動しないか
+    #[test]
+    fn test_click_handlers() {
+        let mut page = Page::new();
+        let html = "<html><head></head><body><p id=\"p\" onclick=\"this.textContent = event.type\">x</p><p><a id=\"a\" href=\"a.html\" onclick=\"return false\">y</a></p><p><a id=\"b\" href=\"b.html\">z</a></p><script>document.getElementById('b').addEventListener('click', function (e) { e.preventDefault(); document.getElementById('p').textContent = e.target.id; });</script></body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+        let p = page
+            .frame()
+            .expect("frame should exist")
+            .borrow()
+            .get_element_by_id("p")
+            .expect("p should exist");
+
+        assert_eq!(None, page.on_click(0, 5));
+        assert_eq!("click", get_text_content(&p));
+        assert_eq!(None, page.on_click(0, CHAR_HEIGHT_WITH_PADDING + 5));
+        assert_eq!(None, page.on_click(0, CHAR_HEIGHT_WITH_PADDING * 2 + 5));
+        assert_eq!("b", get_text_content(&p));
+        assert!(page.update_rendering());
+    }
+
+    // Tabキーでリンクや入力欄の間をフォーカスが文書の順番に移動し、フォーカスリングが描画されるか
+    #[test]
+    fn test_focus_next() {
+        let mut page = Page::new();
+        page.set_url(
+            Url::new("http://example.com/dir/index.html".to_string())
+                .parse()
+                .expect("url should be valid"),
+        );
+        let html = "<html><head><style>a:focus { color: red; } .none { display: none; }</style></head><body><p><a href=\"a.html\">a</a><a>no href</a><a class=\"none\" href=\"none.html\">none</a></p><input type=\"hidden\"><input name=\"q\"><button disabled>off</button><button>on</button></body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+        let focused = |page: &Page| {
+            page.focused_node()
+                .and_then(|node| node.borrow().element_kind())
+        };
+        let has_ring = |page: &Page| {
+            page.display_items().iter().any(|item| {
+                matches!(item, DisplayItem::Border { colors, .. }
+                    if *colors == EdgeColors::all(Color::from_code(FOCUS_RING_COLOR).expect("color should be valid")))
+            })
+        };
+        assert!(!has_ring(&page));
+
+        assert!(page.focus_next(false));
+        assert_eq!(Some(ElementKind::A), focused(&page));
+        assert_eq!(
+            Some("http://example.com:80/dir/a.html".to_string()),
+            page.focused_link()
+        );
+        assert!(has_ring(&page));
+        // :focusのスタイルが適用される
+        assert!(page.display_items().iter().any(|item| matches!(
+            item,
+            DisplayItem::Text { text, color, .. }
+                if text == "a" && *color == Color::from_name("red").expect("color should be valid")
+        )));
+
+        assert!(page.focus_next(false));
+        assert_eq!(Some(ElementKind::Input), focused(&page));
+        assert_eq!(None, page.focused_link());
+        assert!(page.focus_next(false));
+        assert_eq!(Some(ElementKind::Button), focused(&page));
+        // 最後の次は最初に戻り、逆向きでは最後に戻る
+        assert!(page.focus_next(false));
+        assert_eq!(Some(ElementKind::A), focused(&page));
+        assert!(page.focus_next(true));
+        assert_eq!(Some(ElementKind::Button), focused(&page));
+
+        // フォーカスを移せる要素の外をクリックするとフォーカスが外れる
+        page.focus_at(0, 200);
+        assert_eq!(None, focused(&page));
+        assert!(!has_ring(&page));
+    }
+
+    // マウスのドラッグで選択したテキストが強調表示され、取り出せるか
+    #[test]
+    fn test_selection() {
+        let mut page = Page::new();
+        let html = "<html><head></head><body><p>hello world</p><p>second line</p></body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+        let highlights = |page: &Page| {
+            page.display_items()
+                .into_iter()
+                .skip_while(|item| *item != DisplayItem::PushOpacity(SELECTION_OPACITY))
+                .filter_map(|item| match item {
+                    DisplayItem::Rect {
+                        layout_point, size, ..
+                    } => Some((layout_point.x(), layout_point.y(), size.width())),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // "hello "の後ろから、2行目の"sec"の後ろまで
+        page.start_selection(CHAR_WIDTH * 6, 5);
+        assert!(page
+            .selection()
+            .expect("selection should exist")
+            .is_collapsed());
+        assert!(highlights(&page).is_empty());
+        page.extend_selection(CHAR_WIDTH * 3 + 2, CHAR_HEIGHT_WITH_PADDING + 5);
+        assert_eq!("world\nsec", page.selected_text());
+        assert_eq!(
+            alloc::vec![
+                (CHAR_WIDTH * 6, 0, CHAR_WIDTH * 5),
+                (0, CHAR_HEIGHT_WITH_PADDING, CHAR_WIDTH * 3)
+            ],
+            highlights(&page)
+        );
+
+        // テキストの外にドラッグしても選択範囲は変わらない
+        page.extend_selection(0, 500);
+        assert_eq!("world\nsec", page.selected_text());
+
+        page.clear_selection();
+        assert_eq!("", page.selected_text());
+        assert!(highlights(&page).is_empty());
+    }
+
+    // Content-Typeに合わせて、テキストは改行や空白をそのまま、画像は画像だけを表示するか
+    #[test]
+    fn test_content_type() {
+        let mut page = Page::new();
+        let texts = |page: &Page| {
+            page.display_items()
+                .into_iter()
+                .filter_map(|item| match item {
+                    DisplayItem::Text {
+                        text, layout_point, ..
+                    } => Some((text, layout_point.y())),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // テキストはHTMLとして解釈しない
+        page.receive_response(
+            HttpResponse::new(
+                "HTTP/1.1 200 OK\nContent-Type: text/plain; charset=utf-8\n\n<p>a</p>\n  b  c\n"
+                    .to_string(),
+            )
+            .expect("response should be valid"),
+        );
+        let lines = texts(&page);
+        assert_eq!(2, lines.len());
+        assert_eq!("<p>a</p>", lines[0].0);
+        assert_eq!("  b  c", lines[1].0);
+        assert_eq!(lines[0].1 + CHAR_HEIGHT_WITH_PADDING, lines[1].1);
+
+        page.receive_response(
+            HttpResponse::new("HTTP/1.1 200 OK\nContent-Type: Text/HTML\n\n<p>a</p>".to_string())
+                .expect("response should be valid"),
+        );
+        assert_eq!(alloc::vec![("a".to_string(), lines[0].1)], texts(&page));
+
+        // Content-Typeにcharsetがない場合は、<meta charset>の文字エンコーディングで読む
+        let mut raw = b"HTTP/1.1 200 OK\nContent-Type: text/html\n\n".to_vec();
+        raw.extend_from_slice(b"<head><meta charset=shift_jis></head><p>\x82\xa0</p>");
+        page.receive_response(HttpResponse::from_bytes(raw).expect("response should be valid"));
+        assert_eq!(alloc::vec![("あ".to_string(), lines[0].1)], texts(&page));
+
+        let mut raw = b"HTTP/1.1 200 OK\nContent-Type: image/bmp\n\n".to_vec();
+        raw.extend_from_slice(&TEST_BMP);
+        page.receive_response(HttpResponse::from_bytes(raw).expect("response should be valid"));
+        assert!(page
+            .display_items()
+            .iter()
+            .any(|item| matches!(item, DisplayItem::Image { .. })));
+
+        page.receive_response(
+            HttpResponse::new("HTTP/1.1 200 OK\nContent-Type: application/zip\n\nPK".to_string())
+                .expect("response should be valid"),
+        );
+        assert!(texts(&page)
+            .iter()
+            .any(|(text, _)| text == "application/zip (2 bytes)"));
+    }
+
+    // <img>の画像を読み込み、画像の大きさでレイアウトし直すか
+    #[test]
+    fn test_load_images() {
+        let mut page = Page::new();
+        page.set_resource_loader(Rc::new(TestLoader));
+        page.set_url(
+            Url::new("http://example.com/index.html".to_string())
+                .parse()
+                .expect("url should be valid"),
+        );
+        let html = "<html><body><img src=\"img/a.bmp\" width=\"4\"><img src=\"missing.bmp\" alt=\"alt\"></body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+
+        // 読み込む前は代替テキストを表示する
+        let is_image = |item: &DisplayItem| matches!(item, DisplayItem::Image { .. });
+        assert!(!page.display_items().iter().any(is_image));
+
+        assert!(page.load_next_image());
+        let image = page
+            .display_items()
+            .into_iter()
+            .find(is_image)
+            .expect("image should be painted");
+        match image {
+            DisplayItem::Image { size, bitmap, .. } => {
+                // 幅だけが指定されているので、縦横比を保って高さを決める
+                assert_eq!(LayoutSize::new(4, 4), size);
+                assert_eq!(Some(0xff0000ff), bitmap.pixel(0, 1));
+            }
+            _ => unreachable!(),
+        }
+
+        // 読み込めなかった画像は代替テキストのまま
+        assert!(page.load_next_image());
+        assert!(!page.load_next_image());
+        assert!(page
+            .display_items()
+            .iter()
+            .any(|item| matches!(item, DisplayItem::Text { text, .. } if text == "alt")));
+    }
+
+    // 読み込みの各段階のイベントが順に記録され、全ての画像を読み込んでからサブリソースの読み込みが終わるか
+    #[test]
+    fn test_load_events() {
+        let mut page = Page::new();
+        page.set_resource_loader(Rc::new(TestLoader));
+        page.set_url(
+            Url::new("http://example.com/index.html".to_string())
+                .parse()
+                .expect("url should be valid"),
+        );
+        page.start_load("http://example.com/index.html".to_string());
+        assert!(page.is_loading());
+        let html = "<html><body><img src=\"img/a.bmp\"><img src=\"missing.bmp\"></body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+        assert_eq!(
+            alloc::vec![
+                LoadEvent::Started("http://example.com/index.html".to_string()),
+                LoadEvent::DomReady
+            ],
+            page.take_load_events()
+        );
+
+        assert!(page.load_next_image());
+        assert!(page.take_load_events().is_empty());
+        assert!(page.load_next_image());
+        assert_eq!(
+            alloc::vec![LoadEvent::SubresourcesLoaded],
+            page.take_load_events()
+        );
+
+        page.finish_load();
+        assert!(!page.is_loading());
+        assert_eq!(alloc::vec![LoadEvent::Finished], page.take_load_events());
+        assert!(page.take_load_events().is_empty());
+    }
+
+    // 読み込みに失敗した時に、エラーの内容を説明する文書が表示されるか
+    #[test]
+    fn test_receive_error() {
+        let mut page = Page::new();
+        page.receive_error(
+            "http://example.com/<a>".to_string(),
+            &Error::Network("Failed to connect to TCP stream".to_string()),
+        );
+
+        let texts: Vec<String> = page
+            .display_items()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            alloc::vec![
+                "Network error".to_string(),
+                "http://example.com/a".to_string(),
+                "Failed to connect to TCP stream".to_string(),
+            ],
+            texts
+        );
+
+        // エラーの文書はキャッシュしないので、再読み込みで取得し直す
+        assert_eq!(
+            Err(Error::Other("resource loader is not set".to_string())),
+            page.reload(false)
+        );
+    }
+
+    // HTTPキャッシュの新鮮なレスポンスは、ネットワークにアクセスせずに使われるか
+    #[test]
+    fn test_http_cache() {
+        let loader = Rc::new(CountingLoader::default());
+        let mut page = Page::new();
+        page.set_resource_loader(loader.clone());
+        page.set_http_cache(Rc::new(RefCell::new(HttpCache::new())));
+        page.set_url(
+            Url::new("http://example.com:80/index.html".to_string())
+                .parse()
+                .expect("url should be valid"),
+        );
+        let response = page
+            .fetch("http://example.com:80/index.html".to_string())
+            .expect("fetch should succeed");
+        page.receive_response(response);
+        assert_eq!(2, loader.fetches.get());
+
+        // 文書は前回のレスポンスを、スタイルシートはHTTPキャッシュを使う
+        page.reload(false).expect("reload should succeed");
+        assert_eq!(2, loader.fetches.get());
+        assert_eq!(2, page.styles().len());
+
+        // キャッシュを無視すると、両方とも取得し直す
+        page.reload(true).expect("reload should succeed");
+        assert_eq!(4, loader.fetches.get());
+    }
+
+    // 終わらないループを含むスクリプトでも読み込みが終わり、エラーがコンソールに出力されるか
+    #[test]
+    fn test_infinite_loop_script() {
+        let mut page = Page::new();
+        let html =
+            "<html><head></head><body><p>x</p><script>while (true) {}</script></body></html>";
+        let response = HttpResponse::new(alloc::format!("HTTP/1.1 200 OK\n\n{}", html))
+            .expect("response should be valid");
+        page.receive_response(response);
+
+        assert_eq!(
+            alloc::vec![ConsoleMessage::new(
+                ConsoleLevel::Error,
+                "Uncaught InternalError: too many loop iterations".to_string()
+            )],
+            page.console_messages()
+        );
     }
 }
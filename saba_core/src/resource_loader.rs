@@ -0,0 +1,80 @@
+use crate::error::Error;
+use crate::http::Header;
+use crate::http::HttpResponse;
+use crate::http_stream::BodySink;
+use crate::http_stream::Progress;
+use crate::url::Url;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+// ページが外部のリソース（CSS、スクリプト、画像など）を取得するためのトレイト
+// ネットワークの実装は環境ごとに異なるので、UI側で実装したものをPageに設定する
+// （WasabiではHttpClientが実装する）
+pub trait ResourceLoader: Debug {
+    // URLのリソースを取得する
+    fn fetch(&self, url: Url) -> Result<HttpResponse, Error>;
+
+    // リクエストヘッダ（Cookieなど）を付けてURLのリソースを取得する
+    // ヘッダを送れないローダーは、ヘッダを付けずに取得する
+    fn fetch_with_headers(&self, url: Url, headers: Vec<Header>) -> Result<HttpResponse, Error> {
+        let _ = headers;
+        self.fetch(url)
+    }
+
+    // ボディを受信するたびにsinkに渡しながらURLのリソースを取得する
+    // 戻り値のレスポンスのボディは空で、ボディはsinkだけが受け取る
+    // 少しずつ受信できないローダーは、全てを受信してからまとめて渡す
+    fn fetch_streaming(
+        &self,
+        url: Url,
+        headers: Vec<Header>,
+        sink: &mut dyn BodySink,
+    ) -> Result<HttpResponse, Error> {
+        let response = self.fetch_with_headers(url, headers)?;
+        let body = response.body_bytes();
+        let response = response.with_body(Vec::new());
+        sink.on_response(&response);
+        if !body.is_empty() {
+            sink.on_data(
+                &body,
+                Progress {
+                    received: body.len(),
+                    total: Some(body.len()),
+                },
+            );
+        }
+        Ok(response)
+    }
+
+    // フォームの送信などで、URLにbodyをPOSTする
+    // POSTをサポートしていないローダーはエラーを返す
+    fn post(&self, url: Url, headers: Vec<Header>, body: String) -> Result<HttpResponse, Error> {
+        let _ = (headers, body);
+        Err(Error::Other(alloc::format!(
+            "POST is not supported: {}",
+            url.url()
+        )))
+    }
+
+    // 任意のメソッド（HEAD、PUT、DELETEなど）でURLにリクエストを送る
+    // HEADのレスポンスはボディを持たないので、リソースを取得せずに検証子や大きさを確かめられる
+    // GETとPOST以外をサポートしていないローダーはエラーを返す
+    fn request(
+        &self,
+        method: &str,
+        url: Url,
+        headers: Vec<Header>,
+        body: String,
+    ) -> Result<HttpResponse, Error> {
+        match method {
+            "GET" => self.fetch_with_headers(url, headers),
+            "POST" => self.post(url, headers, body),
+            _ => Err(Error::Other(alloc::format!(
+                "{} is not supported: {}",
+                method,
+                url.url()
+            ))),
+        }
+    }
+}
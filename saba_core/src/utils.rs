@@ -1,4 +1,8 @@
+use crate::display_item::DisplayItem;
 use crate::renderer::dom::node::Node;
+use crate::renderer::layout::computed_style::Color;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutRect;
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::String;
@@ -28,3 +32,205 @@ fn convert_dom_to_string_internal(
         None => (),
     }
 }
+
+// 描画する内容のリストを1行1要素の文字列にする
+// フレームバッファがなくても描画の結果を比べられるように、ゴールデンテストで使う
+// クリップと不透明度のグループの内側の要素は、入れ子の深さの分だけ字下げする
+pub fn display_items_to_string(items: &[DisplayItem]) -> String {
+    let mut result = String::new();
+    let mut depth: usize = 0;
+    for item in items {
+        if *item == DisplayItem::PopClip || *item == DisplayItem::PopOpacity {
+            depth = depth.saturating_sub(1);
+        }
+        result.push_str(&"  ".repeat(depth));
+        result.push_str(&display_item_to_string(item));
+        result.push('\n');
+        if let DisplayItem::PushClip(_) | DisplayItem::PushOpacity(_) = item {
+            depth += 1;
+        }
+    }
+    result
+}
+
+fn display_item_to_string(item: &DisplayItem) -> String {
+    match item {
+        DisplayItem::Rect {
+            layout_point,
+            size,
+            color,
+            radius,
+        } => format!(
+            "rect {} {}{}",
+            rect_to_string(&LayoutRect::new(*layout_point, *size)),
+            color_to_string(color),
+            radius_to_string(*radius)
+        ),
+        DisplayItem::Text {
+            text,
+            layout_point,
+            font_size,
+            font_weight,
+            font_style,
+            font_family,
+            color,
+            text_decoration,
+        } => format!(
+            "text {} {:?} size={:?} weight={:?} style={:?} family={} color={} decoration={:?}",
+            point_to_string(layout_point),
+            text,
+            font_size,
+            font_weight,
+            font_style,
+            font_family,
+            color_to_string(color),
+            text_decoration
+        ),
+        DisplayItem::Border {
+            layout_point,
+            size,
+            widths,
+            colors,
+            radius,
+        } => format!(
+            "border {} widths={},{},{},{} colors={},{},{},{}{}",
+            rect_to_string(&LayoutRect::new(*layout_point, *size)),
+            widths.top(),
+            widths.right(),
+            widths.bottom(),
+            widths.left(),
+            color_to_string(&colors.top()),
+            color_to_string(&colors.right()),
+            color_to_string(&colors.bottom()),
+            color_to_string(&colors.left()),
+            radius_to_string(*radius)
+        ),
+        DisplayItem::Line {
+            layout_point,
+            end_point,
+            width,
+            color,
+        } => format!(
+            "line {}-{} width={} color={}",
+            point_to_string(layout_point),
+            point_to_string(end_point),
+            width,
+            color_to_string(color)
+        ),
+        DisplayItem::Image {
+            layout_point,
+            size,
+            bitmap,
+        } => format!(
+            "image {} bitmap={}x{}",
+            rect_to_string(&LayoutRect::new(*layout_point, *size)),
+            bitmap.width(),
+            bitmap.height()
+        ),
+        DisplayItem::PushClip(rect) => format!("push-clip {}", rect_to_string(rect)),
+        DisplayItem::PopClip => String::from("pop-clip"),
+        DisplayItem::PushOpacity(opacity) => format!("push-opacity {}", opacity),
+        DisplayItem::PopOpacity => String::from("pop-opacity"),
+    }
+}
+
+// (x,y)
+fn point_to_string(point: &LayoutPoint) -> String {
+    format!("({},{})", point.x(), point.y())
+}
+
+// (x,y 幅x高さ)
+fn rect_to_string(rect: &LayoutRect) -> String {
+    format!(
+        "({},{} {}x{})",
+        rect.point().x(),
+        rect.point().y(),
+        rect.size().width(),
+        rect.size().height()
+    )
+}
+
+// 角が丸い場合だけ半径を出力する
+fn radius_to_string(radius: i64) -> String {
+    if radius > 0 {
+        format!(" radius={}", radius)
+    } else {
+        String::new()
+    }
+}
+
+// 不透明な色は#rrggbb、半透明な色は不透明度を後ろに付ける
+fn color_to_string(color: &Color) -> String {
+    let code = format!("#{:06x}", color.code_u32());
+    if color.alpha() < 1.0 {
+        format!("{}/{}", code, color.alpha())
+    } else {
+        code
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::layout::computed_style::EdgeColors;
+    use crate::renderer::layout::computed_style::EdgeSizes;
+    use crate::renderer::layout::computed_style::FontSize;
+    use crate::renderer::layout::computed_style::FontStyle;
+    use crate::renderer::layout::computed_style::FontWeight;
+    use crate::renderer::layout::computed_style::TextDecoration;
+    use crate::renderer::layout::layout_object::LayoutSize;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    // 描画する内容が1行ずつ、クリップの内側は字下げされて出力されるか
+    #[test]
+    fn test_display_items_to_string() {
+        let red = Color::from_name("red").expect("red should be a color");
+        let items = vec![
+            DisplayItem::Rect {
+                layout_point: LayoutPoint::new(0, 0),
+                size: LayoutSize::new(100, 20),
+                color: Color::from_rgba(0.0, 0.0, 255.0, 0.5),
+                radius: 0,
+            },
+            DisplayItem::PushClip(LayoutRect::new(
+                LayoutPoint::new(0, 0),
+                LayoutSize::new(100, 20),
+            )),
+            DisplayItem::Text {
+                text: "a\"b".to_string(),
+                layout_point: LayoutPoint::new(2, 2),
+                font_size: FontSize::Medium,
+                font_weight: FontWeight::Bold,
+                font_style: FontStyle::Normal,
+                font_family: "sans-serif".to_string(),
+                color: red.clone(),
+                text_decoration: TextDecoration::Underline,
+            },
+            DisplayItem::Line {
+                layout_point: LayoutPoint::new(2, 18),
+                end_point: LayoutPoint::new(18, 18),
+                width: 1,
+                color: red.clone(),
+            },
+            DisplayItem::PopClip,
+            DisplayItem::Border {
+                layout_point: LayoutPoint::new(0, 0),
+                size: LayoutSize::new(100, 20),
+                widths: EdgeSizes::new(1, 2, 3, 4),
+                colors: EdgeColors::all(red),
+                radius: 4,
+            },
+        ];
+
+        assert_eq!(
+            "rect (0,0 100x20) #0000ff/0.5\n\
+             push-clip (0,0 100x20)\n  \
+             text (2,2) \"a\\\"b\" size=Medium weight=Bold style=Normal family=sans-serif color=#ff0000 decoration=Underline\n  \
+             line (2,18)-(18,18) width=1 color=#ff0000\n\
+             pop-clip\n\
+             border (0,0 100x20) widths=1,2,3,4 colors=#ff0000,#ff0000,#ff0000,#ff0000 radius=4\n",
+            display_items_to_string(&items)
+        );
+    }
+}
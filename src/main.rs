@@ -67,6 +67,9 @@ fn main() -> u64 {
     }
 
     let browser = Browser::new();
+    browser
+        .borrow()
+        .set_resource_loader(Rc::new(HttpClient::new()));
 
     let ui = Rc::new(RefCell::new(WasabiUI::new(browser)));
 
@@ -1,8 +1,15 @@
 use crate::alloc::string::ToString;
+use crate::clock::Clock;
 use crate::cursor::Cursor;
+use crate::font_metrics::string_size;
+use crate::font_metrics::WasabiFontMetrics;
+use crate::scrollbar::Scrollbar;
+use crate::text_input::EditKey;
+use crate::text_input::TextInput;
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::String;
+use alloc::vec::Vec;
 use core::cell::RefCell;
 use noli::error::Result as OsResult;
 use noli::prelude::SystemApi;
@@ -14,11 +21,20 @@ use noli::window::StringSize;
 use noli::window::Window;
 use saba_core::browser::Browser;
 use saba_core::constants::*;
-// use saba_core::display_item::DisplayItem;
+use saba_core::display_item::bounding_rect;
+use saba_core::display_item::changed_display_items;
+use saba_core::display_item::DisplayItem;
 use saba_core::error::Error;
 // use saba_core::http::HttpResponse;
-// use saba_core::renderer::layout::computed_style::FontSize;
-// use saba_core::renderer::layout::computed_style::TextDecoration;
+use saba_core::renderer::image::bitmap::Bitmap;
+use saba_core::renderer::layout::computed_style::Color;
+use saba_core::renderer::layout::computed_style::FontSize;
+use saba_core::renderer::layout::computed_style::FontWeight;
+use saba_core::renderer::layout::computed_style::TextDecoration;
+use saba_core::renderer::layout::font_metrics::FontMetrics;
+use saba_core::renderer::layout::layout_object::LayoutPoint;
+use saba_core::renderer::layout::layout_object::LayoutRect;
+use saba_core::renderer::layout::layout_object::LayoutSize;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum InputMode {
@@ -29,17 +45,41 @@ enum InputMode {
 #[derive(Debug)]
 pub struct WasabiUI {
     _browser: Rc<RefCell<Browser>>,
-    input_url: String,
+    // アドレスバーに入力しているURL
+    address_bar: TextInput,
     input_mode: InputMode,
     window: Window,
     cursor: Cursor,
+    // タイマーのタスクを実行する時刻を決めるための時計
+    clock: Clock,
+    scrollbar: Scrollbar,
+    // スクロールバーのつまみをドラッグしている間は、掴んだ位置（つまみの上端からの距離）
+    scroll_drag: Option<i64>,
+    // 前回のマウスのイベントで左ボタンを押していたか
+    // ボタンを押したままマウスを動かした時に、何度もクリックしたことにならないようにする
+    left_button_down: bool,
+    // コンテンツエリアで左ボタンを押してから離すまでの間は、ドラッグでテキストを選択する
+    selecting: bool,
+    // 最後にスクロールバーを描画した時の、ページの高さ・表示する領域の高さ・スクロール位置
+    scrollbar_state: Option<(i64, i64, i64)>,
+    // 最後にコンテンツエリアに描画した内容
+    painted_items: Vec<DisplayItem>,
 }
 
 impl WasabiUI {
     pub fn new(_browser: Rc<RefCell<Browser>>) -> Self {
+        // レイアウトでUIのフォントと同じ寸法を使う
+        // ページを表示する領域の幅は、スクロールバーの分だけ狭くする
+        {
+            let page = _browser.borrow().current_page();
+            let mut page = page.borrow_mut();
+            page.set_font_metrics(Rc::new(WasabiFontMetrics));
+            page.resize(CONTENT_AREA_WIDTH - SCROLLBAR_WIDTH, CONTENT_AREA_HEIGHT);
+        }
+
         Self {
             _browser,
-            input_url: String::new(),
+            address_bar: TextInput::new(),
             input_mode: InputMode::Normal,
             window: Window::new(
                 "saba".to_string(),
@@ -56,6 +96,18 @@ impl WasabiUI {
                 )
             }),
             cursor: Cursor::new(),
+            clock: Clock::new(),
+            scrollbar: Scrollbar::new(
+                WINDOW_WIDTH - SCROLLBAR_WIDTH,
+                TOOLBAR_HEIGHT + 2,
+                SCROLLBAR_WIDTH,
+                WINDOW_HEIGHT - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT - 2,
+            ),
+            scroll_drag: None,
+            left_button_down: false,
+            selecting: false,
+            scrollbar_state: None,
+            painted_items: Vec::new(),
         }
     }
 
@@ -76,6 +128,29 @@ impl WasabiUI {
         loop {
             self.handle_mouse_input()?;
             self.handle_key_input()?;
+
+            // setTimeout・setIntervalで登録されたタスクのうち、実行する時刻になったものを実行する
+            let now = self.clock.now_ms();
+            self._browser.borrow_mut().run_pending_tasks(now)?;
+
+            // 現在の日時は分からないので、読み込んだ文書のDateヘッダで時計を合わせる
+            if let Some(server_time) = self._browser.borrow_mut().take_server_time() {
+                self.clock.sync_epoch(server_time);
+            }
+            // クッキーやキャッシュの期限の判定に使う日時を進める
+            // 時計を合わせるまでは、HTTPの日付と比べられないので進めない
+            if let Some(epoch_secs) = self.clock.epoch_secs() {
+                self._browser.borrow_mut().set_current_time(epoch_secs);
+            }
+
+            // <meta http-equiv="refresh">で予定された移動の時刻になっていれば移動する
+            self._browser.borrow_mut().run_scheduled_navigation()?;
+
+            // スクリプトなどでDOMツリーが変更されていれば、スタイル・レイアウト・描画をやり直す
+            let page = self._browser.borrow().current_page();
+            page.borrow_mut().update_rendering();
+            self.update_ui()?;
+            self.update_scrollbar()?;
         }
     }
 
@@ -98,6 +173,27 @@ impl WasabiUI {
             self.window.flush_area(self.cursor.rect());
             self.cursor.flush();
 
+            // ボタンを離したら、スクロールバーのドラッグとテキストの選択を終える
+            // noliのマウスのイベントにはホイールの情報がないので、ホイールでのスクロールはできない
+            if !button.l() {
+                self.scroll_drag = None;
+                self.selecting = false;
+            }
+            // 左ボタンを押した瞬間だけ、コンテンツエリアのクリックとして扱う
+            let left_pressed = button.l() && !self.left_button_down;
+            self.left_button_down = button.l();
+
+            // マウスカーソルの下のノードの:hoverと:activeの状態を更新する
+            // スタイルはイベントループのupdate_renderingで反映される
+            self.update_pointer_state(
+                (
+                    position.x - WINDOW_INIT_X_POS,
+                    position.y - WINDOW_INIT_Y_POS,
+                ),
+                left_pressed,
+                button.l(),
+            );
+
             if button.l() || button.c() || button.r() {
                 println!("mouse clicked {:?}", button);
                 // 相対位置を計算する
@@ -116,24 +212,146 @@ impl WasabiUI {
                     return Ok(());
                 }
 
+                // テキストを選択している間は、マウスの位置まで選択範囲を広げる
+                if button.l() && self.selecting {
+                    let (x, y) = Self::viewport_position(relative_pos);
+                    let page = self._browser.borrow().current_page();
+                    page.borrow_mut().extend_selection(x, y);
+                    return Ok(());
+                }
+
+                // スクロールバーをクリック、またはドラッグしているときはスクロールする
+                let content_y = relative_pos.1 - TITLE_BAR_HEIGHT;
+                if button.l()
+                    && (self.scroll_drag.is_some()
+                        || self.scrollbar.contains(relative_pos.0, content_y))
+                {
+                    self.drag_scrollbar(content_y);
+                    return Ok(());
+                }
+
+                // ブックマークのボタンを左クリックされたときは一覧のページに移動し、
+                // 右クリックされたときは現在のページをブックマークに追加する
+                if relative_pos.1 < TOOLBAR_HEIGHT + TITLE_BAR_HEIGHT
+                    && relative_pos.1 >= TITLE_BAR_HEIGHT
+                    && relative_pos.0 >= WINDOW_WIDTH - BOOKMARK_BUTTON_WIDTH
+                {
+                    if button.r() {
+                        self._browser.borrow_mut().bookmark_current_page();
+                    } else {
+                        self._browser.borrow_mut().open_bookmarks()?;
+                    }
+                    self.input_mode = InputMode::Normal;
+                    return Ok(());
+                }
+
+                // 再読み込みのボタンを左クリックされたときは現在のページを読み込み直し、
+                // 右クリックされたときはキャッシュを使わずに文書を取得し直す
+                if relative_pos.1 < TOOLBAR_HEIGHT + TITLE_BAR_HEIGHT
+                    && relative_pos.1 >= TITLE_BAR_HEIGHT
+                    && relative_pos.0 >= WINDOW_WIDTH - BOOKMARK_BUTTON_WIDTH - RELOAD_BUTTON_WIDTH
+                {
+                    self._browser.borrow().reload(button.r())?;
+                    // 同じ内容でも描き直されるように、描画した内容を忘れる
+                    self.painted_items.clear();
+                    self.input_mode = InputMode::Normal;
+                    return Ok(());
+                }
+
+                // 戻るボタンをクリックされたときは履歴の1つ前のページに戻り、
+                // 進むボタンをクリックされたときは1つ後のページに進む
+                if relative_pos.1 < TOOLBAR_HEIGHT + TITLE_BAR_HEIGHT
+                    && relative_pos.1 >= TITLE_BAR_HEIGHT
+                    && relative_pos.0 >= Self::toolbar_buttons_x()
+                {
+                    if relative_pos.0 < Self::toolbar_buttons_x() + HISTORY_BUTTON_WIDTH {
+                        self._browser.borrow_mut().go_back()?;
+                    } else {
+                        self._browser.borrow_mut().go_forward()?;
+                    }
+                    self.input_mode = InputMode::Normal;
+                    return Ok(());
+                }
+
                 // ツールバーの範囲をクリックされたとき、InputModeをEditingに変更する
+                // 表示しているページのURLを編集できるように、アドレスバーに入れておく
                 if relative_pos.1 < TOOLBAR_HEIGHT + TITLE_BAR_HEIGHT
                     && relative_pos.1 >= TITLE_BAR_HEIGHT
                 {
-                    self.clear_address_bar()?;
-                    self.input_url = String::new();
+                    let url = self
+                        ._browser
+                        .borrow()
+                        .current_page()
+                        .borrow()
+                        .url()
+                        .map(|url| url.url())
+                        .unwrap_or_default();
+                    self.address_bar.set_text(&url);
                     self.input_mode = InputMode::Editing;
+                    self.update_address_bar()?;
                     println!("button clicked in toolbar: {button:?} {position:?}");
                     return Ok(());
                 }
 
                 self.input_mode = InputMode::Normal;
+
+                // コンテンツエリアを左クリックされたときは、ページの上の位置でクリックの処理をする
+                // リンクへの移動、フォーカスの移動、フォームの送信、onclickのスクリプトの実行を行う
+                // 押した位置からドラッグでテキストを選択できるように、選択も始める
+                if left_pressed {
+                    let (x, y) = Self::viewport_position(relative_pos);
+                    let page = self._browser.borrow().current_page();
+                    page.borrow_mut().start_selection(x, y);
+                    self.selecting = true;
+                    self._browser.borrow_mut().on_click(x, y)?;
+                }
             }
         }
 
         Ok(())
     }
 
+    // ウィンドウの中の位置relative_posで当たり判定をし、そのノードをホバー状態にする
+    // コンテンツエリアで左ボタンを押した時はそのノードをアクティブ状態にし、ボタンを離したら解除する
+    fn update_pointer_state(
+        &mut self,
+        relative_pos: (i64, i64),
+        left_pressed: bool,
+        left_down: bool,
+    ) {
+        let (x, y) = Self::viewport_position(relative_pos);
+        let page = self._browser.borrow().current_page();
+        let mut page = page.borrow_mut();
+        let viewport = page.viewport();
+        let node = if x >= 0 && y >= 0 && x < viewport.width() && y < viewport.height() {
+            page.clicked(x, y)
+        } else {
+            None
+        };
+
+        if left_pressed {
+            page.set_active_node(node.clone());
+        } else if !left_down {
+            page.set_active_node(None);
+        }
+        page.set_hovered_node(node);
+    }
+
+    // ツールバーの右端に並ぶボタン（戻る・進む・再読み込み・ブックマーク）の左端のx座標
+    // アドレスバーはこの位置より左に描画する
+    fn toolbar_buttons_x() -> i64 {
+        WINDOW_WIDTH - BOOKMARK_BUTTON_WIDTH - RELOAD_BUTTON_WIDTH - HISTORY_BUTTON_WIDTH * 2
+    }
+
+    // ウィンドウの中の位置を、ページを表示する領域（ビューポート）の上の位置に変換する
+    // タイトルバー・ツールバー・余白の分だけずらす
+    fn viewport_position(relative_pos: (i64, i64)) -> (i64, i64) {
+        (
+            relative_pos.0 - WINDOW_PADDING,
+            relative_pos.1 - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT - WINDOW_PADDING,
+        )
+    }
+
     // fn handle_key_input(&mut self) -> Result<(), Error> {
     //     // 4番のシステムコール
     //     if let Some(c) = Api::read_key() {
@@ -220,8 +438,29 @@ impl WasabiUI {
     ) -> Result<(), Error> {
         match self.input_mode {
             InputMode::Normal => {
-                // InputModeがNormalのとき、キー入力を無視する
-                let _ = Api::read_key();
+                // 0x09: Tab
+                // 0x0A: Enter
+                // 0x20: Space、0x06: Ctrl+F
+                // 0x02: Ctrl+B
+                // InputModeがNormalのとき、Tabキーでリンクや入力欄の間をフォーカスが移動し、
+                // Enterキーでフォーカスを持つリンクやフォームを実行する
+                // PageDown/PageUpキーの代わりに、SpaceキーとCtrl+Fで1画面下に、Ctrl+Bで1画面上にスクロールする
+                match Api::read_key() {
+                    Some(c) if c == 0x20 as char || c == 0x06 as char => {
+                        self.scroll_page(1);
+                    }
+                    Some(c) if c == 0x02 as char => {
+                        self.scroll_page(-1);
+                    }
+                    Some(c) if c == 0x09 as char => {
+                        let page = self._browser.borrow().current_page();
+                        page.borrow_mut().focus_next(false);
+                    }
+                    Some(c) if c == 0x0A as char => {
+                        self._browser.borrow_mut().activate_focused()?;
+                    }
+                    _ => {}
+                }
             }
             InputMode::Editing => {
                 // if let Some(c) = Api::read_key() {
@@ -241,17 +480,23 @@ impl WasabiUI {
                 //     }
                 // }
 
-                // 0x08: BS
-                // 0x7F: Delete
-                if let Some(c) = Api::read_key() {
-                    if c == 0x7F as char || c == 0x08 as char {
-                        // 最後の文字を削除する
-                        self.input_url.pop();
-                        self.update_address_bar()?;
-                    } else {
-                        self.input_url.push(c);
-                        self.update_address_bar()?;
+                // 0x0A: Enter
+                // Enterキーで入力したURLに移動し、それ以外のキーはカーソルの位置で編集する
+                match Api::read_key() {
+                    Some(c) if c == 0x0A as char => {
+                        let url = self.address_bar.text();
+                        self.address_bar.clear();
+                        self.input_mode = InputMode::Normal;
+                        self.clear_address_bar()?;
+                        self._browser.borrow_mut().navigate(url)?;
                     }
+                    Some(c) => {
+                        if let Some(key) = EditKey::from_char(c) {
+                            self.address_bar.handle_key(key);
+                            self.update_address_bar()?;
+                        }
+                    }
+                    None => {}
                 }
             }
         }
@@ -281,62 +526,530 @@ impl WasabiUI {
     //     Ok(())
     // }
 
-    // fn update_ui(&mut self) -> Result<(), Error> {
-    //     let display_items = self
-    //         .browser
-    //         .borrow()
-    //         .current_page()
-    //         .borrow()
-    //         .display_items();
-
-    //     for item in display_items {
-    //         match item {
-    //             DisplayItem::Text {
-    //                 text,
-    //                 style,
-    //                 layout_point,
-    //             } => {
-    //                 if self
-    //                     .window
-    //                     .draw_string(
-    //                         style.color().code_u32(),
-    //                         layout_point.x() + WINDOW_PADDING,
-    //                         layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
-    //                         &text,
-    //                         convert_font_size(style.font_size()),
-    //                         style.text_decoration() == TextDecoration::Underline,
-    //                     )
-    //                     .is_err()
-    //                 {
-    //                     return Err(Error::InvalidUI("failed to draw a string".to_string()));
-    //                 }
-    //             }
-    //             DisplayItem::Rect {
-    //                 style,
-    //                 layout_point,
-    //                 layout_size,
-    //             } => {
-    //                 if self
-    //                     .window
-    //                     .fill_rect(
-    //                         style.background_color().code_u32(),
-    //                         layout_point.x() + WINDOW_PADDING,
-    //                         layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
-    //                         layout_size.width(),
-    //                         layout_size.height(),
-    //                     )
-    //                     .is_err()
-    //                 {
-    //                     return Err(Error::InvalidUI("failed to draw a string".to_string()));
-    //                 }
-    //             }
-    //         }
-    //     }
+    // 描画する内容が変わっていれば、コンテンツエリアのうち変わった部分（ダメージ）だけを描き直す
+    fn update_ui(&mut self) -> Result<(), Error> {
+        let (display_items, viewport) = {
+            let page = self._browser.borrow().current_page();
+            let page = page.borrow();
+            (page.display_items(), page.viewport())
+        };
+        if display_items == self.painted_items {
+            return Ok(());
+        }
 
-    //     self.window.flush();
+        let damaged = match self.damaged_rect(&display_items, viewport) {
+            Some(damaged) => damaged,
+            None => {
+                self.painted_items = display_items;
+                return Ok(());
+            }
+        };
+        if self.painted_items.is_empty() {
+            self.clear_content_area()?;
+        } else {
+            self.fill_content_rect(
+                WHITE,
+                damaged.point().x(),
+                damaged.point().y(),
+                damaged.size().width(),
+                damaged.size().height(),
+                damaged,
+            )?;
+        }
 
-    //     Ok(())
-    // }
+        // PushOpacityで積んだ不透明度のスタック
+        // 重なっている全ての不透明度を掛けた値で描画する
+        let mut opacities: Vec<f64> = Vec::new();
+        // PushClipで積んだクリップのスタック
+        // 重なっている全てのクリップとダメージの内側だけに描画する
+        let mut clips: Vec<LayoutRect> = Vec::new();
+        // 描画したテキストと描画した色とクリップ
+        let mut painted_texts: Vec<(&DisplayItem, u32, LayoutRect)> = Vec::new();
+
+        for item in &display_items {
+            match item {
+                DisplayItem::PushClip(rect) => {
+                    clips.push(*rect);
+                    continue;
+                }
+                DisplayItem::PopClip => {
+                    clips.pop();
+                    continue;
+                }
+                DisplayItem::PushOpacity(opacity) => {
+                    opacities.push(*opacity);
+                    continue;
+                }
+                DisplayItem::PopOpacity => {
+                    opacities.pop();
+                    continue;
+                }
+                _ => {}
+            }
+
+            let opacity: f64 = opacities.iter().product();
+            let clip = match clips
+                .iter()
+                .try_fold(damaged, |clip, rect| clip.intersection(rect))
+            {
+                Some(clip) => clip,
+                // クリップが重なっていなければ、描画できる領域がない
+                None => continue,
+            };
+
+            match item {
+                DisplayItem::Rect {
+                    layout_point,
+                    size,
+                    color,
+                    radius,
+                } => {
+                    if let Some(blended) = blend(color, opacity) {
+                        let rect = LayoutRect::new(*layout_point, *size);
+                        self.fill_rounded_content_rect(blended, rect, rect, *radius, clip)?;
+
+                        // 半透明の四角形（テキストの選択範囲など）は背景の白と合成して塗るので、
+                        // 下にあるテキストが隠れないように、重なっているテキストを描き直す
+                        if color.alpha() * opacity < 1.0 {
+                            for (text, text_color, text_clip) in &painted_texts {
+                                if text
+                                    .bounds(&WasabiFontMetrics)
+                                    .and_then(|bounds| bounds.intersection(&rect))
+                                    .and_then(|bounds| bounds.intersection(&clip))
+                                    .is_some()
+                                {
+                                    self.draw_text_item(text, *text_color, *text_clip)?;
+                                }
+                            }
+                        }
+                    }
+                }
+                DisplayItem::Border {
+                    layout_point,
+                    size,
+                    widths,
+                    colors,
+                    radius,
+                } => {
+                    // 上下の辺は角を含めて横幅いっぱいに、左右の辺は上下の辺の間に塗る
+                    let (x, y) = (layout_point.x(), layout_point.y());
+                    let (width, height) = (size.width(), size.height());
+                    let edges = [
+                        (colors.top(), x, y, width, widths.top()),
+                        (
+                            colors.bottom(),
+                            x,
+                            y + height - widths.bottom(),
+                            width,
+                            widths.bottom(),
+                        ),
+                        (
+                            colors.left(),
+                            x,
+                            y + widths.top(),
+                            widths.left(),
+                            height - widths.vertical(),
+                        ),
+                        (
+                            colors.right(),
+                            x + width - widths.right(),
+                            y + widths.top(),
+                            widths.right(),
+                            height - widths.vertical(),
+                        ),
+                    ];
+                    // 角の丸みは外側の矩形の角から、はみ出す画素を除いて表す
+                    let outer = LayoutRect::new(*layout_point, *size);
+                    for (color, x, y, width, height) in edges {
+                        if let Some(color) = blend(&color, opacity) {
+                            let edge = LayoutRect::new(
+                                LayoutPoint::new(x, y),
+                                LayoutSize::new(width, height),
+                            );
+                            self.fill_rounded_content_rect(color, edge, outer, *radius, clip)?;
+                        }
+                    }
+                }
+                DisplayItem::Line {
+                    layout_point,
+                    end_point,
+                    width,
+                    color,
+                } => {
+                    if let Some(color) = blend(color, opacity) {
+                        self.draw_content_line(
+                            color,
+                            (layout_point.x(), layout_point.y()),
+                            (end_point.x(), end_point.y()),
+                            *width,
+                            clip,
+                        )?;
+                    }
+                }
+                DisplayItem::Text { color, .. } => {
+                    if let Some(color) = blend(color, opacity) {
+                        self.draw_text_item(item, color, clip)?;
+                        painted_texts.push((item, color, clip));
+                    }
+                }
+                DisplayItem::Image {
+                    layout_point,
+                    size,
+                    bitmap,
+                } => {
+                    self.draw_image(
+                        bitmap,
+                        layout_point.x(),
+                        layout_point.y(),
+                        *size,
+                        clip,
+                        opacity,
+                    )?;
+                }
+                _ => {}
+            }
+        }
+
+        self.painted_items = display_items;
+
+        // ダメージの部分の画面を更新する
+        self.window.flush_area(
+            Rect::new(
+                WINDOW_INIT_X_POS + WINDOW_PADDING + damaged.point().x(),
+                WINDOW_INIT_Y_POS
+                    + TITLE_BAR_HEIGHT
+                    + TOOLBAR_HEIGHT
+                    + WINDOW_PADDING
+                    + damaged.point().y(),
+                damaged.size().width(),
+                damaged.size().height(),
+            )
+            .expect("failed to create a rect for the content area"),
+        );
+
+        Ok(())
+    }
+
+    // 前回描画した内容から変わった描画内容を全て含む、表示する領域の中の矩形
+    // ページのdamaged_rectは直前のフレームとの差なので、UIが描画する前にページが何度も描画すると
+    // 足りなくなる。そのため、ページと同じ方法で前回UIが描画した内容と比べる
+    // 何も描画していない場合は、表示する領域全体を描き直す
+    // noliは文字の一部だけを描画できないので、ダメージに掛かるテキストは行全体を含むように広げる
+    // 描き直す必要がない場合はNone
+    fn damaged_rect(
+        &self,
+        display_items: &[DisplayItem],
+        viewport: LayoutSize,
+    ) -> Option<LayoutRect> {
+        let viewport_rect = LayoutRect::new(LayoutPoint::new(0, 0), viewport);
+        if self.painted_items.is_empty() {
+            return Some(viewport_rect);
+        }
+
+        let changed = changed_display_items(&self.painted_items, display_items);
+        let mut damaged =
+            bounding_rect(&changed, &WasabiFontMetrics)?.intersection(&viewport_rect)?;
+        loop {
+            let expanded = display_items
+                .iter()
+                .filter(|item| matches!(item, DisplayItem::Text { .. }))
+                .filter_map(|item| item.bounds(&WasabiFontMetrics))
+                .filter(|bounds| bounds.intersection(&damaged).is_some())
+                .fold(damaged, |damaged, bounds| damaged.union(&bounds))
+                .intersection(&viewport_rect)
+                .unwrap_or(damaged);
+            if expanded == damaged {
+                return Some(damaged);
+            }
+            damaged = expanded;
+        }
+    }
+
+    // コンテンツエリアの(x, y)から幅width・高さheightの四角形を塗りつぶす
+    // ツールバーやスクロールバーに重ならないように、クリップ（表示する領域の内側）だけを塗る
+    fn fill_content_rect(
+        &mut self,
+        color: u32,
+        x: i64,
+        y: i64,
+        width: i64,
+        height: i64,
+        clip: LayoutRect,
+    ) -> Result<(), Error> {
+        let rect = match LayoutRect::new(LayoutPoint::new(x, y), LayoutSize::new(width, height))
+            .intersection(&clip)
+        {
+            Some(rect) => rect,
+            None => return Ok(()),
+        };
+        if self
+            .window
+            .fill_rect(
+                color,
+                rect.point().x() + WINDOW_PADDING,
+                rect.point().y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
+                rect.size().width(),
+                rect.size().height(),
+            )
+            .is_err()
+        {
+            return Err(Error::InvalidUI("failed to draw a rect".to_string()));
+        }
+
+        Ok(())
+    }
+
+    // コンテンツエリアのrectを塗りつぶす
+    // outerは角の丸みの半径がradiusの角丸の四角形で、rectのうちその内側だけを塗る
+    // 角のある行は1行ずつ、丸みの外側の画素を除いて塗る
+    fn fill_rounded_content_rect(
+        &mut self,
+        color: u32,
+        rect: LayoutRect,
+        outer: LayoutRect,
+        radius: i64,
+        clip: LayoutRect,
+    ) -> Result<(), Error> {
+        let (x, y) = (rect.point().x(), rect.point().y());
+        let (width, height) = (rect.size().width(), rect.size().height());
+        // 半径は短い辺の半分までにする
+        let radius = radius
+            .min(outer.size().width() / 2)
+            .min(outer.size().height() / 2);
+        if radius <= 0 {
+            return self.fill_content_rect(color, x, y, width, height, clip);
+        }
+
+        // 角のない行はまとめて塗る
+        let outer_top = outer.point().y();
+        let outer_bottom = outer_top + outer.size().height();
+        let middle_top = y.max(outer_top + radius);
+        let middle_bottom = (y + height).min(outer_bottom - radius);
+        if middle_top < middle_bottom {
+            self.fill_content_rect(
+                color,
+                x,
+                middle_top,
+                width,
+                middle_bottom - middle_top,
+                clip,
+            )?;
+        }
+
+        let top_rows = y.max(outer_top)..(y + height).min(outer_top + radius);
+        let bottom_rows = y.max(outer_bottom - radius)..(y + height).min(outer_bottom);
+        for row in top_rows.chain(bottom_rows) {
+            // 外側の矩形の上端または下端から数えた行の位置
+            let from_edge = (row - outer_top).min(outer_bottom - 1 - row);
+            let inset = corner_inset(radius, from_edge);
+            let left = x.max(outer.point().x() + inset);
+            let right = (x + width).min(outer.point().x() + outer.size().width() - inset);
+            self.fill_content_rect(color, left, row, right - left, 1, clip)?;
+        }
+
+        Ok(())
+    }
+
+    // コンテンツエリアのstartからendまで、太さwidthの直線を描画する
+    // 水平・垂直な線（下線や取り消し線）は1つの四角形として塗る
+    // 斜めの線はブレゼンハムのアルゴリズムでたどり、点ごとに太さの分の正方形を塗る
+    fn draw_content_line(
+        &mut self,
+        color: u32,
+        start: (i64, i64),
+        end: (i64, i64),
+        width: i64,
+        clip: LayoutRect,
+    ) -> Result<(), Error> {
+        let (x0, y0) = start;
+        let (x1, y1) = end;
+        if width <= 0 {
+            return Ok(());
+        }
+        if x0 == x1 || y0 == y1 {
+            return self.fill_content_rect(
+                color,
+                x0.min(x1),
+                y0.min(y1),
+                (x1 - x0).abs() + width,
+                (y1 - y0).abs() + width,
+                clip,
+            );
+        }
+
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let (mut x, mut y) = (x0, y0);
+        let mut error = dx + dy;
+        loop {
+            self.fill_content_rect(color, x, y, width, width, clip)?;
+            if x == x1 && y == y1 {
+                break;
+            }
+            let e2 = 2 * error;
+            if e2 >= dy {
+                error += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                error += dx;
+                y += sy;
+            }
+        }
+
+        Ok(())
+    }
+
+    // 画像をレイアウトで決まった大きさに拡大・縮小し、コンテンツエリアの(x, y)に描画する
+    // クリップ（表示する領域の内側）の外にはみ出す部分は描画しない
+    // 1画素ずつ描くと遅いので、横に並んだ同じ色の画素はまとめて1つの四角形として塗る
+    // 透明度が半分未満の画素は、背景が見えるように描画しない
+    // 描画する画素には、opacityの不透明度を掛ける
+    fn draw_image(
+        &mut self,
+        bitmap: &Bitmap,
+        x: i64,
+        y: i64,
+        size: LayoutSize,
+        clip: LayoutRect,
+        opacity: f64,
+    ) -> Result<(), Error> {
+        if opacity <= 0.0 {
+            return Ok(());
+        }
+
+        let top = y.max(clip.point().y());
+        let bottom = (y + size.height()).min(clip.point().y() + clip.size().height());
+        let left = x.max(clip.point().x());
+        let right = (x + size.width()).min(clip.point().x() + clip.size().width());
+
+        for py in top..bottom {
+            let mut px = left;
+            while px < right {
+                let color = bitmap.scaled_pixel(px - x, py - y, size.width(), size.height());
+                let mut end = px + 1;
+                while end < right
+                    && bitmap.scaled_pixel(end - x, py - y, size.width(), size.height()) == color
+                {
+                    end += 1;
+                }
+
+                if let Some(color) = color {
+                    if color >> 24 >= 0x80
+                        && self
+                            .window
+                            .fill_rect(
+                                blend_with_white(color & 0xffffff, opacity),
+                                px + WINDOW_PADDING,
+                                py + WINDOW_PADDING + TOOLBAR_HEIGHT,
+                                end - px,
+                                1,
+                            )
+                            .is_err()
+                    {
+                        return Err(Error::InvalidUI("failed to draw an image".to_string()));
+                    }
+                }
+                px = end;
+            }
+        }
+
+        Ok(())
+    }
+
+    // テキストの描画内容をcolorの色で描画する
+    // noliは文字の一部だけを描画できないので、行の一部でもクリップ（表示する領域の内側）から
+    // 上下にはみ出すテキストは描画せず、左右にはみ出す文字は取り除いて描画する
+    fn draw_text_item(
+        &mut self,
+        item: &DisplayItem,
+        color: u32,
+        clip: LayoutRect,
+    ) -> Result<(), Error> {
+        if let DisplayItem::Text {
+            text,
+            layout_point,
+            font_size,
+            font_weight,
+            text_decoration,
+            ..
+        } = item
+        {
+            let line_height = WasabiFontMetrics.line_height(*font_size);
+            if layout_point.y() < clip.point().y()
+                || layout_point.y() + line_height > clip.point().y() + clip.size().height()
+            {
+                return Ok(());
+            }
+
+            let char_width = WasabiFontMetrics.char_width(*font_size);
+            let skipped = if layout_point.x() < clip.point().x() {
+                (clip.point().x() - layout_point.x() + char_width - 1) / char_width
+            } else {
+                0
+            };
+            let x = layout_point.x() + skipped * char_width;
+            let count = (clip.point().x() + clip.size().width() - x) / char_width;
+            if count <= 0 {
+                return Ok(());
+            }
+            let visible: String = text
+                .chars()
+                .skip(skipped as usize)
+                .take(count as usize)
+                .collect();
+            if visible.is_empty() {
+                return Ok(());
+            }
+
+            self.draw_text(
+                &visible,
+                x + WINDOW_PADDING,
+                layout_point.y() + WINDOW_PADDING + TOOLBAR_HEIGHT,
+                *font_size,
+                *font_weight,
+                color,
+                *text_decoration,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // フォントの大きさと太さに合わせてテキストを描画する
+    // noliのビットマップフォントは大きさごとに描画でき、太字のフォントはないので、
+    // 太字は1ピクセル右にずらして重ね書きする
+    #[allow(clippy::too_many_arguments)]
+    fn draw_text(
+        &mut self,
+        text: &str,
+        x: i64,
+        y: i64,
+        font_size: FontSize,
+        font_weight: FontWeight,
+        color: u32,
+        text_decoration: TextDecoration,
+    ) -> Result<(), Error> {
+        let size = string_size(font_size);
+        let underline = text_decoration == TextDecoration::Underline;
+        let offsets: &[i64] = match font_weight {
+            FontWeight::Normal => &[0],
+            FontWeight::Bold => &[0, 1],
+        };
+        for dx in offsets {
+            if self
+                .window
+                .draw_string(color, x + dx, y, text, size, underline)
+                .is_err()
+            {
+                return Err(Error::InvalidUI("failed to draw a string".to_string()));
+            }
+        }
+
+        Ok(())
+    }
 
     // ウィンドウの初期化を行う
     fn setup(&mut self) -> Result<(), Error> {
@@ -380,18 +1093,169 @@ impl WasabiUI {
         )?;
 
         // アドレスバーの四角を描画
-        self.window
-            .fill_rect(WHITE, 70, 2, WINDOW_WIDTH - 74, 2 + ADDRESSBAR_HEIGHT)?;
+        // 右端は戻る・進む・再読み込み・ブックマークのボタンのために空けておく
+        self.window.fill_rect(
+            WHITE,
+            70,
+            2,
+            Self::toolbar_buttons_x() - 74,
+            2 + ADDRESSBAR_HEIGHT,
+        )?;
 
         // アドレスバーの影の線を描画
-        self.window.draw_line(GREY, 70, 2, WINDOW_WIDTH - 4, 2)?;
+        self.window
+            .draw_line(GREY, 70, 2, Self::toolbar_buttons_x() - 4, 2)?;
         self.window
             .draw_line(GREY, 70, 2, 70, 2 + ADDRESSBAR_HEIGHT)?;
-        self.window.draw_line(BLACK, 71, 3, WINDOW_WIDTH - 5, 3)?;
+        self.window
+            .draw_line(BLACK, 71, 3, Self::toolbar_buttons_x() - 5, 3)?;
 
         self.window
             .draw_line(GREY, 71, 3, 71, 1 + ADDRESSBAR_HEIGHT)?;
 
+        // 戻るボタンと進むボタンを描画
+        self.window.draw_string(
+            BLACK,
+            Self::toolbar_buttons_x() + 8,
+            5,
+            "<",
+            StringSize::Medium,
+            /*underline=*/ false,
+        )?;
+        self.window.draw_string(
+            BLACK,
+            Self::toolbar_buttons_x() + HISTORY_BUTTON_WIDTH + 8,
+            5,
+            ">",
+            StringSize::Medium,
+            /*underline=*/ false,
+        )?;
+
+        // 再読み込みのボタンを描画
+        self.window.draw_string(
+            BLACK,
+            WINDOW_WIDTH - BOOKMARK_BUTTON_WIDTH - RELOAD_BUTTON_WIDTH + 4,
+            5,
+            "Reload",
+            StringSize::Medium,
+            /*underline=*/ false,
+        )?;
+
+        // ブックマークのボタンを描画
+        self.window.draw_string(
+            BLACK,
+            WINDOW_WIDTH - BOOKMARK_BUTTON_WIDTH + 4,
+            5,
+            "Bookmarks",
+            StringSize::Medium,
+            /*underline=*/ false,
+        )?;
+
+        Ok(())
+    }
+
+    // pages画面分スクロールする（負の場合は上に）
+    // 前の画面とのつながりが分かるように、1行分は重ねて表示する
+    fn scroll_page(&mut self, pages: i64) {
+        let page = self._browser.borrow().current_page();
+        let mut page = page.borrow_mut();
+        let step =
+            (page.viewport().height() - CHAR_HEIGHT_WITH_PADDING).max(CHAR_HEIGHT_WITH_PADDING);
+        page.scroll_by(step * pages);
+    }
+
+    // スクロールバーをクリック・ドラッグした時のスクロール
+    // つまみを掴んだ場合はマウスに合わせて動かし、溝をクリックした場合は1画面分スクロールする
+    // yはウィンドウの中のマウスの位置
+    fn drag_scrollbar(&mut self, y: i64) {
+        let (content_height, viewport_height, scroll_offset) = {
+            let page = self._browser.borrow().current_page();
+            let page = page.borrow();
+            (
+                page.content_height(),
+                page.viewport().height(),
+                page.scroll_offset(),
+            )
+        };
+        let (thumb_top, thumb_height) =
+            match self
+                .scrollbar
+                .thumb(content_height, viewport_height, scroll_offset)
+            {
+                Some(thumb) => thumb,
+                None => return,
+            };
+
+        match self.scroll_drag {
+            Some(grab) => {
+                let offset =
+                    self.scrollbar
+                        .scroll_offset_for(y - grab, content_height, viewport_height);
+                let page = self._browser.borrow().current_page();
+                page.borrow_mut().scroll_to(offset);
+            }
+            None if y < thumb_top => self.scroll_page(-1),
+            None if y >= thumb_top + thumb_height => self.scroll_page(1),
+            None => self.scroll_drag = Some(y - thumb_top),
+        }
+    }
+
+    // ページの高さかスクロール位置が変わっていれば、スクロールバーを描き直す
+    fn update_scrollbar(&mut self) -> Result<(), Error> {
+        let state = {
+            let page = self._browser.borrow().current_page();
+            let page = page.borrow();
+            (
+                page.content_height(),
+                page.viewport().height(),
+                page.scroll_offset(),
+            )
+        };
+        if self.scrollbar_state == Some(state) {
+            return Ok(());
+        }
+        self.scrollbar_state = Some(state);
+
+        // 溝を描画し、スクロールできる場合はその上につまみを描画する
+        let (content_height, viewport_height, scroll_offset) = state;
+        let scrollbar = self.scrollbar;
+        if self
+            .window
+            .fill_rect(
+                LIGHTGREY,
+                scrollbar.x(),
+                scrollbar.y(),
+                scrollbar.width(),
+                scrollbar.height(),
+            )
+            .and_then(
+                |_| match scrollbar.thumb(content_height, viewport_height, scroll_offset) {
+                    Some((thumb_top, thumb_height)) => self.window.fill_rect(
+                        DARKGREY,
+                        scrollbar.x() + 2,
+                        thumb_top,
+                        scrollbar.width() - 4,
+                        thumb_height,
+                    ),
+                    None => Ok(()),
+                },
+            )
+            .is_err()
+        {
+            return Err(Error::InvalidUI("failed to draw a scrollbar".to_string()));
+        }
+
+        // スクロールバーの部分の画面を更新する
+        self.window.flush_area(
+            Rect::new(
+                WINDOW_INIT_X_POS + scrollbar.x(),
+                WINDOW_INIT_Y_POS + TITLE_BAR_HEIGHT + scrollbar.y(),
+                scrollbar.width(),
+                scrollbar.height(),
+            )
+            .expect("failed to create a rect for the scrollbar"),
+        );
+
         Ok(())
     }
 
@@ -400,7 +1264,13 @@ impl WasabiUI {
         // アドレスバーを白く塗り潰す
         if self
             .window
-            .fill_rect(WHITE, 72, 4, WINDOW_WIDTH - 76, ADDRESSBAR_HEIGHT - 2)
+            .fill_rect(
+                WHITE,
+                72,
+                4,
+                Self::toolbar_buttons_x() - 76,
+                ADDRESSBAR_HEIGHT - 2,
+            )
             .is_err()
         {
             return Err(Error::InvalidUI(
@@ -408,17 +1278,24 @@ impl WasabiUI {
             ));
         }
 
-        // input_urlをアドレスバーに描画する
+        // 入力しているURLのうち、アドレスバーに収まる部分とカーソルを描画する
+        let columns = (Self::toolbar_buttons_x() - 80) / CHAR_WIDTH;
+        let (text, cursor_column) = self.address_bar.visible(columns as usize);
+        let cursor_x = 74 + cursor_column as i64 * CHAR_WIDTH;
         if self
             .window
             .draw_string(
                 BLACK,
                 74,
                 6,
-                &self.input_url,
+                &text,
                 StringSize::Medium,
                 /*underline=*/ false,
             )
+            .and_then(|_| {
+                self.window
+                    .draw_line(BLACK, cursor_x, 5, cursor_x, 5 + CHAR_HEIGHT)
+            })
             .is_err()
         {
             return Err(Error::InvalidUI(
@@ -445,7 +1322,13 @@ impl WasabiUI {
         // アドレスバーを白く塗り潰す
         if self
             .window
-            .fill_rect(WHITE, 72, 4, WINDOW_WIDTH - 76, ADDRESSBAR_HEIGHT - 2)
+            .fill_rect(
+                WHITE,
+                72,
+                4,
+                Self::toolbar_buttons_x() - 76,
+                ADDRESSBAR_HEIGHT - 2,
+            )
             .is_err()
         {
             return Err(Error::InvalidUI(
@@ -467,34 +1350,54 @@ impl WasabiUI {
         Ok(())
     }
 
-    //     fn clear_content_area(&mut self) -> Result<(), Error> {
-    //         // コンテンツエリアを白く塗りつぶす
-    //         if self
-    //             .window
-    //             .fill_rect(
-    //                 WHITE,
-    //                 0,
-    //                 TOOLBAR_HEIGHT + 2,
-    //                 CONTENT_AREA_WIDTH,
-    //                 CONTENT_AREA_HEIGHT - 2,
-    //             )
-    //             .is_err()
-    //         {
-    //             return Err(Error::InvalidUI(
-    //                 "failed to clear a content area".to_string(),
-    //             ));
-    //         }
+    // コンテンツエリア（スクロールバーを除く）を白く塗りつぶす
+    fn clear_content_area(&mut self) -> Result<(), Error> {
+        if self
+            .window
+            .fill_rect(
+                WHITE,
+                0,
+                TOOLBAR_HEIGHT + 2,
+                WINDOW_WIDTH - SCROLLBAR_WIDTH,
+                WINDOW_HEIGHT - TITLE_BAR_HEIGHT - TOOLBAR_HEIGHT - 2,
+            )
+            .is_err()
+        {
+            return Err(Error::InvalidUI(
+                "failed to clear a content area".to_string(),
+            ));
+        }
 
-    //         self.window.flush();
+        Ok(())
+    }
+}
 
-    //         Ok(())
-    //     }
+// 色の不透明度とopacityを掛けた不透明度で描画する色を求める
+// 完全に透明な場合は描画しないのでNoneを返す
+fn blend(color: &Color, opacity: f64) -> Option<u32> {
+    let alpha = color.alpha() * opacity;
+    if alpha <= 0.0 {
+        return None;
+    }
+    Some(blend_with_white(color.code_u32(), alpha))
+}
+
+// 0xRRGGBBの色を不透明度alphaで背景に合成した色
+// noliのウィンドウからは描画済みの画素の色を読めないので、コンテンツエリアの背景の白と合成する
+fn blend_with_white(color: u32, alpha: f64) -> u32 {
+    let alpha = alpha.clamp(0.0, 1.0);
+    [16, 8, 0].iter().fold(0, |blended, shift| {
+        let channel = ((color >> shift) & 0xff) as f64;
+        let channel = channel * alpha + 255.0 * (1.0 - alpha);
+        blended | (((channel + 0.5) as u32).min(0xff) << shift)
+    })
 }
 
-// fn convert_font_size(size: FontSize) -> StringSize {
-//     match size {
-//         FontSize::Medium => StringSize::Medium,
-//         FontSize::XLarge => StringSize::Large,
-//         FontSize::XXLarge => StringSize::XLarge,
-//     }
-// }
+// 角の丸みの半径がradiusの時に、上端（または下端）からrow行目で角から除く画素の数
+// 行の中心を通る円の弦の長さから求める
+fn corner_inset(radius: i64, row: i64) -> i64 {
+    // 計算を整数で行うため、長さを2倍にして扱う
+    let distance = 2 * (radius - row) - 1;
+    let chord = (4 * radius * radius - distance * distance).max(0).isqrt();
+    radius - (chord + 1) / 2
+}
@@ -0,0 +1,47 @@
+use core::arch::x86_64::_rdtsc;
+
+// タイムスタンプカウンタが1秒間に進む数
+// 実際の周波数は環境によって異なるので、およその値を使う
+const TSC_FREQUENCY: u64 = 1_000_000_000;
+
+// UIのイベントループでタイマーなどに使う時計
+// noliには時刻を取得するシステムコールがないので、CPUのタイムスタンプカウンタから経過時間を求める
+// 現在の日時は分からないので、時計を作った時からの経過時間を返す
+// sync_epochでサーバーの日時に合わせると、epoch_secsで現在の日時を返す
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Clock {
+    start: u64,
+    // 時計を作った時の日時（1970年1月1日からの秒数）
+    epoch_start: Option<u64>,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self {
+            start: Self::counter(),
+            epoch_start: None,
+        }
+    }
+
+    // 現在の日時（1970年1月1日からの秒数）に合わせる
+    pub fn sync_epoch(&mut self, epoch_secs: u64) {
+        self.epoch_start = Some(epoch_secs.saturating_sub(self.now_ms() / 1000));
+    }
+
+    // 現在の日時（1970年1月1日からの秒数）
+    // まだsync_epochで合わせていなければNoneを返す
+    pub fn epoch_secs(&self) -> Option<u64> {
+        self.epoch_start
+            .map(|start| start.saturating_add(self.now_ms() / 1000))
+    }
+
+    // 時計を作った時からの経過時間（ミリ秒）
+    pub fn now_ms(&self) -> u64 {
+        Self::counter().wrapping_sub(self.start) / (TSC_FREQUENCY / 1000)
+    }
+
+    fn counter() -> u64 {
+        // SAFETY: rdtsc命令はカウンタを読むだけで、メモリを変更しない
+        unsafe { _rdtsc() }
+    }
+}
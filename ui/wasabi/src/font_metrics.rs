@@ -0,0 +1,38 @@
+use noli::window::StringSize;
+use saba_core::constants::CHAR_HEIGHT_WITH_PADDING;
+use saba_core::constants::CHAR_WIDTH;
+use saba_core::renderer::layout::computed_style::FontSize;
+use saba_core::renderer::layout::font_metrics::FontMetrics;
+
+// noliのビットマップフォントの寸法
+// StringSize::Mediumが8x16ピクセルで、LargeとXLargeはその2倍と3倍
+#[derive(Debug, Clone, Copy)]
+pub struct WasabiFontMetrics;
+
+impl FontMetrics for WasabiFontMetrics {
+    fn char_width(&self, font_size: FontSize) -> i64 {
+        match font_size {
+            FontSize::Medium => CHAR_WIDTH,
+            FontSize::XLarge => CHAR_WIDTH * 2,
+            FontSize::XXLarge => CHAR_WIDTH * 3,
+        }
+    }
+
+    fn line_height(&self, font_size: FontSize) -> i64 {
+        match font_size {
+            FontSize::Medium => CHAR_HEIGHT_WITH_PADDING,
+            FontSize::XLarge => CHAR_HEIGHT_WITH_PADDING * 2,
+            FontSize::XXLarge => CHAR_HEIGHT_WITH_PADDING * 3,
+        }
+    }
+}
+
+// CSSのフォントサイズを、描画に使うnoliのフォントの大きさにする
+// WasabiFontMetricsの寸法と同じ対応にする
+pub fn string_size(font_size: FontSize) -> StringSize {
+    match font_size {
+        FontSize::Medium => StringSize::Medium,
+        FontSize::XLarge => StringSize::Large,
+        FontSize::XXLarge => StringSize::XLarge,
+    }
+}
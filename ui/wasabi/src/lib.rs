@@ -3,4 +3,8 @@
 extern crate alloc;
 
 pub mod app;
+mod clock;
 mod cursor;
+mod font_metrics;
+mod scrollbar;
+mod text_input;
@@ -0,0 +1,85 @@
+// つまみの最小の高さ
+// 長いページでもマウスで掴めるようにする
+const MIN_THUMB_HEIGHT: i64 = 16;
+
+// コンテンツエリアの右端にある縦のスクロールバー
+// つまみの大きさと位置で、ページ全体のうちどこを表示しているかを示す
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Scrollbar {
+    // 溝（つまみが動く範囲）の左上の位置と大きさ（ウィンドウの中の座標）
+    x: i64,
+    y: i64,
+    width: i64,
+    height: i64,
+}
+
+impl Scrollbar {
+    pub fn new(x: i64, y: i64, width: i64, height: i64) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn x(&self) -> i64 {
+        self.x
+    }
+
+    pub fn y(&self) -> i64 {
+        self.y
+    }
+
+    pub fn width(&self) -> i64 {
+        self.width
+    }
+
+    pub fn height(&self) -> i64 {
+        self.height
+    }
+
+    // ウィンドウの中の位置(x, y)が溝の上にあるか
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    // つまみの上端のy座標と高さ
+    // ページ全体が表示されていてスクロールできない場合はNone
+    pub fn thumb(
+        &self,
+        content_height: i64,
+        viewport_height: i64,
+        scroll_offset: i64,
+    ) -> Option<(i64, i64)> {
+        let max_offset = content_height - viewport_height;
+        if max_offset <= 0 || self.height <= 0 {
+            return None;
+        }
+
+        let thumb_height =
+            (self.height * viewport_height / content_height).clamp(MIN_THUMB_HEIGHT, self.height);
+        let thumb_top =
+            self.y + (self.height - thumb_height) * scroll_offset.clamp(0, max_offset) / max_offset;
+        Some((thumb_top, thumb_height))
+    }
+
+    // つまみの上端をthumb_topまでドラッグした時のスクロール位置
+    pub fn scroll_offset_for(
+        &self,
+        thumb_top: i64,
+        content_height: i64,
+        viewport_height: i64,
+    ) -> i64 {
+        let max_offset = content_height - viewport_height;
+        let (_, thumb_height) = match self.thumb(content_height, viewport_height, 0) {
+            Some(thumb) => thumb,
+            None => return 0,
+        };
+        let track = self.height - thumb_height;
+        if track <= 0 {
+            return 0;
+        }
+        ((thumb_top - self.y) * max_offset / track).clamp(0, max_offset)
+    }
+}
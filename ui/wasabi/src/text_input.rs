@@ -0,0 +1,125 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// テキストの入力欄を編集するキー
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EditKey {
+    // カーソルの位置に文字を挿入する
+    Char(char),
+    // カーソルの前の1文字を削除する
+    Backspace,
+    // カーソルの後の1文字を削除する
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+}
+
+impl EditKey {
+    // Wasabiのキー入力は文字として届くので、編集のキーは制御文字で表す
+    // 0x08: BS
+    // 0x7F: Delete
+    // 矢印キーとHome/Endキーは、Emacsと同じ制御文字でも入力できる
+    // 0x02: Ctrl+B（左）
+    // 0x06: Ctrl+F（右）
+    // 0x01: Ctrl+A（行頭）
+    // 0x05: Ctrl+E（行末）
+    // それ以外の制御文字（EnterやTabなど）はNone
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '\x08' => Some(Self::Backspace),
+            '\x7f' => Some(Self::Delete),
+            '\x02' => Some(Self::Left),
+            '\x06' => Some(Self::Right),
+            '\x01' => Some(Self::Home),
+            '\x05' => Some(Self::End),
+            c if c.is_control() => None,
+            c => Some(Self::Char(c)),
+        }
+    }
+}
+
+// 1行のテキストの入力欄（アドレスバーに使う）
+// カーソルの位置で文字を挿入・削除し、欄より長いテキストはカーソルが見えるように横にスクロールする
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TextInput {
+    chars: Vec<char>,
+    // カーソルの位置（何文字目の前にあるか）
+    cursor: usize,
+    // 欄の左端に表示している文字の位置
+    scroll: usize,
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self {
+            chars: Vec::new(),
+            cursor: 0,
+            scroll: 0,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    // テキストを置き換えて、カーソルを末尾に移動する
+    pub fn set_text(&mut self, text: &str) {
+        self.chars = text.chars().collect();
+        self.cursor = self.chars.len();
+        self.scroll = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.set_text("");
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn handle_key(&mut self, key: EditKey) {
+        match key {
+            EditKey::Char(c) => {
+                self.chars.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            EditKey::Backspace => {
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                    self.chars.remove(self.cursor);
+                }
+            }
+            EditKey::Delete => {
+                if self.cursor < self.chars.len() {
+                    self.chars.remove(self.cursor);
+                }
+            }
+            EditKey::Left => self.cursor = self.cursor.saturating_sub(1),
+            EditKey::Right => self.cursor = (self.cursor + 1).min(self.chars.len()),
+            EditKey::Home => self.cursor = 0,
+            EditKey::End => self.cursor = self.chars.len(),
+        }
+    }
+
+    // 幅がcolumns文字の欄に表示する部分と、欄の中でのカーソルの列
+    // カーソルが欄の外に出ないようにスクロールする
+    // 末尾にカーソルを表示できるように、1文字分の余白を残す
+    pub fn visible(&mut self, columns: usize) -> (String, usize) {
+        let columns = columns.max(1);
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if self.cursor >= self.scroll + columns {
+            self.scroll = self.cursor + 1 - columns;
+        }
+        // 削除して短くなった場合は、右側に空白が残らないように戻す
+        self.scroll = self
+            .scroll
+            .min((self.chars.len() + 1).saturating_sub(columns));
+
+        let end = (self.scroll + columns).min(self.chars.len());
+        let text = self.chars[self.scroll..end].iter().collect();
+        (text, self.cursor - self.scroll)
+    }
+}